@@ -229,6 +229,8 @@ fn bench_rendering_performance() {
                     cursor: None,
                     selection: None,
                     track_all_positions: false,
+                    search_highlights: &[],
+                    folds: &[],
                 };
                 let theme = Theme::default();
                 let _ = render::render_document_direct(
@@ -272,6 +274,8 @@ fn bench_rendering_with_styles() {
                 cursor: None,
                 selection: None,
                 track_all_positions: false,
+                search_highlights: &[],
+                folds: &[],
             };
             let theme = Theme::default();
             let _ = render::render_document_direct(&doc, 80, 0, &[], tracking, &theme);
@@ -299,6 +303,8 @@ fn bench_rendering_reveal_codes() {
             cursor: None,
             selection: None,
             track_all_positions: true,
+            search_highlights: &[],
+            folds: &[],
         };
         let theme = Theme::default();
         let _ = render::render_document_direct(&doc, 80, 0, &[], tracking, &theme);
@@ -310,6 +316,8 @@ fn bench_rendering_reveal_codes() {
             cursor: None,
             selection: None,
             track_all_positions: true,
+            search_highlights: &[],
+            folds: &[],
         };
         let theme = Theme::default();
         let _ = render::render_document_direct(&doc, 80, 0, &reveal_tags, tracking, &theme);
@@ -357,7 +365,7 @@ fn bench_segment_collection() {
                 ITERATIONS
             },
             || {
-                let _ = editor::inspect::collect_segments(&doc, false, 80);
+                let _ = editor::inspect::collect_segments(&doc, false);
             },
         );
         result.print();
@@ -375,12 +383,12 @@ fn bench_segment_collection_with_reveal() {
     let doc = create_styled_document(MEDIUM_DOC_PARAGRAPHS);
 
     let result_normal = benchmark("collect_segments - reveal_codes OFF", ITERATIONS, || {
-        let _ = editor::inspect::collect_segments(&doc, false, 80);
+        let _ = editor::inspect::collect_segments(&doc, false);
     });
     result_normal.print();
 
     let result_reveal = benchmark("collect_segments - reveal_codes ON", ITERATIONS, || {
-        let _ = editor::inspect::collect_segments(&doc, true, 80);
+        let _ = editor::inspect::collect_segments(&doc, true);
     });
     result_reveal.print();
 }
@@ -518,6 +526,8 @@ fn bench_wrap_width_impact() {
                     cursor: None,
                     selection: None,
                     track_all_positions: false,
+                    search_highlights: &[],
+                    folds: &[],
                 };
                 let theme = Theme::default();
                 let _ = render::render_document_direct(&doc, width, 0, &[], tracking, &theme);
@@ -538,13 +548,15 @@ fn bench_memory_allocations() {
     println!("\nDocument stats:");
     println!("  Paragraphs: {}", doc.paragraphs.len());
 
-    let segments = editor::inspect::collect_segments(&doc, false, 80);
+    let segments = editor::inspect::collect_segments(&doc, false);
     println!("  Segments: {}", segments.len());
 
     let tracking = DirectCursorTracking {
         cursor: None,
         selection: None,
         track_all_positions: false,
+        search_highlights: &[],
+        folds: &[],
     };
     let theme = Theme::default();
     let render_result = render::render_document_direct(&doc, 80, 0, &[], tracking, &theme);
@@ -918,6 +930,8 @@ fn bench_user_guide_rendering() {
             cursor: None,
             selection: None,
             track_all_positions: false,
+            search_highlights: &[],
+            folds: &[],
         };
         let theme = Theme::default();
         render::render_document_direct(&doc, 80, 0, &[], tracking, &theme);
@@ -983,6 +997,8 @@ fn bench_real_world_render_flow() {
             cursor: Some(&pointer),
             selection: None,
             track_all_positions: false,
+            search_highlights: &[],
+            folds: &[],
         };
         tracking_times.push(tracking_start.elapsed());
 
@@ -7,6 +7,7 @@
 use std::{
     cmp::Ordering,
     fs, io,
+    ops::Range,
     path::{Path, PathBuf},
     time::{Duration, Instant},
 };
@@ -28,20 +29,44 @@ use ratatui::{
     text::{Line, Span, Text},
     widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
 };
-use tdoc::ftml::{Writer, parse};
+use tdoc::ftml::{ParseError, Writer, parse};
 use tdoc::{Document, InlineStyle, ParagraphType, gemini, html, markdown};
 
-use crate::editor::{CursorPointer, DocumentEditor};
+use crate::choice_prompt::ChoicePrompt;
+use crate::clipboard::{self as system_clipboard, SystemClipboard};
+use crate::clipboard_history::{self, ClipboardContents, ClipboardHistory};
+use crate::command_palette::{self, CommandPaletteState, PaletteEntry};
+use crate::commit_message::{self, CommitMessage};
+use crate::config::{self, Config};
+use crate::diff::{self, DiffLine, LineKind};
+use crate::editor::{CursorPointer, DocumentEditor, ParagraphPath, SearchQuery};
 use crate::editor_display::{CursorDisplay, EditorDisplay};
+use crate::external_change::{self, DiskState, ExternalChange, ExternalChangeChoice, FileStamp};
 use crate::file_dialog::{FileDialogKind, FileDialogResult, FileDialogState};
+use crate::find_bar::{FindBarState, FindField};
+use crate::fold_state;
+use crate::keymap::{self, Command, EditCommand, KeyChord, Keymap, Lookup, Motion};
 use crate::link_dialog::{LinkDialogState, LinkField};
+use crate::lock::{self, Acquisition, Lock, LockChoice, LockOwner};
 use crate::menu_bar::{
     AppAction, MENU_BAR, MenuBarEntry, MenuBarState, menu_title_offset, menu_with_accel,
 };
+use crate::outline::{self, OutlineState};
+use crate::overwrite_prompt::{self, OverwriteChoice};
+use crate::plain_text;
+use crate::quit_prompt::{self, QuitChoice};
+use crate::recovery::{self, RecoveryChoice};
+use crate::save::{self, Backup};
+use crate::terminal;
+use crate::text_field::TextField;
 
-const STATUS_TIMEOUT: Duration = Duration::from_secs(4);
 const DOUBLE_CLICK_TIMEOUT: Duration = Duration::from_millis(400);
-const MOUSE_SCROLL_LINES: usize = 3;
+/// How many commands the command palette shows at a time.
+const COMMAND_PALETTE_ROWS: usize = 12;
+/// The outline's width: a third of the editor area, up to this many columns.
+const OUTLINE_WIDTH: u16 = 32;
+/// Below this width the outline is not shown.
+const MIN_OUTLINE_WIDTH: u16 = 12;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DocumentFormat {
@@ -49,41 +74,158 @@ pub enum DocumentFormat {
     Markdown,
     Html,
     Gemini,
+    PlainText,
 }
 
 impl DocumentFormat {
-    /// Picks the document format from a file's extension. Unknown (or missing)
-    /// extensions default to FTML, Pure's native format.
-    fn from_path(path: &Path) -> Self {
-        let ext = path
-            .extension()
+    /// Picks the format for a new file from its extension. Unknown (or
+    /// missing) extensions default to FTML, Pure's native format.
+    pub fn from_path(path: &Path) -> Self {
+        Self::from_extension(path).unwrap_or(DocumentFormat::Ftml)
+    }
+
+    /// The format a file's extension stands for, if any.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        path.extension()
             .and_then(|ext| ext.to_str())
-            .map(|ext| ext.to_ascii_lowercase());
-        match ext.as_deref() {
-            Some("md") | Some("markdown") | Some("mkd") | Some("mdown") | Some("mdtxt") => {
-                DocumentFormat::Markdown
+            .and_then(Self::from_name)
+    }
+
+    /// The format called `name` — any of the extensions it goes by, or
+    /// `gemini` and `text` — ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "ftml" => Some(DocumentFormat::Ftml),
+            "md" | "markdown" | "mkd" | "mdown" | "mdtxt" => Some(DocumentFormat::Markdown),
+            "html" | "htm" | "xhtml" => Some(DocumentFormat::Html),
+            "gmi" | "gemini" => Some(DocumentFormat::Gemini),
+            "txt" | "text" => Some(DocumentFormat::PlainText),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format of an existing file whose name does not tell: an
+    /// HTML page, markup FTML can read, Markdown, or else plain text.
+    fn sniff(content: &str) -> Self {
+        let start = content.trim_start();
+        let head = start
+            .get(..start.len().min(16))
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if head.starts_with("<!doctype html") || head.starts_with("<html") {
+            DocumentFormat::Html
+        } else if start.starts_with('<') {
+            if parse(io::Cursor::new(start)).is_ok() {
+                DocumentFormat::Ftml
+            } else {
+                DocumentFormat::Html
             }
-            Some("html") | Some("htm") | Some("xhtml") => DocumentFormat::Html,
-            Some("gmi") | Some("gemini") => DocumentFormat::Gemini,
-            _ => DocumentFormat::Ftml,
+        } else if content.lines().any(looks_like_markdown) {
+            DocumentFormat::Markdown
+        } else {
+            DocumentFormat::PlainText
+        }
+    }
+
+    /// How the format is called in messages.
+    fn name(self) -> &'static str {
+        match self {
+            DocumentFormat::Ftml => "FTML",
+            DocumentFormat::Markdown => "Markdown",
+            DocumentFormat::Html => "HTML",
+            DocumentFormat::Gemini => "Gemini",
+            DocumentFormat::PlainText => "plain text",
         }
     }
 }
 
-fn editor_wrap_configuration(width: usize) -> (usize, usize) {
+/// Whether `line` uses Markdown syntax that plain text rarely has by
+/// accident: headings, code fences, links, or bold text. List markers are
+/// no sign, as plain text uses them too.
+fn looks_like_markdown(line: &str) -> bool {
+    let hashes = line.chars().take_while(|ch| *ch == '#').count();
+    (1..=6).contains(&hashes) && line[hashes..].starts_with(' ')
+        || line.starts_with("```")
+        || line
+            .find('[')
+            .is_some_and(|open| line[open..].contains("]("))
+        || line.matches("**").count() >= 2
+}
+
+/// Whether `text` starts with an HTML tag as browsers and mail programs
+/// copy it: a doctype, `<html>`, a `<meta>` tag, or a block or inline
+/// element, closed again somewhere.
+fn looks_like_html(text: &str) -> bool {
+    const ELEMENTS: &[&str] = &[
+        "html",
+        "meta",
+        "body",
+        "div",
+        "p",
+        "h1",
+        "h2",
+        "h3",
+        "h4",
+        "h5",
+        "h6",
+        "ul",
+        "ol",
+        "li",
+        "blockquote",
+        "pre",
+        "table",
+        "span",
+        "b",
+        "strong",
+        "i",
+        "em",
+        "a",
+        "code",
+    ];
+    let Some(tag) = text.strip_prefix('<') else {
+        return false;
+    };
+    let name: String = tag
+        .chars()
+        .take_while(|ch| ch.is_ascii_alphanumeric() || *ch == '!')
+        .collect::<String>()
+        .to_ascii_lowercase();
+    let name_ends = tag[name.len()..].starts_with(['>', ' ', '/']);
+    (name == "!doctype" || name_ends && ELEMENTS.contains(&name.as_str())) && text.contains("</")
+}
+
+/// The paragraphs pasted `text` describes when it reads as HTML or
+/// Markdown; `None` for text to paste as it is.
+fn pasted_structure(text: &str) -> Option<Vec<tdoc::Paragraph>> {
+    let start = text.trim_start();
+    let document = if looks_like_html(start) {
+        html::parse(io::Cursor::new(start)).ok()?
+    } else if text.lines().any(looks_like_markdown) {
+        markdown::parse(io::Cursor::new(text)).ok()?
+    } else {
+        return None;
+    };
+    (!document.paragraphs.is_empty()).then_some(document.paragraphs)
+}
+
+/// The text width and left margin for a terminal `width` columns wide. Once
+/// the terminal is wider than `max_text_width` plus a margin on each side,
+/// the text is centered and kept at `max_text_width`.
+fn editor_wrap_configuration(width: usize, max_text_width: usize) -> (usize, usize) {
     if width == 0 {
         return (1, 0);
     }
-    if width < 60 {
+    let centered_from = max_text_width + 8;
+    if width < 60.min(centered_from) {
         let wrap_width = width.saturating_sub(1).max(1);
         return (wrap_width, 0);
     }
-    if width < 100 {
+    if width < centered_from {
         let padding = 2.min(width / 2);
         let wrap_width = width.saturating_sub(padding.saturating_mul(2)).max(1);
         return (wrap_width, padding);
     }
-    let mut left_padding = width.saturating_sub(100) / 2 + 4;
+    let mut left_padding = width.saturating_sub(centered_from) / 2 + 4;
     let max_padding = width.saturating_sub(1) / 2;
     if left_padding > max_padding {
         left_padding = max_padding;
@@ -92,38 +234,209 @@ fn editor_wrap_configuration(width: usize) -> (usize, usize) {
     (wrap_width, left_padding)
 }
 
-pub fn load_document(path: &PathBuf) -> Result<(Document, DocumentFormat, Option<String>)> {
-    let format = DocumentFormat::from_path(path);
-    if path.exists() {
-        let content = fs::read_to_string(path)
-            .with_context(|| format!("failed to read {}", path.display()))?;
-        let parsed = match format {
-            DocumentFormat::Ftml => parse(std::io::Cursor::new(content))
-                .map_err(|err| -> Box<dyn std::error::Error + Send + Sync> { Box::new(err) }),
-            DocumentFormat::Markdown => markdown::parse(std::io::Cursor::new(content)),
-            DocumentFormat::Html => html::parse(std::io::Cursor::new(content)),
-            DocumentFormat::Gemini => gemini::parse(std::io::Cursor::new(content)),
-        };
-        match parsed {
-            Ok(doc) => Ok((doc, format, None)),
-            Err(err) => {
-                let message = format!("Parse error: {err}. Starting with empty document.");
-                Ok((Document::new(), format, Some(message)))
-            }
+/// A document read by [`load_document`].
+pub struct LoadedDocument {
+    pub document: Document,
+    pub format: DocumentFormat,
+    /// Message for the status line, e.g. about a parse error.
+    pub status: Option<String>,
+    /// A recovery file holding unsaved changes from an earlier session that
+    /// did not end cleanly; see [`App::offer_recovery`].
+    pub recovery: Option<PathBuf>,
+    /// Set when the file could not be parsed; the document then shows the
+    /// file as it was read. See [`App::protect`].
+    pub parse_failure: Option<ParseFailure>,
+}
+
+/// A file that could not be parsed in its format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseFailure {
+    /// What went wrong, and where if known.
+    pub error: String,
+    /// The file as it was read.
+    pub content: String,
+    /// The line the parser's complaint points at, counted from zero, when
+    /// it can be told.
+    pub line: Option<usize>,
+}
+
+/// Read the document at `path`. Files without a known extension are
+/// recognized by their content; a path that does not exist yet gives a new,
+/// empty document. A file that fails to parse gives a document holding its
+/// raw text in a code block, so nothing of it is lost.
+pub fn load_document(path: &PathBuf) -> Result<LoadedDocument> {
+    load_document_as(path, None)
+}
+
+/// Like [`load_document`], but reads the file as `format`, if given, no
+/// matter its extension or content.
+pub fn load_document_as(path: &PathBuf, format: Option<DocumentFormat>) -> Result<LoadedDocument> {
+    let recovery = recovery::find(path);
+    if !path.exists() {
+        return Ok(LoadedDocument {
+            document: Document::new(),
+            format: format.unwrap_or_else(|| DocumentFormat::from_path(path)),
+            status: Some("New document".to_string()),
+            recovery,
+            parse_failure: None,
+        });
+    }
+    let content =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let format = format.or_else(|| DocumentFormat::from_extension(path));
+    Ok(LoadedDocument {
+        recovery,
+        ..read_document(content, format)
+    })
+}
+
+/// Read a document from `content` in `format`, or else the format the
+/// content looks like. Content that fails to parse gives a document holding
+/// the raw text, as [`load_document`] describes.
+pub fn read_document(content: String, format: Option<DocumentFormat>) -> LoadedDocument {
+    let format = format.unwrap_or_else(|| DocumentFormat::sniff(&content));
+    let mut line = None;
+    let parsed = match format {
+        DocumentFormat::Ftml => parse(std::io::Cursor::new(content.as_str())).map_err(
+            |err| -> Box<dyn std::error::Error + Send + Sync> {
+                line = ftml_error_line(&content, &err);
+                Box::new(err)
+            },
+        ),
+        DocumentFormat::Markdown => markdown::parse(std::io::Cursor::new(content.as_str())),
+        DocumentFormat::Html => html::parse(std::io::Cursor::new(content.as_str())),
+        DocumentFormat::Gemini => gemini::parse(std::io::Cursor::new(content.as_str())),
+        DocumentFormat::PlainText => Ok(plain_text::parse(&content)),
+    };
+    let (document, status, parse_failure) = match parsed {
+        Ok(document) => (document, None, None),
+        Err(err) => {
+            let location = line
+                .map(|line| format!(" on line {}", line + 1))
+                .unwrap_or_default();
+            let error = format!(
+                "could not read the file as {}{location}: {err}",
+                format.name()
+            );
+            let message = format!("Parse error: {error}. The file is shown read-only.");
+            let raw = content.replace("\r\n", "\n");
+            let document = Document::new().with_paragraphs(vec![
+                tdoc::Paragraph::new_code_block()
+                    .with_content(vec![tdoc::Span::new_text(raw.trim_end_matches('\n'))]),
+            ]);
+            let failure = ParseFailure {
+                error,
+                content,
+                line,
+            };
+            (document, Some(message), Some(failure))
         }
-    } else {
-        Ok((Document::new(), format, Some("New document".to_string())))
+    };
+    LoadedDocument {
+        document,
+        format,
+        status,
+        recovery: None,
+        parse_failure,
     }
 }
 
-#[derive(Clone, Copy)]
-enum MenuAction {
+/// The line of `content` an FTML parse error comes from. The parser reads
+/// from the start and stops at the first error, so the first line that,
+/// read with everything before it, fails with the same error is where it
+/// stopped. Errors about the end of the file are not placed: the start of
+/// any file fails with them, too.
+fn ftml_error_line(content: &str, err: &ParseError) -> Option<usize> {
+    if matches!(
+        err,
+        ParseError::Io(_) | ParseError::UnexpectedEof | ParseError::NoClosingTag(_)
+    ) {
+        return None;
+    }
+    let message = err.to_string();
+    let line_ends: Vec<usize> = content
+        .match_indices('\n')
+        .map(|(index, _)| index + 1)
+        .chain([content.len()])
+        .collect();
+    let fails_alike = |line: usize| {
+        parse(std::io::Cursor::new(&content[..line_ends[line]]))
+            .is_err_and(|prefix_err| prefix_err.to_string() == message)
+    };
+    let lines: Vec<usize> = (0..line_ends.len()).collect();
+    let line = lines.partition_point(|&line| !fails_alike(line));
+    (line < line_ends.len()).then_some(line)
+}
+
+/// Put the cursor at the start of the line a parse failure points at, in
+/// the raw text [`load_document`] shows for the file.
+fn focus_failure_line(display: &mut EditorDisplay, failure: &ParseFailure) {
+    let Some(line) = failure.line else {
+        return;
+    };
+    let offset = failure
+        .content
+        .split('\n')
+        .take(line)
+        .map(|text| text.trim_end_matches('\r').chars().count() + 1)
+        .sum();
+    if let Some(pointer) = display.pointer_at_global_char_offset(offset) {
+        display.focus_pointer(&pointer);
+    }
+}
+
+/// Render `document` in `format`, as saving writes it. Plain text is
+/// hard-wrapped at `plain_text_wrap` columns, if given.
+pub fn write_document(
+    document: &Document,
+    format: DocumentFormat,
+    plain_text_wrap: Option<usize>,
+) -> Result<Vec<u8>> {
+    let contents = match format {
+        DocumentFormat::Ftml => Writer::new()
+            .write_to_string(document)
+            .context("failed to render FTML")?
+            .into_bytes(),
+        DocumentFormat::Markdown => {
+            let mut contents = Vec::new();
+            markdown::write(&mut contents, document).context("failed to render Markdown")?;
+            contents
+        }
+        DocumentFormat::Html => {
+            // A complete, standalone HTML page (doctype, head, embedded
+            // stylesheet) so the saved file opens directly in any browser.
+            let mut contents = Vec::new();
+            html::write_document(&mut contents, document).context("failed to render HTML")?;
+            contents
+        }
+        DocumentFormat::Gemini => {
+            let mut contents = Vec::new();
+            gemini::write(&mut contents, document).context("failed to render Gemini")?;
+            contents
+        }
+        DocumentFormat::PlainText => {
+            let mut contents = Vec::new();
+            plain_text::write(&mut contents, document, plain_text_wrap)
+                .context("failed to render plain text")?;
+            contents
+        }
+    };
+    Ok(contents)
+}
+
+/// A formatting or editing action offered by the context menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuAction {
     SetParagraphType(ParagraphType),
     SetChecklistItemChecked(bool),
     ApplyInlineStyle(InlineStyle),
     EditLink,
     IndentMore,
     IndentLess,
+    InsertTableRow(bool),
+    InsertTableColumn(bool),
+    DeleteTableRow,
+    DeleteTableColumn,
     Cut,
     Copy,
     Paste,
@@ -150,17 +463,17 @@ impl MenuShortcut {
         }
     }
 
+    /// Whether the key pressed is this shortcut. Terminals report Shift
+    /// with a letter as an uppercase letter, with or without the Shift
+    /// modifier, or — speaking the kitty keyboard protocol — possibly as a
+    /// lowercase one with it, so the key is compared as a chord.
     fn matches(&self, code: KeyCode, modifiers: KeyModifiers) -> bool {
-        match code {
-            KeyCode::Char(ch) if ch == self.key => {
-                if self.requires_shift {
-                    modifiers == KeyModifiers::SHIFT
-                } else {
-                    modifiers.is_empty()
-                }
-            }
-            _ => false,
-        }
+        let shift = if self.requires_shift {
+            KeyModifiers::SHIFT
+        } else {
+            KeyModifiers::NONE
+        };
+        KeyChord::new(code, modifiers) == KeyChord::new(KeyCode::Char(self.key), shift)
     }
 }
 
@@ -289,6 +602,7 @@ fn build_context_menu_entries(
     has_selection: bool,
     can_indent_more: bool,
     can_indent_less: bool,
+    in_table: bool,
     allow_paragraph_change: bool,
     can_paste: bool,
 ) -> Vec<MenuEntry> {
@@ -326,6 +640,35 @@ fn build_context_menu_entries(
         entries.push(MenuEntry::Separator);
     }
 
+    if in_table {
+        entries.push(MenuEntry::Section("Table"));
+        entries.push(MenuEntry::Item(MenuItem::enabled(
+            "Insert row above",
+            MenuAction::InsertTableRow(false),
+        )));
+        entries.push(MenuEntry::Item(MenuItem::enabled(
+            "Insert row below",
+            MenuAction::InsertTableRow(true),
+        )));
+        entries.push(MenuEntry::Item(MenuItem::enabled(
+            "Insert column left",
+            MenuAction::InsertTableColumn(false),
+        )));
+        entries.push(MenuEntry::Item(MenuItem::enabled(
+            "Insert column right",
+            MenuAction::InsertTableColumn(true),
+        )));
+        entries.push(MenuEntry::Item(MenuItem::enabled(
+            "Delete row",
+            MenuAction::DeleteTableRow,
+        )));
+        entries.push(MenuEntry::Item(MenuItem::enabled(
+            "Delete column",
+            MenuAction::DeleteTableColumn,
+        )));
+        entries.push(MenuEntry::Separator);
+    }
+
     entries.extend(default_context_menu_entries(
         has_selection,
         allow_paragraph_change,
@@ -512,11 +855,32 @@ fn default_context_menu_entries(
     ]
 }
 
-fn is_context_menu_shortcut(code: KeyCode, modifiers: KeyModifiers) -> bool {
-    match code {
-        KeyCode::Esc => modifiers.is_empty(),
-        KeyCode::Char(' ') => modifiers.contains(KeyModifiers::CONTROL),
-        _ => false,
+/// Apply a line-editing key (caret movement, deletion, typing) to a text
+/// field; other keys are ignored.
+fn edit_text_field(field: &mut TextField, code: KeyCode, modifiers: KeyModifiers) {
+    match (code, modifiers) {
+        (KeyCode::Left, _) => field.move_cursor_left(),
+        (KeyCode::Right, _) => field.move_cursor_right(),
+        (KeyCode::Home, _) => field.move_cursor_start(),
+        (KeyCode::End, _) => field.move_cursor_end(),
+        (KeyCode::Char('a'), m) if m.contains(KeyModifiers::CONTROL) => field.move_cursor_start(),
+        (KeyCode::Char('e'), m) if m.contains(KeyModifiers::CONTROL) => field.move_cursor_end(),
+        (KeyCode::Char('w'), m) if m.contains(KeyModifiers::CONTROL) => {
+            field.delete_word_backward()
+        }
+        (KeyCode::Backspace, m)
+            if m.contains(KeyModifiers::CONTROL) || m.contains(KeyModifiers::ALT) =>
+        {
+            field.delete_word_backward()
+        }
+        (KeyCode::Backspace, _) => field.backspace(),
+        (KeyCode::Delete, _) => field.delete(),
+        (KeyCode::Char(ch), m)
+            if !m.contains(KeyModifiers::CONTROL) && !m.contains(KeyModifiers::ALT) =>
+        {
+            field.insert_char(ch)
+        }
+        _ => {}
     }
 }
 
@@ -550,15 +914,17 @@ fn open_in_browser(url: &str) -> io::Result<()> {
         .map(|_child| ())
 }
 
-/// What a cut or copy leaves on the internal clipboard.
-struct ClipboardContents {
-    /// Plain-text rendering of the selection — what is offered to the system
-    /// clipboard via OSC 52.
+/// Where the last paste left off, for Paste Previous and Paste as Plain
+/// Text.
+struct PasteRun {
+    /// The entry of the clipboard history to paste instead.
+    next: usize,
+    /// The plain text of what was pasted.
     text: String,
-    /// The selected paragraphs themselves, so in-app paste can restore
-    /// inline styles and paragraph structure. Empty when structured
-    /// extraction was not possible; paste then falls back to `text`.
-    fragment: Vec<tdoc::Paragraph>,
+    /// The cursor and edit count right after the paste; anything done since
+    /// ends the run.
+    cursor: CursorPointer,
+    edit_count: usize,
 }
 
 #[derive(Clone, Debug)]
@@ -577,22 +943,229 @@ enum DragState {
     Scrollbar(ScrollbarDrag),
 }
 
-pub struct App {
+/// An open document with its editing state. [`App`] keeps all of them in
+/// its buffer list and edits the one at `active_buffer`; switching only
+/// changes that index, and a split's other pane shows a buffer of the same
+/// list.
+struct Buffer {
     display: EditorDisplay,
-    /// Path of the current document; `None` while it is untitled (started
-    /// without an argument or via File > New).
+    /// Path of the document; `None` while it is untitled (started without
+    /// an argument or via File > New).
     file_path: Option<PathBuf>,
     document_format: DocumentFormat,
+    /// Tells untitled documents apart: the first is "Untitled", later ones
+    /// are numbered.
+    untitled_number: usize,
     scroll_top: usize,
-    should_quit: bool,
     dirty: bool,
-    status_message: Option<(String, Instant)>,
     selection_anchor: Option<CursorPointer>,
+    /// When the oldest change not yet written to the recovery file was made;
+    /// `None` while the recovery file is up to date.
+    autosave_due: Option<Instant>,
+    /// A recovery file to offer once the buffer is shown.
+    pending_recovery: Option<PathBuf>,
+    /// Another Pure editing the document, to ask about once the buffer is
+    /// shown.
+    pending_lock: Option<LockOwner>,
+    /// Set while the document comes from a file that failed to parse; see
+    /// [`App::protect`].
+    protection: Option<Protection>,
+    /// Set while the document is a Git commit message; see
+    /// [`App::edit_commit_message`].
+    commit_message: Option<CommitMessage>,
+    /// The document's file as last read or saved, to notice when another
+    /// program changes it; see [`App::check_disk`].
+    file_stamp: Option<FileStamp>,
+    /// The lock on the document's file, while this Pure holds it.
+    lock: Option<Lock>,
+    /// Set while the document is read-only because another Pure edits it;
+    /// see [`App::resolve_lock_prompt`].
+    locked_by: Option<LockOwner>,
+}
+
+impl Buffer {
+    /// Where the buffer's unsaved changes are autosaved.
+    fn recovery_origin(&self) -> recovery::Origin<'_> {
+        match &self.file_path {
+            Some(path) => recovery::Origin::File(path),
+            None => recovery::Origin::Untitled(self.untitled_number),
+        }
+    }
+
+    fn name(&self) -> String {
+        document_name(self.file_path.as_deref(), self.untitled_number)
+    }
+}
+
+/// Why an edit of a commit message is refused.
+const COMMENTS_READ_ONLY: &str = "Comment lines cannot be changed";
+const NO_SECTION: &str = "No heading above the cursor";
+
+/// The number of characters in `paragraph`'s own text.
+fn paragraph_char_count(paragraph: &tdoc::Paragraph) -> usize {
+    plain_text::spans_text(paragraph.content()).chars().count()
+}
+
+/// The untitled number of the document read from standard input, which no
+/// other untitled document has.
+const STANDARD_INPUT: usize = 0;
+
+/// How a document is called in the status line and the buffer list.
+fn document_name(path: Option<&Path>, untitled_number: usize) -> String {
+    match path {
+        Some(path) => path.display().to_string(),
+        None if untitled_number == STANDARD_INPUT => "Standard Input".to_string(),
+        None if untitled_number > 1 => format!("Untitled {untitled_number}"),
+        None => "Untitled".to_string(),
+    }
+}
+
+/// Fold what was folded when the document at `path` was last saved.
+fn restore_folds(display: &mut EditorDisplay, path: &Path) {
+    let folds = fold_state::load(path);
+    if !folds.is_empty() {
+        display.restore_folds(&folds);
+    }
+}
+
+/// Whether `a` and `b` name the same file, also when spelled differently.
+fn same_file(a: &Path, b: &Path) -> bool {
+    a == b
+        || matches!(
+            (fs::canonicalize(a), fs::canonicalize(b)),
+            (Ok(a), Ok(b)) if a == b
+        )
+}
+
+/// Lock the existing file at `path`; a new one is locked once it is saved.
+/// Gives the lock, or else the other Pure editing the file.
+fn lock_document(path: Option<&Path>) -> (Option<Lock>, Option<LockOwner>) {
+    match path.filter(|path| path.exists()).map(lock::acquire) {
+        Some(Acquisition::Locked(lock)) => (Some(lock), None),
+        Some(Acquisition::HeldBy(owner)) => (None, Some(owner)),
+        Some(Acquisition::AlreadyHeld | Acquisition::Unavailable) | None => (None, None),
+    }
+}
+
+/// What a key press in one of the choice prompts asks for.
+enum PromptAnswer<T> {
+    /// A button was activated.
+    Chosen(T),
+    /// The prompt was dismissed with Esc.
+    Dismissed,
+}
+
+/// Handle the keys every choice prompt shares: Left / Right (and Tab /
+/// Shift-Tab) move focus, Enter or Space activates the focused button, and a
+/// letter the button it is an accelerator of. Other keys change nothing;
+/// the prompts are modal, so they are consumed all the same.
+fn choice_prompt_key<T: Copy + PartialEq, C>(
+    prompt: &mut ChoicePrompt<T, C>,
+    code: KeyCode,
+    modifiers: KeyModifiers,
+) -> Option<PromptAnswer<T>> {
+    match code {
+        KeyCode::Esc => Some(PromptAnswer::Dismissed),
+        KeyCode::Enter | KeyCode::Char(' ') => Some(PromptAnswer::Chosen(prompt.focus())),
+        KeyCode::Right | KeyCode::Tab => {
+            prompt.focus_next();
+            None
+        }
+        KeyCode::Left | KeyCode::BackTab => {
+            prompt.focus_prev();
+            None
+        }
+        KeyCode::Char(ch)
+            if !modifiers.contains(KeyModifiers::CONTROL)
+                && !modifiers.contains(KeyModifiers::ALT) =>
+        {
+            prompt.from_accelerator(ch).map(PromptAnswer::Chosen)
+        }
+        _ => None,
+    }
+}
+
+/// What completes once the Save As dialog opened from the quit prompt has
+/// saved the document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AfterSave {
+    Quit,
+    CloseBuffer,
+}
+
+/// Which way a split divides the editor area.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SplitDirection {
+    /// One pane above the other.
+    Horizontal,
+    /// The panes side by side.
+    Vertical,
+}
+
+/// The pane without focus. It shows a buffer through a display of its own,
+/// with its own cursor, scroll position and layout, and takes over the
+/// buffer's edits each time it is drawn.
+struct Pane {
+    /// Position of the shown buffer in the buffer list.
+    buffer: usize,
+    display: EditorDisplay,
+    scroll_top: usize,
+}
+
+/// The editor area divided into two panes. The focused pane is the
+/// current buffer as always; the other is kept here.
+struct Split {
+    direction: SplitDirection,
+    other: Pane,
+    /// Whether the focused pane is the bottom (or right) one.
+    focus_second: bool,
+    /// Where the other pane was last drawn, for the mouse.
+    other_area: Rect,
+}
+
+/// Why saving over the current document's file needs a confirmation: the
+/// file failed to parse, so the document is not what the file holds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Protection {
+    /// The document shows the file as it was read and cannot be edited.
+    ReadOnly(ParseFailure),
+    /// The file was opened as plain text instead.
+    PlainText,
+}
+
+pub struct App {
+    /// The open documents, in buffer list order; never empty.
+    buffers: Vec<Buffer>,
+    /// Position of the current document in the buffer list.
+    active_buffer: usize,
+    /// Untitled documents started so far.
+    untitled_count: usize,
+    split: Option<Split>,
+    /// Whether every document is read-only, as with `pure --readonly`.
+    viewer: bool,
+    /// The document read from standard input, as last saved; see
+    /// [`App::use_standard_io`].
+    standard_output: Option<Vec<u8>>,
+    /// Whether a commit message was closed without being saved.
+    aborted_commit: bool,
+    should_quit: bool,
+    status_message: Option<(String, Instant)>,
     /// The last cut/copied content. Copying also sends the plain text to the
-    /// system clipboard via OSC 52, but reading that back is blocked by most
-    /// terminals, so in-app paste (Ctrl+V, menus) uses this buffer while
-    /// system-clipboard paste arrives as a bracketed-paste event.
+    /// system clipboard via OSC 52, which not every terminal takes up, so
+    /// in-app paste (Ctrl+V, menus) pastes this and reads the system
+    /// clipboard only while it is empty, or on request.
     clipboard: Option<ClipboardContents>,
+    /// Where in-app paste reads the system clipboard from.
+    system_clipboard: SystemClipboard,
+    /// The text last pasted through the terminal, until the next cut or
+    /// copy. Paste as Plain Text inserts it again as it is, for when its
+    /// format was guessed wrong.
+    terminal_paste: Option<String>,
+    /// Earlier cuts and copies, newest first; see [`clipboard_history`].
+    clipboard_history: ClipboardHistory,
+    /// Set by a paste, so that Paste Previous right after it can replace
+    /// the pasted text with an older entry of the clipboard history.
+    paste_run: Option<PasteRun>,
     context_menu: Option<ContextMenuState>,
     menu_bar: Option<MenuBarState>,
     file_dialog: Option<FileDialogState>,
@@ -601,19 +1174,60 @@ pub struct App {
     /// link being edited, the selection a new link is created from, or the
     /// (empty) cursor position a new link is inserted at.
     link_edit_range: Option<(CursorPointer, CursorPointer)>,
-    /// Whether the next New command may discard unsaved changes: the first
-    /// one only warns. Cleared again by any edit.
-    confirm_new: bool,
+    find_bar: Option<FindBarState>,
+    /// The outline pane, while shown; see [`App::toggle_outline`].
+    outline: Option<OutlineState>,
+    /// Asks whether to save before quitting or closing the buffer, as the
+    /// context says.
+    quit_prompt: Option<ChoicePrompt<QuitChoice, AfterSave>>,
+    /// Set while the Save As dialog was opened from the quit prompt: once the
+    /// document is saved, the editor quits or the buffer closes.
+    after_save: Option<AfterSave>,
+    /// Offers to restore the recovery file in the context.
+    recovery_prompt: Option<ChoicePrompt<RecoveryChoice, PathBuf>>,
+    overwrite_prompt: Option<ChoicePrompt<OverwriteChoice>>,
+    external_change_prompt: Option<ChoicePrompt<ExternalChangeChoice, ExternalChange>>,
+    /// Asks what to do with a document locked by the owner in the context.
+    lock_prompt: Option<ChoicePrompt<LockChoice, LockOwner>>,
+    command_palette: Option<CommandPaletteState>,
+    /// Whether saving keeps the previous version of the file as a backup.
+    backup: Backup,
+    keymap: Keymap,
+    /// Whether the terminal speaks the kitty keyboard protocol, telling
+    /// apart keys like Ctrl+Shift+S and Ctrl+S.
+    enhanced_keyboard: bool,
+    /// The first keys of a key sequence typed so far, e.g. Ctrl+X of
+    /// Ctrl+X Ctrl+S.
+    pending_keys: Vec<KeyChord>,
+    max_text_width: usize,
+    /// The width plain-text files are hard-wrapped at when saved, if any.
+    plain_text_wrap: Option<usize>,
+    /// Whether the folds of each file are kept for the next session; see
+    /// [`fold_state`].
+    remember_folds: bool,
+    /// Whether the clipboard history is kept for the next session.
+    remember_clipboard: bool,
+    /// Whether Markdown typed into rich-text documents turns into
+    /// formatting.
+    autoformat: bool,
+    status_timeout: Duration,
+    mouse_scroll_lines: usize,
     last_click_instant: Option<Instant>,
     last_click_position: Option<(u16, u16)>,
     last_click_button: Option<MouseButton>,
     mouse_click_count: u8,
     mouse_drag_anchor: Option<CursorPointer>,
     pending_scroll_restore: Option<ScrollRestore>,
+    /// A line to move the cursor to once the document is laid out; see
+    /// [`App::go_to_line`].
+    pending_line_jump: Option<usize>,
     drag_state: Option<DragState>,
     last_viewport_height: usize,
     last_total_lines: usize,
     last_scrollbar_column: u16,
+    /// Top row of the scrollbar; only below the first row while the bottom
+    /// pane of a split has focus.
+    last_scrollbar_row: u16,
     /// Flag to track when we need to rebuild visual positions (after edits, mouse clicks, etc.)
     needs_position_rebuild: bool,
     /// Whether we are attached to a real terminal. The test harness sets this
@@ -632,68 +1246,174 @@ impl App {
         editor.ensure_cursor_selectable();
         let display = EditorDisplay::new(editor);
 
-        Self {
+        let untitled_count = usize::from(path.is_none());
+        let (lock, locked_by) = lock_document(path.as_deref());
+        let buffer = Buffer {
             display,
+            file_stamp: path.as_deref().and_then(FileStamp::read),
             file_path: path,
             document_format: format,
+            untitled_number: untitled_count,
             scroll_top: 0,
-            should_quit: false,
             dirty: false,
-            status_message: initial_status.map(|msg| (msg, Instant::now())),
             selection_anchor: None,
+            autosave_due: None,
+            pending_recovery: None,
+            pending_lock: None,
+            protection: None,
+            commit_message: None,
+            lock,
+            locked_by: None,
+        };
+        Self {
+            buffers: vec![buffer],
+            active_buffer: 0,
+            untitled_count,
+            split: None,
+            viewer: false,
+            standard_output: None,
+            aborted_commit: false,
+            should_quit: false,
+            status_message: initial_status.map(|msg| (msg, Instant::now())),
             clipboard: None,
+            system_clipboard: SystemClipboard::new(system_clipboard::default_sources()),
+            terminal_paste: None,
+            clipboard_history: ClipboardHistory::default(),
+            paste_run: None,
             context_menu: None,
             menu_bar: None,
             file_dialog: None,
             link_dialog: None,
             link_edit_range: None,
-            confirm_new: false,
+            find_bar: None,
+            quit_prompt: None,
+            after_save: None,
+            recovery_prompt: None,
+            overwrite_prompt: None,
+            external_change_prompt: None,
+            lock_prompt: locked_by
+                .map(|owner| ChoicePrompt::new(&lock::CHOICES, LockChoice::ReadOnly, owner)),
+            command_palette: None,
+            outline: None,
+            backup: Backup::None,
+            keymap: Keymap::default(),
+            enhanced_keyboard: false,
+            pending_keys: Vec::new(),
+            max_text_width: config::DEFAULT_MAX_TEXT_WIDTH,
+            plain_text_wrap: None,
+            remember_folds: false,
+            remember_clipboard: false,
+            autoformat: true,
+            status_timeout: config::DEFAULT_STATUS_TIMEOUT,
+            mouse_scroll_lines: config::DEFAULT_MOUSE_SCROLL_LINES,
             last_click_instant: None,
             last_click_position: None,
             last_click_button: None,
             mouse_click_count: 0,
             mouse_drag_anchor: None,
             pending_scroll_restore: None,
+            pending_line_jump: None,
             drag_state: None,
             last_viewport_height: 0,
             last_total_lines: 0,
             last_scrollbar_column: 0,
+            last_scrollbar_row: 0,
             needs_position_rebuild: true, // Rebuild on first render
             interactive: true,
         }
     }
 
+    /// The current document's buffer.
+    fn buffer(&self) -> &Buffer {
+        &self.buffers[self.active_buffer]
+    }
+
+    fn buffer_mut(&mut self) -> &mut Buffer {
+        &mut self.buffers[self.active_buffer]
+    }
+
+    fn display(&self) -> &EditorDisplay {
+        &self.buffer().display
+    }
+
+    fn display_mut(&mut self) -> &mut EditorDisplay {
+        &mut self.buffer_mut().display
+    }
+
     pub fn set_interactive(&mut self, interactive: bool) {
         self.interactive = interactive;
     }
 
-    pub fn should_quit(&self) -> bool {
-        self.should_quit
+    /// Keep the previous version of the file when saving; see
+    /// [`Backup`].
+    pub fn set_backup(&mut self, backup: Backup) {
+        self.backup = backup;
+    }
+
+    /// Apply the user's configuration. Its problems, if any, are shown in
+    /// the status line.
+    pub fn apply_config(&mut self, config: Config) {
+        for buffer in &mut self.buffers {
+            buffer.display.set_theme(config.theme.clone());
+        }
+        if let Some(split) = &mut self.split {
+            split.other.display.set_theme(config.theme);
+        }
+        self.keymap = config.keymap;
+        self.keymap.set_enhanced_keyboard(self.enhanced_keyboard);
+        self.max_text_width = config.max_text_width;
+        self.plain_text_wrap = config.plain_text_wrap;
+        self.status_timeout = config.status_timeout;
+        self.mouse_scroll_lines = config.mouse_scroll_lines;
+        self.backup = config.backup;
+        let remember_folds = config.remember_folds;
+        self.remember_folds = remember_folds;
+        self.autoformat = config.autoformat;
+        self.system_clipboard = SystemClipboard::new(config.clipboard);
+        self.remember_clipboard = config.remember_clipboard;
+        if self.remember_clipboard && self.clipboard_history.is_empty() {
+            self.clipboard_history = clipboard_history::load();
+            if self.clipboard.is_none() {
+                self.clipboard = self.clipboard_history.get(0).cloned();
+            }
+        }
+        let buffer = self.buffer_mut();
+        if remember_folds && let Some(path) = &buffer.file_path {
+            restore_folds(&mut buffer.display, path);
+        }
+        self.needs_position_rebuild = true;
+        if let Some(first) = config.problems.first() {
+            let message = match config.problems.len() {
+                1 => format!("Config: {first}"),
+                count => format!("Config: {first} (and {} more)", count - 1),
+            };
+            self.status_message = Some((message, Instant::now()));
+        }
     }
 
-    pub fn has_status_message(&self) -> bool {
-        self.status_message.is_some()
+    pub fn should_quit(&self) -> bool {
+        self.should_quit
     }
 
     fn prepare_selection(&mut self, extend: bool) {
         if extend {
-            if self.selection_anchor.is_none() {
-                self.selection_anchor = Some(self.display.cursor_pointer());
+            if self.buffer().selection_anchor.is_none() {
+                self.buffer_mut().selection_anchor = Some(self.display().cursor_pointer());
             }
         } else {
-            self.selection_anchor = None;
+            self.buffer_mut().selection_anchor = None;
         }
     }
 
     fn current_selection(&mut self) -> Option<(CursorPointer, CursorPointer)> {
-        let anchor = self.selection_anchor.clone()?;
-        let focus = self.display.cursor_pointer();
-        match self.display.compare_pointers(&anchor, &focus) {
+        let anchor = self.buffer().selection_anchor.clone()?;
+        let focus = self.display().cursor_pointer();
+        match self.display().compare_pointers(&anchor, &focus) {
             Some(Ordering::Less) => Some((anchor, focus)),
             Some(Ordering::Equal) => None,
             Some(Ordering::Greater) => Some((focus, anchor)),
             None => {
-                self.selection_anchor = None;
+                self.buffer_mut().selection_anchor = None;
                 None
             }
         }
@@ -704,12 +1424,12 @@ impl App {
             return false;
         };
         if self
-            .display
+            .display_mut()
             .apply_inline_style_to_selection(&selection, style)
         {
             self.mark_dirty();
-            self.display.set_preferred_column(None);
-            self.selection_anchor = None;
+            self.display_mut().set_preferred_column(None);
+            self.buffer_mut().selection_anchor = None;
             true
         } else {
             false
@@ -718,19 +1438,19 @@ impl App {
 
     fn indent_selection_or_cursor(&mut self) -> bool {
         if let Some(selection) = self.current_selection() {
-            if self.display.indent_selection(&selection) {
-                self.selection_anchor = None;
+            if self.display_mut().indent_selection(&selection) {
+                self.buffer_mut().selection_anchor = None;
                 self.mark_dirty();
-                self.display.set_preferred_column(None);
+                self.display_mut().set_preferred_column(None);
                 return true;
             }
             return false;
         }
 
-        if self.display.indent_current_paragraph() {
-            self.selection_anchor = None;
+        if self.display_mut().indent_current_paragraph() {
+            self.buffer_mut().selection_anchor = None;
             self.mark_dirty();
-            self.display.set_preferred_column(None);
+            self.display_mut().set_preferred_column(None);
             return true;
         }
 
@@ -739,19 +1459,19 @@ impl App {
 
     fn unindent_selection_or_cursor(&mut self) -> bool {
         if let Some(selection) = self.current_selection() {
-            if self.display.unindent_selection(&selection) {
-                self.selection_anchor = None;
+            if self.display_mut().unindent_selection(&selection) {
+                self.buffer_mut().selection_anchor = None;
                 self.mark_dirty();
-                self.display.set_preferred_column(None);
+                self.display_mut().set_preferred_column(None);
                 return true;
             }
             return false;
         }
 
-        if self.display.unindent_current_paragraph() {
-            self.selection_anchor = None;
+        if self.display_mut().unindent_current_paragraph() {
+            self.buffer_mut().selection_anchor = None;
             self.mark_dirty();
-            self.display.set_preferred_column(None);
+            self.display_mut().set_preferred_column(None);
             return true;
         }
 
@@ -761,17 +1481,17 @@ impl App {
     fn insert_char_with_selection(&mut self, ch: char) -> bool {
         let mut selection_changed = false;
         if let Some(selection) = self.current_selection() {
-            if !self.display.remove_selection(&selection) {
+            if !self.display_mut().remove_selection(&selection) {
                 return false;
             }
-            self.selection_anchor = None;
+            self.buffer_mut().selection_anchor = None;
             selection_changed = true;
         }
 
-        let inserted = self.display.insert_char(ch);
+        let inserted = self.display_mut().insert_char(ch);
         if selection_changed || inserted {
             self.mark_dirty();
-            self.display.set_preferred_column(None);
+            self.display_mut().set_preferred_column(None);
         }
 
         inserted
@@ -783,9 +1503,9 @@ impl App {
         &mut self,
         selection: &(CursorPointer, CursorPointer),
     ) -> Option<ClipboardContents> {
-        let text = self.display.selection_text(selection)?;
+        let text = self.display().selection_text(selection)?;
         let fragment = self
-            .display
+            .display()
             .selection_fragment(selection)
             .unwrap_or_default();
         Some(ClipboardContents { text, fragment })
@@ -798,14 +1518,51 @@ impl App {
     fn copy_to_clipboard(&mut self, contents: ClipboardContents) {
         if self.interactive {
             execute!(
-                io::stdout(),
+                terminal::output(),
                 CopyToClipboard::to_clipboard_from(&contents.text)
             )
             .ok();
         }
+        self.clipboard_history.push(contents.clone());
+        self.terminal_paste = None;
+        if self.remember_clipboard
+            && let Err(err) = clipboard_history::store(&self.clipboard_history)
+        {
+            self.status_message = Some((
+                format!("Could not remember the clipboard: {err}"),
+                Instant::now(),
+            ));
+        }
         self.clipboard = Some(contents);
     }
 
+    /// Copy the selection serialized as Markdown or HTML, for pasting
+    /// formatted excerpts into chat and mail.
+    fn copy_selection_as(&mut self, format: DocumentFormat) -> bool {
+        let Some(selection) = self.current_selection() else {
+            self.status_message = Some(("Nothing selected".to_string(), Instant::now()));
+            return false;
+        };
+        let Some(fragment) = self.display().selection_fragment(&selection) else {
+            return false;
+        };
+        let document = Document::new().with_paragraphs(fragment.clone());
+        let mut text = Vec::new();
+        let (written, name) = match format {
+            DocumentFormat::Html => (html::write(&mut text, &document), "HTML"),
+            _ => (markdown::write(&mut text, &document), "Markdown"),
+        };
+        if let Err(err) = written {
+            self.status_message =
+                Some((format!("Could not copy as {name}: {err}"), Instant::now()));
+            return false;
+        }
+        let text = String::from_utf8_lossy(&text).trim_end().to_string();
+        self.copy_to_clipboard(ClipboardContents { text, fragment });
+        self.status_message = Some((format!("Copied as {name}"), Instant::now()));
+        true
+    }
+
     fn copy_selection(&mut self) -> bool {
         let Some(selection) = self.current_selection() else {
             self.status_message = Some(("Nothing selected".to_string(), Instant::now()));
@@ -827,13 +1584,13 @@ impl App {
         let Some(contents) = self.selection_clipboard_contents(&selection) else {
             return false;
         };
-        if !self.display.remove_selection(&selection) {
+        if !self.display_mut().remove_selection(&selection) {
             return false;
         }
         self.copy_to_clipboard(contents);
-        self.selection_anchor = None;
+        self.buffer_mut().selection_anchor = None;
         self.mark_dirty();
-        self.display.set_preferred_column(None);
+        self.display_mut().set_preferred_column(None);
         self.needs_position_rebuild = true;
         self.status_message = Some(("Cut to clipboard".to_string(), Instant::now()));
         true
@@ -846,117 +1603,300 @@ impl App {
         let Some(selection) = self.current_selection() else {
             return false;
         };
-        if !self.display.remove_selection(&selection) {
+        if !self.display_mut().remove_selection(&selection) {
             return false;
         }
-        self.selection_anchor = None;
+        self.buffer_mut().selection_anchor = None;
         self.mark_dirty();
-        self.display.set_preferred_column(None);
+        self.display_mut().set_preferred_column(None);
         self.needs_position_rebuild = true;
         true
     }
 
     /// Replace the selection (if any) and insert via `insert`. Shared tail
-    /// of the plain-text and structured paste paths.
-    fn paste_with(&mut self, insert: impl FnOnce(&mut EditorDisplay) -> bool) {
+    /// of the plain-text and structured paste paths. Returns whether
+    /// anything was inserted.
+    fn paste_with(&mut self, insert: impl FnOnce(&mut EditorDisplay) -> bool) -> bool {
         if let Some(selection) = self.current_selection() {
-            if !self.display.remove_selection(&selection) {
-                return;
+            if !self.display_mut().remove_selection(&selection) {
+                return false;
             }
-            self.selection_anchor = None;
+            self.buffer_mut().selection_anchor = None;
             self.mark_dirty();
         }
-        if insert(&mut self.display) {
+        let inserted = insert(self.display_mut());
+        if inserted {
             self.mark_dirty();
-            self.display.set_preferred_column(None);
+            self.display_mut().set_preferred_column(None);
         }
         self.needs_position_rebuild = true;
-        self.display.set_cursor_following(true);
+        self.display_mut().set_cursor_following(true);
+        inserted
+    }
+
+    /// Insert pasted text at the cursor. Used for bracketed paste (system
+    /// clipboard) and as fallback for internal paste. Markdown or HTML
+    /// becomes the structure it describes, except where formatting has no
+    /// place: in plain-text documents and in code.
+    fn paste_text(&mut self, text: &str) -> bool {
+        let structure = (self.buffer().document_format != DocumentFormat::PlainText
+            && !self.display().cursor_in_code())
+        .then(|| pasted_structure(text))
+        .flatten();
+        match structure {
+            Some(fragment) => self.paste_with(|display| display.insert_fragment(&fragment)),
+            None => self.paste_with(|display| display.insert_text(text)),
+        }
+    }
+
+    /// Paste cut or copied contents, with their formatting if they have
+    /// any.
+    fn paste_contents(&mut self, contents: &ClipboardContents) -> bool {
+        if contents.fragment.is_empty() {
+            self.paste_text(&contents.text)
+        } else {
+            self.paste_with(|display| display.insert_fragment(&contents.fragment))
+        }
+    }
+
+    /// Whether in-app paste may find something: on the internal clipboard,
+    /// or on the system clipboard while attached to a terminal.
+    fn can_paste(&self) -> bool {
+        self.clipboard.is_some() || (self.interactive && self.system_clipboard.readable())
+    }
+
+    /// Whether Paste as Plain Text may find something: what can be pasted,
+    /// or the text last pasted through the terminal.
+    fn can_paste_as_plain_text(&self) -> bool {
+        self.terminal_paste.is_some() || self.can_paste()
     }
 
-    /// Insert pasted plain text at the cursor. Used for bracketed paste
-    /// (system clipboard) and as fallback for internal paste.
-    fn paste_text(&mut self, text: &str) {
-        self.paste_with(|display| display.insert_text(text));
+    /// What the system clipboard holds; Pure's own copy if it is the text
+    /// last copied here, whose formatting that still has. `None` if the
+    /// system clipboard cannot be read.
+    fn read_system_clipboard(&mut self) -> Option<ClipboardContents> {
+        if !self.interactive {
+            return None;
+        }
+        let text = self.system_clipboard.read()?.replace("\r\n", "\n");
+        match &self.clipboard {
+            Some(contents) if contents.text.trim_end() == text.trim_end() => Some(contents.clone()),
+            _ => Some(ClipboardContents {
+                text,
+                fragment: Vec::new(),
+            }),
+        }
     }
 
+    /// Paste what was last cut or copied in Pure. Only with nothing cut or
+    /// copied yet is the system clipboard read: copying here may not have
+    /// reached it, and reading it can take a while.
     fn paste_from_clipboard(&mut self) {
-        let Some(contents) = &self.clipboard else {
+        let contents = match &self.clipboard {
+            Some(contents) => Some(contents.clone()),
+            None => self.read_system_clipboard(),
+        };
+        let Some(contents) = contents else {
             self.status_message = Some((
                 "Nothing to paste — use the terminal's paste shortcut instead".to_string(),
                 Instant::now(),
             ));
             return;
         };
-        if contents.fragment.is_empty() {
-            let text = contents.text.clone();
-            self.paste_text(&text);
-        } else {
-            let fragment = contents.fragment.clone();
-            self.paste_with(|display| display.insert_fragment(&fragment));
-        }
+        self.paste_clipboard_contents(&contents);
     }
 
-    fn undo(&mut self) {
-        if self.display.undo() {
-            self.after_history_restore();
-        } else {
-            self.status_message = Some(("Nothing to undo".to_string(), Instant::now()));
-        }
+    /// Paste what the system clipboard holds, for text copied in another
+    /// application after the last cut or copy in Pure.
+    fn paste_from_system_clipboard(&mut self) {
+        let Some(contents) = self.read_system_clipboard() else {
+            self.status_message = Some((
+                "Cannot read the system clipboard — use the terminal's paste shortcut instead"
+                    .to_string(),
+                Instant::now(),
+            ));
+            return;
+        };
+        self.paste_clipboard_contents(&contents);
     }
 
-    fn redo(&mut self) {
-        if self.display.redo() {
-            self.after_history_restore();
-        } else {
-            self.status_message = Some(("Nothing to redo".to_string(), Instant::now()));
+    fn paste_clipboard_contents(&mut self, contents: &ClipboardContents) {
+        if self.paste_contents(contents) {
+            self.start_paste_run_of(&contents.text);
         }
     }
 
-    fn after_history_restore(&mut self) {
-        self.mark_dirty();
-        self.selection_anchor = None;
-        self.display.set_preferred_column(None);
-        self.needs_position_rebuild = true;
+    /// Start a paste run for `text` just pasted from the clipboard or the
+    /// terminal. Paste Previous goes on with the entry before this one, or
+    /// with the newest if this came from another application.
+    fn start_paste_run_of(&mut self, text: &str) {
+        let next = usize::from(
+            self.clipboard_history
+                .get(0)
+                .is_some_and(|newest| newest.text == text),
+        );
+        self.start_paste_run(next, text.to_string());
     }
 
-    fn capture_reveal_toggle_snapshot(&self) -> RevealToggleSnapshot {
-        let viewport = self.display.last_view_height().max(1);
-        let max_scroll = self
-            .display
-            .last_total_lines()
-            .saturating_sub(viewport)
-            .min(self.display.last_total_lines());
-        let clamped_scroll = self.scroll_top.min(max_scroll);
-        let ratio = if max_scroll == 0 {
-            0.0
-        } else {
-            clamped_scroll as f64 / max_scroll as f64
+    /// Paste entry `index` of the clipboard history and make it what
+    /// Ctrl+V pastes next.
+    fn paste_from_history(&mut self, index: usize) {
+        let Some(contents) = self.clipboard_history.get(index).cloned() else {
+            return;
         };
-        RevealToggleSnapshot {
-            scroll_ratio: ratio,
-            cursor_pointer: self.display.cursor_stable_pointer(),
+        if self.paste_contents(&contents) {
+            self.start_paste_run(index + 1, contents.text.clone());
+            self.status_message = Some((
+                format!(
+                    "Pasted clipboard entry {} of {}",
+                    index + 1,
+                    self.clipboard_history.len()
+                ),
+                Instant::now(),
+            ));
         }
+        self.clipboard = Some(contents);
     }
 
-    fn restore_view_after_reveal_toggle(&mut self, snapshot: RevealToggleSnapshot) {
-        let _ = self.display.move_to_pointer(&snapshot.cursor_pointer);
-        self.pending_scroll_restore = Some(ScrollRestore {
-            ratio: snapshot.scroll_ratio,
-            ensure_cursor_visible: true,
+    fn start_paste_run(&mut self, next: usize, text: String) {
+        self.paste_run = Some(PasteRun {
+            next,
+            text,
+            cursor: self.display().cursor_pointer(),
+            edit_count: self.display().edit_count(),
         });
     }
 
-    fn apply_pending_scroll_restore(&mut self, viewport_height: usize) {
-        let Some(restore) = self.pending_scroll_restore.take() else {
-            return;
-        };
-        let viewport = viewport_height.max(1);
-        let max_scroll = self
-            .display
+    /// The paste run, if nothing was done since the paste.
+    fn take_paste_run(&mut self) -> Option<PasteRun> {
+        self.paste_run.take().filter(|run| {
+            run.cursor == self.display().cursor_pointer()
+                && run.edit_count == self.display().edit_count()
+        })
+    }
+
+    /// Replace the text just pasted with the entry of the clipboard history
+    /// before it, going round to the newest after the oldest.
+    fn paste_previous(&mut self) {
+        let Some(run) = self.take_paste_run() else {
+            self.status_message = Some((
+                "Paste Previous only works right after a paste".to_string(),
+                Instant::now(),
+            ));
+            return;
+        };
+        if self.clipboard_history.is_empty() || !self.display_mut().undo() {
+            return;
+        }
+        self.after_history_restore();
+        self.paste_from_history(run.next % self.clipboard_history.len());
+    }
+
+    /// Open the clipboard history: the command palette, listing earlier
+    /// cuts and copies to paste.
+    fn open_clipboard_history(&mut self) {
+        if self.clipboard_history.is_empty() {
+            self.status_message =
+                Some(("The clipboard history is empty".to_string(), Instant::now()));
+            return;
+        }
+        let entries = self
+            .clipboard_history
+            .iter()
+            .enumerate()
+            .map(|(index, contents)| PaletteEntry {
+                label: contents.preview(),
+                command: Command::App(AppAction::PasteFromHistory(index)),
+                shortcut: None,
+                enabled: true,
+            })
+            .collect();
+        self.command_palette = Some(CommandPaletteState::new("Clipboard History", entries));
+    }
+
+    /// Paste the clipboard's text as it is, without formatting — for when
+    /// the formatting of a paste is not wanted. Right after a paste, that
+    /// paste is replaced by its text; otherwise text pasted through the
+    /// terminal since the last cut or copy goes in again.
+    fn paste_clipboard_as_plain_text(&mut self) {
+        if let Some(run) = self.take_paste_run()
+            && self.display_mut().undo()
+        {
+            self.after_history_restore();
+            self.paste_with(|display| display.insert_text(&run.text));
+            return;
+        }
+        let text = match (&self.terminal_paste, &self.clipboard) {
+            (Some(text), _) => text.clone(),
+            (None, Some(contents)) => contents.text.clone(),
+            (None, None) => match self.read_system_clipboard() {
+                Some(contents) => contents.text,
+                None => return,
+            },
+        };
+        self.paste_with(|display| display.insert_text(&text));
+    }
+
+    fn undo(&mut self) {
+        if self.display_mut().undo() {
+            self.after_history_restore();
+        } else {
+            self.status_message = Some(("Nothing to undo".to_string(), Instant::now()));
+        }
+    }
+
+    fn redo(&mut self) {
+        if self.display_mut().redo() {
+            self.after_history_restore();
+        } else {
+            self.status_message = Some(("Nothing to redo".to_string(), Instant::now()));
+        }
+    }
+
+    fn after_history_restore(&mut self) {
+        self.mark_dirty();
+        self.buffer_mut().selection_anchor = None;
+        self.display_mut().set_preferred_column(None);
+        self.needs_position_rebuild = true;
+    }
+
+    fn capture_reveal_toggle_snapshot(&self) -> RevealToggleSnapshot {
+        let viewport = self.display().last_view_height().max(1);
+        let max_scroll = self
+            .display()
+            .last_total_lines()
+            .saturating_sub(viewport)
+            .min(self.display().last_total_lines());
+        let clamped_scroll = self.buffer().scroll_top.min(max_scroll);
+        let ratio = if max_scroll == 0 {
+            0.0
+        } else {
+            clamped_scroll as f64 / max_scroll as f64
+        };
+        RevealToggleSnapshot {
+            scroll_ratio: ratio,
+            cursor_pointer: self.display().cursor_stable_pointer(),
+        }
+    }
+
+    fn restore_view_after_reveal_toggle(&mut self, snapshot: RevealToggleSnapshot) {
+        let _ = self.display_mut().move_to_pointer(&snapshot.cursor_pointer);
+        self.pending_scroll_restore = Some(ScrollRestore {
+            ratio: snapshot.scroll_ratio,
+            ensure_cursor_visible: true,
+        });
+    }
+
+    fn apply_pending_scroll_restore(&mut self, viewport_height: usize) {
+        let Some(restore) = self.pending_scroll_restore.take() else {
+            return;
+        };
+        let viewport = viewport_height.max(1);
+        let max_scroll = self
+            .display()
             .get_total_lines()
             .saturating_sub(viewport)
-            .min(self.display.get_total_lines());
+            .min(self.display().get_total_lines());
         let mut target = if max_scroll == 0 {
             0
         } else {
@@ -965,11 +1905,12 @@ impl App {
         if target > max_scroll {
             target = max_scroll;
         }
-        self.scroll_top = target;
+        self.buffer_mut().scroll_top = target;
         if restore.ensure_cursor_visible
-            && let Some(cursor) = &self.display.cursor_visual()
+            && let Some(cursor) = &self.display().cursor_visual()
         {
-            self.scroll_top = self.scroll_top_for_cursor(cursor.line, viewport, max_scroll);
+            self.buffer_mut().scroll_top =
+                self.scroll_top_for_cursor(cursor.line, viewport, max_scroll);
         }
     }
 
@@ -979,13 +1920,27 @@ impl App {
             return;
         }
 
+        let find_bar_height = match &self.find_bar {
+            Some(bar) if bar.replacing() => 2,
+            Some(_) => 1,
+            None => 0,
+        };
         let vertical = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .constraints([
+                Constraint::Min(1),
+                Constraint::Length(find_bar_height),
+                Constraint::Length(1),
+            ])
             .split(area);
 
-        let editor_area = vertical[0];
-        let status_area = vertical[1];
+        let mut editor_area = self.draw_outline(frame, vertical[0]);
+        let find_bar_area = vertical[1];
+        let status_area = vertical[2];
+        if let Some((focused, other)) = self.split_editor_area(frame, editor_area) {
+            editor_area = focused;
+            self.draw_other_pane(frame, other);
+        }
 
         let horizontal = Layout::default()
             .direction(Direction::Horizontal)
@@ -996,17 +1951,17 @@ impl App {
 
         let render_start = Instant::now();
         let width = text_area.width.max(1) as usize;
-        let (wrap_width, left_padding) = editor_wrap_configuration(width);
+        let (wrap_width, left_padding) = editor_wrap_configuration(width, self.max_text_width);
         let selection = self.current_selection();
 
         // Use full position tracking when needed (mouse events, first render)
         // Otherwise use cached layout (fast - includes incremental updates from edits)
         if self.needs_position_rebuild {
             self.needs_position_rebuild = false;
-            self.display
+            self.display_mut()
                 .render_document_with_positions(wrap_width, left_padding, selection);
         } else {
-            self.display
+            self.display_mut()
                 .render_document(wrap_width, left_padding, selection);
         };
 
@@ -1015,55 +1970,64 @@ impl App {
             // eprintln!("  render_document: {:?}", render_time);
         }
 
-        self.display.update_after_render(text_area);
-        let _cursor_visual = self.display.cursor_visual();
+        self.display_mut().update_after_render(text_area);
+        self.apply_pending_line_jump();
+        let _cursor_visual = self.display().cursor_visual();
         let viewport_height = text_area.height as usize;
         self.apply_pending_scroll_restore(viewport_height);
-        self.adjust_scroll(self.display.get_total_lines(), viewport_height);
+        self.adjust_scroll(self.display().get_total_lines(), viewport_height);
 
         // Store viewport and total lines for scrollbar calculations
         self.last_viewport_height = viewport_height;
-        self.last_total_lines = self.display.get_total_lines();
+        self.last_total_lines = self.display().get_total_lines();
         self.last_scrollbar_column = scrollbar_area.x;
+        self.last_scrollbar_row = scrollbar_area.y;
 
-        if let Some(lines) = self.display.get_lines() {
+        if let Some(lines) = self.display().get_lines() {
             let paragraph = Paragraph::new(Text::from(lines))
                 .wrap(Wrap { trim: false })
                 .block(Block::default().borders(Borders::NONE))
-                .scroll((self.scroll_top as u16, 0));
+                .scroll((self.buffer().scroll_top as u16, 0));
             frame.render_widget(paragraph, text_area);
         }
 
         // Draw custom scrollbar
         self.draw_scrollbar(frame, scrollbar_area);
 
-        if let Some(cursor) = self.display.cursor_visual()
-            && cursor.line >= self.scroll_top
-            && cursor.line < self.scroll_top + viewport_height
+        if let Some(cursor) = self.display().cursor_visual()
+            && cursor.line >= self.buffer().scroll_top
+            && cursor.line < self.buffer().scroll_top + viewport_height
             && text_area.width > 0
+            && !self.outline.as_ref().is_some_and(OutlineState::focused)
         {
-            let cursor_y = text_area.y + (cursor.line - self.scroll_top) as u16;
+            let cursor_y = text_area.y + (cursor.line - self.buffer().scroll_top) as u16;
             let cursor_x = text_area.x + cursor.column.min(text_area.width - 1);
             frame.set_cursor_position(Position::new(cursor_x, cursor_y));
 
             // Change cursor style based on selection state
             if self.interactive {
-                let cursor_style = if self.selection_anchor.is_some() {
+                let cursor_style = if self.buffer().selection_anchor.is_some() {
                     SetCursorStyle::BlinkingUnderScore
                 } else {
                     SetCursorStyle::DefaultUserShape
                 };
-                execute!(io::stdout(), cursor_style).ok();
+                execute!(terminal::output(), cursor_style).ok();
             }
         }
 
-        let status_line =
-            self.status_line(self.display.get_content_lines(), status_area.width as usize);
+        let status_line = self.status_line(
+            self.display().get_content_lines(),
+            status_area.width as usize,
+        );
         let status_widget = Paragraph::new(status_line)
             .block(Block::default().borders(Borders::NONE))
-            .style(self.display.theme().status_bar_style());
+            .style(self.display().theme().status_bar_style());
         frame.render_widget(status_widget, status_area);
 
+        if self.find_bar.is_some() {
+            self.render_find_bar(frame, find_bar_area);
+        }
+
         if self.context_menu.is_some() {
             self.render_context_menu(frame, area);
         }
@@ -1079,6 +2043,30 @@ impl App {
         if self.link_dialog.is_some() {
             self.render_link_dialog(frame, area);
         }
+
+        if self.command_palette.is_some() {
+            self.render_command_palette(frame, area);
+        }
+
+        if self.quit_prompt.is_some() {
+            self.render_quit_prompt(frame, area);
+        }
+
+        if self.overwrite_prompt.is_some() {
+            self.render_overwrite_prompt(frame, area);
+        }
+
+        if self.external_change_prompt.is_some() {
+            self.render_external_change_prompt(frame, area);
+        }
+
+        if self.recovery_prompt.is_some() {
+            self.render_recovery_prompt(frame, area);
+        }
+
+        if self.lock_prompt.is_some() {
+            self.render_lock_prompt(frame, area);
+        }
     }
 
     fn render_file_dialog(&self, frame: &mut Frame, area: Rect) {
@@ -1089,7 +2077,7 @@ impl App {
             return;
         }
 
-        let theme = self.display.theme();
+        let theme = self.display().theme();
         let popup_style = theme.menu_style();
 
         // Input line, separator, up to eight listing rows, and a footer,
@@ -1181,11 +2169,10 @@ impl App {
         // Footer: pending confirmation warning, otherwise key hints.
         let footer_area = Rect::new(inner.x + 1, inner.y + inner.height - 1, inner.width - 2, 1);
         let footer = if dialog.pending_confirm().is_some() {
-            let warning = match dialog.kind() {
-                FileDialogKind::Open => "Unsaved changes — press Enter again to discard them",
-                FileDialogKind::SaveAs => "File exists — press Enter again to overwrite",
-            };
-            Span::styled(warning, Style::default().fg(Color::LightYellow))
+            Span::styled(
+                "File exists — press Enter again to overwrite",
+                Style::default().fg(Color::LightYellow),
+            )
         } else {
             let action = match dialog.kind() {
                 FileDialogKind::Open => "open",
@@ -1202,6 +2189,104 @@ impl App {
         );
     }
 
+    fn render_command_palette(&self, frame: &mut Frame, area: Rect) {
+        let Some(palette) = &self.command_palette else {
+            return;
+        };
+        if area.width < 20 || area.height < 8 {
+            return;
+        }
+
+        let theme = self.display().theme();
+        let popup_style = theme.menu_style();
+
+        // Query line, separator and the matching commands, plus the border.
+        let width = 64.min(area.width.saturating_sub(4));
+        let list_rows = palette.match_count().clamp(1, COMMAND_PALETTE_ROWS) as u16;
+        let height = (list_rows + 4).min(area.height.saturating_sub(2));
+        let popup_area = Rect::new(
+            area.x + (area.width.saturating_sub(width)) / 2,
+            area.y + (area.height.saturating_sub(height)) / 3,
+            width,
+            height,
+        );
+
+        frame.render_widget(Clear, popup_area);
+
+        let block = Block::default()
+            .title(palette.title())
+            .borders(Borders::ALL)
+            .style(popup_style)
+            .border_style(Style::default().fg(Color::Gray));
+        let inner = block.inner(popup_area);
+        frame.render_widget(block, popup_area);
+        if inner.width < 5 || inner.height < 3 {
+            return;
+        }
+
+        // Query input, horizontally scrolled so the cursor stays visible.
+        let input_area = Rect::new(inner.x + 1, inner.y, inner.width - 2, 1);
+        let visible = input_area.width as usize - 2;
+        let query = palette.query();
+        let skip = (query.cursor() + 1).saturating_sub(visible);
+        let shown: String = query.text().chars().skip(skip).take(visible).collect();
+        frame.render_widget(
+            Paragraph::new(format!("> {shown}")).style(popup_style),
+            input_area,
+        );
+        frame.set_cursor_position(Position::new(
+            input_area.x + 2 + (query.cursor() - skip) as u16,
+            input_area.y,
+        ));
+
+        let separator = "─".repeat(inner.width as usize);
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(
+                separator,
+                Style::default().fg(Color::DarkGray),
+            )))
+            .style(popup_style),
+            Rect::new(inner.x, inner.y + 1, inner.width, 1),
+        );
+
+        let list_area = Rect::new(inner.x, inner.y + 2, inner.width, inner.height - 2);
+        if palette.match_count() == 0 {
+            frame.render_widget(
+                Paragraph::new(" (no matches)")
+                    .style(popup_style.patch(theme.menu_disabled_style())),
+                list_area,
+            );
+            return;
+        }
+
+        let row_width = inner.width as usize - 2;
+        let items: Vec<ListItem> = palette
+            .matches()
+            .map(|entry| {
+                let shortcut = entry.shortcut.as_deref().unwrap_or("");
+                let label_width = row_width.saturating_sub(shortcut.chars().count() + 1);
+                let label: String = entry.label.chars().take(label_width).collect();
+                let content = format!(" {label:<label_width$} {shortcut} ");
+                let style = if entry.enabled {
+                    Style::default()
+                } else {
+                    theme.menu_disabled_style()
+                };
+                ListItem::new(Line::from(Span::styled(content, style)))
+            })
+            .collect();
+        let highlight_style = match palette.selected_entry() {
+            Some(entry) if !entry.enabled => theme.menu_selected_disabled_style(),
+            _ => theme.menu_selected_style(),
+        };
+        let mut list_state = ListState::default();
+        list_state.select(palette.selected());
+        let list = List::new(items)
+            .highlight_style(highlight_style)
+            .style(popup_style);
+        frame.render_stateful_widget(list, list_area, &mut list_state);
+    }
+
     fn render_menu_bar(&self, frame: &mut Frame, area: Rect) {
         let Some(state) = &self.menu_bar else {
             return;
@@ -1210,7 +2295,7 @@ impl App {
             return;
         }
 
-        let theme = self.display.theme();
+        let theme = self.display().theme();
         let bar_style = theme.menu_bar_style();
         let bar_area = Rect::new(area.x, area.y, area.width, 1);
 
@@ -1263,23 +2348,27 @@ impl App {
         }
 
         let menu = &MENU_BAR[menu_index];
-        let theme = self.display.theme();
+        let theme = self.display().theme();
 
         // Resolve labels up front: checked toggles get a checkmark prefix.
-        let rows: Vec<Option<(String, Option<&'static str>, bool)>> = menu
+        let rows: Vec<Option<(String, Option<String>, bool)>> = menu
             .entries
             .iter()
             .map(|entry| match entry {
                 MenuBarEntry::Separator => None,
                 MenuBarEntry::Item(item) => {
-                    let checked = item.action == Some(AppAction::ToggleRevealCodes)
-                        && self.display.reveal_codes();
+                    let checked = match item.action {
+                        Some(AppAction::ToggleRevealCodes) => self.display().reveal_codes(),
+                        Some(AppAction::ToggleOutline) => self.outline.is_some(),
+                        _ => false,
+                    };
                     let label = if checked {
                         format!("✓ {}", item.label)
                     } else {
                         item.label.to_string()
                     };
-                    Some((label, item.shortcut, item.action.is_some()))
+                    let shortcut = item.action.and_then(|action| self.shortcut_label(action));
+                    Some((label, shortcut, item.action.is_some()))
                 }
             })
             .collect();
@@ -1293,7 +2382,7 @@ impl App {
         let max_shortcut_width = rows
             .iter()
             .flatten()
-            .filter_map(|(_, shortcut, _)| shortcut.map(|s| s.chars().count()))
+            .filter_map(|(_, shortcut, _)| shortcut.as_ref().map(|s| s.chars().count()))
             .max()
             .unwrap_or(0);
         let gap_width = if max_shortcut_width > 0 { 2 } else { 0 };
@@ -1326,7 +2415,7 @@ impl App {
                     let content = format!(
                         " {label:<max_label_width$}{gap}{shortcut:>max_shortcut_width$} ",
                         gap = " ".repeat(gap_width),
-                        shortcut = shortcut.unwrap_or(""),
+                        shortcut = shortcut.as_deref().unwrap_or(""),
                     );
                     let style = if *enabled {
                         Style::default()
@@ -1359,6 +2448,141 @@ impl App {
         frame.render_stateful_widget(list, popup_area, &mut list_state);
     }
 
+    /// Divide the editor area between the panes of a split and draw the
+    /// line between them. Returns the focused pane's area and the other's.
+    fn split_editor_area(&mut self, frame: &mut Frame, area: Rect) -> Option<(Rect, Rect)> {
+        let split = self.split.as_ref()?;
+        let (direction, divider) = match split.direction {
+            SplitDirection::Horizontal => (Direction::Vertical, "─"),
+            SplitDirection::Vertical => (Direction::Horizontal, "│"),
+        };
+        let areas = Layout::default()
+            .direction(direction)
+            .constraints([
+                Constraint::Fill(1),
+                Constraint::Length(1),
+                Constraint::Fill(1),
+            ])
+            .split(area);
+        let divider_area = areas[1];
+        let lines: Vec<Line> = (0..divider_area.height)
+            .map(|_| Line::from(divider.repeat(divider_area.width as usize)))
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines).style(Style::default().fg(Color::DarkGray)),
+            divider_area,
+        );
+        Some(if split.focus_second {
+            (areas[2], areas[0])
+        } else {
+            (areas[0], areas[2])
+        })
+    }
+
+    /// Draw the outline at the left of the editor area, with a line between
+    /// the two, and return the area left for the editor. A narrow terminal
+    /// leaves no room for it.
+    fn draw_outline(&mut self, frame: &mut Frame, area: Rect) -> Rect {
+        let paragraph = self.cursor_root_paragraph();
+        let display = &self.buffers[self.active_buffer].display;
+        let selected_style = display.theme().menu_selected_style();
+        let Some(outline) = &mut self.outline else {
+            return area;
+        };
+        let width = (area.width / 3).min(OUTLINE_WIDTH);
+        if width < MIN_OUTLINE_WIDTH {
+            outline.set_area(Rect::default());
+            return area;
+        }
+        let areas = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(width),
+                Constraint::Length(1),
+                Constraint::Min(1),
+            ])
+            .split(area);
+        let (pane, divider_area) = (areas[0], areas[1]);
+
+        outline.refresh(display.document());
+        outline.set_area(pane);
+        let current = outline.current(paragraph);
+        outline.keep_in_view(current, pane.height as usize);
+
+        let lines: Vec<Line> = if outline.entries().is_empty() {
+            vec![Line::styled(
+                " No headings",
+                Style::default().fg(Color::DarkGray),
+            )]
+        } else {
+            outline
+                .entries()
+                .iter()
+                .enumerate()
+                .skip(outline.scroll())
+                .take(pane.height as usize)
+                .map(|(index, entry)| {
+                    let marker = if Some(index) == current { "▸" } else { " " };
+                    let indent = "  ".repeat(entry.level - 1);
+                    let title = if entry.title.is_empty() {
+                        "(empty)"
+                    } else {
+                        entry.title.as_str()
+                    };
+                    let text = format!("{marker}{indent}{title}");
+                    if outline.focused() && index == outline.selected_index() {
+                        let padding = (pane.width as usize).saturating_sub(text.chars().count());
+                        Line::styled(format!("{text}{}", " ".repeat(padding)), selected_style)
+                    } else if Some(index) == current {
+                        Line::styled(text, Style::default().add_modifier(Modifier::BOLD))
+                    } else {
+                        Line::from(text)
+                    }
+                })
+                .collect()
+        };
+        frame.render_widget(Paragraph::new(lines), pane);
+
+        let divider: Vec<Line> = (0..divider_area.height).map(|_| Line::from("│")).collect();
+        frame.render_widget(
+            Paragraph::new(divider).style(Style::default().fg(Color::DarkGray)),
+            divider_area,
+        );
+        areas[2]
+    }
+
+    /// Draw the pane without focus: its document as edited so far, at the
+    /// pane's own scroll position, without a cursor or scrollbar.
+    fn draw_other_pane(&mut self, frame: &mut Frame, area: Rect) {
+        self.sync_other_pane();
+        let max_text_width = self.max_text_width;
+        let Some(split) = &mut self.split else {
+            return;
+        };
+        split.other_area = area;
+        let pane = &mut split.other;
+        // The column a focused pane uses for its scrollbar stays empty.
+        let text_area = Rect {
+            width: area.width.saturating_sub(1).max(1),
+            ..area
+        };
+        let (wrap_width, left_padding) =
+            editor_wrap_configuration(text_area.width as usize, max_text_width);
+        pane.display.render_document(wrap_width, left_padding, None);
+        pane.display.update_after_render(text_area);
+        let max_scroll = pane
+            .display
+            .get_total_lines()
+            .saturating_sub(text_area.height as usize);
+        pane.scroll_top = pane.scroll_top.min(max_scroll);
+        if let Some(lines) = pane.display.get_lines() {
+            let paragraph = Paragraph::new(Text::from(lines))
+                .wrap(Wrap { trim: false })
+                .scroll((pane.scroll_top as u16, 0));
+            frame.render_widget(paragraph, text_area);
+        }
+    }
+
     fn draw_scrollbar(&self, frame: &mut Frame, area: Rect) {
         if area.height == 0 || self.last_total_lines <= self.last_viewport_height {
             return;
@@ -1381,7 +2605,7 @@ impl App {
                 // Draw knob
                 let span = Span::styled(
                     " ",
-                    self.display
+                    self.display()
                         .theme()
                         .scrollbar_knob_style()
                         .add_modifier(Modifier::REVERSED),
@@ -1389,7 +2613,7 @@ impl App {
                 frame.render_widget(Paragraph::new(Line::from(span)), Rect::new(x, y, 1, 1));
             } else {
                 // Draw track
-                let span = Span::styled(" ", self.display.theme().scrollbar_track_style());
+                let span = Span::styled(" ", self.display().theme().scrollbar_track_style());
                 frame.render_widget(Paragraph::new(Line::from(span)), Rect::new(x, y, 1, 1));
             }
         }
@@ -1453,7 +2677,7 @@ impl App {
         frame.render_widget(Clear, popup_area);
 
         let separator_width = popup_area.width.saturating_sub(4).max(4) as usize;
-        let popup_style = self.display.theme().menu_style();
+        let popup_style = self.display().theme().menu_style();
 
         let mut items = Vec::new();
         let gap = if has_shortcuts { "  " } else { "" };
@@ -1501,7 +2725,7 @@ impl App {
                     let style = if item.is_enabled() {
                         Style::default()
                     } else {
-                        self.display.theme().menu_disabled_style()
+                        self.display().theme().menu_disabled_style()
                     };
                     items.push(ListItem::new(Line::from(Span::styled(content, style))));
                 }
@@ -1516,9 +2740,9 @@ impl App {
             let selected_entry = &menu.entries()[menu.selected_index()];
             match selected_entry {
                 MenuEntry::Item(item) if !item.is_enabled() => {
-                    self.display.theme().menu_selected_disabled_style()
+                    self.display().theme().menu_selected_disabled_style()
                 }
-                _ => self.display.theme().menu_selected_style(),
+                _ => self.display().theme().menu_selected_style(),
             }
         };
 
@@ -1539,12 +2763,13 @@ impl App {
     fn open_context_menu(&mut self) {
         let has_selection = self.current_selection().is_some();
         let entries = build_context_menu_entries(
-            self.display.current_checklist_item_state(),
+            self.display().current_checklist_item_state(),
             has_selection,
-            self.display.can_indent_more(),
-            self.display.can_indent_less(),
-            self.display.can_change_paragraph_type(),
-            self.clipboard.is_some(),
+            self.display().can_indent_more(),
+            self.display().can_indent_less(),
+            self.display().cursor_in_table(),
+            self.display().can_change_paragraph_type(),
+            self.can_paste(),
         );
         self.context_menu = Some(ContextMenuState::new(entries));
     }
@@ -1609,20 +2834,23 @@ impl App {
     }
 
     fn execute_menu_action(&mut self, action: MenuAction) -> bool {
+        if action != MenuAction::Copy && self.refuse_edit() {
+            return false;
+        }
         match action {
             MenuAction::SetParagraphType(kind) => {
                 let handled = if let Some(selection) = self.current_selection() {
                     if self
-                        .display
+                        .display_mut()
                         .set_paragraph_type_for_selection(&selection, kind)
                     {
                         self.mark_dirty();
-                        self.selection_anchor = None;
+                        self.buffer_mut().selection_anchor = None;
                         true
                     } else {
                         false
                     }
-                } else if self.display.set_paragraph_type(kind) {
+                } else if self.display_mut().set_paragraph_type(kind) {
                     self.mark_dirty();
                     true
                 } else {
@@ -1630,12 +2858,15 @@ impl App {
                 };
 
                 if handled {
-                    self.display.set_preferred_column(None);
+                    self.display_mut().set_preferred_column(None);
                 }
                 true
             }
             MenuAction::SetChecklistItemChecked(checked) => {
-                if self.display.set_current_checklist_item_checked(checked) {
+                if self
+                    .display_mut()
+                    .set_current_checklist_item_checked(checked)
+                {
                     self.mark_dirty();
                 }
                 true
@@ -1656,6 +2887,22 @@ impl App {
                 self.unindent_selection_or_cursor();
                 true
             }
+            MenuAction::InsertTableRow(below) => {
+                self.apply_table_action(|display| display.insert_table_row(below));
+                true
+            }
+            MenuAction::InsertTableColumn(right) => {
+                self.apply_table_action(|display| display.insert_table_column(right));
+                true
+            }
+            MenuAction::DeleteTableRow => {
+                self.apply_table_action(|display| display.delete_table_row());
+                true
+            }
+            MenuAction::DeleteTableColumn => {
+                self.apply_table_action(|display| display.delete_table_column());
+                true
+            }
             MenuAction::Cut => {
                 self.cut_selection();
                 true
@@ -1671,12 +2918,26 @@ impl App {
         }
     }
 
+    fn apply_table_action<F>(&mut self, action: F)
+    where
+        F: FnOnce(&mut EditorDisplay) -> bool,
+    {
+        self.buffer_mut().selection_anchor = None;
+        if action(self.display_mut()) {
+            self.mark_dirty();
+            self.display_mut().set_preferred_column(None);
+        }
+    }
+
     fn toggle_reveal_codes(&mut self) {
         let snapshot = self.capture_reveal_toggle_snapshot();
-        let enabled = !self.display.reveal_codes();
-        self.display.set_reveal_codes(enabled);
+        let enabled = !self.display().reveal_codes();
+        self.display_mut().set_reveal_codes(enabled);
+        if let Some(split) = &mut self.split {
+            split.other.display.set_reveal_codes(enabled);
+        }
         self.restore_view_after_reveal_toggle(snapshot);
-        self.display.set_preferred_column(None);
+        self.display_mut().set_preferred_column(None);
         let message = if enabled {
             "Reveal codes enabled"
         } else {
@@ -1686,15 +2947,15 @@ impl App {
     }
 
     /// Appends a debug dump of the document tree (with cursor position) to
-    /// `pure-tree-dump.txt` in the temp directory. Bound to F12 in dev
-    /// (debug) builds only, for diagnosing structural corruption in a live
-    /// session.
+    /// `pure-tree-dump.txt` in the temp directory. Available (on F12 and in
+    /// the command palette) in dev (debug) builds only, for diagnosing
+    /// structural corruption in a live session.
     #[cfg(debug_assertions)]
     fn dump_document_tree(&mut self) {
         use std::io::Write;
 
-        let pointer = self.display.cursor_pointer();
-        let dump = crate::editor::inspect::dump_tree(self.display.document(), Some(&pointer));
+        let pointer = self.display().cursor_pointer();
+        let dump = crate::editor::inspect::dump_tree(self.display().document(), Some(&pointer));
         let path = std::env::temp_dir().join("pure-tree-dump.txt");
         let stamp = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -1725,13 +2986,22 @@ impl App {
             if self.context_menu.is_some() {
                 return Ok(false);
             }
+            if !self.pending_keys.is_empty() {
+                return Ok(false);
+            }
             // Activation from normal editing
             match code {
                 KeyCode::F(10) => {
                     self.menu_bar = Some(MenuBarState::new());
                     return Ok(true);
                 }
+                // Alt+letter opens a menu unless the keymap binds it (as
+                // Emacs does with Alt+F)
                 KeyCode::Char(ch) if modifiers.contains(KeyModifiers::ALT) => {
+                    let chord = KeyChord::new(code, modifiers);
+                    if self.keymap.lookup(&[chord]) != Lookup::Unbound {
+                        return Ok(false);
+                    }
                     if let Some(index) = menu_with_accel(ch) {
                         self.menu_bar = Some(MenuBarState::open_at(index));
                         return Ok(true);
@@ -1785,13 +3055,35 @@ impl App {
     }
 
     fn execute_app_action(&mut self, action: AppAction) -> Result<()> {
-        let previous_cursor = self.display.cursor_pointer();
+        let previous_cursor = self.display().cursor_pointer();
         match action {
+            // Neither touches what edits are refused for in a commit message.
+            AppAction::Undo | AppAction::Redo | AppAction::Replace if self.is_read_only() => {
+                self.refuse_edit();
+            }
+            AppAction::Cut
+            | AppAction::Paste
+            | AppAction::PasteAsPlainText
+            | AppAction::PasteFromSystem
+            | AppAction::PastePrevious
+            | AppAction::ClipboardHistory
+            | AppAction::PasteFromHistory(_)
+            | AppAction::InsertLineBreak
+            | AppAction::InsertSiblingParagraph
+            | AppAction::CutSection
+                if self.refuse_edit() => {}
+            AppAction::Save | AppAction::SaveAs if self.viewer => {
+                self.refuse_edit();
+            }
+            AppAction::Save if self.buffer().locked_by.is_some() => {
+                self.refuse_edit();
+            }
             AppAction::New => self.new_document(),
             AppAction::Open => self.open_file_dialog(FileDialogKind::Open),
-            AppAction::Save => self.save()?,
+            AppAction::OpenAsPlainText => self.open_as_plain_text(),
+            AppAction::Save => self.save_or_report(),
             AppAction::SaveAs => self.open_file_dialog(FileDialogKind::SaveAs),
-            AppAction::Quit => self.should_quit = true,
+            AppAction::Quit => self.request_quit(),
             AppAction::Undo => self.undo(),
             AppAction::Redo => self.redo(),
             AppAction::Cut => {
@@ -1800,63 +3092,477 @@ impl App {
             AppAction::Copy => {
                 self.copy_selection();
             }
+            AppAction::CopyAsMarkdown => {
+                self.copy_selection_as(DocumentFormat::Markdown);
+            }
+            AppAction::CopyAsHtml => {
+                self.copy_selection_as(DocumentFormat::Html);
+            }
             AppAction::Paste => self.paste_from_clipboard(),
+            AppAction::PasteAsPlainText => self.paste_clipboard_as_plain_text(),
+            AppAction::PasteFromSystem => self.paste_from_system_clipboard(),
+            AppAction::PastePrevious => self.paste_previous(),
+            AppAction::ClipboardHistory => self.open_clipboard_history(),
+            AppAction::PasteFromHistory(index) => self.paste_from_history(index),
+            AppAction::Find => self.open_find_bar(false),
+            AppAction::Replace => self.open_find_bar(true),
             AppAction::InsertLineBreak => {
                 self.insert_char_with_selection('\n');
             }
             AppAction::InsertSiblingParagraph => {
                 self.prepare_selection(false);
-                if self.display.insert_paragraph_break_as_sibling() {
+                if self.display_mut().insert_paragraph_break_as_sibling() {
                     self.mark_dirty();
-                    self.display.set_preferred_column(None);
+                    self.display_mut().set_preferred_column(None);
                 }
             }
             AppAction::FormattingMenu => self.open_context_menu(),
+            AppAction::SelectSection => {
+                self.select_section();
+            }
+            AppAction::CutSection => self.cut_section(),
+            AppAction::CopySection => {
+                if self.select_section() {
+                    self.copy_selection();
+                }
+            }
+            AppAction::MoveSectionUp => self.move_section(true),
+            AppAction::MoveSectionDown => self.move_section(false),
+            AppAction::PromoteSection => self.shift_section(-1),
+            AppAction::DemoteSection => self.shift_section(1),
             AppAction::ToggleRevealCodes => self.toggle_reveal_codes(),
+            AppAction::ToggleOutline => self.toggle_outline(),
+            AppAction::ToggleFold => self.toggle_fold(),
+            AppAction::FoldAllSections => self.fold_all_sections(),
+            AppAction::UnfoldAll => self.unfold_all(),
+            AppAction::CommandPalette => self.open_command_palette(),
+            AppAction::SplitHorizontally => self.split_view(SplitDirection::Horizontal),
+            AppAction::SplitVertically => self.split_view(SplitDirection::Vertical),
+            AppAction::OtherPane => self.focus_other_pane(),
+            AppAction::Unsplit => self.unsplit(),
+            AppAction::NextBuffer => self.cycle_buffer(1),
+            AppAction::PreviousBuffer => self.cycle_buffer(-1),
+            AppAction::BufferList => self.open_buffer_list(),
+            AppAction::CloseBuffer => self.request_close_buffer(),
+            AppAction::SwitchToBuffer(index) => self.show_buffer(index),
+            #[cfg(debug_assertions)]
+            AppAction::DumpDocumentTree => self.dump_document_tree(),
         }
-        if self.display.cursor_pointer() != previous_cursor {
-            self.display.set_cursor_following(true);
+        if self.display().cursor_pointer() != previous_cursor {
+            self.display_mut().set_cursor_following(true);
         }
         Ok(())
     }
 
-    fn status_line(&mut self, content_lines: usize, terminal_width: usize) -> Line<'static> {
-        self.prune_status_message();
-
-        // If there's a status message, show it prominently
-        if let Some((message, _)) = &self.status_message {
-            let position = self.cursor_position_text();
-            return Line::from(vec![
-                Span::raw(format!("{} ", position)),
-                Span::raw(message.clone()),
-            ]);
+    /// Run a command bound to a key.
+    fn run_command(&mut self, command: Command) -> Result<()> {
+        let previous_cursor = self.display().cursor_pointer();
+        match command {
+            Command::App(action) => self.execute_app_action(action)?,
+            Command::Menu(action) => {
+                self.execute_menu_action(action);
+            }
+            Command::Edit(command) => self.run_edit_command(command),
         }
+        if self.display().cursor_pointer() != previous_cursor {
+            self.display_mut().set_cursor_following(true);
+        }
+        Ok(())
+    }
 
-        let position = self.cursor_position_text();
-        let filename = self
-            .file_path
-            .as_ref()
-            .map(|path| path.display().to_string())
-            .unwrap_or_else(|| "Untitled".to_string());
-        let marker = if self.dirty { "*" } else { "" };
-        let breadcrumbs = self.breadcrumbs_text();
-        let word_count = self.count_words();
-
-        // Shortcuts ordered from least to most important (reversed order for display)
-        let all_shortcuts = ["F10:Menu", "^S:Save", "^Q:Quit"];
-
-        // Build the left part of the status line
-        let mut spans = Vec::new();
-
-        // Position
-        spans.push(Span::styled(position, Style::default().fg(Color::White)));
-        spans.push(Span::raw(" "));
-
-        // Filename
-        spans.push(Span::styled(
+    fn run_edit_command(&mut self, command: EditCommand) {
+        match command {
+            EditCommand::Backspace | EditCommand::DeleteWordBackward
+                if self.refuse_joining_comment(false) => {}
+            EditCommand::Delete | EditCommand::DeleteWordForward
+                if self.refuse_joining_comment(true) => {}
+            EditCommand::Backspace
+            | EditCommand::Delete
+            | EditCommand::DeleteWordBackward
+            | EditCommand::DeleteWordForward
+            | EditCommand::InsertParagraphBreak
+            | EditCommand::Tab
+                if self.refuse_edit() => {}
+            EditCommand::Move(motion) => {
+                self.prepare_selection(false);
+                self.move_cursor(motion);
+            }
+            EditCommand::Select(motion) => {
+                self.prepare_selection(true);
+                self.move_cursor(motion);
+            }
+            EditCommand::ScrollUp => {
+                self.prepare_selection(false);
+                self.buffer_mut().scroll_top = self
+                    .buffer()
+                    .scroll_top
+                    .saturating_sub(self.display().last_view_height());
+                self.detach_cursor_follow();
+            }
+            EditCommand::ScrollDown => {
+                self.prepare_selection(false);
+                self.buffer_mut().scroll_top += self.display().last_view_height();
+                self.detach_cursor_follow();
+            }
+            EditCommand::Backspace => {
+                if !self.delete_selection() && self.display_mut().backspace() {
+                    self.mark_dirty();
+                    self.display_mut().set_preferred_column(None);
+                }
+            }
+            EditCommand::Delete => {
+                if !self.delete_selection() && self.display_mut().delete() {
+                    self.mark_dirty();
+                    self.display_mut().set_preferred_column(None);
+                }
+            }
+            EditCommand::DeleteWordBackward => {
+                if !self.delete_selection() && self.display_mut().delete_word_backward() {
+                    self.mark_dirty();
+                    self.display_mut().set_preferred_column(None);
+                }
+            }
+            EditCommand::DeleteWordForward => {
+                if !self.delete_selection() && self.display_mut().delete_word_forward() {
+                    self.mark_dirty();
+                    self.display_mut().set_preferred_column(None);
+                }
+            }
+            EditCommand::InsertParagraphBreak => {
+                // Enter on a code fence makes a code block of it instead.
+                let fenced = self.autoformats() && self.display_mut().autoformat_code_fence();
+                if fenced || self.insert_paragraph_break() {
+                    self.mark_dirty();
+                    self.display_mut().set_preferred_column(None);
+                }
+            }
+            EditCommand::Tab if self.display().cursor_in_table() => {
+                self.buffer_mut().selection_anchor = None;
+                // Tab in the last cell appends a fresh row
+                let appends_row = self.display().cursor_in_last_table_cell();
+                if self.display_mut().next_table_cell() && appends_row {
+                    self.mark_dirty();
+                }
+                self.display_mut().set_preferred_column(None);
+            }
+            EditCommand::Tab => {
+                self.insert_char_with_selection('\t');
+            }
+            EditCommand::PreviousCell => {
+                if self.display().cursor_in_table() {
+                    self.buffer_mut().selection_anchor = None;
+                    self.display_mut().previous_table_cell();
+                    self.display_mut().set_preferred_column(None);
+                }
+            }
+        }
+    }
+
+    fn move_cursor(&mut self, motion: Motion) {
+        let moved = match motion {
+            Motion::Left => self.display_mut().move_left(),
+            Motion::Right => self.display_mut().move_right(),
+            Motion::WordLeft => self.display_mut().move_word_left(),
+            Motion::WordRight => self.display_mut().move_word_right(),
+            Motion::Up => {
+                self.display_mut().move_cursor_vertical(-1);
+                false
+            }
+            Motion::Down => {
+                self.display_mut().move_cursor_vertical(1);
+                false
+            }
+            Motion::LineStart => {
+                self.display_mut().move_to_visual_line_start();
+                false
+            }
+            Motion::LineEnd => {
+                self.display_mut().move_to_visual_line_end();
+                false
+            }
+            Motion::PageUp => {
+                self.display_mut().move_page(-1);
+                false
+            }
+            Motion::PageDown => {
+                self.display_mut().move_page(1);
+                false
+            }
+        };
+        // Horizontal moves forget the column vertical moves aim for
+        if moved {
+            self.display_mut().set_preferred_column(None);
+        }
+    }
+
+    /// Handle a key press in the document: look the key, together with the
+    /// keys of a sequence typed so far, up in the keymap, and type it when
+    /// it is not bound. A held key repeats typing, moving and deleting, but
+    /// not commands that open, toggle or save something.
+    fn handle_editing_key(
+        &mut self,
+        code: KeyCode,
+        modifiers: KeyModifiers,
+        repeat: bool,
+    ) -> Result<()> {
+        if code == KeyCode::Esc && !self.pending_keys.is_empty() {
+            self.pending_keys.clear();
+            self.status_message = None;
+            return Ok(());
+        }
+
+        let chord = KeyChord::new(code, modifiers);
+        self.pending_keys.push(chord);
+        match self.keymap.lookup(&self.pending_keys) {
+            Lookup::Command(command) => {
+                if self.pending_keys.len() > 1 {
+                    self.status_message = None;
+                }
+                self.pending_keys.clear();
+                if repeat && !matches!(command, Command::Edit(_)) {
+                    return Ok(());
+                }
+                self.run_command(command)
+            }
+            Lookup::Prefix => {
+                let typed = keymap::sequence_text(&self.pending_keys);
+                self.status_message = Some((format!("{typed} -"), Instant::now()));
+                Ok(())
+            }
+            Lookup::Unbound if self.pending_keys.len() > 1 => {
+                let typed = keymap::sequence_text(&self.pending_keys);
+                self.pending_keys.clear();
+                self.status_message = Some((format!("{typed} is not bound"), Instant::now()));
+                Ok(())
+            }
+            Lookup::Unbound => {
+                self.pending_keys.clear();
+                if let Some(command) = self.keymap.fallback(chord) {
+                    return self.run_command(command);
+                }
+                if let KeyCode::Char(ch) = code
+                    && !modifiers.contains(KeyModifiers::CONTROL)
+                    && !modifiers.contains(KeyModifiers::ALT)
+                    && !self.refuse_edit()
+                {
+                    if self.insert_char_with_selection(ch) && self.autoformats() {
+                        self.display_mut().autoformat();
+                    }
+                    self.display_mut().set_cursor_following(true);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// The menu label of the key bound to `action`, if any.
+    fn shortcut_label(&self, action: AppAction) -> Option<String> {
+        self.keymap.shortcut_label(Command::App(action))
+    }
+
+    /// Open the command palette, listing the menu bar's actions, then the
+    /// context menu's, with their shortcuts and whether they apply at the
+    /// cursor.
+    fn open_command_palette(&mut self) {
+        let has_selection = self.current_selection().is_some();
+        let mut entries = Vec::new();
+        for menu in MENU_BAR {
+            for entry in menu.entries {
+                let MenuBarEntry::Item(item) = entry else {
+                    continue;
+                };
+                let Some(action) = item.action else {
+                    continue;
+                };
+                if action == AppAction::CommandPalette {
+                    continue;
+                }
+                entries.push(PaletteEntry {
+                    label: format!("{}: {}", menu.title, item.label.trim_end_matches("...")),
+                    command: Command::App(action),
+                    shortcut: self.shortcut_label(action),
+                    enabled: self.app_action_enabled(action, has_selection),
+                });
+            }
+        }
+        for (label, action) in command_palette::MENU_ACTIONS {
+            let command = Command::Menu(*action);
+            entries.push(PaletteEntry {
+                label: label.to_string(),
+                command,
+                shortcut: self.keymap.shortcut_label(command),
+                enabled: self.menu_action_enabled(*action, has_selection),
+            });
+        }
+        #[cfg(debug_assertions)]
+        entries.push(PaletteEntry {
+            label: "Debug: Dump Document Tree".to_string(),
+            command: Command::App(AppAction::DumpDocumentTree),
+            shortcut: self.shortcut_label(AppAction::DumpDocumentTree),
+            enabled: true,
+        });
+        self.command_palette = Some(CommandPaletteState::new("Command Palette", entries));
+    }
+
+    fn app_action_enabled(&self, action: AppAction, has_selection: bool) -> bool {
+        match action {
+            AppAction::Undo
+            | AppAction::Redo
+            | AppAction::Cut
+            | AppAction::Paste
+            | AppAction::PasteAsPlainText
+            | AppAction::PasteFromSystem
+            | AppAction::PastePrevious
+            | AppAction::ClipboardHistory
+            | AppAction::PasteFromHistory(_)
+            | AppAction::Replace
+            | AppAction::InsertLineBreak
+            | AppAction::InsertSiblingParagraph
+            | AppAction::CutSection
+            | AppAction::MoveSectionUp
+            | AppAction::MoveSectionDown
+            | AppAction::PromoteSection
+            | AppAction::DemoteSection
+                if self.is_read_only() =>
+            {
+                false
+            }
+            AppAction::Save => !self.viewer && self.buffer().locked_by.is_none(),
+            AppAction::SaveAs => !self.viewer,
+            AppAction::OpenAsPlainText => {
+                matches!(self.buffer().protection, Some(Protection::ReadOnly(_)))
+            }
+            AppAction::Cut
+            | AppAction::Copy
+            | AppAction::CopyAsMarkdown
+            | AppAction::CopyAsHtml => has_selection,
+            AppAction::Paste => self.can_paste(),
+            AppAction::PasteAsPlainText => self.can_paste_as_plain_text(),
+            AppAction::PasteFromSystem => self.interactive && self.system_clipboard.readable(),
+            AppAction::PastePrevious | AppAction::ClipboardHistory => {
+                !self.clipboard_history.is_empty()
+            }
+            AppAction::OtherPane | AppAction::Unsplit => self.split.is_some(),
+            AppAction::SelectSection | AppAction::CutSection | AppAction::CopySection => {
+                self.display().current_section().is_some()
+            }
+            AppAction::MoveSectionUp => self.display().section_swap(true).is_some(),
+            AppAction::MoveSectionDown => self.display().section_swap(false).is_some(),
+            AppAction::PromoteSection => self.display().can_shift_current_section(-1),
+            AppAction::DemoteSection => self.display().can_shift_current_section(1),
+            AppAction::ToggleFold => self.display().can_toggle_fold(),
+            AppAction::UnfoldAll => self.display().has_folds(),
+            _ => true,
+        }
+    }
+
+    /// Whether `action` applies at the cursor, by the same rules that
+    /// enable the context menu's items.
+    fn menu_action_enabled(&self, action: MenuAction, has_selection: bool) -> bool {
+        match action {
+            _ if action != MenuAction::Copy && self.is_read_only() => false,
+            MenuAction::SetParagraphType(_) => self.display().can_change_paragraph_type(),
+            MenuAction::SetChecklistItemChecked(checked) => {
+                self.display().current_checklist_item_state() == Some(!checked)
+            }
+            MenuAction::ApplyInlineStyle(_) | MenuAction::Cut | MenuAction::Copy => has_selection,
+            MenuAction::EditLink => true,
+            MenuAction::IndentMore => self.display().can_indent_more(),
+            MenuAction::IndentLess => self.display().can_indent_less(),
+            MenuAction::InsertTableRow(_)
+            | MenuAction::InsertTableColumn(_)
+            | MenuAction::DeleteTableRow
+            | MenuAction::DeleteTableColumn => self.display().cursor_in_table(),
+            MenuAction::Paste => self.can_paste(),
+        }
+    }
+
+    /// Handle a key press while the command palette is open. The palette is
+    /// modal: every key is consumed.
+    fn handle_command_palette_key(
+        &mut self,
+        code: KeyCode,
+        modifiers: KeyModifiers,
+    ) -> Result<bool> {
+        let Some(palette) = self.command_palette.as_mut() else {
+            return Ok(false);
+        };
+        match code {
+            KeyCode::Esc => self.command_palette = None,
+            KeyCode::Up => palette.move_selection(-1),
+            KeyCode::Down => palette.move_selection(1),
+            KeyCode::PageUp => palette.move_selection(-(COMMAND_PALETTE_ROWS as isize)),
+            KeyCode::PageDown => palette.move_selection(COMMAND_PALETTE_ROWS as isize),
+            KeyCode::Enter => {
+                // Disabled entries stay highlighted without doing anything,
+                // like in the context menu.
+                if let Some(entry) = palette.selected_entry()
+                    && entry.enabled
+                {
+                    let command = entry.command;
+                    self.command_palette = None;
+                    self.run_command(command)?;
+                }
+            }
+            _ => {
+                let before = palette.query().text().to_string();
+                edit_text_field(palette.query_mut(), code, modifiers);
+                if palette.query().text() != before {
+                    palette.refresh();
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    fn status_line(&mut self, content_lines: usize, terminal_width: usize) -> Line<'static> {
+        self.prune_status_message();
+
+        // If there's a status message, show it prominently
+        if let Some((message, _)) = &self.status_message {
+            let position = self.cursor_position_text();
+            return Line::from(vec![
+                Span::raw(format!("{} ", position)),
+                Span::raw(message.clone()),
+            ]);
+        }
+
+        let position = self.cursor_position_text();
+        let filename = self.buffer().name();
+        let marker = if self.buffer().dirty { "*" } else { "" };
+        let breadcrumbs = self.breadcrumbs_text();
+        let word_count = self.count_words();
+
+        // Shortcuts ordered from least to most important (reversed order for display)
+        let mut all_shortcuts = vec!["F10:Menu".to_string()];
+        for (action, name) in [(AppAction::Save, "Save"), (AppAction::Quit, "Quit")] {
+            if let Some(label) = self.shortcut_label(action) {
+                all_shortcuts.push(format!("{label}:{name}"));
+            }
+        }
+
+        // Build the left part of the status line
+        let mut spans = Vec::new();
+
+        // Position
+        spans.push(Span::styled(position, Style::default().fg(Color::White)));
+        spans.push(Span::raw(" "));
+
+        // Filename
+        spans.push(Span::styled(
             format!("{}{}", filename, marker),
-            self.display.theme().filename_style(),
+            self.display().theme().filename_style(),
         ));
+        if self.is_read_only() {
+            spans.push(Span::raw(" [read-only]"));
+        }
+        if self.buffer_count() > 1 {
+            spans.push(Span::raw(format!(
+                " [{}/{}]",
+                self.active_buffer + 1,
+                self.buffer_count()
+            )));
+        }
 
         // Breadcrumbs
         if !breadcrumbs.is_empty() {
@@ -1889,7 +3595,7 @@ impl App {
 
             // Check if this shortcut would fit with at least min_padding
             if left_width + min_padding + test_width <= terminal_width {
-                shortcuts_to_show.insert(0, *shortcut); // Insert at beginning to maintain order
+                shortcuts_to_show.insert(0, shortcut.as_str()); // Insert at beginning to maintain order
                 shortcuts_width = test_width;
             } else {
                 break; // If this doesn't fit, neither will less important ones
@@ -1923,7 +3629,7 @@ impl App {
 
     fn prune_status_message(&mut self) {
         if let Some((_, instant)) = &self.status_message
-            && instant.elapsed() > STATUS_TIMEOUT
+            && instant.elapsed() > self.status_timeout
         {
             self.status_message = None;
         }
@@ -1932,16 +3638,17 @@ impl App {
     fn adjust_scroll(&mut self, total_lines: usize, viewport_height: usize) {
         let viewport = viewport_height.max(1);
         let max_scroll = total_lines.saturating_sub(viewport).min(total_lines);
-        if self.scroll_top > max_scroll {
-            self.scroll_top = max_scroll;
+        if self.buffer().scroll_top > max_scroll {
+            self.buffer_mut().scroll_top = max_scroll;
         }
-        if self.display.cursor_following() {
+        if self.display().cursor_following() {
             // Use cursor_visual() to get the visual cursor position from the cached layout
-            if let Some(cursor) = self.display.cursor_visual() {
-                self.scroll_top = self.scroll_top_for_cursor(cursor.line, viewport, max_scroll);
+            if let Some(cursor) = self.display().cursor_visual() {
+                self.buffer_mut().scroll_top =
+                    self.scroll_top_for_cursor(cursor.line, viewport, max_scroll);
             }
-            if self.scroll_top > max_scroll {
-                self.scroll_top = max_scroll;
+            if self.buffer().scroll_top > max_scroll {
+                self.buffer_mut().scroll_top = max_scroll;
             }
         }
     }
@@ -1961,7 +3668,7 @@ impl App {
         let knob_start = if max_scroll == 0 || knob_travel == 0 {
             0
         } else {
-            (self.scroll_top * knob_travel) / max_scroll
+            (self.buffer().scroll_top * knob_travel) / max_scroll
         };
 
         Some(ScrollbarGeometry {
@@ -1980,7 +3687,7 @@ impl App {
 
         let knob_travel = self.last_viewport_height.saturating_sub(knob_size);
         if knob_travel == 0 {
-            return self.scroll_top.min(max_scroll);
+            return self.buffer().scroll_top.min(max_scroll);
         }
 
         let clamped_start = knob_start.min(knob_travel);
@@ -2007,7 +3714,7 @@ impl App {
         };
         anchor = anchor.min(knob_size.saturating_sub(1));
 
-        let mut new_scroll = self.scroll_top;
+        let mut new_scroll = self.buffer().scroll_top;
         if pointer_row < knob_start || pointer_row >= knob_end {
             let desired_anchor = knob_size / 2;
             anchor = desired_anchor.min(knob_size.saturating_sub(1));
@@ -2019,12 +3726,12 @@ impl App {
             anchor_within_knob: anchor,
         }));
 
-        let previous = self.scroll_top;
+        let previous = self.buffer().scroll_top;
         let max_scroll = self
             .last_total_lines
             .saturating_sub(self.last_viewport_height);
-        self.scroll_top = new_scroll.min(max_scroll);
-        previous != self.scroll_top
+        self.buffer_mut().scroll_top = new_scroll.min(max_scroll);
+        previous != self.buffer().scroll_top
     }
 
     fn update_scrollbar_drag(&mut self, pointer_row: usize) -> bool {
@@ -2048,8 +3755,8 @@ impl App {
             .scroll_offset_from_knob_start(target_start, knob_size)
             .min(max_scroll);
 
-        if new_scroll != self.scroll_top {
-            self.scroll_top = new_scroll;
+        if new_scroll != self.buffer().scroll_top {
+            self.buffer_mut().scroll_top = new_scroll;
             true
         } else {
             false
@@ -2070,7 +3777,7 @@ impl App {
         viewport: usize,
         max_scroll: usize,
     ) -> usize {
-        let mut scroll = self.scroll_top.min(max_scroll);
+        let mut scroll = self.buffer().scroll_top.min(max_scroll);
         if viewport == 0 {
             return scroll;
         }
@@ -2097,8 +3804,14 @@ impl App {
         scroll.min(max_scroll)
     }
 
+    /// Whether Markdown typed into the document turns into formatting. Plain
+    /// text keeps no formatting, so there it stays as typed.
+    fn autoformats(&self) -> bool {
+        self.autoformat && self.buffer().document_format != DocumentFormat::PlainText
+    }
+
     fn insert_paragraph_break(&mut self) -> bool {
-        self.display.insert_paragraph_break()
+        self.display_mut().insert_paragraph_break()
     }
 
     fn register_click(&mut self, button: MouseButton, column: u16, row: u16) -> u8 {
@@ -2129,25 +3842,25 @@ impl App {
         if delta == 0 {
             return;
         }
-        self.display.set_cursor_following(false);
-        let viewport = self.display.last_view_height().max(1);
+        self.display_mut().set_cursor_following(false);
+        let viewport = self.display().last_view_height().max(1);
         let max_scroll = self
-            .display
+            .display()
             .last_total_lines()
             .saturating_sub(viewport)
-            .min(self.display.last_total_lines());
+            .min(self.display().last_total_lines());
         let max_scroll = max_scroll as isize;
-        let mut new_scroll = self.scroll_top as isize + delta;
+        let mut new_scroll = self.buffer().scroll_top as isize + delta;
         if new_scroll < 0 {
             new_scroll = 0;
         } else if new_scroll > max_scroll {
             new_scroll = max_scroll;
         }
-        self.scroll_top = new_scroll.max(0) as usize;
+        self.buffer_mut().scroll_top = new_scroll.max(0) as usize;
     }
 
     fn detach_cursor_follow(&mut self) {
-        self.display.detach_cursor_follow();
+        self.display_mut().detach_cursor_follow();
     }
 
     fn handle_mouse_event(&mut self, event: MouseEvent) {
@@ -2165,12 +3878,52 @@ impl App {
             return;
         }
 
+        if let Some(outline) = &mut self.outline
+            && outline
+                .area()
+                .contains(Position::new(event.column, event.row))
+        {
+            // A click on a heading moves the cursor there.
+            let lines = self.mouse_scroll_lines as isize;
+            match event.kind {
+                MouseEventKind::ScrollUp => outline.scroll_by(-lines),
+                MouseEventKind::ScrollDown => outline.scroll_by(lines),
+                MouseEventKind::Down(MouseButton::Left) => {
+                    if let Some(index) = outline.entry_at_row(event.row) {
+                        outline.set_focused(false);
+                        self.go_to_outline_entry(index);
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        if let Some(split) = &mut self.split
+            && split
+                .other_area
+                .contains(Position::new(event.column, event.row))
+        {
+            // The other pane scrolls on its own; a click moves the focus
+            // there.
+            let lines = self.mouse_scroll_lines;
+            match event.kind {
+                MouseEventKind::ScrollUp => {
+                    split.other.scroll_top = split.other.scroll_top.saturating_sub(lines);
+                }
+                MouseEventKind::ScrollDown => split.other.scroll_top += lines,
+                MouseEventKind::Down(MouseButton::Left) => self.focus_other_pane(),
+                _ => {}
+            }
+            return;
+        }
+
         match event.kind {
             MouseEventKind::ScrollUp => {
-                self.scroll_by_lines(-(MOUSE_SCROLL_LINES as isize));
+                self.scroll_by_lines(-(self.mouse_scroll_lines as isize));
             }
             MouseEventKind::ScrollDown => {
-                self.scroll_by_lines(MOUSE_SCROLL_LINES as isize);
+                self.scroll_by_lines(self.mouse_scroll_lines as isize);
             }
             MouseEventKind::Down(MouseButton::Left) => self.handle_mouse_down(event),
             MouseEventKind::Drag(MouseButton::Left) => self.handle_mouse_drag(event),
@@ -2185,23 +3938,24 @@ impl App {
         // ensure_paragraph_positions() which only computes positions for clicked paragraphs.
 
         // Check if click is on scrollbar (rightmost column)
-        if event.column == self.last_scrollbar_column
-            && event.row < self.last_viewport_height as u16
+        if let Some(row) = self.scrollbar_row(event.row)
+            && event.column == self.last_scrollbar_column
         {
             // Clicked on scrollbar
-            self.display.set_cursor_following(false);
-            if self.begin_scrollbar_drag(event.row as usize) {
+            self.display_mut().set_cursor_following(false);
+            if self.begin_scrollbar_drag(row) {
                 // Scroll position changed, no need to redraw as the main loop will handle it
             }
             return;
         }
 
+        let scroll_top = self.buffer().scroll_top;
         let Some(display) =
-            self.display
-                .pointer_from_mouse(event.column, event.row, self.scroll_top)
+            self.display_mut()
+                .pointer_from_mouse(event.column, event.row, scroll_top)
         else {
             if !event.modifiers.contains(KeyModifiers::SHIFT) {
-                self.selection_anchor = None;
+                self.buffer_mut().selection_anchor = None;
             }
             self.mouse_drag_anchor = None;
             return;
@@ -2222,46 +3976,54 @@ impl App {
     // the Edit Link dialog instead.
     fn handle_single_click(&mut self, display: CursorDisplay, modifiers: KeyModifiers) {
         if modifiers.contains(KeyModifiers::SHIFT) {
-            if self.selection_anchor.is_none() {
-                self.selection_anchor = Some(self.display.cursor_pointer());
+            if self.buffer().selection_anchor.is_none() {
+                self.buffer_mut().selection_anchor = Some(self.display().cursor_pointer());
             }
             self.mouse_drag_anchor = None;
         } else {
-            self.selection_anchor = None;
+            self.buffer_mut().selection_anchor = None;
             self.mouse_drag_anchor = Some(display.pointer.clone());
         }
-        self.display.focus_display(&display);
+        self.display_mut().focus_display(&display);
     }
 
     fn handle_double_click(&mut self, display: CursorDisplay) {
         self.mouse_drag_anchor = None;
-        if let Some((start, end)) = self.display.word_boundaries_at(&display.pointer) {
-            self.selection_anchor = Some(start.clone());
-            self.display.focus_pointer(&end);
+        if let Some((start, end)) = self.display().word_boundaries_at(&display.pointer) {
+            self.buffer_mut().selection_anchor = Some(start.clone());
+            self.display_mut().focus_pointer(&end);
         } else {
-            self.selection_anchor = None;
-            self.display.focus_display(&display);
+            self.buffer_mut().selection_anchor = None;
+            self.display_mut().focus_display(&display);
         }
     }
 
     fn handle_triple_click(&mut self, display: CursorDisplay) {
         self.mouse_drag_anchor = None;
-        if let Some((line_start, line_end)) =
-            self.display.visual_line_boundaries(display.position.line)
+        if let Some((line_start, line_end)) = self
+            .display_mut()
+            .visual_line_boundaries(display.position.line)
         {
-            self.selection_anchor = Some(line_start.pointer.clone());
-            self.display.focus_display(&line_end);
+            self.buffer_mut().selection_anchor = Some(line_start.pointer.clone());
+            self.display_mut().focus_display(&line_end);
         } else {
-            self.selection_anchor = None;
-            self.display.focus_display(&display);
+            self.buffer_mut().selection_anchor = None;
+            self.display_mut().focus_display(&display);
         }
     }
 
+    /// The row within the scrollbar at screen row `row`, if it is beside
+    /// the text.
+    fn scrollbar_row(&self, row: u16) -> Option<usize> {
+        let row = row.checked_sub(self.last_scrollbar_row)? as usize;
+        (row < self.last_viewport_height).then_some(row)
+    }
+
     fn handle_mouse_drag(&mut self, event: MouseEvent) {
         // Handle scrollbar dragging
         if self.is_dragging_scrollbar() {
-            if event.row < self.last_viewport_height as u16 {
-                self.update_scrollbar_drag(event.row as usize);
+            if let Some(row) = self.scrollbar_row(event.row) {
+                self.update_scrollbar_drag(row);
             }
             return;
         }
@@ -2269,16 +4031,17 @@ impl App {
         let Some(anchor) = self.mouse_drag_anchor.clone() else {
             return;
         };
+        let scroll_top = self.buffer().scroll_top;
         let Some(display) =
-            self.display
-                .pointer_from_mouse(event.column, event.row, self.scroll_top)
+            self.display_mut()
+                .pointer_from_mouse(event.column, event.row, scroll_top)
         else {
             return;
         };
-        if self.selection_anchor.is_none() {
-            self.selection_anchor = Some(anchor);
+        if self.buffer().selection_anchor.is_none() {
+            self.buffer_mut().selection_anchor = Some(anchor);
         }
-        self.display.focus_display(&display);
+        self.display_mut().focus_display(&display);
     }
 
     fn handle_mouse_up(&mut self, button: MouseButton) {
@@ -2290,12 +4053,35 @@ impl App {
 
     pub fn handle_event(&mut self, event: Event) -> Result<()> {
         match event {
+            // Terminals speaking the kitty keyboard protocol also report held
+            // keys as repeats, and letting go of them; releases change
+            // nothing.
             Event::Key(KeyEvent {
                 code,
                 modifiers,
-                kind: KeyEventKind::Press,
+                kind: kind @ (KeyEventKind::Press | KeyEventKind::Repeat),
                 ..
             }) => {
+                if self.handle_lock_prompt_key(code, modifiers) {
+                    return Ok(());
+                }
+
+                if self.handle_recovery_prompt_key(code, modifiers) {
+                    return Ok(());
+                }
+
+                if self.handle_quit_prompt_key(code, modifiers) {
+                    return Ok(());
+                }
+
+                if self.handle_overwrite_prompt_key(code, modifiers) {
+                    return Ok(());
+                }
+
+                if self.handle_external_change_prompt_key(code, modifiers) {
+                    return Ok(());
+                }
+
                 if self.handle_file_dialog_key(code, modifiers) {
                     return Ok(());
                 }
@@ -2304,6 +4090,10 @@ impl App {
                     return Ok(());
                 }
 
+                if self.handle_command_palette_key(code, modifiers)? {
+                    return Ok(());
+                }
+
                 if self.handle_menu_bar_key(code, modifiers)? {
                     return Ok(());
                 }
@@ -2316,620 +4106,2472 @@ impl App {
                     return Ok(());
                 }
 
-                if is_context_menu_shortcut(code, modifiers) {
-                    if self.context_menu.is_some() {
-                        self.close_context_menu();
-                    } else {
-                        self.open_context_menu();
-                    }
+                if self.handle_outline_key(code, modifiers) {
                     return Ok(());
                 }
 
-                let previous_cursor = self.display.cursor_pointer();
+                if self.handle_find_bar_key(code, modifiers) {
+                    return Ok(());
+                }
 
-                match (code, modifiers) {
-                    (KeyCode::Char('q'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        self.should_quit = true;
-                    }
-                    (KeyCode::Char('s'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        self.save()?;
-                    }
-                    (KeyCode::Char('o'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        self.open_file_dialog(FileDialogKind::Open);
-                    }
-                    (KeyCode::Char('k'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        self.open_link_dialog();
-                    }
-                    (KeyCode::Char('n'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        self.new_document();
-                    }
-                    (KeyCode::F(9), _) => {
-                        self.toggle_reveal_codes();
-                    }
-                    #[cfg(debug_assertions)]
-                    (KeyCode::F(12), _) => {
-                        self.dump_document_tree();
-                    }
-                    (KeyCode::Char('c'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        // Copies the selection; without one the key is
-                        // ignored (quitting is Ctrl+Q).
-                        if self.current_selection().is_some() {
-                            self.copy_selection();
-                        }
-                    }
-                    (KeyCode::Char('x'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        self.cut_selection();
-                    }
-                    (KeyCode::Char('v'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        self.paste_from_clipboard();
-                    }
-                    (KeyCode::Char('z'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        self.undo();
-                    }
-                    (KeyCode::Char('y'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        self.redo();
-                    }
-                    (KeyCode::Char(']'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        self.indent_selection_or_cursor();
-                    }
-                    (KeyCode::Char('['), m) if m.contains(KeyModifiers::CONTROL) => {
-                        self.unindent_selection_or_cursor();
-                    }
-                    (KeyCode::Left, m)
-                        if m.contains(KeyModifiers::SHIFT | KeyModifiers::CONTROL) =>
-                    {
-                        self.prepare_selection(true);
-                        if self.display.move_word_left() {
-                            self.display.set_preferred_column(None);
-                        }
-                    }
-                    (KeyCode::Right, m)
-                        if m.contains(KeyModifiers::SHIFT | KeyModifiers::CONTROL) =>
-                    {
-                        self.prepare_selection(true);
-                        if self.display.move_word_right() {
-                            self.display.set_preferred_column(None);
-                        }
-                    }
-                    (KeyCode::Left, m) if m.contains(KeyModifiers::SHIFT) => {
-                        self.prepare_selection(true);
-                        if self.display.move_left() {
-                            self.display.set_preferred_column(None);
-                        }
-                    }
-                    (KeyCode::Right, m) if m.contains(KeyModifiers::SHIFT) => {
-                        self.prepare_selection(true);
-                        if self.display.move_right() {
-                            self.display.set_preferred_column(None);
-                        }
-                    }
-                    (KeyCode::Left, m) if m.contains(KeyModifiers::CONTROL) => {
-                        self.prepare_selection(false);
-                        if self.display.move_word_left() {
-                            self.display.set_preferred_column(None);
-                        }
-                    }
-                    (KeyCode::Right, m) if m.contains(KeyModifiers::CONTROL) => {
-                        self.prepare_selection(false);
-                        if self.display.move_word_right() {
-                            self.display.set_preferred_column(None);
-                        }
-                    }
-                    (KeyCode::Left, _) => {
-                        self.prepare_selection(false);
-                        if self.display.move_left() {
-                            self.display.set_preferred_column(None);
-                        }
-                    }
-                    (KeyCode::Right, _) => {
-                        self.prepare_selection(false);
-                        if self.display.move_right() {
-                            self.display.set_preferred_column(None);
-                        }
-                    }
-                    (KeyCode::Home, m) if m.contains(KeyModifiers::SHIFT) => {
-                        self.prepare_selection(true);
-                        self.display.move_to_visual_line_start();
-                    }
-                    (KeyCode::End, m) if m.contains(KeyModifiers::SHIFT) => {
-                        self.prepare_selection(true);
-                        self.display.move_to_visual_line_end();
-                    }
-                    (KeyCode::Home, _) => {
-                        self.prepare_selection(false);
-                        self.display.move_to_visual_line_start();
-                    }
-                    (KeyCode::End, _) => {
-                        self.prepare_selection(false);
-                        self.display.move_to_visual_line_end();
-                    }
-                    (KeyCode::Char('a'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        self.prepare_selection(false);
-                        self.display.move_to_visual_line_start();
-                    }
-                    (KeyCode::Char('j'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        self.insert_char_with_selection('\n');
+                self.handle_editing_key(code, modifiers, kind == KeyEventKind::Repeat)?;
+            }
+            Event::Mouse(mouse_event) => {
+                if self.file_dialog.is_some()
+                    || self.link_dialog.is_some()
+                    || self.command_palette.is_some()
+                    || self.quit_prompt.is_some()
+                    || self.overwrite_prompt.is_some()
+                    || self.external_change_prompt.is_some()
+                    || self.recovery_prompt.is_some()
+                    || self.lock_prompt.is_some()
+                {
+                    return Ok(());
+                }
+                self.handle_mouse_event(mouse_event);
+            }
+            Event::Paste(text)
+                if self.context_menu.is_none()
+                    && self.menu_bar.is_none()
+                    && self.quit_prompt.is_none()
+                    && self.overwrite_prompt.is_none()
+                    && self.external_change_prompt.is_none()
+                    && self.recovery_prompt.is_none()
+                    && self.lock_prompt.is_none() =>
+            {
+                if let Some(dialog) = self.file_dialog.as_mut() {
+                    dialog.insert_str(&text);
+                } else if let Some(dialog) = self.link_dialog.as_mut() {
+                    dialog.insert_str(&text);
+                } else if let Some(palette) = self.command_palette.as_mut() {
+                    palette.query_mut().insert_str(&text);
+                    palette.refresh();
+                } else if let Some(bar) = self.find_bar.as_mut() {
+                    bar.focused_field_mut().insert_str(&text);
+                    if bar.focus() == FindField::Query {
+                        self.refresh_search();
                     }
-                    (KeyCode::Char('p'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        self.prepare_selection(false);
-                        if self.display.insert_paragraph_break_as_sibling() {
-                            self.mark_dirty();
-                            self.display.set_preferred_column(None);
-                        }
+                } else if !self.refuse_edit() {
+                    if self.paste_text(&text) {
+                        self.start_paste_run_of(&text);
                     }
-                    (KeyCode::Char('e'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        self.prepare_selection(false);
-                        self.display.move_to_visual_line_end();
+                    self.terminal_paste = Some(text);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Do what is due without the user: drop an expired status message,
+    /// autosave, look for changes on disk and retry locks. Returns whether
+    /// any of it shows, so the screen needs drawing again.
+    pub fn on_tick(&mut self) -> bool {
+        let status_message = self.status_message.clone();
+        let dialog_open = self.dialog_open();
+        self.prune_status_message();
+        if self
+            .buffer()
+            .autosave_due
+            .is_some_and(|since| since.elapsed() >= recovery::AUTOSAVE_INTERVAL)
+        {
+            self.write_recovery();
+        }
+        let active = self.active_buffer;
+        for (_, buffer) in self
+            .buffers
+            .iter_mut()
+            .enumerate()
+            .filter(|(index, _)| *index != active)
+        {
+            if buffer
+                .autosave_due
+                .is_some_and(|since| since.elapsed() >= recovery::AUTOSAVE_INTERVAL)
+            {
+                match recovery::write(buffer.recovery_origin(), buffer.display.document()) {
+                    Ok(_) => buffer.autosave_due = None,
+                    Err(err) => {
+                        let message = format!("Autosave of {} failed: {err}", buffer.name());
+                        self.status_message = Some((message, Instant::now()));
+                        buffer.autosave_due = Some(Instant::now());
                     }
-                    (KeyCode::Char('w'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        if self.display.delete_word_backward() {
-                            self.mark_dirty();
-                            self.display.set_preferred_column(None);
-                        }
-                    }
-                    (KeyCode::Backspace, m)
-                        if m.contains(KeyModifiers::CONTROL) || m.contains(KeyModifiers::ALT) =>
-                    {
-                        if !self.delete_selection() && self.display.delete_word_backward() {
-                            self.mark_dirty();
-                            self.display.set_preferred_column(None);
-                        }
-                    }
-                    (KeyCode::Backspace, _) => {
-                        if !self.delete_selection() && self.display.backspace() {
-                            self.mark_dirty();
-                            self.display.set_preferred_column(None);
-                        }
-                    }
-                    (KeyCode::Delete, m)
-                        if m.contains(KeyModifiers::CONTROL) || m.contains(KeyModifiers::ALT) =>
-                    {
-                        if !self.delete_selection() && self.display.delete_word_forward() {
-                            self.mark_dirty();
-                            self.display.set_preferred_column(None);
-                        }
-                    }
-                    (KeyCode::Delete, _) => {
-                        if !self.delete_selection() && self.display.delete() {
-                            self.mark_dirty();
-                            self.display.set_preferred_column(None);
-                        }
-                    }
-                    (KeyCode::Enter, m) => {
-                        if m.contains(KeyModifiers::SHIFT) || m.contains(KeyModifiers::CONTROL) {
-                            self.insert_char_with_selection('\n');
-                        } else if self.insert_paragraph_break() {
-                            self.mark_dirty();
-                            self.display.set_preferred_column(None);
-                        }
-                    }
-                    (KeyCode::Tab, _) => {
-                        self.insert_char_with_selection('\t');
-                    }
-                    (KeyCode::Char(ch), m)
-                        if !m.contains(KeyModifiers::CONTROL) && !m.contains(KeyModifiers::ALT) =>
-                    {
-                        self.insert_char_with_selection(ch);
-                    }
-                    (KeyCode::Up, m) if m.contains(KeyModifiers::SHIFT) => {
-                        self.prepare_selection(true);
-                        self.display.move_cursor_vertical(-1);
-                    }
-                    (KeyCode::Up, m) if m.contains(KeyModifiers::CONTROL) => {
-                        self.prepare_selection(false);
-                        self.scroll_top = self
-                            .scroll_top
-                            .saturating_sub(self.display.last_view_height());
-                        self.detach_cursor_follow();
-                    }
-                    (KeyCode::Up, _) => {
-                        self.prepare_selection(false);
-                        self.display.move_cursor_vertical(-1);
-                    }
-                    (KeyCode::Down, m) if m.contains(KeyModifiers::SHIFT) => {
-                        self.prepare_selection(true);
-                        self.display.move_cursor_vertical(1);
-                    }
-                    (KeyCode::Down, m) if m.contains(KeyModifiers::CONTROL) => {
-                        self.prepare_selection(false);
-                        self.scroll_top += self.display.last_view_height();
-                        self.detach_cursor_follow();
-                    }
-                    (KeyCode::Down, _) => {
-                        self.prepare_selection(false);
-                        self.display.move_cursor_vertical(1);
-                    }
-                    (KeyCode::PageUp, modifiers) => {
-                        let extend_selection = modifiers.contains(KeyModifiers::SHIFT);
-                        self.prepare_selection(extend_selection);
-                        self.display.move_page(-1);
-                    }
-                    (KeyCode::PageDown, modifiers) => {
-                        let extend_selection = modifiers.contains(KeyModifiers::SHIFT);
-                        self.prepare_selection(extend_selection);
-                        self.display.move_page(1);
-                    }
-                    _ => {}
                 }
+            }
+        }
+        self.check_disk();
+        self.retry_lock();
+        self.status_message != status_message || self.dialog_open() != dialog_open
+    }
 
-                if self.display.cursor_pointer() != previous_cursor {
-                    self.display.set_cursor_following(true);
-                }
+    /// Once the other Pure is done with a document opened read-only because
+    /// of it, lock the document and make it editable.
+    fn retry_lock(&mut self) {
+        if self.buffer().locked_by.is_none() || self.dialog_open() {
+            return;
+        }
+        let (lock, locked_by) = lock_document(self.buffer().file_path.as_deref());
+        if lock.is_some() {
+            let name = self.buffer().name();
+            self.status_message = Some((
+                format!("{name} is no longer open elsewhere and can be edited"),
+                Instant::now(),
+            ));
+        }
+        if lock.is_some() || locked_by.is_some() {
+            self.buffer_mut().lock = lock;
+            self.buffer_mut().locked_by = locked_by;
+        }
+    }
+
+    /// Write the document to its recovery file. A failure is reported once
+    /// in the status line and retried after the next interval.
+    fn write_recovery(&mut self) {
+        match recovery::write(self.recovery_origin(), self.display().document()) {
+            Ok(_) => self.buffer_mut().autosave_due = None,
+            Err(err) => {
+                self.status_message = Some((format!("Autosave failed: {err}"), Instant::now()));
+                self.buffer_mut().autosave_due = Some(Instant::now());
             }
-            Event::Mouse(mouse_event) => {
-                if self.file_dialog.is_some() || self.link_dialog.is_some() {
-                    return Ok(());
-                }
-                self.handle_mouse_event(mouse_event);
+        }
+    }
+
+    /// Look whether another program changed the current document's file
+    /// since it was read or saved. A document without unsaved changes is
+    /// simply read again; otherwise the user is asked. Nothing is checked
+    /// while a dialog waits for the user.
+    fn check_disk(&mut self) {
+        if self.dialog_open() {
+            return;
+        }
+        let (Some(path), Some(stamp)) = (&self.buffer().file_path, &self.buffer().file_stamp)
+        else {
+            return;
+        };
+        match stamp.check(path) {
+            DiskState::Unchanged => {}
+            DiskState::Touched(stamp) => self.buffer_mut().file_stamp = Some(stamp),
+            DiskState::Removed => {
+                let message = format!(
+                    "{} was removed from disk; saving writes it again",
+                    path.display()
+                );
+                self.buffer_mut().file_stamp = None;
+                self.status_message = Some((message, Instant::now()));
             }
-            Event::Paste(text) if self.context_menu.is_none() && self.menu_bar.is_none() => {
-                if let Some(dialog) = self.file_dialog.as_mut() {
-                    dialog.insert_str(&text);
-                } else if let Some(dialog) = self.link_dialog.as_mut() {
-                    dialog.insert_str(&text);
-                } else {
-                    self.paste_text(&text);
-                }
+            DiskState::Changed(stamp) if self.buffer().dirty => {
+                self.external_change_prompt = Some(ChoicePrompt::new(
+                    &external_change::CHOICES,
+                    ExternalChangeChoice::KeepMine,
+                    ExternalChange::new(stamp, false),
+                ));
             }
-            _ => {}
+            DiskState::Changed(_) => match self.reload() {
+                Ok(()) => {
+                    let name = self.buffer().name();
+                    let message = format!("Reloaded {name}, which changed on disk");
+                    self.status_message = Some((message, Instant::now()));
+                }
+                Err(err) => self.status_message = Some((format!("{err:#}"), Instant::now())),
+            },
         }
-        Ok(())
     }
 
-    pub fn on_tick(&mut self) {
-        self.prune_status_message();
+    /// The file's new stamp, if another program changed it since it was
+    /// read or saved. A file that is gone can simply be written again.
+    fn changed_on_disk(&mut self) -> Option<FileStamp> {
+        let (Some(path), Some(stamp)) = (&self.buffer().file_path, &self.buffer().file_stamp)
+        else {
+            return None;
+        };
+        match stamp.check(path) {
+            DiskState::Unchanged => None,
+            DiskState::Touched(stamp) => {
+                self.buffer_mut().file_stamp = Some(stamp);
+                None
+            }
+            DiskState::Changed(stamp) => Some(stamp),
+            DiskState::Removed => {
+                self.buffer_mut().file_stamp = None;
+                None
+            }
+        }
     }
 
-    fn save(&mut self) -> Result<()> {
-        // An untitled document needs a name first; saving continues from
-        // the Save As dialog.
-        let Some(path) = &self.file_path else {
-            self.open_file_dialog(FileDialogKind::SaveAs);
+    /// Read the current document's file again, dropping the unsaved changes
+    /// and the undo history. The cursor stays about where it was, as
+    /// counted in characters from the start of the document.
+    fn reload(&mut self) -> Result<()> {
+        let Some(path) = self.buffer().file_path.clone() else {
             return Ok(());
         };
+        let commit = self.buffer().commit_message.is_some();
+        let loaded = if commit {
+            commit_message::load(&path)?
+        } else {
+            load_document_as(&path, Some(self.buffer().document_format))?
+        };
+        let offset = self.display().cursor_global_char_offset();
+        let scroll_top = self.buffer().scroll_top;
+        self.replace_document(loaded.document, Some(path), loaded.format);
+        if commit {
+            self.edit_commit_message();
+        }
+        if let Some(pointer) = self.display().pointer_at_global_char_offset(offset) {
+            self.display_mut().focus_pointer(&pointer);
+        }
+        self.buffer_mut().scroll_top = scroll_top;
+        if let Some(failure) = loaded.parse_failure {
+            self.protect(failure);
+        }
+        Ok(())
+    }
 
-        match self.document_format {
-            DocumentFormat::Ftml => {
-                let writer = Writer::new();
-                let contents = writer
-                    .write_to_string(self.display.document())
-                    .context("failed to render FTML")?;
-                fs::write(path, contents)
-                    .with_context(|| format!("failed to write {}", path.display()))?;
-            }
-            DocumentFormat::Markdown => {
-                let mut contents = Vec::new();
-                markdown::write(&mut contents, self.display.document())
-                    .context("failed to render Markdown")?;
-                fs::write(path, contents)
-                    .with_context(|| format!("failed to write {}", path.display()))?;
-            }
-            DocumentFormat::Html => {
-                // A complete, standalone HTML page (doctype, head, embedded
-                // stylesheet) so the saved file opens directly in any browser.
-                let mut contents = Vec::new();
-                html::write_document(&mut contents, self.display.document())
-                    .context("failed to render HTML")?;
-                fs::write(path, contents)
-                    .with_context(|| format!("failed to write {}", path.display()))?;
-            }
-            DocumentFormat::Gemini => {
-                let mut contents = Vec::new();
-                gemini::write(&mut contents, self.display.document())
-                    .context("failed to render Gemini")?;
-                fs::write(path, contents)
-                    .with_context(|| format!("failed to write {}", path.display()))?;
+    /// Whether a prompt, dialog or menu waits for the user.
+    fn dialog_open(&self) -> bool {
+        self.recovery_prompt.is_some()
+            || self.lock_prompt.is_some()
+            || self.quit_prompt.is_some()
+            || self.overwrite_prompt.is_some()
+            || self.external_change_prompt.is_some()
+            || self.file_dialog.is_some()
+            || self.link_dialog.is_some()
+            || self.command_palette.is_some()
+            || self.menu_bar.is_some()
+            || self.context_menu.is_some()
+    }
+
+    /// Offer to restore the unsaved changes in `recovery`, found when the
+    /// current document was opened.
+    pub fn offer_recovery(&mut self, recovery: PathBuf) {
+        // Restoring would edit the document; the recovery file is kept for
+        // a later session. A document open elsewhere has its recovery file
+        // written by that session.
+        if self.viewer || self.lock_prompt.is_some() || self.buffer().locked_by.is_some() {
+            return;
+        }
+        self.recovery_prompt = Some(ChoicePrompt::new(
+            &recovery::CHOICES,
+            RecoveryChoice::Restore,
+            recovery,
+        ));
+    }
+
+    /// Keep the current document — the raw text of a file that failed to
+    /// parse, as [`load_document`] gives it — read-only until it is opened
+    /// as plain text, and ask before saving over the file. The cursor starts
+    /// on the line the parser complained about.
+    pub fn protect(&mut self, failure: ParseFailure) {
+        focus_failure_line(self.display_mut(), &failure);
+        self.buffer_mut().protection = Some(Protection::ReadOnly(failure));
+    }
+
+    /// Tell whether the terminal speaks the kitty keyboard protocol. Without
+    /// it, bindings of keys the terminal sends like others are neither
+    /// bound nor shown in menus.
+    pub fn set_enhanced_keyboard(&mut self, enhanced: bool) {
+        self.enhanced_keyboard = enhanced;
+        self.keymap.set_enhanced_keyboard(enhanced);
+    }
+
+    /// Let go of the locks on the open documents, leaving them editable.
+    pub fn release_locks(&mut self) {
+        for buffer in &mut self.buffers {
+            buffer.lock = None;
+        }
+    }
+
+    /// Make every document read-only, for viewing files without the risk
+    /// of changing them. Saving is refused, too.
+    /// Documents are not locked then: they are not going to change.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.viewer = read_only;
+        if read_only {
+            self.lock_prompt = None;
+            for buffer in &mut self.buffers {
+                buffer.lock = None;
+                buffer.locked_by = None;
+                buffer.pending_lock = None;
             }
         }
+    }
 
-        self.dirty = false;
-        self.status_message = Some(("Saved".to_string(), Instant::now()));
-        Ok(())
+    fn is_read_only(&self) -> bool {
+        self.viewer
+            || self.buffer().locked_by.is_some()
+            || matches!(self.buffer().protection, Some(Protection::ReadOnly(_)))
     }
 
-    fn open_file_dialog(&mut self, kind: FileDialogKind) {
-        let initial_input = match kind {
-            // Start in the current file's directory so its siblings are
-            // listed right away.
-            FileDialogKind::Open => match self.file_path.as_ref().and_then(|path| path.parent()) {
-                Some(parent) if !parent.as_os_str().is_empty() => {
-                    let parent = parent.display().to_string();
-                    if parent.ends_with('/') {
-                        parent
-                    } else {
-                        format!("{parent}/")
-                    }
-                }
-                _ => String::new(),
-            },
-            // Suggest the current path so saving under a sibling name only
-            // needs the file name edited.
-            FileDialogKind::SaveAs => self
-                .file_path
-                .as_ref()
-                .map(|path| path.display().to_string())
-                .unwrap_or_default(),
+    /// Whether the current document cannot be edited — or not where the
+    /// cursor or the selection is; if so, the status line says why.
+    fn refuse_edit(&mut self) -> bool {
+        let message = if self.viewer {
+            "Read-only: Pure was started with --readonly".to_string()
+        } else if let Some(owner) = &self.buffer().locked_by {
+            format!("Read-only: {owner} is editing this file")
+        } else if self.is_read_only() {
+            "Read-only: could not parse the file. Try File > Open as Plain Text".to_string()
+        } else if self.edit_touches_comments() {
+            COMMENTS_READ_ONLY.to_string()
+        } else {
+            return false;
         };
-        self.file_dialog = Some(FileDialogState::new(kind, initial_input));
+        self.status_message = Some((message, Instant::now()));
+        true
     }
 
-    /// Handle a key press while the file dialog is open. The dialog is
-    /// modal: every key is consumed.
-    fn handle_file_dialog_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
-        if self.file_dialog.is_none() {
+    /// Take the current document, which has no file, to be the one read
+    /// from standard input, as with `pure -`. Saving it keeps its contents
+    /// for [`App::standard_output`] instead of asking for a file name.
+    pub fn use_standard_io(&mut self) {
+        self.buffer_mut().untitled_number = STANDARD_INPUT;
+        self.untitled_count = 0;
+    }
+
+    fn is_standard_input(&self) -> bool {
+        self.buffer().file_path.is_none() && self.buffer().untitled_number == STANDARD_INPUT
+    }
+
+    /// The document read from standard input as last saved, to write to
+    /// standard output on exit; `None` until it is saved.
+    pub fn standard_output(&self) -> Option<&[u8]> {
+        self.standard_output.as_deref()
+    }
+
+    /// Edit the current document as a Git commit message, as
+    /// [`commit_message::parse`] reads it: the comment blocks cannot be
+    /// changed, and quitting without saving aborts the commit.
+    pub fn edit_commit_message(&mut self) {
+        self.buffer_mut().commit_message = Some(CommitMessage::new(self.display().document()));
+    }
+
+    /// Whether a commit message was closed, or is still open, without
+    /// having been saved.
+    pub fn commit_aborted(&self) -> bool {
+        self.aborted_commit
+            || self
+                .buffers
+                .iter()
+                .filter_map(|buffer| buffer.commit_message.as_ref())
+                .any(|message| !message.saved)
+    }
+
+    /// Whether the top-level paragraph at `index` is a comment block of a
+    /// commit message.
+    fn is_comment(&self, index: usize) -> bool {
+        let Some(message) = &self.buffer().commit_message else {
+            return false;
+        };
+        self.display()
+            .document()
+            .paragraphs
+            .get(index)
+            .is_some_and(|paragraph| message.is_comment(paragraph))
+    }
+
+    /// Whether an edit at the cursor, or of the selection, would change a
+    /// comment block.
+    fn edit_touches_comments(&mut self) -> bool {
+        if self.buffer().commit_message.is_none() {
             return false;
         }
+        let cursor = self.display().cursor_pointer();
+        let (start, end) = self
+            .current_selection()
+            .unwrap_or_else(|| (cursor.clone(), cursor));
+        let (Some(first), Some(last)) = (
+            start.paragraph_path.root_index(),
+            end.paragraph_path.root_index(),
+        ) else {
+            return false;
+        };
+        (first..=last).any(|index| self.is_comment(index))
+    }
 
-        match (code, modifiers) {
-            (KeyCode::Esc, _) => {
-                self.file_dialog = None;
-            }
-            (KeyCode::Enter, _) => {
-                let result = self
-                    .file_dialog
-                    .as_mut()
-                    .expect("file dialog checked above")
-                    .enter();
-                if let FileDialogResult::Accept(path) = result {
-                    self.accept_file_dialog(path);
+    /// Whether deleting at the cursor — `forward` or backward — would join
+    /// a comment block to the paragraph next to it; if so, the status line
+    /// says it cannot be done.
+    fn refuse_joining_comment(&mut self, forward: bool) -> bool {
+        if self.buffer().commit_message.is_none() || self.current_selection().is_some() {
+            return false;
+        }
+        let Some(root) = self.display().cursor_pointer().paragraph_path.root_index() else {
+            return false;
+        };
+        let Some(neighbour) = (if forward {
+            root.checked_add(1)
+        } else {
+            root.checked_sub(1)
+        }) else {
+            return false;
+        };
+        if !self.is_comment(neighbour) {
+            return false;
+        }
+        let path = ParagraphPath::new_root(neighbour);
+        let Some(start) = self
+            .display()
+            .pointer_at_paragraph_char_offset(&path, 0)
+            .and_then(|pointer| self.display().global_char_offset_of_pointer(&pointer))
+        else {
+            return false;
+        };
+        // With nothing between the cursor and the comment, deleting pulls
+        // the two together.
+        let edge = if forward {
+            start
+        } else {
+            start + paragraph_char_count(&self.display().document().paragraphs[neighbour])
+        };
+        if self.display().cursor_global_char_offset() != edge {
+            return false;
+        }
+        self.status_message = Some((COMMENTS_READ_ONLY.to_string(), Instant::now()));
+        true
+    }
+
+    /// Show the outline with focus, or move the focus to it, or hide it
+    /// when it has focus already.
+    fn toggle_outline(&mut self) {
+        let paragraph = self.cursor_root_paragraph();
+        match &mut self.outline {
+            Some(outline) if outline.focused() => self.outline = None,
+            Some(outline) => {
+                if let Some(current) = outline.current(paragraph) {
+                    outline.select(current);
                 }
+                outline.set_focused(true);
+            }
+            None => {
+                self.outline = Some(OutlineState::new(self.display().document(), paragraph));
+            }
+        }
+        self.needs_position_rebuild = true;
+    }
+
+    fn toggle_fold(&mut self) {
+        let message = match self.display_mut().toggle_fold() {
+            Some(true) => "Folded",
+            Some(false) => "Unfolded",
+            None => "Nothing to fold here",
+        };
+        self.status_message = Some((message.to_string(), Instant::now()));
+        self.folds_changed();
+    }
+
+    fn fold_all_sections(&mut self) {
+        if !self.display_mut().fold_all_sections() {
+            self.status_message = Some(("No sections to fold".to_string(), Instant::now()));
+        }
+        self.folds_changed();
+    }
+
+    fn unfold_all(&mut self) {
+        if !self.display_mut().unfold_all() {
+            self.status_message = Some(("Nothing is folded".to_string(), Instant::now()));
+        }
+        self.folds_changed();
+    }
+
+    fn folds_changed(&mut self) {
+        self.display_mut().set_preferred_column(None);
+        self.needs_position_rebuild = true;
+        self.store_folds();
+    }
+
+    /// Remember the current document's folds, if folds are remembered. Only
+    /// folds made while there are no unsaved changes fit the file.
+    fn store_folds(&self) {
+        if !self.remember_folds || self.buffer().dirty {
+            return;
+        }
+        if let Some(path) = &self.buffer().file_path {
+            let _ = fold_state::store(path, &self.display().fold_keys());
+        }
+    }
+
+    /// The top-level paragraph the cursor is in.
+    fn cursor_root_paragraph(&self) -> usize {
+        self.display()
+            .cursor_pointer()
+            .paragraph_path
+            .root_index()
+            .unwrap_or(0)
+    }
+
+    /// Handle a key while the outline has focus. Keys bound to application
+    /// commands, such as saving or hiding the outline again, go to the
+    /// keymap; the document's own keys do nothing.
+    fn handle_outline_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        let Some(outline) = self.outline.as_mut().filter(|outline| outline.focused()) else {
+            return false;
+        };
+        let page = outline.area().height.max(1) as isize;
+        match code {
+            KeyCode::Up => outline.move_selection(-1),
+            KeyCode::Down => outline.move_selection(1),
+            KeyCode::PageUp => outline.move_selection(-page),
+            KeyCode::PageDown => outline.move_selection(page),
+            KeyCode::Home => outline.select(0),
+            KeyCode::End => outline.select(usize::MAX),
+            KeyCode::Esc | KeyCode::Tab => outline.set_focused(false),
+            KeyCode::Enter => {
+                outline.set_focused(false);
+                let index = outline.selected_index();
+                self.go_to_outline_entry(index);
             }
             _ => {
-                let Some(dialog) = self.file_dialog.as_mut() else {
-                    return true;
-                };
-                match (code, modifiers) {
-                    (KeyCode::Tab, _) => dialog.complete(),
-                    (KeyCode::Up, _) => dialog.move_selection(-1),
-                    (KeyCode::Down, _) => dialog.move_selection(1),
-                    (KeyCode::Left, _) => dialog.move_cursor_left(),
-                    (KeyCode::Right, _) => dialog.move_cursor_right(),
-                    (KeyCode::Home, _) => dialog.move_cursor_start(),
-                    (KeyCode::End, _) => dialog.move_cursor_end(),
-                    (KeyCode::Char('a'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        dialog.move_cursor_start()
-                    }
-                    (KeyCode::Char('e'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        dialog.move_cursor_end()
-                    }
-                    (KeyCode::Char('w'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        dialog.delete_word_backward()
-                    }
-                    (KeyCode::Backspace, m)
-                        if m.contains(KeyModifiers::CONTROL) || m.contains(KeyModifiers::ALT) =>
-                    {
-                        dialog.delete_word_backward()
-                    }
-                    (KeyCode::Backspace, _) => dialog.backspace(),
-                    (KeyCode::Delete, _) => dialog.delete(),
-                    (KeyCode::Char(ch), m)
-                        if !m.contains(KeyModifiers::CONTROL) && !m.contains(KeyModifiers::ALT) =>
-                    {
-                        dialog.insert_char(ch)
+                if !self.pending_keys.is_empty() {
+                    return false;
+                }
+                return !matches!(
+                    self.keymap.lookup(&[KeyChord::new(code, modifiers)]),
+                    Lookup::Prefix | Lookup::Command(Command::App(_))
+                );
+            }
+        }
+        true
+    }
+
+    /// Move the cursor to the start of the heading listed as the outline's
+    /// entry `index`.
+    fn go_to_outline_entry(&mut self, index: usize) {
+        let Some(entry) = self
+            .outline
+            .as_ref()
+            .and_then(|outline| outline.entries().get(index))
+        else {
+            return;
+        };
+        let path = ParagraphPath::new_root(entry.paragraph);
+        self.buffer_mut().selection_anchor = None;
+        self.display_mut().move_to_paragraph_char_offset(&path, 0);
+        let pointer = self.display().cursor_pointer();
+        self.display_mut().focus_pointer(&pointer);
+        self.display_mut().set_preferred_column(None);
+        self.display_mut().set_cursor_following(true);
+    }
+
+    /// Select the section at the cursor, from the start of its heading to
+    /// the end of its last paragraph. Returns whether there was one.
+    fn select_section(&mut self) -> bool {
+        let Some((start, end)) = self.display().current_section_bounds() else {
+            self.status_message = Some((NO_SECTION.to_string(), Instant::now()));
+            return false;
+        };
+        self.buffer_mut().selection_anchor = Some(start);
+        self.display_mut().focus_pointer(&end);
+        self.display_mut().set_preferred_column(None);
+        true
+    }
+
+    /// Cut the section at the cursor, heading and all, to the clipboard.
+    fn cut_section(&mut self) {
+        let Some(section) = self.display().current_section() else {
+            self.status_message = Some((NO_SECTION.to_string(), Instant::now()));
+            return;
+        };
+        if self.refuse_section_edit(section.clone()) || !self.select_section() {
+            return;
+        }
+        let contents = self
+            .current_selection()
+            .and_then(|selection| self.selection_clipboard_contents(&selection));
+        self.buffer_mut().selection_anchor = None;
+        let Some(contents) = contents else {
+            return;
+        };
+        if !self.display_mut().remove_root_paragraphs(section) {
+            return;
+        }
+        self.copy_to_clipboard(contents);
+        self.mark_dirty();
+        self.display_mut().set_preferred_column(None);
+        self.needs_position_rebuild = true;
+        self.status_message = Some(("Cut to clipboard".to_string(), Instant::now()));
+    }
+
+    /// Move the section at the cursor up (or down) past the section of the
+    /// same level next to it.
+    fn move_section(&mut self, up: bool) {
+        let Some((first, second)) = self.display().section_swap(up) else {
+            let message = if self.display().current_section().is_none() {
+                NO_SECTION
+            } else if up {
+                "No section of the same level above to move past"
+            } else {
+                "No section of the same level below to move past"
+            };
+            self.status_message = Some((message.to_string(), Instant::now()));
+            return;
+        };
+        if self.refuse_section_edit(first.start..second.end) {
+            return;
+        }
+        if self.display_mut().move_current_section(up) {
+            self.buffer_mut().selection_anchor = None;
+            self.mark_dirty();
+            self.display_mut().set_preferred_column(None);
+        }
+    }
+
+    /// Promote (`delta` < 0) or demote (`delta` > 0) every heading of the
+    /// section at the cursor.
+    fn shift_section(&mut self, delta: isize) {
+        let Some(section) = self.display().current_section() else {
+            self.status_message = Some((NO_SECTION.to_string(), Instant::now()));
+            return;
+        };
+        if !self.display().can_shift_current_section(delta) {
+            let message = if delta < 0 {
+                "Heading 1 cannot be promoted further"
+            } else {
+                "Heading 3 cannot be demoted further"
+            };
+            self.status_message = Some((message.to_string(), Instant::now()));
+            return;
+        }
+        if self.refuse_section_edit(section) {
+            return;
+        }
+        if self.display_mut().shift_current_section(delta) {
+            self.mark_dirty();
+        }
+    }
+
+    /// Whether the top-level paragraphs `range` cannot be changed; if so,
+    /// the status line says why.
+    fn refuse_section_edit(&mut self, range: Range<usize>) -> bool {
+        if self.refuse_edit() {
+            return true;
+        }
+        if range.into_iter().any(|index| self.is_comment(index)) {
+            self.status_message = Some((COMMENTS_READ_ONLY.to_string(), Instant::now()));
+            return true;
+        }
+        false
+    }
+
+    /// Move the cursor to the start of line `line` (counted from one, as
+    /// the status line shows it) once the document is laid out. Lines past
+    /// the end go to the last one.
+    pub fn go_to_line(&mut self, line: usize) {
+        self.pending_line_jump = Some(line.max(1));
+        self.needs_position_rebuild = true;
+    }
+
+    fn apply_pending_line_jump(&mut self) {
+        let Some(line) = self.pending_line_jump.take() else {
+            return;
+        };
+        let positions = self.display().visual_positions();
+        let key = |display: &&CursorDisplay| {
+            (
+                display.position.content_line,
+                display.position.content_column,
+            )
+        };
+        let target = positions
+            .iter()
+            .filter(|display| display.position.content_line + 1 >= line)
+            .min_by_key(key)
+            .or_else(|| positions.iter().max_by_key(key));
+        if let Some(target) = target {
+            self.display_mut().focus_display(target);
+        }
+    }
+
+    /// Move the cursor to the first top-level heading called `text`,
+    /// ignoring case, or else the first one containing it. Returns whether
+    /// there was one; if not, the status line says so.
+    pub fn go_to_heading(&mut self, text: &str) -> bool {
+        let wanted = text.trim().to_lowercase();
+        let headings: Vec<(usize, String)> = outline::entries(self.display().document())
+            .into_iter()
+            .map(|entry| (entry.paragraph, entry.title.to_lowercase()))
+            .collect();
+        let found = headings
+            .iter()
+            .find(|(_, heading)| *heading == wanted)
+            .or_else(|| {
+                headings
+                    .iter()
+                    .find(|(_, heading)| heading.contains(&wanted))
+            });
+        let Some((index, _)) = found else {
+            self.status_message = Some((format!("No heading \"{}\"", text.trim()), Instant::now()));
+            return false;
+        };
+        self.display_mut()
+            .move_to_paragraph_char_offset(&ParagraphPath::new_root(*index), 0);
+        let pointer = self.display().cursor_pointer();
+        self.display_mut().focus_pointer(&pointer);
+        true
+    }
+
+    /// Make the read-only document editable by reading its file as plain
+    /// text. Saving over the file still asks first.
+    fn open_as_plain_text(&mut self) {
+        let Some(Protection::ReadOnly(failure)) = self.buffer_mut().protection.take() else {
+            self.status_message = Some((
+                "Only a file that could not be parsed can be opened as plain text".to_string(),
+                Instant::now(),
+            ));
+            return;
+        };
+        let path = self.buffer().file_path.clone();
+        self.replace_document(
+            plain_text::parse(&failure.content),
+            path,
+            DocumentFormat::PlainText,
+        );
+        self.buffer_mut().protection = Some(Protection::PlainText);
+        self.status_message = Some(("Opened as plain text".to_string(), Instant::now()));
+    }
+
+    /// Handle a key press while the recovery prompt is open. The prompt is
+    /// modal: every key is consumed. Esc closes it without deciding, leaving
+    /// the recovery file in place.
+    fn handle_recovery_prompt_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        let Some(prompt) = self.recovery_prompt.as_mut() else {
+            return false;
+        };
+
+        match choice_prompt_key(prompt, code, modifiers) {
+            Some(PromptAnswer::Chosen(choice)) => self.resolve_recovery_prompt(choice),
+            Some(PromptAnswer::Dismissed) => self.recovery_prompt = None,
+            None => {}
+        }
+        true
+    }
+
+    /// Handle a key press while the lock prompt is open. The prompt is
+    /// modal: every key is consumed. Esc opens the document read-only.
+    fn handle_lock_prompt_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        let Some(prompt) = self.lock_prompt.as_mut() else {
+            return false;
+        };
+
+        match choice_prompt_key(prompt, code, modifiers) {
+            Some(PromptAnswer::Chosen(choice)) => self.resolve_lock_prompt(choice),
+            Some(PromptAnswer::Dismissed) => self.resolve_lock_prompt(LockChoice::ReadOnly),
+            None => {}
+        }
+        true
+    }
+
+    /// Close the lock prompt and carry out `choice`. A document opened
+    /// read-only becomes editable once the other Pure is done with it; see
+    /// [`App::retry_lock`]. Editing anyway takes the lock over.
+    fn resolve_lock_prompt(&mut self, choice: LockChoice) {
+        let Some(prompt) = self.lock_prompt.take() else {
+            return;
+        };
+        match choice {
+            LockChoice::ReadOnly => {
+                self.buffer_mut().locked_by = Some(prompt.into_context());
+                self.status_message = Some(("Opened read-only".to_string(), Instant::now()));
+            }
+            LockChoice::EditAnyway => {
+                self.buffer_mut().lock =
+                    self.buffer().file_path.as_deref().and_then(lock::take_over);
+            }
+        }
+    }
+
+    /// Close the recovery prompt and carry out `choice`. A restored document
+    /// counts as modified; its recovery file stays until it is saved. An
+    /// untitled document takes over the recovery file of the earlier
+    /// session as its own.
+    fn resolve_recovery_prompt(&mut self, choice: RecoveryChoice) {
+        let Some(prompt) = self.recovery_prompt.take() else {
+            return;
+        };
+        match choice {
+            RecoveryChoice::Restore => match recovery::read(prompt.context()) {
+                Ok(document) => {
+                    let path = self.buffer().file_path.clone();
+                    self.replace_document(document, path, self.buffer().document_format);
+                    self.mark_dirty();
+                    if self.buffer().file_path.is_none() {
+                        self.write_recovery();
+                        if self.buffer().autosave_due.is_none() {
+                            let _ = fs::remove_file(prompt.context());
+                        }
+                    }
+                    self.status_message =
+                        Some(("Restored unsaved changes".to_string(), Instant::now()));
+                }
+                Err(err) => {
+                    self.status_message = Some((format!("{err:#}"), Instant::now()));
+                }
+            },
+            RecoveryChoice::Discard => {
+                let _ = fs::remove_file(prompt.context());
+                self.status_message =
+                    Some(("Discarded recovered changes".to_string(), Instant::now()));
+            }
+        }
+    }
+
+    /// Save on the user's request. Failures end up in the status line, so
+    /// the document stays open for another attempt.
+    fn save_or_report(&mut self) {
+        if let Err(err) = self.save() {
+            self.status_message = Some((format!("{err:#}"), Instant::now()));
+        }
+    }
+
+    fn save(&mut self) -> Result<()> {
+        // An untitled document needs a name first; saving continues from
+        // the Save As dialog.
+        if self.buffer().file_path.is_none() && !self.is_standard_input() {
+            self.open_file_dialog(FileDialogKind::SaveAs);
+            return Ok(());
+        }
+        // The document is not what the file holds, so replacing the file
+        // takes a confirmation; saving continues from the prompt.
+        if self.buffer().protection.is_some() {
+            self.overwrite_prompt = Some(ChoicePrompt::new(
+                &overwrite_prompt::CHOICES,
+                OverwriteChoice::Cancel,
+                (),
+            ));
+            return Ok(());
+        }
+        // Another program wrote the file since it was read; saving continues
+        // from the prompt if the user keeps their version.
+        if let Some(stamp) = self.changed_on_disk() {
+            self.external_change_prompt = Some(ChoicePrompt::new(
+                &external_change::CHOICES,
+                ExternalChangeChoice::KeepMine,
+                ExternalChange::new(stamp, true),
+            ));
+            return Ok(());
+        }
+
+        let contents = write_document(
+            self.display().document(),
+            self.buffer().document_format,
+            self.plain_text_wrap,
+        )?;
+        let buffer = &mut self.buffers[self.active_buffer];
+        match &buffer.file_path {
+            Some(path) => {
+                save::write_atomically(path, &contents, self.backup)
+                    .with_context(|| format!("failed to write {}", path.display()))?;
+                buffer.file_stamp = FileStamp::written(path, &contents);
+                // A new file, or one saved under a new name.
+                if buffer.lock.is_none() && buffer.locked_by.is_none() && !self.viewer {
+                    (buffer.lock, buffer.locked_by) = lock_document(Some(path));
+                }
+            }
+            // Written once Pure exits.
+            None => self.standard_output = Some(contents),
+        }
+
+        self.buffer_mut().dirty = false;
+        self.buffer_mut().autosave_due = None;
+        recovery::remove(self.recovery_origin());
+        self.store_folds();
+        if let Some(message) = &mut self.buffer_mut().commit_message {
+            message.saved = true;
+        }
+        self.status_message = Some(("Saved".to_string(), Instant::now()));
+        Ok(())
+    }
+
+    fn open_file_dialog(&mut self, kind: FileDialogKind) {
+        let initial_input = match kind {
+            // Start in the current file's directory so its siblings are
+            // listed right away.
+            FileDialogKind::Open => match self
+                .buffer()
+                .file_path
+                .as_ref()
+                .and_then(|path| path.parent())
+            {
+                Some(parent) if !parent.as_os_str().is_empty() => {
+                    let parent = parent.display().to_string();
+                    if parent.ends_with('/') {
+                        parent
+                    } else {
+                        format!("{parent}/")
+                    }
+                }
+                _ => String::new(),
+            },
+            // Suggest the current path so saving under a sibling name only
+            // needs the file name edited.
+            FileDialogKind::SaveAs => self
+                .buffer()
+                .file_path
+                .as_ref()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+        };
+        self.file_dialog = Some(FileDialogState::new(kind, initial_input));
+    }
+
+    /// Handle a key press while the file dialog is open. The dialog is
+    /// modal: every key is consumed.
+    fn handle_file_dialog_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        if self.file_dialog.is_none() {
+            return false;
+        }
+
+        match (code, modifiers) {
+            (KeyCode::Esc, _) => {
+                self.file_dialog = None;
+                self.after_save = None;
+            }
+            (KeyCode::Enter, _) => {
+                let result = self
+                    .file_dialog
+                    .as_mut()
+                    .expect("file dialog checked above")
+                    .enter();
+                if let FileDialogResult::Accept(path) = result {
+                    self.accept_file_dialog(path);
+                }
+            }
+            _ => {
+                let Some(dialog) = self.file_dialog.as_mut() else {
+                    return true;
+                };
+                match (code, modifiers) {
+                    (KeyCode::Tab, _) => dialog.complete(),
+                    (KeyCode::Up, _) => dialog.move_selection(-1),
+                    (KeyCode::Down, _) => dialog.move_selection(1),
+                    (KeyCode::Left, _) => dialog.move_cursor_left(),
+                    (KeyCode::Right, _) => dialog.move_cursor_right(),
+                    (KeyCode::Home, _) => dialog.move_cursor_start(),
+                    (KeyCode::End, _) => dialog.move_cursor_end(),
+                    (KeyCode::Char('a'), m) if m.contains(KeyModifiers::CONTROL) => {
+                        dialog.move_cursor_start()
+                    }
+                    (KeyCode::Char('e'), m) if m.contains(KeyModifiers::CONTROL) => {
+                        dialog.move_cursor_end()
+                    }
+                    (KeyCode::Char('w'), m) if m.contains(KeyModifiers::CONTROL) => {
+                        dialog.delete_word_backward()
+                    }
+                    (KeyCode::Backspace, m)
+                        if m.contains(KeyModifiers::CONTROL) || m.contains(KeyModifiers::ALT) =>
+                    {
+                        dialog.delete_word_backward()
+                    }
+                    (KeyCode::Backspace, _) => dialog.backspace(),
+                    (KeyCode::Delete, _) => dialog.delete(),
+                    (KeyCode::Char(ch), m)
+                        if !m.contains(KeyModifiers::CONTROL) && !m.contains(KeyModifiers::ALT) =>
+                    {
+                        dialog.insert_char(ch)
                     }
                     _ => {}
                 }
             }
         }
-        true
+        true
+    }
+
+    /// Act on a path accepted in the file dialog. Overwriting another file
+    /// requires a second Enter on the unchanged path.
+    fn accept_file_dialog(&mut self, path: PathBuf) {
+        let current_path = self.buffer().file_path.as_deref() == Some(path.as_path());
+        let Some(dialog) = self.file_dialog.as_mut() else {
+            return;
+        };
+        let kind = dialog.kind();
+        let needs_confirmation = kind == FileDialogKind::SaveAs && !current_path && path.exists();
+        if needs_confirmation && dialog.pending_confirm() != Some(path.as_path()) {
+            dialog.set_pending_confirm(path);
+            return;
+        }
+
+        self.file_dialog = None;
+        match kind {
+            FileDialogKind::Open => self.open_file(path),
+            FileDialogKind::SaveAs => self.save_as(path),
+        }
+    }
+
+    /// Open the link dialog, seeded from the cursor context: an existing link
+    /// under the cursor is loaded for editing; a single-paragraph selection
+    /// becomes the text of a new link; otherwise a new link is inserted at the
+    /// cursor.
+    fn open_link_dialog(&mut self) {
+        if let Some(link) = self.display().link_at_cursor() {
+            self.link_edit_range = Some(link.range);
+            self.link_dialog = Some(LinkDialogState::new(
+                link.text,
+                link.target.unwrap_or_default(),
+                true,
+            ));
+            return;
+        }
+
+        if let Some(selection) = self.current_selection()
+            && selection.0.paragraph_path == selection.1.paragraph_path
+        {
+            let text = self
+                .display()
+                .selection_text(&selection)
+                .unwrap_or_default();
+            self.link_edit_range = Some(selection);
+            self.link_dialog = Some(LinkDialogState::new(text, String::new(), false));
+            return;
+        }
+
+        let cursor = self.display().cursor_pointer();
+        self.link_edit_range = Some((cursor.clone(), cursor));
+        self.link_dialog = Some(LinkDialogState::new(String::new(), String::new(), false));
+    }
+
+    /// Handle a key press while the link dialog is open. The dialog is modal:
+    /// every key is consumed. Enter always saves and Esc always cancels,
+    /// whatever has focus; Space activates a focused button.
+    fn handle_link_dialog_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        if self.link_dialog.is_none() {
+            return false;
+        }
+
+        let focus = self.link_dialog.as_ref().map(|dialog| dialog.focus());
+        match (code, modifiers) {
+            (KeyCode::Esc, _) => self.cancel_link_dialog(),
+            (KeyCode::Enter, _) => self.accept_link_dialog(),
+            (KeyCode::Tab, _) | (KeyCode::Down, _) => {
+                if let Some(dialog) = self.link_dialog.as_mut() {
+                    dialog.focus_next();
+                }
+            }
+            (KeyCode::BackTab, _) | (KeyCode::Up, _) => {
+                if let Some(dialog) = self.link_dialog.as_mut() {
+                    dialog.focus_prev();
+                }
+            }
+            // Space activates a focused button; in a text field it types.
+            (KeyCode::Char(' '), m)
+                if !m.contains(KeyModifiers::CONTROL)
+                    && !m.contains(KeyModifiers::ALT)
+                    && focus.is_some_and(LinkField::is_button) =>
+            {
+                self.activate_link_button();
+            }
+            _ => {
+                let Some(dialog) = self.link_dialog.as_mut() else {
+                    return true;
+                };
+                match (code, modifiers) {
+                    (KeyCode::Left, _) => dialog.move_cursor_left(),
+                    (KeyCode::Right, _) => dialog.move_cursor_right(),
+                    (KeyCode::Home, _) => dialog.move_cursor_start(),
+                    (KeyCode::End, _) => dialog.move_cursor_end(),
+                    (KeyCode::Char('a'), m) if m.contains(KeyModifiers::CONTROL) => {
+                        dialog.move_cursor_start()
+                    }
+                    (KeyCode::Char('e'), m) if m.contains(KeyModifiers::CONTROL) => {
+                        dialog.move_cursor_end()
+                    }
+                    (KeyCode::Char('w'), m) if m.contains(KeyModifiers::CONTROL) => {
+                        dialog.delete_word_backward()
+                    }
+                    (KeyCode::Backspace, m)
+                        if m.contains(KeyModifiers::CONTROL) || m.contains(KeyModifiers::ALT) =>
+                    {
+                        dialog.delete_word_backward()
+                    }
+                    (KeyCode::Backspace, _) => dialog.backspace(),
+                    (KeyCode::Delete, _) => dialog.delete(),
+                    (KeyCode::Char(ch), m)
+                        if !m.contains(KeyModifiers::CONTROL) && !m.contains(KeyModifiers::ALT) =>
+                    {
+                        dialog.insert_char(ch)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        true
+    }
+
+    /// Activate the focused button (Space). Open launches the browser; Cancel
+    /// and Save mirror the Esc/Enter accelerators.
+    fn activate_link_button(&mut self) {
+        match self.link_dialog.as_ref().map(|dialog| dialog.focus()) {
+            Some(LinkField::Open) => self.open_link_in_browser(),
+            Some(LinkField::Cancel) => self.cancel_link_dialog(),
+            Some(LinkField::Save) => self.accept_link_dialog(),
+            _ => {}
+        }
+    }
+
+    /// Dismiss the link dialog without changing the document.
+    fn cancel_link_dialog(&mut self) {
+        self.link_dialog = None;
+        self.link_edit_range = None;
+    }
+
+    /// Apply the link dialog: write the text and target back onto the
+    /// document. An empty target unlinks (keeping the text as plain text); an
+    /// empty text falls back to showing the URL itself.
+    fn accept_link_dialog(&mut self) {
+        let Some(dialog) = self.link_dialog.take() else {
+            return;
+        };
+        let range = self.link_edit_range.take();
+        let target = dialog.target().trim().to_string();
+        let text = dialog.text().to_string();
+        let display_text = if text.is_empty() {
+            target.clone()
+        } else {
+            text
+        };
+        let target_opt = if target.is_empty() {
+            None
+        } else {
+            Some(target.as_str())
+        };
+
+        if display_text.is_empty() && target_opt.is_none() {
+            return;
+        }
+
+        if let Some(range) = range
+            && self
+                .display_mut()
+                .set_link(&range, &display_text, target_opt)
+        {
+            self.mark_dirty();
+            self.buffer_mut().selection_anchor = None;
+            self.display_mut().set_preferred_column(None);
+            self.needs_position_rebuild = true;
+        }
+    }
+
+    /// Open the link's target in the user's default browser by handing the URL
+    /// to the platform opener (`xdg-open`, `open`, or `start`). The dialog
+    /// stays open so the field values are not lost.
+    fn open_link_in_browser(&mut self) {
+        let Some(dialog) = self.link_dialog.as_ref() else {
+            return;
+        };
+        let target = dialog.target().trim().to_string();
+        if target.is_empty() {
+            self.status_message = Some(("No link target to open".to_string(), Instant::now()));
+            return;
+        }
+        if self.interactive {
+            match open_in_browser(&target) {
+                Ok(()) => {
+                    self.status_message = Some((format!("Opening {target}"), Instant::now()));
+                }
+                Err(err) => {
+                    self.status_message =
+                        Some((format!("Could not open link: {err}"), Instant::now()));
+                }
+            }
+        } else {
+            self.status_message = Some((format!("Opening {target}"), Instant::now()));
+        }
+    }
+
+    fn render_link_dialog(&self, frame: &mut Frame, area: Rect) {
+        let Some(dialog) = &self.link_dialog else {
+            return;
+        };
+        if area.width < 24 || area.height < 9 {
+            return;
+        }
+
+        let theme = self.display().theme();
+        let popup_style = theme.menu_style();
+
+        // Two input rows, a separator, the Open button, and a footer, plus the
+        // border.
+        let width = 60.min(area.width.saturating_sub(4));
+        let height = 7u16.min(area.height.saturating_sub(2));
+        let popup_area = Rect::new(
+            area.x + (area.width.saturating_sub(width)) / 2,
+            area.y + (area.height.saturating_sub(height)) / 2,
+            width,
+            height,
+        );
+
+        frame.render_widget(Clear, popup_area);
+
+        let title = if dialog.editing() {
+            "Edit Link"
+        } else {
+            "Insert Link"
+        };
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .style(popup_style)
+            .border_style(Style::default().fg(Color::Gray));
+        let inner = block.inner(popup_area);
+        frame.render_widget(block, popup_area);
+        if inner.width < 10 || inner.height < 5 {
+            return;
+        }
+
+        const LABEL_WIDTH: u16 = 6;
+        let field_x = inner.x + LABEL_WIDTH;
+        let field_width = inner.width.saturating_sub(LABEL_WIDTH).max(1);
+
+        // The text and URL input rows, each prefixed with its label.
+        let fields = [
+            (LinkField::Text, "Text:", dialog.text(), inner.y),
+            (LinkField::Target, "URL:", dialog.target(), inner.y + 1),
+        ];
+        for (field, label, value, y) in fields {
+            frame.render_widget(
+                Paragraph::new(Line::from(Span::styled(label, theme.menu_disabled_style())))
+                    .style(popup_style),
+                Rect::new(inner.x, y, LABEL_WIDTH, 1),
+            );
+            let focused = dialog.focus() == field;
+            let caret = if focused {
+                dialog.active_cursor().unwrap_or(0)
+            } else {
+                0
+            };
+            let visible = field_width as usize;
+            let skip = if focused {
+                (caret + 1).saturating_sub(visible)
+            } else {
+                0
+            };
+            let shown: String = value.chars().skip(skip).take(visible).collect();
+            frame.render_widget(
+                Paragraph::new(shown).style(popup_style),
+                Rect::new(field_x, y, field_width, 1),
+            );
+            if focused {
+                frame.set_cursor_position(Position::new(field_x + (caret - skip) as u16, y));
+            }
+        }
+
+        // Separator below the inputs.
+        let separator = "─".repeat(inner.width as usize);
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(
+                separator,
+                Style::default().fg(Color::DarkGray),
+            )))
+            .style(popup_style),
+            Rect::new(inner.x, inner.y + 2, inner.width, 1),
+        );
+
+        // Button row: Open on the left, Cancel and Save flush right.
+        let button_row = inner.y + 3;
+        let has_target = !dialog.target().trim().is_empty();
+        let button_style = |field: LinkField, enabled: bool| -> Style {
+            if dialog.focus() == field {
+                theme.menu_selected_style()
+            } else if enabled {
+                popup_style
+            } else {
+                popup_style.patch(theme.menu_disabled_style())
+            }
+        };
+
+        let open = "[ Open ]";
+        let cancel = "[ Cancel ]";
+        let save = "[ Save ]";
+        let open_w = open.chars().count() as u16;
+        let cancel_w = cancel.chars().count() as u16;
+        let save_w = save.chars().count() as u16;
+        let open_x = inner.x;
+        let save_x = inner.x + inner.width.saturating_sub(save_w);
+        let cancel_x = save_x.saturating_sub(cancel_w + 1);
+
+        let buttons = [
+            (LinkField::Open, open, open_x, open_w, has_target),
+            (LinkField::Cancel, cancel, cancel_x, cancel_w, true),
+            (LinkField::Save, save, save_x, save_w, true),
+        ];
+        for (field, label, x, w, enabled) in buttons {
+            frame.render_widget(
+                Paragraph::new(Line::from(Span::styled(
+                    label,
+                    button_style(field, enabled),
+                )))
+                .style(popup_style),
+                Rect::new(x, button_row, w, 1),
+            );
+            // Park the caret on the focused button so it does not show through
+            // the popup at the document cursor's position.
+            if dialog.focus() == field {
+                frame.set_cursor_position(Position::new(x, button_row));
+            }
+        }
+
+        // Footer hints.
+        let hint = if dialog.focus().is_button() {
+            "Space: activate   Enter: save   Esc: cancel"
+        } else {
+            "Enter: save   Esc: cancel   Tab: next"
+        };
+        frame.render_widget(
+            Paragraph::new(Line::from(Span::styled(hint, theme.menu_disabled_style())))
+                .style(popup_style),
+            Rect::new(inner.x, inner.y + 4, inner.width, 1),
+        );
+    }
+
+    /// Open the find bar, or switch an open one between finding and
+    /// replacing. A selection within one paragraph seeds the query, and the
+    /// search starts at the cursor.
+    fn open_find_bar(&mut self, replacing: bool) {
+        if let Some(bar) = self.find_bar.as_mut() {
+            bar.set_replacing(replacing);
+            return;
+        }
+
+        let mut query = String::new();
+        if let Some(selection) = self.current_selection()
+            && selection.0.paragraph_path == selection.1.paragraph_path
+        {
+            query = self
+                .display()
+                .selection_text(&selection)
+                .unwrap_or_default();
+            self.display_mut().focus_pointer(&selection.0);
+        }
+        self.buffer_mut().selection_anchor = None;
+        self.find_bar = Some(FindBarState::new(query, replacing));
+        self.refresh_search();
+    }
+
+    /// Close the find bar, leaving the cursor on the match it was on.
+    fn close_find_bar(&mut self) {
+        self.find_bar = None;
+        self.display_mut().clear_search_matches();
+    }
+
+    /// Handle a key press while the find bar is open. Typing goes into the
+    /// focused field and every other key is consumed too, except Ctrl+S and
+    /// Ctrl+Q, so the document can still be saved and the editor left.
+    fn handle_find_bar_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        if self.find_bar.is_none() || self.find_bar_passes_through(code, modifiers) {
+            return false;
+        }
+        let Some(bar) = self.find_bar.as_mut() else {
+            return false;
+        };
+
+        let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+        let alt = modifiers.contains(KeyModifiers::ALT);
+        let shift = modifiers.contains(KeyModifiers::SHIFT);
+        match code {
+            KeyCode::Esc => self.close_find_bar(),
+            KeyCode::Char('f') if ctrl => bar.set_replacing(false),
+            KeyCode::Char('h') if ctrl => bar.set_replacing(true),
+            KeyCode::Tab | KeyCode::BackTab => bar.toggle_focus(),
+            KeyCode::Enter if bar.focus() == FindField::Replacement => self.replace_current_match(),
+            KeyCode::Enter | KeyCode::F(3) => self.step_search(shift),
+            KeyCode::Down => self.step_search(false),
+            KeyCode::Up => self.step_search(true),
+            KeyCode::Char('c') if alt => {
+                bar.toggle_case_sensitive();
+                self.refresh_search();
+            }
+            KeyCode::Char('w') if alt => {
+                bar.toggle_whole_word();
+                self.refresh_search();
+            }
+            KeyCode::Char('r') if alt => {
+                bar.toggle_regex();
+                self.refresh_search();
+            }
+            KeyCode::Char('a') if alt && bar.replacing() => self.replace_all_matches(),
+            _ => {
+                let focus = bar.focus();
+                let field = bar.focused_field_mut();
+                let before = field.text().to_string();
+                edit_text_field(field, code, modifiers);
+                if focus == FindField::Query && field.text() != before {
+                    self.refresh_search();
+                }
+            }
+        }
+        true
+    }
+
+    /// Whether a key pressed in the find bar goes to the keymap instead:
+    /// saving and quitting work while searching, and so do key sequences
+    /// leading up to them.
+    fn find_bar_passes_through(&self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        if !self.pending_keys.is_empty() {
+            return true;
+        }
+        if !modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+            return false;
+        }
+        matches!(
+            self.keymap.lookup(&[KeyChord::new(code, modifiers)]),
+            Lookup::Prefix | Lookup::Command(Command::App(AppAction::Save | AppAction::Quit))
+        )
+    }
+
+    /// Search for the find bar's query again, e.g. after it or the search
+    /// options changed. The first match at or after the cursor becomes the
+    /// current one and the cursor moves there.
+    fn refresh_search(&mut self) {
+        let display = &mut self.buffers[self.active_buffer].display;
+        let Some(bar) = self.find_bar.as_mut() else {
+            return;
+        };
+        if bar.query().text().is_empty() {
+            bar.set_results(Vec::new(), None);
+            display.clear_search_matches();
+            return;
+        }
+        let query = match SearchQuery::new(bar.query().text(), bar.options()) {
+            Ok(query) => query,
+            Err(_) => {
+                bar.set_error("Invalid pattern".to_string());
+                display.clear_search_matches();
+                return;
+            }
+        };
+
+        let matches = display.find_all(&query);
+        let cursor = display.cursor_pointer();
+        let current = matches
+            .iter()
+            .position(|search_match| {
+                display
+                    .compare_pointers(&search_match.start, &cursor)
+                    .is_some_and(|ordering| ordering != Ordering::Less)
+            })
+            .or(if matches.is_empty() { None } else { Some(0) });
+        bar.set_results(matches, current);
+        display.set_search_matches(bar.matches(), current);
+        if let Some(search_match) = bar.current_match() {
+            let start = search_match.start.clone();
+            display.focus_pointer(&start);
+        }
+    }
+
+    /// Move to the next (or previous) match, wrapping around the document.
+    fn step_search(&mut self, backward: bool) {
+        let display = &mut self.buffers[self.active_buffer].display;
+        let Some(bar) = self.find_bar.as_mut() else {
+            return;
+        };
+        let Some(start) = bar
+            .step(backward)
+            .map(|search_match| search_match.start.clone())
+        else {
+            if !bar.query().text().is_empty() {
+                self.status_message = Some(("No matches".to_string(), Instant::now()));
+            }
+            return;
+        };
+        display.set_search_matches(bar.matches(), bar.current());
+        display.focus_pointer(&start);
+    }
+
+    /// Replace the current match and move on to the next one.
+    fn replace_current_match(&mut self) {
+        let Some(bar) = self.find_bar.as_ref() else {
+            return;
+        };
+        let Some(search_match) = bar.current_match().cloned() else {
+            self.status_message = Some(("No matches".to_string(), Instant::now()));
+            return;
+        };
+        if let Some(index) = search_match.start.paragraph_path.root_index()
+            && self.is_comment(index)
+        {
+            self.status_message = Some((COMMENTS_READ_ONLY.to_string(), Instant::now()));
+            return;
+        }
+        let Ok(query) = SearchQuery::new(bar.query().text(), bar.options()) else {
+            return;
+        };
+        let replacement = bar.replacement().text().to_string();
+        if self
+            .display_mut()
+            .replace_match(&query, &search_match, &replacement)
+        {
+            self.mark_dirty();
+            self.needs_position_rebuild = true;
+        }
+        self.refresh_search();
+    }
+
+    /// Replace every match of the query in one undoable step.
+    fn replace_all_matches(&mut self) {
+        let Some(bar) = self.find_bar.as_ref() else {
+            return;
+        };
+        let Ok(query) = SearchQuery::new(bar.query().text(), bar.options()) else {
+            return;
+        };
+        let replacement = bar.replacement().text().to_string();
+        if self.buffer().commit_message.is_some()
+            && self.display().find_all(&query).iter().any(|search_match| {
+                search_match
+                    .start
+                    .paragraph_path
+                    .root_index()
+                    .is_some_and(|index| self.is_comment(index))
+            })
+        {
+            self.status_message = Some((
+                "Some matches are in comment lines, which cannot be changed".to_string(),
+                Instant::now(),
+            ));
+            return;
+        }
+        let count = self.display_mut().replace_all(&query, &replacement);
+        let message = match count {
+            0 => "No matches".to_string(),
+            1 => "Replaced 1 match".to_string(),
+            count => format!("Replaced {count} matches"),
+        };
+        self.status_message = Some((message, Instant::now()));
+        if count > 0 {
+            self.mark_dirty();
+            self.needs_position_rebuild = true;
+        }
+        self.refresh_search();
+    }
+
+    /// Draw the find bar: the query row, plus the replacement row while
+    /// replacing. Each row shows a label and the field, with the match
+    /// summary and search options (or the replace hints) on the right.
+    fn render_find_bar(&self, frame: &mut Frame, area: Rect) {
+        let Some(bar) = &self.find_bar else {
+            return;
+        };
+        let theme = self.display().theme();
+        let bar_style = theme.menu_style();
+        let hint_style = theme.menu_disabled_style();
+
+        let options = bar.options();
+        let checkbox = |checked: bool| if checked { "[x]" } else { "[ ]" };
+        let option_text = format!(
+            "{} Case  {} Word  {} Regex ",
+            checkbox(options.case_sensitive),
+            checkbox(options.whole_word),
+            checkbox(options.regex),
+        );
+        let mut rows = vec![(
+            FindField::Query,
+            "Find:",
+            bar.query(),
+            format!("{}  {}", bar.status_text(), option_text),
+        )];
+        if bar.replacing() {
+            rows.push((
+                FindField::Replacement,
+                "Replace:",
+                bar.replacement(),
+                "Enter: Replace  Alt+A: All ".to_string(),
+            ));
+        }
+
+        const LABEL_WIDTH: u16 = 10;
+        const MIN_FIELD_WIDTH: u16 = 10;
+        for (index, (field, label, value, info)) in rows.into_iter().enumerate() {
+            let y = area.y + index as u16;
+            if y >= area.y + area.height {
+                break;
+            }
+            let row = Rect::new(area.x, y, area.width, 1);
+            frame.render_widget(Paragraph::new("").style(bar_style), row);
+
+            let info_width = (info.chars().count() as u16)
+                .min(area.width.saturating_sub(LABEL_WIDTH + MIN_FIELD_WIDTH + 1));
+            let field_x = area.x + LABEL_WIDTH.min(area.width);
+            let field_width = area
+                .width
+                .saturating_sub(LABEL_WIDTH + info_width + 1)
+                .max(1);
+            frame.render_widget(
+                Paragraph::new(Line::from(Span::styled(format!(" {label}"), hint_style)))
+                    .style(bar_style),
+                Rect::new(area.x, y, LABEL_WIDTH.min(area.width), 1),
+            );
+
+            let focused = bar.focus() == field;
+            let caret = if focused { value.cursor() } else { 0 };
+            let visible = field_width as usize;
+            let skip = (caret + 1).saturating_sub(visible);
+            let shown: String = value.text().chars().skip(skip).take(visible).collect();
+            frame.render_widget(
+                Paragraph::new(shown).style(bar_style),
+                Rect::new(field_x, y, field_width, 1),
+            );
+            if focused {
+                frame.set_cursor_position(Position::new(field_x + (caret - skip) as u16, y));
+            }
+
+            let info_x = area.x + area.width.saturating_sub(info_width);
+            frame.render_widget(
+                Paragraph::new(Line::from(Span::styled(info, hint_style))).style(bar_style),
+                Rect::new(info_x, y, info_width, 1),
+            );
+        }
+    }
+
+    /// A display for `document` that looks like the current one: same theme,
+    /// same reveal codes mode.
+    fn new_display(&self, document: Document) -> EditorDisplay {
+        let mut editor = DocumentEditor::new(document);
+        editor.ensure_cursor_selectable();
+        let mut display = EditorDisplay::new(editor);
+        display.set_theme(self.display().theme().clone());
+        display.set_reveal_codes(self.display().reveal_codes());
+        display
+    }
+
+    /// Swap in `document` as the current document and reset all
+    /// per-document state (undo history, selection, scroll, dirty flag).
+    /// Reveal codes mode survives the swap.
+    fn replace_document(
+        &mut self,
+        document: Document,
+        path: Option<PathBuf>,
+        format: DocumentFormat,
+    ) {
+        if self.buffer().dirty {
+            // The unsaved changes are being discarded on purpose.
+            recovery::remove(self.recovery_origin());
+        }
+        let display = self.new_display(document);
+        let remember_folds = self.remember_folds;
+        let buffer = self.buffer_mut();
+        if path != buffer.file_path {
+            buffer.lock = None;
+            buffer.locked_by = None;
+        }
+        buffer.display = display;
+        if remember_folds && let Some(path) = &path {
+            restore_folds(&mut buffer.display, path);
+        }
+        buffer.file_stamp = path.as_deref().and_then(FileStamp::read);
+        buffer.file_path = path;
+        buffer.document_format = format;
+        buffer.protection = None;
+        buffer.commit_message = None;
+        buffer.dirty = false;
+        buffer.autosave_due = None;
+        buffer.scroll_top = 0;
+        buffer.selection_anchor = None;
+        self.find_bar = None;
+        self.needs_position_rebuild = true;
+    }
+
+    /// Where the current document's unsaved changes are autosaved.
+    fn recovery_origin(&self) -> recovery::Origin<'_> {
+        self.buffer().recovery_origin()
+    }
+
+    fn next_untitled_number(&mut self) -> usize {
+        self.untitled_count += 1;
+        self.untitled_count
+    }
+
+    pub fn buffer_count(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// A background buffer holding `document`.
+    fn new_buffer(
+        &mut self,
+        document: Document,
+        path: Option<PathBuf>,
+        format: DocumentFormat,
+    ) -> Buffer {
+        let untitled_number = if path.is_none() {
+            self.next_untitled_number()
+        } else {
+            0
+        };
+        Buffer {
+            display: self.new_display(document),
+            file_stamp: path.as_deref().and_then(FileStamp::read),
+            file_path: path,
+            document_format: format,
+            untitled_number,
+            scroll_top: 0,
+            dirty: false,
+            selection_anchor: None,
+            autosave_due: None,
+            pending_recovery: None,
+            pending_lock: None,
+            protection: None,
+            commit_message: None,
+            lock: None,
+            locked_by: None,
+        }
+    }
+
+    /// Open another document in a buffer at the end of the buffer list,
+    /// keeping the current one in view — as for the further paths given on
+    /// the command line. Its recovery file, if any, is offered once the
+    /// buffer is shown.
+    pub fn add_buffer(&mut self, loaded: LoadedDocument, path: PathBuf) {
+        let (lock, locked_by) = if self.viewer {
+            (None, None)
+        } else {
+            lock_document(Some(&path))
+        };
+        let mut buffer = self.new_buffer(loaded.document, Some(path), loaded.format);
+        if self.remember_folds
+            && let Some(path) = &buffer.file_path
+        {
+            restore_folds(&mut buffer.display, path);
+        }
+        buffer.lock = lock;
+        // The recovery file of a document open elsewhere belongs to that
+        // session.
+        buffer.pending_recovery = loaded.recovery.filter(|_| locked_by.is_none());
+        buffer.pending_lock = locked_by;
+        if let Some(failure) = loaded.parse_failure {
+            focus_failure_line(&mut buffer.display, &failure);
+            buffer.protection = Some(Protection::ReadOnly(failure));
+        }
+        self.buffers.push(buffer);
+    }
+
+    /// Make the buffer at `index` in the buffer list the current one,
+    /// keeping reveal codes mode as it is.
+    fn enter_buffer(&mut self, index: usize) {
+        let reveal_codes = self.display().reveal_codes();
+        self.active_buffer = index;
+        let buffer = self.buffer_mut();
+        buffer.display.set_reveal_codes(reveal_codes);
+        let recovery = buffer.pending_recovery.take();
+        let locked_by = buffer.pending_lock.take();
+        // Anything pointing into the previous document is stale now.
+        self.find_bar = None;
+        self.pending_scroll_restore = None;
+        self.pending_line_jump = None;
+        self.mouse_drag_anchor = None;
+        self.drag_state = None;
+        self.paste_run = None;
+        self.needs_position_rebuild = true;
+        if let Some(recovery) = recovery {
+            self.offer_recovery(recovery);
+        }
+        if let Some(owner) = locked_by {
+            self.lock_prompt = Some(ChoicePrompt::new(
+                &lock::CHOICES,
+                LockChoice::ReadOnly,
+                owner,
+            ));
+        }
+    }
+
+    /// Make the buffer at `index` in the buffer list the current one.
+    fn switch_to_buffer(&mut self, index: usize) {
+        if index != self.active_buffer && index < self.buffer_count() {
+            self.enter_buffer(index);
+        }
+    }
+
+    /// Show the next (`delta` 1) or previous (`delta` -1) buffer, wrapping
+    /// around at the ends of the buffer list.
+    fn cycle_buffer(&mut self, delta: isize) {
+        let count = self.buffer_count();
+        if count == 1 {
+            self.status_message = Some(("No other buffers".to_string(), Instant::now()));
+            return;
+        }
+        let index = (self.active_buffer as isize + delta).rem_euclid(count as isize) as usize;
+        self.show_buffer(index);
+    }
+
+    /// Switch to the buffer at `index` on the user's request, making way in
+    /// the status line for its name.
+    fn show_buffer(&mut self, index: usize) {
+        self.switch_to_buffer(index);
+        self.status_message = None;
+    }
+
+    /// The name of every buffer in buffer list order, with whether it has
+    /// unsaved changes.
+    fn buffer_list(&self) -> Vec<(String, bool)> {
+        self.buffers
+            .iter()
+            .map(|buffer| (buffer.name(), buffer.dirty))
+            .collect()
+    }
+
+    /// Position in the buffer list of the buffer holding the file at `path`.
+    fn buffer_index_of(&self, path: &Path) -> Option<usize> {
+        self.buffers.iter().position(|buffer| {
+            buffer
+                .file_path
+                .as_deref()
+                .is_some_and(|other| same_file(other, path))
+        })
+    }
+
+    /// Open the buffer switcher: the command palette, listing the open
+    /// buffers.
+    fn open_buffer_list(&mut self) {
+        let entries = self
+            .buffer_list()
+            .into_iter()
+            .enumerate()
+            .map(|(index, (name, dirty))| {
+                let marker = if dirty { "*" } else { "" };
+                let current = if index == self.active_buffer {
+                    " (current)"
+                } else {
+                    ""
+                };
+                PaletteEntry {
+                    label: format!("{name}{marker}{current}"),
+                    command: Command::App(AppAction::SwitchToBuffer(index)),
+                    shortcut: None,
+                    enabled: true,
+                }
+            })
+            .collect();
+        self.command_palette = Some(CommandPaletteState::new("Buffers", entries));
+    }
+
+    /// Close the current buffer, or — with unsaved changes — ask first
+    /// whether to save them.
+    fn request_close_buffer(&mut self) {
+        if self.buffer().dirty {
+            self.quit_prompt = Some(ChoicePrompt::new(
+                &quit_prompt::CHOICES,
+                QuitChoice::Save,
+                AfterSave::CloseBuffer,
+            ));
+        } else {
+            self.close_buffer();
+        }
+    }
+
+    /// Close the current buffer, discarding its unsaved changes, and show
+    /// the one after it. Closing the last buffer leaves an untitled
+    /// document.
+    fn close_buffer(&mut self) {
+        let name = self.buffer().name();
+        if let Some(message) = &self.buffer().commit_message {
+            self.aborted_commit |= !message.saved;
+        }
+        if self.buffer_count() == 1 {
+            self.replace_document(Document::new(), None, DocumentFormat::Ftml);
+            let number = self.next_untitled_number();
+            self.buffer_mut().untitled_number = number;
+        } else {
+            if self.buffer().dirty {
+                recovery::remove(self.recovery_origin());
+            }
+            let closed = self.active_buffer;
+            self.buffers.remove(closed);
+            self.enter_buffer(closed.min(self.buffer_count() - 1));
+            self.forget_pane_buffer(closed);
+        }
+        if self.recovery_prompt.is_none() {
+            self.status_message = Some((format!("Closed {name}"), Instant::now()));
+        }
     }
 
-    /// Act on a path accepted in the file dialog. Destructive accepts —
-    /// opening over unsaved changes, overwriting another file — require a
-    /// second Enter on the unchanged path.
-    fn accept_file_dialog(&mut self, path: PathBuf) {
-        let Some(dialog) = self.file_dialog.as_mut() else {
+    /// After the buffer at `closed` was closed: a pane showing it shows the
+    /// current buffer instead, and one showing a later buffer follows it to
+    /// its new position.
+    fn forget_pane_buffer(&mut self, closed: usize) {
+        let Some(split) = &self.split else {
             return;
         };
-        let kind = dialog.kind();
-        let needs_confirmation = match kind {
-            FileDialogKind::Open => self.dirty,
-            FileDialogKind::SaveAs => {
-                self.file_path.as_deref() != Some(path.as_path()) && path.exists()
+        if split.other.buffer == closed {
+            let pane = self.new_pane(
+                self.active_buffer,
+                &self.display().cursor_pointer(),
+                self.buffer().scroll_top,
+            );
+            if let Some(split) = &mut self.split {
+                split.other = pane;
             }
-        };
-        if needs_confirmation && dialog.pending_confirm() != Some(path.as_path()) {
-            dialog.set_pending_confirm(path);
-            return;
+        } else if split.other.buffer > closed
+            && let Some(split) = &mut self.split
+        {
+            split.other.buffer -= 1;
         }
+    }
 
-        self.file_dialog = None;
-        match kind {
-            FileDialogKind::Open => self.open_file(path),
-            FileDialogKind::SaveAs => self.save_as(path),
+    /// A pane showing the buffer at `index` in the buffer list, with its
+    /// cursor at `cursor`.
+    fn new_pane(&self, index: usize, cursor: &CursorPointer, scroll_top: usize) -> Pane {
+        let document = self.buffers[index].display.document();
+        let mut display = self.new_display(document.clone());
+        display.focus_pointer(cursor);
+        Pane {
+            buffer: index,
+            display,
+            scroll_top,
         }
     }
 
-    /// Open the link dialog, seeded from the cursor context: an existing link
-    /// under the cursor is loaded for editing; a single-paragraph selection
-    /// becomes the text of a new link; otherwise a new link is inserted at the
-    /// cursor.
-    fn open_link_dialog(&mut self) {
-        if let Some(link) = self.display.link_at_cursor() {
-            self.link_edit_range = Some(link.range);
-            self.link_dialog = Some(LinkDialogState::new(
-                link.text,
-                link.target.unwrap_or_default(),
-                true,
+    /// Split the editor area in two, both panes showing the current
+    /// document at the cursor; focus stays in the top (or left) one. An
+    /// existing split just changes direction.
+    fn split_view(&mut self, direction: SplitDirection) {
+        if let Some(split) = &mut self.split {
+            split.direction = direction;
+        } else {
+            let other = self.new_pane(
+                self.active_buffer,
+                &self.display().cursor_pointer(),
+                self.buffer().scroll_top,
+            );
+            self.split = Some(Split {
+                direction,
+                other,
+                focus_second: false,
+                other_area: Rect::default(),
+            });
+        }
+        self.needs_position_rebuild = true;
+    }
+
+    /// Go back to a single pane, keeping the focused one.
+    fn unsplit(&mut self) {
+        if self.split.take().is_none() {
+            self.status_message = Some(("The editor is not split".to_string(), Instant::now()));
+        }
+        self.needs_position_rebuild = true;
+    }
+
+    /// Move the focus to the other pane. Its buffer becomes the current one
+    /// with the pane's cursor and scroll position, and the pane left behind
+    /// keeps showing the previous buffer where it was.
+    fn focus_other_pane(&mut self) {
+        self.sync_other_pane();
+        let Some(split) = &mut self.split else {
+            self.status_message = Some(("The editor is not split".to_string(), Instant::now()));
+            return;
+        };
+        split.focus_second = !split.focus_second;
+        let target = split.other.buffer;
+        let target_cursor = split.other.display.cursor_pointer();
+        let target_scroll = split.other.scroll_top;
+
+        let previous = self.active_buffer;
+        let cursor = self.display().cursor_pointer();
+        let scroll_top = self.buffer().scroll_top;
+        self.show_buffer(target);
+        self.display_mut().focus_pointer(&target_cursor);
+        self.buffer_mut().scroll_top = target_scroll;
+        self.buffer_mut().selection_anchor = None;
+        self.display_mut().set_cursor_following(false);
+        self.needs_position_rebuild = true;
+
+        let pane = self.new_pane(previous, &cursor, scroll_top);
+        if let Some(split) = &mut self.split {
+            split.other = pane;
+        }
+    }
+
+    /// Bring the other pane's copy of its document up to date with the
+    /// edits made to the buffer since it was last drawn.
+    fn sync_other_pane(&mut self) {
+        let Some(split) = &mut self.split else {
+            return;
+        };
+        let source = &mut self.buffers[split.other.buffer].display;
+        split.other.display.sync_with(source);
+    }
+
+    /// Start an untitled document in a new buffer.
+    fn new_document(&mut self) {
+        let buffer = self.new_buffer(Document::new(), None, DocumentFormat::Ftml);
+        self.buffers.push(buffer);
+        self.switch_to_buffer(self.buffer_count() - 1);
+        self.status_message = Some(("New document".to_string(), Instant::now()));
+    }
+
+    /// Open the document at `path` in a new buffer, or show the buffer that
+    /// has it already. A nonexistent path starts a new document there,
+    /// mirroring the CLI.
+    fn open_file(&mut self, path: PathBuf) {
+        if let Some(index) = self.buffer_index_of(&path) {
+            self.switch_to_buffer(index);
+            self.status_message = Some((
+                format!("{} is already open", path.display()),
+                Instant::now(),
             ));
             return;
         }
+        match load_document(&path) {
+            Ok(loaded) => {
+                let message = loaded
+                    .status
+                    .clone()
+                    .unwrap_or_else(|| format!("Opened {}", path.display()));
+                self.add_buffer(loaded, path);
+                self.switch_to_buffer(self.buffer_count() - 1);
+                self.status_message = Some((message, Instant::now()));
+            }
+            Err(err) => {
+                self.status_message = Some((format!("{err:#}"), Instant::now()));
+            }
+        }
+    }
 
-        if let Some(selection) = self.current_selection()
-            && selection.0.paragraph_path == selection.1.paragraph_path
+    /// Save under a new path; the format follows the new extension. On
+    /// failure the previous path and format are restored.
+    fn save_as(&mut self, path: PathBuf) {
+        if self.buffer().protection.is_some()
+            && self
+                .buffer()
+                .file_path
+                .as_deref()
+                .is_some_and(|current| same_file(current, &path))
         {
-            let text = self.display.selection_text(&selection).unwrap_or_default();
-            self.link_edit_range = Some(selection);
-            self.link_dialog = Some(LinkDialogState::new(text, String::new(), false));
+            self.overwrite_prompt = Some(ChoicePrompt::new(
+                &overwrite_prompt::CHOICES,
+                OverwriteChoice::Cancel,
+                (),
+            ));
             return;
         }
+        let after_save = self.after_save.take();
+        let buffer = self.buffer_mut();
+        let previous_path = buffer.file_path.take();
+        let previous_format = buffer.document_format;
+        // Another file is safe to write, whatever became of the previous one.
+        let previous_protection = buffer.protection.take();
+        let previous_stamp = buffer.file_stamp.take();
+        // The new file is locked once it is written.
+        buffer.lock = None;
+        let previous_locked_by = buffer.locked_by.take();
+        buffer.document_format = DocumentFormat::from_path(&path);
+        buffer.file_path = Some(path);
+        match self.save() {
+            Ok(()) => {
+                if let Some(path) = &self.buffer().file_path {
+                    self.status_message =
+                        Some((format!("Saved {}", path.display()), Instant::now()));
+                }
+                if previous_path.is_none() {
+                    recovery::remove(recovery::Origin::Untitled(self.buffer().untitled_number));
+                }
+                if let Some(after_save) = after_save {
+                    self.continue_after_save(after_save);
+                }
+            }
+            Err(err) => {
+                let buffer = self.buffer_mut();
+                buffer.file_path = previous_path;
+                buffer.document_format = previous_format;
+                buffer.protection = previous_protection;
+                buffer.file_stamp = previous_stamp;
+                (buffer.lock, buffer.locked_by) = lock_document(buffer.file_path.as_deref());
+                if previous_locked_by.is_some() {
+                    buffer.locked_by = previous_locked_by;
+                }
+                self.status_message = Some((format!("{err:#}"), Instant::now()));
+            }
+        }
+    }
 
-        let cursor = self.display.cursor_pointer();
-        self.link_edit_range = Some((cursor.clone(), cursor));
-        self.link_dialog = Some(LinkDialogState::new(String::new(), String::new(), false));
+    /// Quit, or — while any buffer has unsaved changes — show the first
+    /// such buffer and ask whether to save them. Each answer but Cancel
+    /// moves on to the next one.
+    fn request_quit(&mut self) {
+        if !self.buffer().dirty
+            && let Some(index) = self.buffer_list().iter().position(|(_, dirty)| *dirty)
+        {
+            self.switch_to_buffer(index);
+        }
+        if self.buffer().dirty {
+            self.quit_prompt = Some(ChoicePrompt::new(
+                &quit_prompt::CHOICES,
+                QuitChoice::Save,
+                AfterSave::Quit,
+            ));
+        } else {
+            self.should_quit = true;
+        }
     }
 
-    /// Handle a key press while the link dialog is open. The dialog is modal:
-    /// every key is consumed. Enter always saves and Esc always cancels,
-    /// whatever has focus; Space activates a focused button.
-    fn handle_link_dialog_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
-        if self.link_dialog.is_none() {
+    /// Finish quitting, or closing the buffer, once the document was saved
+    /// from the quit prompt.
+    fn continue_after_save(&mut self, after_save: AfterSave) {
+        match after_save {
+            AfterSave::Quit => self.request_quit(),
+            AfterSave::CloseBuffer => self.close_buffer(),
+        }
+    }
+
+    /// Handle a key press while the quit prompt is open. The prompt is
+    /// modal: every key is consumed. Enter activates the focused button, Esc
+    /// cancels, and each button can be chosen by its accelerator letter.
+    fn handle_quit_prompt_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        let Some(prompt) = self.quit_prompt.as_mut() else {
             return false;
+        };
+
+        match choice_prompt_key(prompt, code, modifiers) {
+            Some(PromptAnswer::Chosen(choice)) => self.resolve_quit_prompt(choice),
+            Some(PromptAnswer::Dismissed) => self.resolve_quit_prompt(QuitChoice::Cancel),
+            None => {}
         }
+        true
+    }
 
-        let focus = self.link_dialog.as_ref().map(|dialog| dialog.focus());
-        match (code, modifiers) {
-            (KeyCode::Esc, _) => self.cancel_link_dialog(),
-            (KeyCode::Enter, _) => self.accept_link_dialog(),
-            (KeyCode::Tab, _) | (KeyCode::Down, _) => {
-                if let Some(dialog) = self.link_dialog.as_mut() {
-                    dialog.focus_next();
-                }
+    /// Close the quit prompt and carry out `choice`. Saving an untitled
+    /// document goes through the Save As dialog, which continues once the
+    /// document is written; a failed save keeps the document open.
+    /// Discarding closes the buffer.
+    fn resolve_quit_prompt(&mut self, choice: QuitChoice) {
+        let Some(prompt) = self.quit_prompt.take() else {
+            return;
+        };
+        let after_save = prompt.into_context();
+        match choice {
+            QuitChoice::Save if self.buffer().file_path.is_none() => {
+                self.after_save = Some(after_save);
+                self.open_file_dialog(FileDialogKind::SaveAs);
             }
-            (KeyCode::BackTab, _) | (KeyCode::Up, _) => {
-                if let Some(dialog) = self.link_dialog.as_mut() {
-                    dialog.focus_prev();
-                }
+            QuitChoice::Save if self.buffer().protection.is_some() => {
+                self.after_save = Some(after_save);
+                self.overwrite_prompt = Some(ChoicePrompt::new(
+                    &overwrite_prompt::CHOICES,
+                    OverwriteChoice::Cancel,
+                    (),
+                ));
             }
-            // Space activates a focused button; in a text field it types.
-            (KeyCode::Char(' '), m)
-                if !m.contains(KeyModifiers::CONTROL)
-                    && !m.contains(KeyModifiers::ALT)
-                    && focus.is_some_and(LinkField::is_button) =>
-            {
-                self.activate_link_button();
+            QuitChoice::Save => match self.save() {
+                // The file changed on disk; the prompt continues.
+                Ok(()) if self.external_change_prompt.is_some() => {
+                    self.after_save = Some(after_save);
+                }
+                Ok(()) => self.continue_after_save(after_save),
+                Err(err) => {
+                    self.status_message = Some((format!("{err:#}"), Instant::now()));
+                }
+            },
+            QuitChoice::Discard => {
+                self.close_buffer();
+                if after_save == AfterSave::Quit {
+                    self.request_quit();
+                }
             }
-            _ => {
-                let Some(dialog) = self.link_dialog.as_mut() else {
-                    return true;
-                };
-                match (code, modifiers) {
-                    (KeyCode::Left, _) => dialog.move_cursor_left(),
-                    (KeyCode::Right, _) => dialog.move_cursor_right(),
-                    (KeyCode::Home, _) => dialog.move_cursor_start(),
-                    (KeyCode::End, _) => dialog.move_cursor_end(),
-                    (KeyCode::Char('a'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        dialog.move_cursor_start()
-                    }
-                    (KeyCode::Char('e'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        dialog.move_cursor_end()
-                    }
-                    (KeyCode::Char('w'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        dialog.delete_word_backward()
-                    }
-                    (KeyCode::Backspace, m)
-                        if m.contains(KeyModifiers::CONTROL) || m.contains(KeyModifiers::ALT) =>
-                    {
-                        dialog.delete_word_backward()
+            QuitChoice::Cancel => {}
+        }
+    }
+
+    /// Handle a key press while the overwrite prompt is open. The prompt is
+    /// modal: every key is consumed. Esc cancels.
+    fn handle_overwrite_prompt_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        let Some(prompt) = self.overwrite_prompt.as_mut() else {
+            return false;
+        };
+
+        match choice_prompt_key(prompt, code, modifiers) {
+            Some(PromptAnswer::Chosen(choice)) => self.resolve_overwrite_prompt(choice),
+            Some(PromptAnswer::Dismissed) => self.resolve_overwrite_prompt(OverwriteChoice::Cancel),
+            None => {}
+        }
+        true
+    }
+
+    /// Close the overwrite prompt and carry out `choice`. Once the file is
+    /// overwritten it holds the document, so it is no longer protected; a
+    /// failed save keeps it protected. Quitting or closing the buffer
+    /// continues after saving, and stops on Cancel.
+    fn resolve_overwrite_prompt(&mut self, choice: OverwriteChoice) {
+        if self.overwrite_prompt.take().is_none() {
+            return;
+        }
+        match choice {
+            OverwriteChoice::Overwrite => {
+                let protection = self.buffer_mut().protection.take();
+                match self.save() {
+                    // The file changed on disk as well; the prompt continues.
+                    Ok(()) if self.external_change_prompt.is_some() => {}
+                    Ok(()) => {
+                        if let Some(after_save) = self.after_save.take() {
+                            self.continue_after_save(after_save);
+                        }
                     }
-                    (KeyCode::Backspace, _) => dialog.backspace(),
-                    (KeyCode::Delete, _) => dialog.delete(),
-                    (KeyCode::Char(ch), m)
-                        if !m.contains(KeyModifiers::CONTROL) && !m.contains(KeyModifiers::ALT) =>
-                    {
-                        dialog.insert_char(ch)
+                    Err(err) => {
+                        self.buffer_mut().protection = protection;
+                        self.after_save = None;
+                        self.status_message = Some((format!("{err:#}"), Instant::now()));
                     }
-                    _ => {}
                 }
             }
+            OverwriteChoice::SaveAs => self.open_file_dialog(FileDialogKind::SaveAs),
+            OverwriteChoice::Cancel => {
+                self.after_save = None;
+                self.status_message = Some(("Not saved".to_string(), Instant::now()));
+            }
         }
-        true
     }
 
-    /// Activate the focused button (Space). Open launches the browser; Cancel
-    /// and Save mirror the Esc/Enter accelerators.
-    fn activate_link_button(&mut self) {
-        match self.link_dialog.as_ref().map(|dialog| dialog.focus()) {
-            Some(LinkField::Open) => self.open_link_in_browser(),
-            Some(LinkField::Cancel) => self.cancel_link_dialog(),
-            Some(LinkField::Save) => self.accept_link_dialog(),
-            _ => {}
+    /// Handle a key press while the external change prompt is open. The
+    /// prompt is modal: every key is consumed. Up / Down and Page Up / Page
+    /// Down scroll the differences. Esc keeps the document as it is without
+    /// saving it.
+    fn handle_external_change_prompt_key(
+        &mut self,
+        code: KeyCode,
+        modifiers: KeyModifiers,
+    ) -> bool {
+        let page = self.last_viewport_height.saturating_sub(6).max(1);
+        let Some(prompt) = self.external_change_prompt.as_mut() else {
+            return false;
+        };
+
+        let change = prompt.context_mut();
+        match code {
+            KeyCode::Up => change.scroll_by(-1, page),
+            KeyCode::Down => change.scroll_by(1, page),
+            KeyCode::PageUp => change.scroll_by(-(page as isize), page),
+            KeyCode::PageDown => change.scroll_by(page as isize, page),
+            _ => match choice_prompt_key(prompt, code, modifiers) {
+                Some(PromptAnswer::Chosen(choice)) => self.resolve_external_change_prompt(choice),
+                Some(PromptAnswer::Dismissed) => {
+                    let prompt = self.external_change_prompt.take();
+                    if let Some(change) = prompt.map(ChoicePrompt::into_context) {
+                        self.buffer_mut().file_stamp = Some(change.stamp().clone());
+                        let message = if change.saving() {
+                            self.after_save = None;
+                            "Not saved"
+                        } else {
+                            "Kept your version"
+                        };
+                        self.status_message = Some((message.to_string(), Instant::now()));
+                    }
+                }
+                None => {}
+            },
         }
+        true
     }
 
-    /// Dismiss the link dialog without changing the document.
-    fn cancel_link_dialog(&mut self) {
-        self.link_dialog = None;
-        self.link_edit_range = None;
-    }
+    /// Carry out `choice` in the external change prompt. Show Differences
+    /// toggles the differences and leaves the prompt open; the other
+    /// buttons close it. Reload drops the unsaved changes. Keep Mine takes
+    /// the file's new version as seen, so the next save replaces it without
+    /// asking again — right away, if the prompt interrupted saving, and then
+    /// quitting or closing the buffer continues.
+    fn resolve_external_change_prompt(&mut self, choice: ExternalChangeChoice) {
+        if choice == ExternalChangeChoice::ShowDifferences {
+            let differences = match &self.external_change_prompt {
+                Some(prompt) if prompt.context().differences().is_some() => None,
+                Some(_) => match self.disk_differences() {
+                    Ok(differences) => Some(differences),
+                    Err(err) => {
+                        self.status_message = Some((format!("{err:#}"), Instant::now()));
+                        return;
+                    }
+                },
+                None => return,
+            };
+            if let Some(prompt) = self.external_change_prompt.as_mut() {
+                prompt.context_mut().set_differences(differences);
+            }
+            return;
+        }
 
-    /// Apply the link dialog: write the text and target back onto the
-    /// document. An empty target unlinks (keeping the text as plain text); an
-    /// empty text falls back to showing the URL itself.
-    fn accept_link_dialog(&mut self) {
-        let Some(dialog) = self.link_dialog.take() else {
+        let Some(change) = self
+            .external_change_prompt
+            .take()
+            .map(ChoicePrompt::into_context)
+        else {
             return;
         };
-        let range = self.link_edit_range.take();
-        let target = dialog.target().trim().to_string();
-        let text = dialog.text().to_string();
-        let display_text = if text.is_empty() {
-            target.clone()
-        } else {
-            text
+        match choice {
+            ExternalChangeChoice::Reload => {
+                self.after_save = None;
+                match self.reload() {
+                    Ok(()) => {
+                        let name = self.buffer().name();
+                        self.status_message = Some((format!("Reloaded {name}"), Instant::now()));
+                    }
+                    Err(err) => {
+                        self.status_message = Some((format!("{err:#}"), Instant::now()));
+                    }
+                }
+            }
+            ExternalChangeChoice::KeepMine => {
+                self.buffer_mut().file_stamp = Some(change.stamp().clone());
+                if !change.saving() {
+                    self.status_message = Some((
+                        "Kept your version; saving replaces the file on disk".to_string(),
+                        Instant::now(),
+                    ));
+                    return;
+                }
+                match self.save() {
+                    Ok(()) => {
+                        if let Some(after_save) = self.after_save.take() {
+                            self.continue_after_save(after_save);
+                        }
+                    }
+                    Err(err) => {
+                        self.after_save = None;
+                        self.status_message = Some((format!("{err:#}"), Instant::now()));
+                    }
+                }
+            }
+            ExternalChangeChoice::ShowDifferences => {}
+        }
+    }
+
+    /// How the current document differs from its file on disk: lines only
+    /// the file has are removed, lines only saving would write are added.
+    fn disk_differences(&self) -> Result<Vec<DiffLine>> {
+        let Some(path) = &self.buffer().file_path else {
+            return Ok(Vec::new());
         };
-        let target_opt = if target.is_empty() {
-            None
+        let on_disk =
+            fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let mine = write_document(
+            self.display().document(),
+            self.buffer().document_format,
+            self.plain_text_wrap,
+        )?;
+        Ok(diff::unified(
+            &String::from_utf8_lossy(&on_disk),
+            &String::from_utf8_lossy(&mine),
+            2,
+        ))
+    }
+
+    fn render_quit_prompt(&self, frame: &mut Frame, area: Rect) {
+        let Some(prompt) = &self.quit_prompt else {
+            return;
+        };
+        let name = self
+            .buffer()
+            .file_path
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "the untitled document".to_string());
+        let when = if *prompt.context() == AfterSave::CloseBuffer {
+            "closing it"
         } else {
-            Some(target.as_str())
+            "quitting"
         };
+        let buttons = prompt.buttons();
+        self.render_choice_prompt(
+            frame,
+            area,
+            "Unsaved Changes",
+            &format!("Save changes to {name} before {when}?"),
+            &buttons,
+        );
+    }
 
-        if display_text.is_empty() && target_opt.is_none() {
+    fn render_recovery_prompt(&self, frame: &mut Frame, area: Rect) {
+        let Some(prompt) = &self.recovery_prompt else {
             return;
-        }
-
-        if let Some(range) = range
-            && self.display.set_link(&range, &display_text, target_opt)
-        {
-            self.mark_dirty();
-            self.selection_anchor = None;
-            self.display.set_preferred_column(None);
-            self.needs_position_rebuild = true;
-        }
+        };
+        let name = self
+            .buffer()
+            .file_path
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let buttons = prompt.buttons();
+        self.render_choice_prompt(
+            frame,
+            area,
+            "Recover Unsaved Changes",
+            &format!(
+                "{name} has unsaved changes from a session that did not end cleanly. Restore them?"
+            ),
+            &buttons,
+        );
     }
 
-    /// Open the link's target in the user's default browser by handing the URL
-    /// to the platform opener (`xdg-open`, `open`, or `start`). The dialog
-    /// stays open so the field values are not lost.
-    fn open_link_in_browser(&mut self) {
-        let Some(dialog) = self.link_dialog.as_ref() else {
+    fn render_lock_prompt(&self, frame: &mut Frame, area: Rect) {
+        let Some(prompt) = &self.lock_prompt else {
             return;
         };
-        let target = dialog.target().trim().to_string();
-        if target.is_empty() {
-            self.status_message = Some(("No link target to open".to_string(), Instant::now()));
+        let name = self
+            .buffer()
+            .file_path
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let buttons = prompt.buttons();
+        self.render_choice_prompt(
+            frame,
+            area,
+            "File in Use",
+            &format!(
+                "{name} is open in Pure as {}. Open it read-only?",
+                prompt.context()
+            ),
+            &buttons,
+        );
+    }
+
+    fn render_overwrite_prompt(&self, frame: &mut Frame, area: Rect) {
+        let Some(prompt) = &self.overwrite_prompt else {
             return;
-        }
-        if self.interactive {
-            match open_in_browser(&target) {
-                Ok(()) => {
-                    self.status_message = Some((format!("Opening {target}"), Instant::now()));
-                }
-                Err(err) => {
-                    self.status_message =
-                        Some((format!("Could not open link: {err}"), Instant::now()));
-                }
-            }
-        } else {
-            self.status_message = Some((format!("Opening {target}"), Instant::now()));
-        }
+        };
+        let name = self
+            .buffer()
+            .file_path
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let buttons = prompt.buttons();
+        self.render_choice_prompt(
+            frame,
+            area,
+            "Overwrite File",
+            &format!("{name} could not be parsed. Replace it with the document shown here?"),
+            &buttons,
+        );
     }
 
-    fn render_link_dialog(&self, frame: &mut Frame, area: Rect) {
-        let Some(dialog) = &self.link_dialog else {
+    fn render_external_change_prompt(&self, frame: &mut Frame, area: Rect) {
+        let Some(prompt) = &self.external_change_prompt else {
             return;
         };
-        if area.width < 24 || area.height < 9 {
+        let name = self
+            .buffer()
+            .file_path
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let buttons = prompt.buttons();
+        let change = prompt.context();
+        let Some(differences) = change.differences() else {
+            self.render_choice_prompt(
+                frame,
+                area,
+                "File Changed on Disk",
+                &format!("{name} was changed by another program. Reload it, or keep your version?"),
+                &buttons,
+            );
+            return;
+        };
+        if area.width < 24 || area.height < 10 {
             return;
         }
 
-        let theme = self.display.theme();
+        let theme = self.display().theme();
         let popup_style = theme.menu_style();
 
-        // Two input rows, a separator, the Open button, and a footer, plus the
-        // border.
-        let width = 60.min(area.width.saturating_sub(4));
-        let height = 7u16.min(area.height.saturating_sub(2));
+        // As much of the screen as there is, up to a hundred columns.
+        let width = 100.min(area.width.saturating_sub(4));
+        let height = area.height.saturating_sub(4);
         let popup_area = Rect::new(
             area.x + (area.width.saturating_sub(width)) / 2,
             area.y + (area.height.saturating_sub(height)) / 2,
@@ -2938,206 +6580,142 @@ impl App {
         );
 
         frame.render_widget(Clear, popup_area);
-
-        let title = if dialog.editing() {
-            "Edit Link"
-        } else {
-            "Insert Link"
-        };
         let block = Block::default()
-            .title(title)
+            .title(format!("{name}: - on disk, + yours"))
             .borders(Borders::ALL)
             .style(popup_style)
             .border_style(Style::default().fg(Color::Gray));
         let inner = block.inner(popup_area);
         frame.render_widget(block, popup_area);
-        if inner.width < 10 || inner.height < 5 {
+        if inner.width < 10 || inner.height < 4 {
             return;
         }
 
-        const LABEL_WIDTH: u16 = 6;
-        let field_x = inner.x + LABEL_WIDTH;
-        let field_width = inner.width.saturating_sub(LABEL_WIDTH).max(1);
-
-        // The text and URL input rows, each prefixed with its label.
-        let fields = [
-            (LinkField::Text, "Text:", dialog.text(), inner.y),
-            (LinkField::Target, "URL:", dialog.target(), inner.y + 1),
-        ];
-        for (field, label, value, y) in fields {
-            frame.render_widget(
-                Paragraph::new(Line::from(Span::styled(label, theme.menu_disabled_style())))
-                    .style(popup_style),
-                Rect::new(inner.x, y, LABEL_WIDTH, 1),
-            );
-            let focused = dialog.focus() == field;
-            let caret = if focused {
-                dialog.active_cursor().unwrap_or(0)
-            } else {
-                0
-            };
-            let visible = field_width as usize;
-            let skip = if focused {
-                (caret + 1).saturating_sub(visible)
-            } else {
-                0
-            };
-            let shown: String = value.chars().skip(skip).take(visible).collect();
-            frame.render_widget(
-                Paragraph::new(shown).style(popup_style),
-                Rect::new(field_x, y, field_width, 1),
-            );
-            if focused {
-                frame.set_cursor_position(Position::new(field_x + (caret - skip) as u16, y));
-            }
-        }
-
-        // Separator below the inputs.
-        let separator = "─".repeat(inner.width as usize);
-        frame.render_widget(
-            Paragraph::new(Line::from(Span::styled(
-                separator,
-                Style::default().fg(Color::DarkGray),
-            )))
-            .style(popup_style),
-            Rect::new(inner.x, inner.y + 2, inner.width, 1),
-        );
-
-        // Button row: Open on the left, Cancel and Save flush right.
-        let button_row = inner.y + 3;
-        let has_target = !dialog.target().trim().is_empty();
-        let button_style = |field: LinkField, enabled: bool| -> Style {
-            if dialog.focus() == field {
-                theme.menu_selected_style()
-            } else if enabled {
-                popup_style
-            } else {
-                popup_style.patch(theme.menu_disabled_style())
-            }
-        };
-
-        let open = "[ Open ]";
-        let cancel = "[ Cancel ]";
-        let save = "[ Save ]";
-        let open_w = open.chars().count() as u16;
-        let cancel_w = cancel.chars().count() as u16;
-        let save_w = save.chars().count() as u16;
-        let open_x = inner.x;
-        let save_x = inner.x + inner.width.saturating_sub(save_w);
-        let cancel_x = save_x.saturating_sub(cancel_w + 1);
-
-        let buttons = [
-            (LinkField::Open, open, open_x, open_w, has_target),
-            (LinkField::Cancel, cancel, cancel_x, cancel_w, true),
-            (LinkField::Save, save, save_x, save_w, true),
-        ];
-        for (field, label, x, w, enabled) in buttons {
-            frame.render_widget(
-                Paragraph::new(Line::from(Span::styled(
-                    label,
-                    button_style(field, enabled),
-                )))
-                .style(popup_style),
-                Rect::new(x, button_row, w, 1),
-            );
-            // Park the caret on the focused button so it does not show through
-            // the popup at the document cursor's position.
-            if dialog.focus() == field {
-                frame.set_cursor_position(Position::new(x, button_row));
-            }
-        }
-
-        // Footer hints.
-        let hint = if dialog.focus().is_button() {
-            "Space: activate   Enter: save   Esc: cancel"
+        // The differences, a blank line, and the button row.
+        let rows = inner.height as usize - 2;
+        let first = change.scroll().min(differences.len().saturating_sub(rows));
+        let lines: Vec<Line> = if differences.is_empty() {
+            vec![Line::from("The file holds what saving would write.")]
         } else {
-            "Enter: save   Esc: cancel   Tab: next"
+            differences[first..]
+                .iter()
+                .take(rows)
+                .map(|line| {
+                    let (prefix, style) = match line.kind {
+                        LineKind::Same => ("  ", popup_style),
+                        LineKind::Removed => ("- ", popup_style.fg(Color::Red)),
+                        LineKind::Added => ("+ ", popup_style.fg(Color::Green)),
+                        LineKind::Gap => ("  ", popup_style.add_modifier(Modifier::DIM)),
+                    };
+                    let text = match line.kind {
+                        LineKind::Gap => "…",
+                        _ => line.text.as_str(),
+                    };
+                    Line::from(Span::styled(format!("{prefix}{text}"), style))
+                })
+                .collect()
         };
         frame.render_widget(
-            Paragraph::new(Line::from(Span::styled(hint, theme.menu_disabled_style())))
-                .style(popup_style),
-            Rect::new(inner.x, inner.y + 4, inner.width, 1),
+            Paragraph::new(lines).style(popup_style),
+            Rect::new(inner.x, inner.y, inner.width, rows as u16),
         );
+        self.render_prompt_buttons(frame, inner, &buttons);
     }
 
-    /// Swap in `document` as the current document and reset all
-    /// per-document state (undo history, selection, scroll, dirty flag).
-    /// Reveal codes mode survives the swap.
-    fn replace_document(
-        &mut self,
-        document: Document,
-        path: Option<PathBuf>,
-        format: DocumentFormat,
+    /// Draw a small centered dialog asking `question`, with a row of
+    /// `(label, focused)` buttons flush right. Each label's first letter is
+    /// underlined as its accelerator.
+    fn render_choice_prompt(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        title: &str,
+        question: &str,
+        buttons: &[(&str, bool)],
     ) {
-        let reveal_codes = self.display.reveal_codes();
-        let mut editor = DocumentEditor::new(document);
-        editor.ensure_cursor_selectable();
-        self.display = EditorDisplay::new(editor);
-        self.display.set_reveal_codes(reveal_codes);
-        self.file_path = path;
-        self.document_format = format;
-        self.dirty = false;
-        self.confirm_new = false;
-        self.scroll_top = 0;
-        self.selection_anchor = None;
-        self.needs_position_rebuild = true;
-    }
-
-    /// Start an untitled document. With unsaved changes the first call only
-    /// warns; repeating the command discards them.
-    fn new_document(&mut self) {
-        if self.dirty && !self.confirm_new {
-            self.confirm_new = true;
-            self.status_message = Some((
-                "Unsaved changes — select New again to discard them".to_string(),
-                Instant::now(),
-            ));
+        if area.width < 24 || area.height < 8 {
             return;
         }
-        self.replace_document(Document::new(), None, DocumentFormat::Ftml);
-        self.status_message = Some(("New document".to_string(), Instant::now()));
-    }
 
-    /// Replace the current document with the one loaded from `path`. A
-    /// nonexistent path starts a new document there, mirroring the CLI.
-    fn open_file(&mut self, path: PathBuf) {
-        match load_document(&path) {
-            Ok((document, format, message)) => {
-                let message = message.unwrap_or_else(|| format!("Opened {}", path.display()));
-                self.replace_document(document, Some(path), format);
-                self.status_message = Some((message, Instant::now()));
-            }
-            Err(err) => {
-                self.status_message = Some((format!("{err:#}"), Instant::now()));
-            }
+        let theme = self.display().theme();
+        let popup_style = theme.menu_style();
+
+        // Two lines for the question, a blank line, and the button row, plus
+        // the border.
+        let width = 50.min(area.width.saturating_sub(4));
+        let height = 6u16.min(area.height.saturating_sub(2));
+        let popup_area = Rect::new(
+            area.x + (area.width.saturating_sub(width)) / 2,
+            area.y + (area.height.saturating_sub(height)) / 2,
+            width,
+            height,
+        );
+
+        frame.render_widget(Clear, popup_area);
+        let block = Block::default()
+            .title(title.to_string())
+            .borders(Borders::ALL)
+            .style(popup_style)
+            .border_style(Style::default().fg(Color::Gray));
+        let inner = block.inner(popup_area);
+        frame.render_widget(block, popup_area);
+        if inner.width < 10 || inner.height < 4 {
+            return;
         }
+
+        frame.render_widget(
+            Paragraph::new(question.to_string())
+                .style(popup_style)
+                .wrap(Wrap { trim: true }),
+            Rect::new(inner.x, inner.y, inner.width, 2),
+        );
+        self.render_prompt_buttons(frame, inner, buttons);
     }
 
-    /// Save under a new path; the format follows the new extension. On
-    /// failure the previous path and format are restored.
-    fn save_as(&mut self, path: PathBuf) {
-        let previous_path = self.file_path.take();
-        let previous_format = self.document_format;
-        self.document_format = DocumentFormat::from_path(&path);
-        self.file_path = Some(path);
-        match self.save() {
-            Ok(()) => {
-                if let Some(path) = &self.file_path {
-                    self.status_message =
-                        Some((format!("Saved {}", path.display()), Instant::now()));
-                }
-            }
-            Err(err) => {
-                self.file_path = previous_path;
-                self.document_format = previous_format;
-                self.status_message = Some((format!("{err:#}"), Instant::now()));
-            }
+    /// Draw a prompt's `(label, focused)` buttons flush right on the last
+    /// row of `inner`, each label's first letter underlined as its
+    /// accelerator.
+    fn render_prompt_buttons(&self, frame: &mut Frame, inner: Rect, buttons: &[(&str, bool)]) {
+        let theme = self.display().theme();
+        let popup_style = theme.menu_style();
+        let labels: Vec<String> = buttons
+            .iter()
+            .map(|(label, _)| format!("[ {label} ]"))
+            .collect();
+        let total: u16 = labels
+            .iter()
+            .map(|text| text.chars().count() as u16 + 1)
+            .sum::<u16>()
+            .saturating_sub(1);
+        let mut x = inner.x + inner.width.saturating_sub(total);
+        let y = inner.y + inner.height - 1;
+        for (text, (_, focused)) in labels.iter().zip(buttons) {
+            let style = if *focused {
+                theme.menu_selected_style()
+            } else {
+                popup_style
+            };
+            let (before, rest) = text.split_at(2);
+            let (accel, after) = rest.split_at(1);
+            let line = Line::from(vec![
+                Span::styled(before.to_string(), style),
+                Span::styled(accel.to_string(), style.add_modifier(Modifier::UNDERLINED)),
+                Span::styled(after.to_string(), style),
+            ]);
+            let button_width = text.chars().count() as u16;
+            frame.render_widget(
+                Paragraph::new(line).style(popup_style),
+                Rect::new(x, y, button_width.min(inner.width), 1),
+            );
+            x += button_width + 1;
         }
     }
 
     fn mark_dirty(&mut self) {
-        self.dirty = true;
-        self.confirm_new = false;
+        self.buffer_mut().dirty = true;
+        self.buffer_mut()
+            .autosave_due
+            .get_or_insert_with(Instant::now);
         // EditorDisplay now handles layout updates automatically in its wrapper methods
         // (insert_char, delete, backspace, etc.) which includes position tracking via
         // incremental updates. No need to force a full re-render here.
@@ -3189,7 +6767,7 @@ impl App {
             content_words + children_words + entries_words + checklist_words
         }
 
-        self.display
+        self.display()
             .document()
             .paragraphs
             .iter()
@@ -3198,7 +6776,7 @@ impl App {
     }
 
     fn cursor_position_text(&self) -> String {
-        if let Some(position) = self.display.cursor_visual() {
+        if let Some(position) = self.display().cursor_visual() {
             let line = position.content_line + 1;
             let column = usize::from(position.content_column) + 1;
             format!("{}:{}", line, column)
@@ -3208,7 +6786,7 @@ impl App {
     }
 
     fn breadcrumbs_text(&self) -> String {
-        if let Some(labels) = self.display.cursor_breadcrumbs()
+        if let Some(labels) = self.display().cursor_breadcrumbs()
             && !labels.is_empty()
         {
             labels.join(" > ")
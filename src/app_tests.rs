@@ -4,6 +4,7 @@
 use std::io::Cursor;
 
 use super::*;
+use crate::test_harness::TempDir;
 
 /// All span text in a document, concatenated, for content assertions that
 /// don't care about exact structure or styling. Styled spans carry their text
@@ -28,17 +29,16 @@ fn doc_text(document: &Document) -> String {
 /// Save `document` under a temp file with `extension`, reload it, and return
 /// the reloaded document together with the format `load_document` detected.
 fn save_then_load(extension: &str, document: Document) -> (Document, DocumentFormat) {
-    let path = std::env::temp_dir().join(format!("pure_roundtrip_test.{extension}"));
-    let _ = fs::remove_file(&path);
+    let dir = TempDir::new("roundtrip");
+    let path = dir.join(format!("roundtrip_test.{extension}"));
 
     let format = DocumentFormat::from_path(&path);
     let mut app = App::new(document, Some(path.clone()), format, None);
     app.set_interactive(false);
     app.save().expect("save document");
 
-    let (reloaded, detected, _) = load_document(&path).expect("reload document");
-    let _ = fs::remove_file(&path);
-    (reloaded, detected)
+    let loaded = load_document(&path).expect("reload document");
+    (loaded.document, loaded.format)
 }
 
 #[test]
@@ -52,6 +52,9 @@ fn from_path_detects_every_format() {
         ("capsule.gmi", DocumentFormat::Gemini),
         ("capsule.gemini", DocumentFormat::Gemini),
         ("doc.ftml", DocumentFormat::Ftml),
+        ("notes.txt", DocumentFormat::PlainText),
+        ("NOTES.TEXT", DocumentFormat::PlainText),
+        // New files without a known extension are FTML.
         ("README", DocumentFormat::Ftml),
     ];
     for (name, expected) in cases {
@@ -63,6 +66,67 @@ fn from_path_detects_every_format() {
     }
 }
 
+#[test]
+fn sniffing_recognizes_files_without_a_known_extension() {
+    let cases = [
+        (
+            "<!DOCTYPE html>\n<html><body><p>Hi</p></body></html>",
+            DocumentFormat::Html,
+        ),
+        ("<p>Hello <b>world</b></p>\n", DocumentFormat::Ftml),
+        ("<div><span>Not FTML</span></div>", DocumentFormat::Html),
+        ("# Title\n\nSome text.\n", DocumentFormat::Markdown),
+        (
+            "See [the docs](https://example.com).\n",
+            DocumentFormat::Markdown,
+        ),
+        (
+            "Shopping:\n- milk\n- #2 pencils\n",
+            DocumentFormat::PlainText,
+        ),
+        ("", DocumentFormat::PlainText),
+    ];
+    for (content, expected) in cases {
+        assert_eq!(DocumentFormat::sniff(content), expected, "{content:?}");
+    }
+}
+
+#[test]
+fn unknown_files_load_by_their_content() {
+    let dir = TempDir::new("sniff");
+    let path = dir.join("NOTES");
+    fs::write(&path, "Milk\nEggs\n\nCall Bob").expect("write notes");
+
+    let loaded = load_document(&path).expect("load notes");
+    assert_eq!(loaded.format, DocumentFormat::PlainText);
+    assert_eq!(loaded.status, None);
+    assert_eq!(loaded.document.paragraphs.len(), 2);
+    assert_eq!(doc_text(&loaded.document), "Milk\nEggsCall Bob");
+
+    // Saving keeps the file plain text rather than turning it into FTML.
+    let mut app = App::new(loaded.document, Some(path.clone()), loaded.format, None);
+    app.set_interactive(false);
+    app.save().expect("save notes");
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "Milk\nEggs\n\nCall Bob\n"
+    );
+}
+
+#[test]
+fn plain_text_round_trips_through_save_and_load() {
+    let source = parse(Cursor::new(
+        "<h1>Title</h1><p>A <b>plain</b> paragraph.<br />Second line.</p>",
+    ))
+    .expect("parse source");
+
+    let (reloaded, format) = save_then_load("txt", source);
+
+    assert_eq!(format, DocumentFormat::PlainText);
+    assert_eq!(reloaded.paragraphs.len(), 2);
+    assert_eq!(doc_text(&reloaded), "TitleA plain paragraph.\nSecond line.");
+}
+
 #[test]
 fn html_round_trips_through_save_and_load() {
     let source =
@@ -95,8 +159,905 @@ fn gemini_round_trips_through_save_and_load() {
     assert_eq!(format, DocumentFormat::Gemini);
     let text = doc_text(&reloaded);
     assert!(text.contains("Title"), "heading survived: {text:?}");
+    assert!(text.contains("plain paragraph"), "body survived: {text:?}");
+}
+
+#[test]
+fn autosave_writes_recovery_file_until_saved() {
+    let dir = TempDir::new("autosave");
+    let path = dir.join("notes.ftml");
+    let recovery_path = dir.join(".notes.ftml.pure-recovery");
+
+    let document = parse(Cursor::new("<p>Hello</p>")).expect("parse source");
+    let mut app = App::new(document, Some(path.clone()), DocumentFormat::Ftml, None);
+    app.set_interactive(false);
+
+    // Nothing to autosave without changes.
+    app.on_tick();
+    assert!(!recovery_path.exists());
+
+    app.display_mut().insert_char('X');
+    app.mark_dirty();
+    app.on_tick();
+    assert!(!recovery_path.exists(), "autosave waits for the interval");
+
+    app.buffer_mut().autosave_due = Instant::now().checked_sub(recovery::AUTOSAVE_INTERVAL);
+    app.on_tick();
+    let recovered = fs::read_to_string(&recovery_path).expect("recovery file written");
+    assert!(recovered.contains("XHello"), "got: {recovered}");
+    assert_eq!(app.buffer().autosave_due, None);
+
+    app.save().expect("save document");
+    assert!(!recovery_path.exists(), "saving removes the recovery file");
+}
+
+#[test]
+fn buffers_keep_their_own_document_state() {
+    let first = parse(Cursor::new("<p>First</p>")).expect("parse source");
+    let mut app = App::new(
+        first,
+        Some(PathBuf::from("first.ftml")),
+        DocumentFormat::Ftml,
+        None,
+    );
+    app.set_interactive(false);
+    app.display_mut().insert_char('X');
+    app.mark_dirty();
+    app.buffer_mut().scroll_top = 3;
+
+    let second = LoadedDocument {
+        document: markdown::parse(Cursor::new("Second\n")).expect("parse source"),
+        format: DocumentFormat::Markdown,
+        status: None,
+        recovery: None,
+        parse_failure: None,
+    };
+    app.add_buffer(second, PathBuf::from("second.md"));
+    assert_eq!(app.buffer_count(), 2);
+    assert_eq!(
+        doc_text(app.display().document()),
+        "XFirst",
+        "still in view"
+    );
+
+    // Paste Previous would undo an edit of the other document.
+    app.start_paste_run(0, "X".to_string());
+    app.switch_to_buffer(1);
+    assert!(app.paste_run.is_none());
+    assert_eq!(doc_text(app.display().document()), "Second");
+    assert_eq!(app.buffer().document_format, DocumentFormat::Markdown);
+    assert!(!app.buffer().dirty);
+    assert_eq!(app.buffer().scroll_top, 0);
+
+    app.switch_to_buffer(0);
+    assert_eq!(app.buffer().file_path, Some(PathBuf::from("first.ftml")));
+    assert_eq!(app.buffer().document_format, DocumentFormat::Ftml);
+    assert!(app.buffer().dirty);
+    assert_eq!(app.buffer().scroll_top, 3);
+    app.undo();
+    assert_eq!(
+        doc_text(app.display().document()),
+        "First",
+        "the undo history came along"
+    );
+}
+
+#[test]
+fn background_buffers_are_autosaved() {
+    let dir = TempDir::new("autosave-buffers");
+    let path = dir.join("notes.ftml");
+    let recovery_path = dir.join(".notes.ftml.pure-recovery");
+
+    let document = parse(Cursor::new("<p>Hello</p>")).expect("parse source");
+    let mut app = App::new(document, Some(path.clone()), DocumentFormat::Ftml, None);
+    app.set_interactive(false);
+    app.display_mut().insert_char('X');
+    app.mark_dirty();
+    app.new_document();
+    assert_eq!(app.active_buffer, 1);
+
+    app.buffers[0].autosave_due = Instant::now().checked_sub(recovery::AUTOSAVE_INTERVAL);
+    app.on_tick();
+    let recovered = fs::read_to_string(&recovery_path).expect("recovery file written");
+    assert!(recovered.contains("XHello"), "got: {recovered}");
+    assert_eq!(app.buffers[0].autosave_due, None);
+}
+
+#[test]
+fn ftml_errors_are_placed_where_the_parser_stopped() {
+    let line_of = |content: &str| {
+        let err = parse(Cursor::new(content)).expect_err("content fails to parse");
+        ftml_error_line(content, &err)
+    };
+    // The offending text appears earlier, where it is fine.
+    assert_eq!(
+        line_of("<p>Fine</p>\n<p>stray text</p>\nstray text\n"),
+        Some(2)
+    );
+    assert_eq!(
+        line_of("<p>One</p>\n<p>Two</p>\n<p><h1>Three</h1></p>\n"),
+        Some(2)
+    );
+    assert_eq!(line_of("<p>One</p>\n<p><b>Two</p>\n"), Some(1));
+    assert_eq!(line_of("<ul>\n<li>One</li>\n"), Some(1));
+    // Any start of the file ends too early, too.
+    assert_eq!(line_of("<p>One</p>\n<p><b>Two\n"), None);
+}
+
+#[test]
+fn files_that_fail_to_parse_open_read_only() {
+    let dir = TempDir::new("parse-failure");
+    let path = dir.join("broken.ftml");
+    let original = "<p>Fine</p>\n<p>Also fine</p>\nstray text\n";
+    fs::write(&path, original).expect("write broken file");
+
+    let loaded = load_document(&path).expect("load broken file");
+    let failure = loaded.parse_failure.expect("parse failure reported");
+    assert_eq!(failure.line, Some(2));
     assert!(
-        text.contains("plain paragraph"),
-        "body survived: {text:?}"
+        loaded
+            .status
+            .as_deref()
+            .unwrap_or_default()
+            .contains("line 3"),
+        "got: {:?}",
+        loaded.status
+    );
+    assert_eq!(
+        loaded.document.paragraphs,
+        vec![
+            tdoc::Paragraph::new_code_block()
+                .with_content(vec![tdoc::Span::new_text(original.trim_end())])
+        ]
+    );
+
+    let mut app = App::new(loaded.document, Some(path.clone()), loaded.format, None);
+    app.set_interactive(false);
+    app.protect(failure);
+    assert_eq!(
+        app.display().cursor_pointer().offset,
+        "<p>Fine</p>\n<p>Also fine</p>\n".len(),
+        "the cursor starts on the offending line"
+    );
+
+    app.handle_event(Event::Key(KeyEvent::new(
+        KeyCode::Char('x'),
+        KeyModifiers::NONE,
+    )))
+    .unwrap();
+    app.handle_event(Event::Paste("pasted".to_string()))
+        .unwrap();
+    assert!(!app.buffer().dirty);
+    assert_eq!(doc_text(app.display().document()), original.trim_end());
+
+    // Saving over the file asks first, and Cancel leaves it alone.
+    app.save().expect("save");
+    assert!(app.overwrite_prompt.is_some());
+    app.handle_event(Event::Key(KeyEvent::new(
+        KeyCode::Enter,
+        KeyModifiers::NONE,
+    )))
+    .unwrap();
+    assert!(app.overwrite_prompt.is_none());
+    assert_eq!(fs::read_to_string(&path).unwrap(), original);
+}
+
+#[test]
+fn opening_as_plain_text_makes_the_file_editable() {
+    let dir = TempDir::new("open-as-text");
+    let path = dir.join("broken.ftml");
+    fs::write(&path, "<p>Fine</p>\n\nstray text\n").expect("write broken file");
+
+    let loaded = load_document(&path).expect("load broken file");
+    let mut app = App::new(loaded.document, Some(path.clone()), loaded.format, None);
+    app.set_interactive(false);
+    app.protect(loaded.parse_failure.expect("parse failure reported"));
+
+    app.execute_app_action(AppAction::OpenAsPlainText).unwrap();
+    assert_eq!(app.buffer().document_format, DocumentFormat::PlainText);
+    assert_eq!(app.display().document().paragraphs.len(), 2);
+    app.handle_event(Event::Key(KeyEvent::new(
+        KeyCode::Char('>'),
+        KeyModifiers::NONE,
+    )))
+    .unwrap();
+    assert!(app.buffer().dirty);
+
+    // Still not written without asking; Overwrite writes the plain text.
+    app.save().expect("save");
+    assert!(app.overwrite_prompt.is_some());
+    app.handle_event(Event::Key(KeyEvent::new(
+        KeyCode::Char('o'),
+        KeyModifiers::NONE,
+    )))
+    .unwrap();
+    assert!(!app.buffer().dirty);
+    assert_eq!(app.buffer().protection, None);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "><p>Fine</p>\n\nstray text\n"
+    );
+}
+
+#[test]
+fn a_format_override_ignores_the_extension() {
+    let dir = TempDir::new("format-override");
+    let path = dir.join("notes.txt");
+    fs::write(&path, "# Notes\n\nSome **bold** text.\n").expect("write file");
+
+    let loaded = load_document(&path).expect("load as plain text");
+    assert_eq!(loaded.format, DocumentFormat::PlainText);
+    let loaded = load_document_as(&path, Some(DocumentFormat::Markdown)).expect("load as Markdown");
+    assert_eq!(loaded.format, DocumentFormat::Markdown);
+    assert!(matches!(
+        loaded.document.paragraphs[0],
+        tdoc::Paragraph::Header1 { .. }
+    ));
+    assert_eq!(
+        DocumentFormat::from_name("GMI"),
+        Some(DocumentFormat::Gemini)
+    );
+}
+
+#[test]
+fn read_only_mode_refuses_edits_and_saving() {
+    let dir = TempDir::new("read-only");
+    let path = dir.join("notes.ftml");
+    fs::write(&path, "<p>Hello</p>\n").expect("write file");
+
+    let loaded = load_document(&path).expect("load file");
+    let mut app = App::new(loaded.document, Some(path.clone()), loaded.format, None);
+    app.set_interactive(false);
+    app.set_read_only(true);
+    app.handle_event(Event::Key(KeyEvent::new(
+        KeyCode::Char('x'),
+        KeyModifiers::NONE,
+    )))
+    .unwrap();
+    assert!(!app.buffer().dirty);
+    assert_eq!(doc_text(app.display().document()), "Hello");
+    assert!(
+        app.status_message
+            .as_ref()
+            .is_some_and(|(message, _)| message.contains("--readonly"))
+    );
+    assert!(!app.app_action_enabled(AppAction::Save, false));
+
+    fs::write(&path, "changed elsewhere").expect("rewrite file");
+    app.execute_app_action(AppAction::Save).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "changed elsewhere");
+}
+
+fn press(app: &mut App, code: KeyCode) {
+    app.handle_event(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+        .unwrap();
+}
+
+#[test]
+fn standard_input_is_saved_for_standard_output() {
+    let loaded = read_document("Hello\n".to_string(), None);
+    assert_eq!(loaded.format, DocumentFormat::PlainText);
+    let mut app = App::new(loaded.document, None, loaded.format, None);
+    app.set_interactive(false);
+    app.use_standard_io();
+    assert_eq!(app.buffer_list()[0].0, "Standard Input");
+    assert_eq!(app.standard_output(), None);
+
+    press(&mut app, KeyCode::End);
+    press(&mut app, KeyCode::Char('!'));
+    app.save().expect("save");
+    assert!(app.file_dialog.is_none(), "no file name is asked for");
+    assert!(!app.buffer().dirty);
+    assert_eq!(app.standard_output(), Some(&b"Hello!\n"[..]));
+}
+
+#[test]
+fn commit_message_comments_cannot_be_changed() {
+    let dir = TempDir::new("commit");
+    let path = dir.join("COMMIT_EDITMSG");
+    let comments = "# Please enter the commit message for your changes.\n#\n# On branch main\n";
+    fs::write(&path, format!("\n{comments}")).expect("write template");
+
+    let loaded = crate::commit_message::load(&path).expect("load commit message");
+    let mut app = App::new(loaded.document, Some(path.clone()), loaded.format, None);
+    app.set_interactive(false);
+    app.edit_commit_message();
+    for ch in "Fix it".chars() {
+        press(&mut app, KeyCode::Char(ch));
+    }
+    // Deleting at the end of the message would pull the comments in.
+    press(&mut app, KeyCode::Delete);
+    assert_eq!(app.display().document().paragraphs.len(), 2);
+    assert_eq!(
+        app.status_message
+            .as_ref()
+            .map(|(message, _)| message.as_str()),
+        Some(COMMENTS_READ_ONLY)
+    );
+    press(&mut app, KeyCode::Down);
+    press(&mut app, KeyCode::Char('x'));
+    press(&mut app, KeyCode::Backspace);
+    press(&mut app, KeyCode::Enter);
+    assert_eq!(
+        doc_text(app.display().document()),
+        format!("Fix it{}", comments.trim_end())
+    );
+
+    assert!(app.commit_aborted());
+    app.save().expect("save");
+    assert!(!app.commit_aborted());
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        format!("Fix it\n\n{comments}")
+    );
+
+    // Discarding the changes closes the message unsaved, which aborts the
+    // commit all the same.
+    let loaded = crate::commit_message::load(&path).expect("load commit message");
+    let mut app = App::new(loaded.document, Some(path.clone()), loaded.format, None);
+    app.set_interactive(false);
+    app.edit_commit_message();
+    press(&mut app, KeyCode::Char('!'));
+    app.execute_app_action(AppAction::Quit).unwrap();
+    press(&mut app, KeyCode::Char('d'));
+    assert!(app.should_quit());
+    assert!(app.commit_aborted());
+}
+
+#[test]
+fn backspace_does_not_join_text_to_a_comment_above() {
+    let document = crate::commit_message::parse("Subject\n# one\n# two\nBelow\n");
+    let mut app = App::new(document, None, DocumentFormat::PlainText, None);
+    app.set_interactive(false);
+    app.edit_commit_message();
+    press(&mut app, KeyCode::Down);
+    press(&mut app, KeyCode::Down);
+    press(&mut app, KeyCode::Down);
+    press(&mut app, KeyCode::Home);
+    press(&mut app, KeyCode::Backspace);
+    assert_eq!(app.display().document().paragraphs.len(), 3);
+    press(&mut app, KeyCode::Char('>'));
+    assert_eq!(
+        doc_text(app.display().document()),
+        "Subject# one\n# two>Below"
+    );
+}
+
+fn status(app: &App) -> Option<&str> {
+    app.status_message
+        .as_ref()
+        .map(|(message, _)| message.as_str())
+}
+
+#[test]
+fn a_clean_document_is_reloaded_when_its_file_changes() {
+    let dir = TempDir::new("reload");
+    let path = dir.join("notes.txt");
+    fs::write(&path, "First line\nSecond line\n").expect("write file");
+
+    let loaded = load_document(&path).expect("load");
+    let mut app = App::new(loaded.document, Some(path.clone()), loaded.format, None);
+    app.set_interactive(false);
+    press(&mut app, KeyCode::Down);
+    press(&mut app, KeyCode::Right);
+    let offset = app.display().cursor_global_char_offset();
+    assert!(!app.on_tick(), "nothing changed yet");
+    assert_eq!(status(&app), None);
+
+    fs::write(&path, "First line, edited elsewhere\nSecond line\n").expect("change file");
+    assert!(app.on_tick(), "the reload is drawn right away");
+    assert!(doc_text(app.display().document()).contains("edited elsewhere"));
+    assert!(!app.buffer().dirty);
+    assert_eq!(app.display().cursor_global_char_offset(), offset);
+    assert!(status(&app).is_some_and(|message| message.starts_with("Reloaded")));
+}
+
+#[test]
+fn unsaved_changes_are_not_overwritten_by_the_file_or_the_other_way_round() {
+    let dir = TempDir::new("external");
+    let path = dir.join("notes.txt");
+    fs::write(&path, "Mine\n").expect("write file");
+
+    let loaded = load_document(&path).expect("load");
+    let mut app = App::new(loaded.document, Some(path.clone()), loaded.format, None);
+    app.set_interactive(false);
+    press(&mut app, KeyCode::End);
+    press(&mut app, KeyCode::Char('!'));
+
+    // Noticed while editing: keeping the document asks nothing more.
+    fs::write(&path, "Theirs\n").expect("change file");
+    assert!(app.on_tick());
+    assert!(app.external_change_prompt.is_some());
+    press(&mut app, KeyCode::Char('s'));
+    let differences = app
+        .external_change_prompt
+        .as_ref()
+        .and_then(|prompt| prompt.context().differences())
+        .expect("differences shown")
+        .to_vec();
+    assert_eq!(
+        differences
+            .iter()
+            .map(|line| (line.kind, line.text.as_str()))
+            .collect::<Vec<_>>(),
+        vec![(LineKind::Removed, "Theirs"), (LineKind::Added, "Mine!")]
+    );
+    press(&mut app, KeyCode::Char('k'));
+    assert!(app.external_change_prompt.is_none());
+    assert!(app.buffer().dirty);
+    app.on_tick();
+    assert!(app.external_change_prompt.is_none());
+
+    // Noticed when saving: the file is only replaced on request.
+    fs::write(&path, "Theirs, again\n").expect("change file");
+    app.save().expect("save");
+    assert!(app.external_change_prompt.is_some());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "Theirs, again\n");
+    press(&mut app, KeyCode::Char('k'));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "Mine!\n");
+    assert!(!app.buffer().dirty);
+
+    // Reload drops the unsaved changes.
+    press(&mut app, KeyCode::Char('?'));
+    fs::write(&path, "Theirs at last\n").expect("change file");
+    app.on_tick();
+    press(&mut app, KeyCode::Char('r'));
+    assert_eq!(doc_text(app.display().document()).trim(), "Theirs at last");
+    assert!(!app.buffer().dirty);
+}
+
+#[test]
+fn open_documents_are_locked_until_closed() {
+    let dir = TempDir::new("locked");
+    let path = dir.join("notes.txt");
+    let lock_file = dir.join(".notes.txt.pure-lock");
+    fs::write(&path, "Notes\n").expect("write file");
+
+    let loaded = load_document(&path).expect("load");
+    let app = App::new(loaded.document, Some(path.clone()), loaded.format, None);
+    assert!(lock_file.exists());
+    drop(app);
+    assert!(!lock_file.exists());
+
+    // A new file is locked once it exists.
+    let new_path = dir.join("new.txt");
+    let mut app = App::new(
+        Document::new(),
+        Some(new_path),
+        DocumentFormat::PlainText,
+        None,
+    );
+    app.set_interactive(false);
+    assert!(!dir.join(".new.txt.pure-lock").exists());
+    app.save().expect("save");
+    assert!(dir.join(".new.txt.pure-lock").exists());
+    drop(app);
+    assert!(!dir.join(".new.txt.pure-lock").exists());
+}
+
+#[test]
+fn a_document_open_elsewhere_can_be_opened_read_only() {
+    let dir = TempDir::new("lock-held");
+    let path = dir.join("notes.txt");
+    let lock_file = dir.join(".notes.txt.pure-lock");
+    fs::write(&path, "Notes\n").expect("write file");
+    // Another Pure on another host, which cannot be checked on.
+    fs::write(&lock_file, "someone@elsewhere.1\n").expect("write lock");
+
+    let loaded = load_document(&path).expect("load");
+    let mut app = App::new(loaded.document, Some(path.clone()), loaded.format, None);
+    app.set_interactive(false);
+    assert!(app.lock_prompt.is_some());
+    press(&mut app, KeyCode::Enter);
+    press(&mut app, KeyCode::Char('x'));
+    assert_eq!(doc_text(app.display().document()).trim(), "Notes");
+    assert!(status(&app).is_some_and(|message| message.contains("someone@elsewhere")));
+
+    // Once the other Pure is done, the document can be edited.
+    fs::remove_file(&lock_file).expect("release lock");
+    app.on_tick();
+    assert!(lock_file.exists());
+    press(&mut app, KeyCode::Char('x'));
+    assert_eq!(doc_text(app.display().document()).trim(), "xNotes");
+    drop(app);
+
+    // Editing anyway takes the lock over.
+    fs::write(&lock_file, "someone@elsewhere.1\n").expect("write lock");
+    let loaded = load_document(&path).expect("load");
+    let mut app = App::new(loaded.document, Some(path.clone()), loaded.format, None);
+    app.set_interactive(false);
+    press(&mut app, KeyCode::Char('e'));
+    assert!(app.lock_prompt.is_none());
+    assert!(!app.is_read_only());
+    drop(app);
+    assert!(!lock_file.exists());
+}
+
+fn headings(app: &App) -> Vec<String> {
+    crate::outline::entries(app.display().document())
+        .into_iter()
+        .map(|entry| entry.title)
+        .collect()
+}
+
+#[test]
+fn sections_are_moved_and_cut_whole() {
+    let document = tdoc::ftml! {
+        h1 { "Trip" }
+        h2 { "Route" }
+        p { "North along the coast." }
+        h3 { "Stops" }
+        h2 { "Packing" }
+        p { "Travel light." }
+    };
+    let mut app = App::new(document, None, DocumentFormat::Ftml, None);
+    app.set_interactive(false);
+    press(&mut app, KeyCode::Down);
+    app.handle_event(Event::Key(KeyEvent::new(
+        KeyCode::Down,
+        KeyModifiers::ALT | KeyModifiers::SHIFT,
+    )))
+    .unwrap();
+    assert_eq!(headings(&app), ["Trip", "Packing", "Route", "Stops"]);
+    assert!(!app.app_action_enabled(AppAction::MoveSectionDown, false));
+
+    app.execute_app_action(AppAction::CutSection).unwrap();
+    assert_eq!(headings(&app), ["Trip", "Packing"]);
+    assert_eq!(status(&app), Some("Cut to clipboard"));
+    assert!(app.clipboard.as_ref().is_some_and(|contents| {
+        contents.text.contains("Route") && contents.text.contains("Stops")
+    }));
+    app.execute_app_action(AppAction::Undo).unwrap();
+    assert_eq!(headings(&app), ["Trip", "Packing", "Route", "Stops"]);
+
+    app.set_read_only(true);
+    app.execute_app_action(AppAction::CutSection).unwrap();
+    app.execute_app_action(AppAction::PromoteSection).unwrap();
+    assert_eq!(headings(&app), ["Trip", "Packing", "Route", "Stops"]);
+    assert!(app.app_action_enabled(AppAction::CopySection, false));
+    assert!(!app.app_action_enabled(AppAction::CutSection, false));
+}
+
+#[test]
+fn folding_changes_the_view_only() {
+    let document = tdoc::ftml! {
+        h2 { "Route" }
+        p { "North along the coast." }
+        h2 { "Packing" }
+        p { "Travel light." }
+    };
+    let mut app = App::new(document.clone(), None, DocumentFormat::Ftml, None);
+    app.set_interactive(false);
+    app.set_read_only(true);
+    press(&mut app, KeyCode::F(8));
+    assert_eq!(status(&app), Some("Folded"));
+    app.execute_app_action(AppAction::FoldAllSections).unwrap();
+    assert_eq!(app.display().fold_keys(), ["0", "2"]);
+    assert!(!app.buffer().dirty);
+    assert_eq!(app.display().document(), &document);
+
+    assert!(app.app_action_enabled(AppAction::UnfoldAll, false));
+    app.execute_app_action(AppAction::UnfoldAll).unwrap();
+    assert!(!app.display().has_folds());
+    app.execute_app_action(AppAction::UnfoldAll).unwrap();
+    assert_eq!(status(&app), Some("Nothing is folded"));
+}
+
+fn type_text(app: &mut App, text: &str) {
+    for ch in text.chars() {
+        press(app, KeyCode::Char(ch));
+    }
+}
+
+#[test]
+fn markdown_typed_into_rich_text_turns_into_formatting() {
+    let mut app = App::new(
+        tdoc::ftml! { p { "" } },
+        None,
+        DocumentFormat::Markdown,
+        None,
+    );
+    app.set_interactive(false);
+    type_text(&mut app, "## Plan");
+    let heading = &app.display().document().paragraphs[0];
+    assert_eq!(heading.paragraph_type(), tdoc::ParagraphType::Header2);
+    assert_eq!(doc_text(app.display().document()), "Plan");
+
+    press(&mut app, KeyCode::Enter);
+    type_text(&mut app, "```");
+    press(&mut app, KeyCode::Enter);
+    let block = &app.display().document().paragraphs[1];
+    assert_eq!(block.paragraph_type(), tdoc::ParagraphType::CodeBlock);
+    app.execute_app_action(AppAction::Undo).unwrap();
+    let fence = &app.display().document().paragraphs[1];
+    assert_eq!(fence.paragraph_type(), tdoc::ParagraphType::Text);
+    assert_eq!(doc_text(app.display().document()), "Plan```");
+
+    // Plain text keeps no formatting, so Markdown stays as typed there.
+    let mut app = App::new(
+        tdoc::ftml! { p { "" } },
+        None,
+        DocumentFormat::PlainText,
+        None,
+    );
+    app.set_interactive(false);
+    type_text(&mut app, "# Plan **now**");
+    assert_eq!(doc_text(app.display().document()), "# Plan **now**");
+
+    let mut app = App::new(
+        tdoc::ftml! { p { "" } },
+        None,
+        DocumentFormat::Markdown,
+        None,
+    );
+    app.set_interactive(false);
+    app.apply_config(Config {
+        autoformat: false,
+        ..Config::default()
+    });
+    type_text(&mut app, "- item");
+    assert_eq!(doc_text(app.display().document()), "- item");
+}
+
+#[test]
+fn pasted_markdown_and_html_keep_their_structure() {
+    let mut app = App::new(tdoc::ftml! { p { "" } }, None, DocumentFormat::Ftml, None);
+    app.set_interactive(false);
+    let cut = ClipboardContents {
+        text: "Cut earlier".to_string(),
+        fragment: tdoc::ftml! { p { b { "Cut earlier" } } }.paragraphs,
+    };
+    app.clipboard = Some(cut.clone());
+    app.handle_event(Event::Paste("# Plan\n\nGo **now**.".to_string()))
+        .unwrap();
+    assert_eq!(app.clipboard, Some(cut), "the cut is still there to paste");
+    let document = app.display().document();
+    assert_eq!(document.paragraphs.len(), 2);
+    assert_eq!(
+        document.paragraphs[0].paragraph_type(),
+        tdoc::ParagraphType::Header1
+    );
+    assert_eq!(doc_text(document), "PlanGo now.");
+
+    // When the guess is wrong, the same text can go in as it is, replacing
+    // the paste.
+    app.execute_app_action(AppAction::PasteAsPlainText).unwrap();
+    assert_eq!(doc_text(app.display().document()), "# PlanGo **now**.");
+    app.execute_app_action(AppAction::Undo).unwrap();
+    assert_eq!(doc_text(app.display().document()), "");
+
+    // Also when the paste replaced a selection, which stays removed.
+    type_text(&mut app, "old");
+    app.handle_event(Event::Key(KeyEvent::new(
+        KeyCode::Home,
+        KeyModifiers::SHIFT,
+    )))
+    .unwrap();
+    app.handle_event(Event::Paste("# Plan".to_string()))
+        .unwrap();
+    app.execute_app_action(AppAction::PasteAsPlainText).unwrap();
+    assert_eq!(doc_text(app.display().document()), "# Plan");
+    app.execute_app_action(AppAction::Undo).unwrap();
+    assert_eq!(doc_text(app.display().document()), "");
+
+    // Angle brackets alone do not make HTML.
+    app.handle_event(Event::Paste("<name> goes here, see </docs>".to_string()))
+        .unwrap();
+    assert_eq!(
+        doc_text(app.display().document()),
+        "<name> goes here, see </docs>"
+    );
+
+    app.execute_app_action(AppAction::Undo).unwrap();
+    app.handle_event(Event::Paste(
+        r#"<p>Read <a href="https://example.com">this</a></p>"#.to_string(),
+    ))
+    .unwrap();
+    assert_eq!(doc_text(app.display().document()), "Read this");
+    let link = app.display().document().paragraphs[0]
+        .content()
+        .iter()
+        .find(|span| span.style == InlineStyle::Link)
+        .expect("the pasted link");
+    assert_eq!(link.link_target.as_deref(), Some("https://example.com"));
+
+    // Plain text keeps no formatting, so Markdown stays as pasted there.
+    let mut app = App::new(
+        tdoc::ftml! { p { "" } },
+        None,
+        DocumentFormat::PlainText,
+        None,
+    );
+    app.set_interactive(false);
+    app.handle_event(Event::Paste("# Plan".to_string()))
+        .unwrap();
+    assert_eq!(doc_text(app.display().document()), "# Plan");
+}
+
+#[cfg(unix)]
+#[test]
+fn paste_prefers_what_was_copied_here() {
+    let mut app = App::new(tdoc::ftml! { p { "" } }, None, DocumentFormat::Ftml, None);
+    app.system_clipboard = SystemClipboard::new(vec![system_clipboard::ClipboardSource::Command(
+        "printf 'Elsewhere'".to_string(),
+    )]);
+    // Only a terminal session reads the system clipboard.
+    app.set_interactive(true);
+    app.execute_app_action(AppAction::Paste).unwrap();
+    assert_eq!(doc_text(app.display().document()), "Elsewhere");
+    assert!(app.clipboard.is_none(), "so the next paste reads it again");
+
+    // The copy may not have reached the system clipboard.
+    app.clipboard = Some(ClipboardContents {
+        text: "Here".to_string(),
+        fragment: Vec::new(),
+    });
+    app.execute_app_action(AppAction::Paste).unwrap();
+    assert_eq!(doc_text(app.display().document()), "ElsewhereHere");
+    app.execute_app_action(AppAction::PasteFromSystem).unwrap();
+    assert_eq!(doc_text(app.display().document()), "ElsewhereHereElsewhere");
+
+    // A system clipboard holding Pure's copy gives it back formatted.
+    app.clipboard = Some(ClipboardContents {
+        text: "Elsewhere".to_string(),
+        fragment: tdoc::ftml! { p { b { "Elsewhere" } } }.paragraphs,
+    });
+    app.execute_app_action(AppAction::PasteFromSystem).unwrap();
+    let bold = app
+        .display()
+        .document()
+        .paragraphs
+        .iter()
+        .flat_map(|paragraph| paragraph.content().iter())
+        .any(|span| span.style == InlineStyle::Bold);
+    assert!(bold, "pasted with its formatting");
+    app.set_interactive(false);
+}
+
+#[test]
+fn selections_are_copied_as_markdown_and_html() {
+    let document = tdoc::ftml! {
+        h1 { "Plan" }
+        p { "Go " b { "now" } "." }
+    };
+    let mut app = App::new(document, None, DocumentFormat::Ftml, None);
+    app.set_interactive(false);
+    app.execute_app_action(AppAction::SelectSection).unwrap();
+
+    app.execute_app_action(AppAction::CopyAsMarkdown).unwrap();
+    assert_eq!(status(&app), Some("Copied as Markdown"));
+    let copied = &app.clipboard.as_ref().expect("copied").text;
+    assert_eq!(copied, "# Plan\n\nGo **now**.");
+
+    app.execute_app_action(AppAction::CopyAsHtml).unwrap();
+    let copied = &app.clipboard.as_ref().expect("copied").text;
+    assert!(copied.contains("<h1>Plan</h1>"), "{copied}");
+    assert!(copied.contains("<b>now</b>"), "{copied}");
+
+    // Pasting it back here keeps the formatting rather than the markup.
+    app.execute_app_action(AppAction::Paste).unwrap();
+    assert_eq!(doc_text(app.display().document()), "PlanGo now.");
+}
+
+#[test]
+fn earlier_copies_are_pasted_from_the_clipboard_history() {
+    let document = tdoc::ftml! { p { "alpha" } p { "beta" } p { "" } };
+    let mut app = App::new(document, None, DocumentFormat::Ftml, None);
+    app.set_interactive(false);
+    let select_line = |app: &mut App| {
+        press(app, KeyCode::Home);
+        app.handle_event(Event::Key(KeyEvent::new(KeyCode::End, KeyModifiers::SHIFT)))
+            .unwrap();
+    };
+    select_line(&mut app);
+    app.execute_app_action(AppAction::Copy).unwrap();
+    press(&mut app, KeyCode::Down);
+    select_line(&mut app);
+    app.execute_app_action(AppAction::Copy).unwrap();
+    press(&mut app, KeyCode::Down);
+    press(&mut app, KeyCode::Down);
+
+    app.execute_app_action(AppAction::Paste).unwrap();
+    assert_eq!(doc_text(app.display().document()), "alphabetabeta");
+    app.execute_app_action(AppAction::PastePrevious).unwrap();
+    assert_eq!(doc_text(app.display().document()), "alphabetaalpha");
+    // After the oldest entry comes the newest again.
+    app.execute_app_action(AppAction::PastePrevious).unwrap();
+    assert_eq!(doc_text(app.display().document()), "alphabetabeta");
+
+    // Anything else in between ends the run.
+    type_text(&mut app, "!");
+    app.execute_app_action(AppAction::PastePrevious).unwrap();
+    assert_eq!(doc_text(app.display().document()), "alphabetabeta!");
+    assert_eq!(
+        status(&app),
+        Some("Paste Previous only works right after a paste")
+    );
+
+    app.execute_app_action(AppAction::ClipboardHistory).unwrap();
+    assert!(app.command_palette.is_some());
+    press(&mut app, KeyCode::Down);
+    press(&mut app, KeyCode::Enter);
+    assert!(app.command_palette.is_none());
+    assert_eq!(doc_text(app.display().document()), "alphabetabeta!alpha");
+    // The picked entry is what Ctrl+V pastes next.
+    app.execute_app_action(AppAction::Paste).unwrap();
+    assert_eq!(
+        doc_text(app.display().document()),
+        "alphabetabeta!alphaalpha"
+    );
+}
+
+#[test]
+fn held_and_released_keys_from_the_kitty_keyboard_protocol() {
+    use KeyEventKind::{Press, Release, Repeat};
+
+    let mut app = App::new(tdoc::ftml! { p { "" } }, None, DocumentFormat::Ftml, None);
+    app.set_interactive(false);
+    app.set_enhanced_keyboard(true);
+    let key = |app: &mut App, ch, modifiers, kind| {
+        let event = KeyEvent::new_with_kind(KeyCode::Char(ch), modifiers, kind);
+        app.handle_event(Event::Key(event)).unwrap();
+    };
+    key(&mut app, 'a', KeyModifiers::NONE, Press);
+    key(&mut app, 'a', KeyModifiers::NONE, Repeat);
+    key(&mut app, 'a', KeyModifiers::NONE, Release);
+    assert_eq!(doc_text(app.display().document()), "aa");
+
+    // Holding a key that opens something opens it once.
+    let ctrl_shift = KeyModifiers::CONTROL | KeyModifiers::SHIFT;
+    key(&mut app, 'p', ctrl_shift, Repeat);
+    assert!(app.command_palette.is_none());
+    key(&mut app, 'p', ctrl_shift, Press);
+    assert!(app.command_palette.is_some());
+    press(&mut app, KeyCode::Esc);
+
+    // With alternate keys reported, Ctrl+Shift+Z arrives as a capital Z.
+    app.execute_app_action(AppAction::Undo).unwrap();
+    assert_eq!(doc_text(app.display().document()), "");
+    key(&mut app, 'Z', KeyModifiers::CONTROL, Press);
+    assert_eq!(doc_text(app.display().document()), "aa");
+}
+
+#[test]
+fn keys_only_the_kitty_keyboard_protocol_tells_apart() {
+    let mut app = App::new(tdoc::ftml! { p { "" } }, None, DocumentFormat::Ftml, None);
+    app.set_interactive(false);
+    let ctrl = |app: &mut App, ch, modifiers| {
+        let event = KeyEvent::new(KeyCode::Char(ch), KeyModifiers::CONTROL | modifiers);
+        app.handle_event(Event::Key(event)).unwrap();
+    };
+
+    // Elsewhere, Ctrl+Shift+S arrives as Ctrl+S, so it is neither bound nor
+    // shown as the shortcut of Save As.
+    let save_as = Command::App(AppAction::SaveAs);
+    assert_eq!(app.keymap.shortcut_label(save_as), None);
+    ctrl(&mut app, 's', KeyModifiers::SHIFT);
+    assert!(app.file_dialog.is_none());
+
+    app.set_enhanced_keyboard(true);
+    assert_eq!(
+        app.keymap.shortcut_label(save_as).as_deref(),
+        Some("Ctrl+Shift+S")
+    );
+
+    // Ctrl+J breaks the line, Enter the paragraph.
+    type_text(&mut app, "a");
+    ctrl(&mut app, 'j', KeyModifiers::NONE);
+    type_text(&mut app, "b");
+    assert_eq!(app.display().document().paragraphs.len(), 1);
+    press(&mut app, KeyCode::Enter);
+    assert_eq!(app.display().document().paragraphs.len(), 2);
+
+    // Esc arrives on its own, and takes effect at once.
+    press(&mut app, KeyCode::Esc);
+    assert!(app.context_menu.is_some());
+    press(&mut app, KeyCode::Esc);
+
+    // A configuration read later keeps what the terminal can tell apart.
+    app.apply_config(Config::default());
+    assert_eq!(
+        app.keymap.shortcut_label(save_as).as_deref(),
+        Some("Ctrl+Shift+S")
     );
 }
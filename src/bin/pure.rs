@@ -1,6 +1,8 @@
 use std::{
-    env, io,
-    path::PathBuf,
+    env,
+    io::{self, IsTerminal, Read, Write},
+    path::Path,
+    process,
     time::{Duration, Instant},
 };
 
@@ -9,49 +11,162 @@ use crossterm::{
     cursor::SetCursorStyle,
     event::{
         self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
-        Event,
+        Event, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
     },
     execute,
-    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+    terminal::{
+        EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+        supports_keyboard_enhancement,
+    },
 };
 use ratatui::{Terminal, backend::CrosstermBackend};
 
-use pure_tui::app::{App, DocumentFormat, load_document};
+use pure_tui::app::{App, DocumentFormat, LoadedDocument, load_document_as, read_document};
+use pure_tui::cli::{self, Arguments, Invocation, StartPosition};
+use pure_tui::commit_message;
+use pure_tui::config::{self, Config};
+use pure_tui::headless;
+use pure_tui::recovery;
+use pure_tui::terminal;
 use tdoc::Document;
 
 fn main() -> Result<()> {
-    run()
+    let args = match cli::parse(env::args_os().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("pure: {err}");
+            eprintln!("Try 'pure --help' for more information.");
+            process::exit(2);
+        }
+    };
+    run(args)
 }
 
-fn run() -> Result<()> {
+fn run(args: Arguments) -> Result<()> {
+    let config = match &args.config {
+        Some(path) => {
+            config::load_from(path).with_context(|| format!("failed to read {}", path.display()))?
+        }
+        None => config::load(),
+    };
+    let (paths, start, read_only) = match args.invocation {
+        Invocation::Help => {
+            print!("{}", cli::USAGE);
+            return Ok(());
+        }
+        Invocation::Version => {
+            println!("pure {}", env!("CARGO_PKG_VERSION"));
+            return Ok(());
+        }
+        Invocation::Convert { input, output } => {
+            return headless::convert(&input, args.format, &output, config.plain_text_wrap);
+        }
+        Invocation::Cat { path, width } => return cat(&path, args.format, width, config),
+        Invocation::Edit {
+            paths,
+            start,
+            read_only,
+        } => (paths, start, read_only),
+    };
+
     // Without an argument, start with an untitled document; saving it asks
-    // for a name through the Save As dialog.
-    let path = env::args().nth(1).map(PathBuf::from);
-    let (document, format, initial_status) = match &path {
-        Some(path) => load_document(path)?,
-        None => (
-            Document::new(),
-            DocumentFormat::Ftml,
-            Some("New document".to_string()),
-        ),
+    // for a name through the Save As dialog. Every further path opens in a
+    // buffer of its own, with the first one shown. `-` reads the document
+    // from standard input, to write it to standard output on exit; a Git
+    // commit message is read with its comments kept as they are.
+    let mut paths = paths.into_iter();
+    let mut path = paths.next();
+    let mut input = None;
+    if path.as_deref() == Some(Path::new("-")) {
+        path = None;
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .context("failed to read standard input")?;
+        input = Some(text);
+    }
+    let commit = args.format.is_none()
+        && path
+            .as_deref()
+            .is_some_and(commit_message::is_commit_message);
+    let loaded = match (&path, &input) {
+        (_, Some(text)) => read_document(text.clone(), args.format),
+        (Some(path), None) if commit => commit_message::load(path)?,
+        (Some(path), None) => load_document_as(path, args.format)?,
+        (None, None) => LoadedDocument {
+            document: Document::new(),
+            format: args.format.unwrap_or(DocumentFormat::Ftml),
+            status: Some("New document".to_string()),
+            recovery: recovery::find_untitled(),
+            parse_failure: None,
+        },
     };
-    let mut app = App::new(document, path, format, initial_status);
+    let mut app = App::new(loaded.document, path, loaded.format, loaded.status);
+    if input.is_some() {
+        app.use_standard_io();
+    }
+    if commit {
+        app.edit_commit_message();
+    }
+    app.apply_config(config);
+    app.set_read_only(read_only);
+    if let Some(failure) = loaded.parse_failure {
+        app.protect(failure);
+    }
+    if let Some(recovery) = loaded.recovery {
+        app.offer_recovery(recovery);
+    }
+    match start {
+        Some(StartPosition::Line(line)) => app.go_to_line(line),
+        Some(StartPosition::Heading(text)) => {
+            app.go_to_heading(&text);
+        }
+        None => {}
+    }
+    for path in paths {
+        let loaded = load_document_as(&path, args.format)?;
+        app.add_buffer(loaded, path);
+    }
 
+    // Standard output may carry the document, or have been redirected;
+    // either way, the editor is drawn on the terminal.
+    if !io::stdout().is_terminal() {
+        terminal::use_controlling_terminal().context("failed to open the terminal")?;
+    }
     enable_raw_mode().context("failed to enable raw mode")?;
-    let mut stdout = io::stdout();
+    let mut output = terminal::output();
     execute!(
-        stdout,
+        output,
         EnterAlternateScreen,
         EnableMouseCapture,
         EnableBracketedPaste
     )
     .context("failed to initialize terminal")?;
-    let backend = CrosstermBackend::new(stdout);
+    // Terminals that speak the kitty keyboard protocol can tell apart keys
+    // that look the same in the legacy encoding — Ctrl+Shift+Z and Ctrl+Z,
+    // Ctrl+[ and Esc, Shift+Enter and Enter — and report held and released
+    // keys. Others are left as they are, and the keymap leaves out the
+    // bindings they cannot send.
+    let enhanced_keyboard = supports_keyboard_enhancement().unwrap_or(false)
+        && execute!(
+            output,
+            PushKeyboardEnhancementFlags(
+                KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                    | KeyboardEnhancementFlags::REPORT_ALTERNATE_KEYS
+                    | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+            )
+        )
+        .is_ok();
+    app.set_enhanced_keyboard(enhanced_keyboard);
+    let backend = CrosstermBackend::new(output);
     let mut terminal = Terminal::new(backend).context("failed to create terminal backend")?;
     terminal.clear().ok();
 
     let res = run_app(&mut terminal, &mut app).context("application error");
 
+    if enhanced_keyboard {
+        execute!(terminal.backend_mut(), PopKeyboardEnhancementFlags).ok();
+    }
     disable_raw_mode().ok();
     execute!(
         terminal.backend_mut(),
@@ -63,7 +178,47 @@ fn run() -> Result<()> {
     .ok();
     terminal.show_cursor().ok();
 
-    res
+    res?;
+    if let Some(input) = input {
+        // Unsaved, the document goes on unchanged.
+        let contents = app.standard_output().unwrap_or(input.as_bytes());
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(contents)?;
+        stdout.flush()?;
+    }
+    if app.commit_aborted() {
+        // Exiting skips destructors; the lock files go first.
+        drop(app);
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// `pure cat <file>`: print the document as the editor lays it out — in
+/// color on a terminal, as plain text into a pipe. The width defaults to the
+/// configured maximum text width, or the terminal's width if that is
+/// narrower.
+fn cat(
+    path: &Path,
+    format: Option<DocumentFormat>,
+    width: Option<usize>,
+    config: Config,
+) -> Result<()> {
+    let stdout = io::stdout();
+    let ansi = stdout.is_terminal();
+    let width = width.unwrap_or_else(|| {
+        let terminal_width = crossterm::terminal::size()
+            .ok()
+            .filter(|_| ansi)
+            .map(|(columns, _)| columns as usize);
+        terminal_width.map_or(config.max_text_width, |columns| {
+            columns.min(config.max_text_width)
+        })
+    });
+    let mut out = io::BufWriter::new(stdout.lock());
+    headless::cat(path, format, width, ansi, &config.theme, &mut out)?;
+    out.flush()?;
+    Ok(())
 }
 
 fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
@@ -101,15 +256,12 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut A
             needs_redraw = true;
         }
 
-        // Handle tick for status message updates
+        // Handle tick for status messages, autosave and changes on disk
         if last_tick.elapsed() >= tick_rate {
-            let had_message_before = app.has_status_message();
-            app.on_tick();
-            last_tick = Instant::now();
-            // Only redraw if status message changed (was pruned)
-            if had_message_before && !app.has_status_message() {
+            if app.on_tick() {
                 needs_redraw = true;
             }
+            last_tick = Instant::now();
         }
     }
 
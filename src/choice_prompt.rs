@@ -0,0 +1,129 @@
+//! Modal prompt asking the user to pick one of a row of buttons.
+//!
+//! The quit, overwrite, recovery, lock and external change prompts are all
+//! driven the same way: Left / Right (and Tab / Shift-Tab) move focus
+//! between the buttons, wrapping around, Enter activates the focused one,
+//! and each button has letter accelerators. A [`ChoicePrompt`] is given the
+//! buttons as a table of [`Choice`]s, the one to focus first, and whatever
+//! the prompt needs to carry out the answer; the surrounding
+//! [`crate::app::App`] handles the keys and carries out the choice.
+
+/// A button of a prompt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Choice<T> {
+    pub value: T,
+    pub label: &'static str,
+    /// The letters choosing the button, in lower case.
+    pub accelerators: &'static str,
+}
+
+pub struct ChoicePrompt<T: 'static, C = ()> {
+    choices: &'static [Choice<T>],
+    /// Index into `choices` of the focused button.
+    focus: usize,
+    context: C,
+}
+
+impl<T: Copy + PartialEq, C> ChoicePrompt<T, C> {
+    /// A prompt offering `choices`, in display order, with `focus` focused.
+    pub fn new(choices: &'static [Choice<T>], focus: T, context: C) -> Self {
+        Self {
+            choices,
+            focus: choices
+                .iter()
+                .position(|choice| choice.value == focus)
+                .unwrap_or(0),
+            context,
+        }
+    }
+
+    pub fn focus(&self) -> T {
+        self.choices[self.focus].value
+    }
+
+    /// Move focus to the next button, wrapping around.
+    pub fn focus_next(&mut self) {
+        self.focus = (self.focus + 1) % self.choices.len();
+    }
+
+    /// Move focus to the previous button, wrapping around.
+    pub fn focus_prev(&mut self) {
+        self.focus = (self.focus + self.choices.len() - 1) % self.choices.len();
+    }
+
+    /// The button whose accelerator is `ch` (case-insensitive).
+    pub fn from_accelerator(&self, ch: char) -> Option<T> {
+        let ch = ch.to_ascii_lowercase();
+        self.choices
+            .iter()
+            .find(|choice| choice.accelerators.contains(ch))
+            .map(|choice| choice.value)
+    }
+
+    /// The button labels in display order, each with whether it has focus.
+    pub fn buttons(&self) -> Vec<(&'static str, bool)> {
+        self.choices
+            .iter()
+            .enumerate()
+            .map(|(index, choice)| (choice.label, index == self.focus))
+            .collect()
+    }
+
+    pub fn context(&self) -> &C {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut C {
+        &mut self.context
+    }
+
+    pub fn into_context(self) -> C {
+        self.context
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHOICES: [Choice<u8>; 3] = [
+        Choice {
+            value: 1,
+            label: "One",
+            accelerators: "o",
+        },
+        Choice {
+            value: 2,
+            label: "Two",
+            accelerators: "ty",
+        },
+        Choice {
+            value: 3,
+            label: "Three",
+            accelerators: "h",
+        },
+    ];
+
+    #[test]
+    fn focus_wraps_around_the_buttons() {
+        let mut prompt = ChoicePrompt::new(&CHOICES, 2, ());
+        assert_eq!(prompt.focus(), 2);
+        prompt.focus_next();
+        prompt.focus_next();
+        assert_eq!(prompt.focus(), 1);
+        prompt.focus_prev();
+        assert_eq!(prompt.focus(), 3);
+        assert_eq!(
+            prompt.buttons(),
+            vec![("One", false), ("Two", false), ("Three", true)]
+        );
+    }
+
+    #[test]
+    fn accelerators_ignore_case() {
+        let prompt = ChoicePrompt::new(&CHOICES, 1, ());
+        assert_eq!(prompt.from_accelerator('Y'), Some(2));
+        assert_eq!(prompt.from_accelerator('h'), Some(3));
+        assert_eq!(prompt.from_accelerator('x'), None);
+    }
+}
@@ -0,0 +1,374 @@
+//! Command-line arguments.
+//!
+//! `pure [OPTIONS] [+LINE] [FILE...]` opens files for editing; `pure convert`
+//! and `pure cat` run without the editor (see [`crate::headless`]). Options
+//! may come before or after the files, long options take their value either
+//! as the next argument or after `=`, and `--` ends the options, so a file
+//! called `-r` or `+3` can still be opened. Everything is checked before the
+//! terminal is switched to the editor, so mistakes are reported on a plain
+//! terminal.
+
+use std::ffi::OsString;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::app::DocumentFormat;
+
+pub const USAGE: &str = "\
+Usage: pure [OPTIONS] [+LINE] [FILE...]
+       pure convert [OPTIONS] <INPUT> <OUTPUT>
+       pure cat [OPTIONS] [--width COLUMNS] <FILE>
+
+Edit FTML, Markdown, HTML, Gemini and plain-text documents. A FILE of -
+edits standard input and writes the result to standard output on exit.
+
+Options:
+  +LINE                  Start with the cursor on line LINE of the first file
+  -g, --goto HEADING     Start with the cursor on the heading HEADING
+  -f, --format FORMAT    Read files as FORMAT (ftml, markdown, html, gemini or
+                         text), whatever their extension
+  -r, --readonly         Open files for viewing only
+  -c, --config FILE      Read the configuration from FILE
+  -w, --width COLUMNS    Wrap the output of pure cat at COLUMNS
+  -h, --help             Print this help
+  -V, --version          Print the version
+";
+
+/// What the command line asks for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Arguments {
+    /// A configuration file to read instead of the user's.
+    pub config: Option<PathBuf>,
+    /// Read files in this format, whatever their extension says.
+    pub format: Option<DocumentFormat>,
+    pub invocation: Invocation,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Invocation {
+    /// Open the editor.
+    Edit {
+        /// The files to open, each in a buffer of its own; none starts an
+        /// untitled document.
+        paths: Vec<PathBuf>,
+        /// Where to put the cursor in the first file.
+        start: Option<StartPosition>,
+        read_only: bool,
+    },
+    /// `pure convert <input> <output>`.
+    Convert {
+        input: PathBuf,
+        output: PathBuf,
+    },
+    /// `pure cat <file>`, wrapped at `width` columns if given.
+    Cat {
+        path: PathBuf,
+        width: Option<usize>,
+    },
+    Help,
+    Version,
+}
+
+/// Where the cursor starts in the first file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StartPosition {
+    /// A line, counted from one, as the status line shows it.
+    Line(usize),
+    /// The first heading with this text.
+    Heading(String),
+}
+
+/// A command line Pure cannot make sense of.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UsageError(String);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+fn usage_error(message: impl Into<String>) -> UsageError {
+    UsageError(message.into())
+}
+
+/// Make sense of the arguments following the program name.
+pub fn parse(args: impl IntoIterator<Item = OsString>) -> Result<Arguments, UsageError> {
+    let mut args = args.into_iter();
+    let mut config = None;
+    let mut format = None;
+    let mut start = None;
+    let mut read_only = false;
+    let mut width = None;
+    // Options that only make sense in some modes, for the error message.
+    let mut edit_option = None;
+    let mut positional: Vec<OsString> = Vec::new();
+    let mut options_done = false;
+    // How many positional arguments came before `--`; only the first of
+    // those can name a subcommand.
+    let mut before_separator = None;
+
+    while let Some(arg) = args.next() {
+        let text = arg.to_str().unwrap_or_default().to_string();
+        if options_done || text == "-" || !(text.starts_with('-') || text.starts_with('+')) {
+            positional.push(arg);
+            continue;
+        }
+        if text == "--" {
+            options_done = true;
+            before_separator = Some(positional.len());
+            continue;
+        }
+        if let Some(line) = text.strip_prefix('+') {
+            // `+` followed by anything but a number is a file name.
+            match line.parse::<usize>() {
+                Ok(line) => {
+                    start = Some(StartPosition::Line(line));
+                    edit_option = Some("+LINE".to_string());
+                }
+                Err(_) => positional.push(arg),
+            }
+            continue;
+        }
+
+        let (name, inline_value) = match text.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+            _ => (text.as_str(), None),
+        };
+        let mut value = |name: &str| -> Result<String, UsageError> {
+            match inline_value.clone() {
+                Some(value) => Ok(value),
+                None => args
+                    .next()
+                    .and_then(|value| value.into_string().ok())
+                    .ok_or_else(|| usage_error(format!("{name} needs a value"))),
+            }
+        };
+        match name {
+            "-h" | "--help" | "-V" | "--version" | "-r" | "--readonly"
+                if inline_value.is_some() =>
+            {
+                return Err(usage_error(format!("{name} does not take a value")));
+            }
+            "-h" | "--help" => return Ok(Arguments::new(Invocation::Help)),
+            "-V" | "--version" => return Ok(Arguments::new(Invocation::Version)),
+            "-r" | "--readonly" => {
+                read_only = true;
+                edit_option = Some(name.to_string());
+            }
+            "-g" | "--goto" => {
+                start = Some(StartPosition::Heading(value(name)?));
+                edit_option = Some(name.to_string());
+            }
+            "-f" | "--format" => {
+                let name = value(name)?;
+                format = Some(DocumentFormat::from_name(&name).ok_or_else(|| {
+                    usage_error(format!(
+                        "unknown format '{name}'; use ftml, markdown, html, gemini or text"
+                    ))
+                })?);
+            }
+            "-c" | "--config" => config = Some(PathBuf::from(value(name)?)),
+            "-w" | "--width" => {
+                let columns = value(name)?;
+                match columns.parse::<usize>() {
+                    Ok(columns) if columns > 0 => width = Some((name.to_string(), columns)),
+                    _ => return Err(usage_error(format!("{name} needs a number of columns"))),
+                }
+            }
+            _ => return Err(usage_error(format!("unknown option '{name}'"))),
+        }
+    }
+
+    let subcommand = positional
+        .first()
+        .filter(|_| before_separator.is_none_or(|count| count > 0))
+        .and_then(|arg| arg.to_str())
+        .filter(|arg| matches!(*arg, "convert" | "cat"));
+    if let Some(subcommand) = subcommand {
+        if let Some(option) = edit_option {
+            return Err(usage_error(format!(
+                "{option} does not apply to pure {subcommand}"
+            )));
+        }
+        if let Some((option, _)) = width.as_ref().filter(|_| subcommand != "cat") {
+            return Err(usage_error(format!(
+                "{option} does not apply to pure {subcommand}"
+            )));
+        }
+    } else if let Some((option, _)) = &width {
+        return Err(usage_error(format!("{option} only applies to pure cat")));
+    }
+
+    let invocation = match subcommand {
+        Some("convert") => match <[OsString; 2]>::try_from(positional.split_off(1)) {
+            Ok([input, output]) => Invocation::Convert {
+                input: input.into(),
+                output: output.into(),
+            },
+            Err(_) => return Err(usage_error("convert needs an input and an output file")),
+        },
+        Some(_) => match <[OsString; 1]>::try_from(positional.split_off(1)) {
+            Ok([path]) => Invocation::Cat {
+                path: path.into(),
+                width: width.map(|(_, columns)| columns),
+            },
+            Err(_) => return Err(usage_error("cat needs exactly one file")),
+        },
+        None if positional.len() > 1 && positional.iter().any(|arg| arg == "-") => {
+            return Err(usage_error(
+                "- (standard input) cannot be opened together with other files",
+            ));
+        }
+        None => Invocation::Edit {
+            paths: positional.into_iter().map(PathBuf::from).collect(),
+            start,
+            read_only,
+        },
+    };
+    Ok(Arguments {
+        config,
+        format,
+        invocation,
+    })
+}
+
+impl Arguments {
+    fn new(invocation: Invocation) -> Self {
+        Self {
+            config: None,
+            format: None,
+            invocation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(args: &[&str]) -> Result<Arguments, UsageError> {
+        parse(args.iter().map(OsString::from))
+    }
+
+    fn edit(args: &[&str]) -> (Vec<PathBuf>, Option<StartPosition>, bool) {
+        match parsed(args).expect("valid arguments").invocation {
+            Invocation::Edit {
+                paths,
+                start,
+                read_only,
+            } => (paths, start, read_only),
+            other => panic!("expected Edit, got {other:?}"),
+        }
+    }
+
+    fn error(args: &[&str]) -> String {
+        parsed(args).expect_err("invalid arguments").to_string()
+    }
+
+    #[test]
+    fn files_and_start_positions() {
+        assert_eq!(edit(&[]), (vec![], None, false));
+        assert_eq!(
+            edit(&["+12", "notes.md", "todo.md", "-r"]),
+            (
+                vec!["notes.md".into(), "todo.md".into()],
+                Some(StartPosition::Line(12)),
+                true
+            )
+        );
+        assert_eq!(
+            edit(&["--goto=Next Steps", "notes.md"]).1,
+            Some(StartPosition::Heading("Next Steps".to_string()))
+        );
+        assert_eq!(
+            edit(&["-g", "Intro", "notes.md"]).1,
+            Some(StartPosition::Heading("Intro".to_string()))
+        );
+        assert_eq!(edit(&["-"]).0, vec![PathBuf::from("-")]);
+        // Names that only look like options.
+        assert_eq!(edit(&["+notes"]).0, vec![PathBuf::from("+notes")]);
+        assert_eq!(
+            edit(&["--", "-r", "+3"]),
+            (vec!["-r".into(), "+3".into()], None, false)
+        );
+        // A file that happens to be called like a subcommand.
+        assert_eq!(
+            edit(&["--", "cat", "notes.md"]).0,
+            vec![PathBuf::from("cat"), PathBuf::from("notes.md")]
+        );
+    }
+
+    #[test]
+    fn format_and_config_apply_to_every_mode() {
+        let args = parsed(&["--format", "md", "-c", "pure.toml", "notes.txt"]).unwrap();
+        assert_eq!(args.format, Some(DocumentFormat::Markdown));
+        assert_eq!(args.config, Some(PathBuf::from("pure.toml")));
+
+        let args = parsed(&["cat", "--format=text", "--width", "40", "notes.md"]).unwrap();
+        assert_eq!(args.format, Some(DocumentFormat::PlainText));
+        assert_eq!(
+            args.invocation,
+            Invocation::Cat {
+                path: "notes.md".into(),
+                width: Some(40)
+            }
+        );
+        assert_eq!(
+            parsed(&["convert", "--", "in.md", "-out.html"])
+                .unwrap()
+                .invocation,
+            Invocation::Convert {
+                input: "in.md".into(),
+                output: "-out.html".into()
+            }
+        );
+    }
+
+    #[test]
+    fn help_and_version_win() {
+        assert_eq!(
+            parsed(&["notes.md", "--help"]).unwrap().invocation,
+            Invocation::Help
+        );
+        assert_eq!(parsed(&["-V"]).unwrap().invocation, Invocation::Version);
+    }
+
+    #[test]
+    fn mistakes_are_usage_errors() {
+        assert_eq!(error(&["--frobnicate"]), "unknown option '--frobnicate'");
+        assert_eq!(error(&["notes.md", "--goto"]), "--goto needs a value");
+        assert_eq!(
+            error(&["--readonly=yes"]),
+            "--readonly does not take a value"
+        );
+        assert!(error(&["-f", "docx"]).starts_with("unknown format 'docx'"));
+        assert_eq!(
+            error(&["--width", "0", "cat", "a.md"]),
+            "--width needs a number of columns"
+        );
+        assert_eq!(
+            error(&["--width", "40", "a.md"]),
+            "--width only applies to pure cat"
+        );
+        assert_eq!(
+            error(&["cat", "+3", "a.md"]),
+            "+LINE does not apply to pure cat"
+        );
+        assert_eq!(
+            error(&["convert", "-r", "a.md", "b.md"]),
+            "-r does not apply to pure convert"
+        );
+        assert_eq!(
+            error(&["convert", "a.md"]),
+            "convert needs an input and an output file"
+        );
+        assert_eq!(error(&["cat"]), "cat needs exactly one file");
+        assert_eq!(
+            error(&["-", "notes.md"]),
+            "- (standard input) cannot be opened together with other files"
+        );
+    }
+}
@@ -0,0 +1,291 @@
+//! Reading the system clipboard.
+//!
+//! Terminals keep the system clipboard from the programs running in them,
+//! so reading it takes help. Pure asks its sources in order until one has
+//! text: external helpers such as `wl-paste`, `xclip` or `pbpaste`, or
+//! whatever the configuration lists instead. Only there can the terminal
+//! itself be asked, with an OSC 52 query that some terminals answer: the
+//! answer arrives among the keys typed meanwhile, which are lost while Pure
+//! waits for it. A terminal that leaves the query unanswered is not asked
+//! again, so each paste does not wait for it.
+//!
+//! Pure's own clipboard comes first: the surrounding [`crate::app::App`]
+//! pastes what was last cut or copied in Pure, and reads the system
+//! clipboard only while its own is empty, or when asked to. Terminals such
+//! as the VTE-based ones ignore what Pure copies through OSC 52, so there
+//! the system clipboard may still hold something older; and reading it
+//! takes a moment.
+//!
+//! Copying goes the other way through OSC 52, which far more terminals
+//! support; see [`crate::app::App`].
+
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+/// How long a helper may take to print the clipboard.
+const HELPER_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long the terminal may take to answer the OSC 52 query.
+#[cfg(unix)]
+const TERMINAL_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// The name that stands for the terminal's OSC 52 query among the sources
+/// in the configuration.
+pub const TERMINAL_SOURCE: &str = "osc52";
+
+/// Somewhere the system clipboard can be read from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClipboardSource {
+    /// The terminal, asked with an OSC 52 query.
+    Terminal,
+    /// A shell command that prints the clipboard.
+    Command(String),
+}
+
+impl ClipboardSource {
+    /// The source called `name` in the configuration: `osc52` or a command.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim() {
+            "" => None,
+            TERMINAL_SOURCE => Some(ClipboardSource::Terminal),
+            command => Some(ClipboardSource::Command(command.to_string())),
+        }
+    }
+}
+
+/// The sources tried when the configuration names none: the helper of the
+/// platform's clipboard, if there is one.
+pub fn default_sources() -> Vec<ClipboardSource> {
+    let helpers: &[&str] = if cfg!(target_os = "macos") {
+        &["pbpaste"]
+    } else if cfg!(windows) {
+        &["powershell -NoProfile -Command Get-Clipboard"]
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        &["wl-paste --no-newline"]
+    } else if std::env::var_os("DISPLAY").is_some() {
+        &[
+            "xclip -selection clipboard -out",
+            "xsel --clipboard --output",
+        ]
+    } else {
+        &[]
+    };
+    helpers
+        .iter()
+        .map(|helper| ClipboardSource::Command(helper.to_string()))
+        .collect()
+}
+
+pub struct SystemClipboard {
+    sources: Vec<ClipboardSource>,
+    /// Set once the terminal has left a query unanswered.
+    terminal_silent: bool,
+}
+
+impl SystemClipboard {
+    pub fn new(sources: Vec<ClipboardSource>) -> Self {
+        Self {
+            sources,
+            terminal_silent: false,
+        }
+    }
+
+    /// Whether there is any source left to ask.
+    pub fn readable(&self) -> bool {
+        self.sources
+            .iter()
+            .any(|source| *source != ClipboardSource::Terminal || !self.terminal_silent)
+    }
+
+    /// The clipboard's text from the first source that has any.
+    pub fn read(&mut self) -> Option<String> {
+        for index in 0..self.sources.len() {
+            let text = match &self.sources[index] {
+                ClipboardSource::Terminal if self.terminal_silent => continue,
+                ClipboardSource::Terminal => match query_terminal() {
+                    Some(text) => Some(text),
+                    None => {
+                        self.terminal_silent = true;
+                        None
+                    }
+                },
+                ClipboardSource::Command(command) => run_helper(command),
+            };
+            if let Some(text) = text.filter(|text| !text.is_empty()) {
+                return Some(text);
+            }
+        }
+        None
+    }
+}
+
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    }
+}
+
+/// What `command` prints, if it succeeds within [`HELPER_TIMEOUT`].
+fn run_helper(command: &str) -> Option<String> {
+    let mut child = shell(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    // Read on the side, so a large clipboard cannot fill the pipe and keep
+    // the helper from finishing.
+    let mut stdout = child.stdout.take()?;
+    let reader = thread::spawn(move || {
+        let mut output = Vec::new();
+        stdout.read_to_end(&mut output).map(|_| output)
+    });
+    let deadline = Instant::now() + HELPER_TIMEOUT;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => {
+                let output = reader.join().ok()?.ok()?;
+                return Some(String::from_utf8_lossy(&output).into_owned());
+            }
+            Ok(Some(_)) => return None,
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    }
+}
+
+/// The clipboard as the terminal reports it, or `None` if it does not
+/// answer in time.
+#[cfg(unix)]
+fn query_terminal() -> Option<String> {
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use std::os::fd::AsRawFd;
+
+    // Keyboard input and the answer arrive on the controlling terminal,
+    // whichever file descriptor reads it.
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    tty.write_all(b"\x1b]52;c;?\x07").ok()?;
+    tty.flush().ok()?;
+
+    let deadline = Instant::now() + TERMINAL_TIMEOUT;
+    let mut answer = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        if let Some(text) = parse_answer(&answer) {
+            return text;
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return None;
+        }
+        let mut pollfd = libc::pollfd {
+            fd: tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: one valid pollfd is passed.
+        let ready = unsafe { libc::poll(&mut pollfd, 1, remaining.as_millis() as libc::c_int) };
+        if ready <= 0 {
+            return None;
+        }
+        let read = tty.read(&mut buf).ok()?;
+        if read == 0 {
+            return None;
+        }
+        answer.extend_from_slice(&buf[..read]);
+    }
+}
+
+#[cfg(not(unix))]
+fn query_terminal() -> Option<String> {
+    None
+}
+
+/// The clipboard from a complete answer to the OSC 52 query,
+/// `ESC ] 52 ; c ; <base64> BEL` (or ending with `ESC \`): `None` while the
+/// answer is incomplete, `Some(None)` if it cannot be read.
+fn parse_answer(answer: &[u8]) -> Option<Option<String>> {
+    const START: &[u8] = b"\x1b]52;";
+    let start = answer
+        .windows(START.len())
+        .position(|window| window == START)?;
+    let rest = &answer[start + START.len()..];
+    let end = rest
+        .iter()
+        .position(|&byte| byte == b'\x07' || byte == b'\x1b')?;
+    if rest[end] == b'\x1b' && rest.get(end + 1) != Some(&b'\\') {
+        return (rest.len() > end + 1).then_some(None);
+    }
+    let payload = &rest[..end];
+    let data = match payload.iter().position(|&byte| byte == b';') {
+        Some(separator) => &payload[separator + 1..],
+        None => return Some(None),
+    };
+    Some(
+        STANDARD
+            .decode(data)
+            .ok()
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_to_the_query_are_decoded() {
+        assert_eq!(
+            parse_answer(b"\x1b]52;c;SGVsbG8=\x07"),
+            Some(Some("Hello".to_string()))
+        );
+        assert_eq!(
+            parse_answer(b"x\x1b]52;c;SGVsbG8=\x1b\\"),
+            Some(Some("Hello".to_string()))
+        );
+        // Still arriving.
+        assert_eq!(parse_answer(b"\x1b]52;c;SGVs"), None);
+        assert_eq!(parse_answer(b"\x1b]52;c;!!\x07"), Some(None));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn helpers_are_asked_in_order() {
+        let mut clipboard = SystemClipboard::new(vec![
+            ClipboardSource::Command("false".to_string()),
+            ClipboardSource::Command("printf ''".to_string()),
+            ClipboardSource::Command("printf 'from the helper'".to_string()),
+        ]);
+        assert!(clipboard.readable());
+        assert_eq!(clipboard.read().as_deref(), Some("from the helper"));
+        assert_eq!(
+            ClipboardSource::from_name(" osc52 "),
+            Some(ClipboardSource::Terminal)
+        );
+    }
+
+    #[test]
+    fn the_terminal_is_only_asked_when_configured() {
+        assert!(!default_sources().contains(&ClipboardSource::Terminal));
+    }
+}
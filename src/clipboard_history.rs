@@ -0,0 +1,237 @@
+//! Clipboard history: the kill ring.
+//!
+//! Every cut and copy lands on Pure's clipboard and at the front of a short
+//! history, formatting included, so that something cut a while ago can
+//! still be pasted. The surrounding [`crate::app::App`] lists the history
+//! in a picker and cycles through it with Paste Previous, the way Emacs'
+//! yank-pop does.
+//!
+//! With `remember_clipboard` set in the configuration, the history is kept
+//! in Pure's state directory (`$XDG_STATE_HOME/pure/clipboard.toml`,
+//! falling back to `~/.local/state/pure/clipboard.toml`) for the next
+//! session. Only the owner can read the file.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tdoc::Document;
+use tdoc::ftml::{Writer, parse};
+use toml::{Table, Value};
+
+use crate::recovery;
+use crate::save;
+
+/// How many cuts and copies the history keeps.
+pub const CAPACITY: usize = 20;
+
+/// The longest preview of an entry, in characters.
+const PREVIEW_LEN: usize = 60;
+
+/// What a cut or copy leaves on the clipboard.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClipboardContents {
+    /// Plain-text rendering of the selection (or its Markdown or HTML) —
+    /// what is offered to the system clipboard via OSC 52.
+    pub text: String,
+    /// The selected paragraphs themselves, so in-app paste can restore
+    /// inline styles and paragraph structure. Empty when structured
+    /// extraction was not possible; paste then falls back to `text`.
+    pub fragment: Vec<tdoc::Paragraph>,
+}
+
+impl ClipboardContents {
+    /// The text on one line, shortened to fit a list.
+    pub fn preview(&self) -> String {
+        let line = self.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.chars().count() <= PREVIEW_LEN {
+            return line;
+        }
+        let mut preview: String = line.chars().take(PREVIEW_LEN - 1).collect();
+        preview.push('…');
+        preview
+    }
+}
+
+/// The cut and copied contents, newest first.
+#[derive(Clone, Debug, Default)]
+pub struct ClipboardHistory {
+    entries: VecDeque<ClipboardContents>,
+}
+
+impl ClipboardHistory {
+    /// Put `contents` at the front, moving it there if it is in the
+    /// history already, and drop the oldest entry when the history is
+    /// full.
+    pub fn push(&mut self, contents: ClipboardContents) {
+        self.entries.retain(|entry| entry.text != contents.text);
+        self.entries.push_front(contents);
+        self.entries.truncate(CAPACITY);
+    }
+
+    pub fn get(&self, index: usize) -> Option<&ClipboardContents> {
+        self.entries.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ClipboardContents> {
+        self.entries.iter()
+    }
+}
+
+/// The file holding the remembered history.
+fn history_file() -> Option<PathBuf> {
+    Some(recovery::state_home()?.join("clipboard.toml"))
+}
+
+/// The history remembered from the last session, as written by [`store`];
+/// empty if there is none or it cannot be read.
+pub fn load() -> ClipboardHistory {
+    history_file().map(|file| read(&file)).unwrap_or_default()
+}
+
+/// Remember `history` for the next session.
+pub fn store(history: &ClipboardHistory) -> io::Result<()> {
+    match history_file() {
+        Some(file) => write(&file, history),
+        None => Ok(()),
+    }
+}
+
+fn read(file: &Path) -> ClipboardHistory {
+    let Some(table) = fs::read_to_string(file)
+        .ok()
+        .and_then(|text| text.parse::<Table>().ok())
+    else {
+        return ClipboardHistory::default();
+    };
+    let entries = table
+        .get("entries")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let entries = entries
+        .iter()
+        .filter_map(|entry| {
+            let text = entry.get("text")?.as_str()?.to_string();
+            let fragment = entry
+                .get("ftml")
+                .and_then(Value::as_str)
+                .and_then(|ftml| parse(io::Cursor::new(ftml)).ok())
+                .map(|document| document.paragraphs)
+                .unwrap_or_default();
+            Some(ClipboardContents { text, fragment })
+        })
+        .take(CAPACITY)
+        .collect();
+    ClipboardHistory { entries }
+}
+
+fn write(file: &Path, history: &ClipboardHistory) -> io::Result<()> {
+    let entries = history
+        .iter()
+        .map(|entry| {
+            let mut table = Table::new();
+            table.insert("text".to_string(), Value::from(entry.text.as_str()));
+            if !entry.fragment.is_empty() {
+                let document = Document::new().with_paragraphs(entry.fragment.clone());
+                if let Ok(ftml) = Writer::new().write_to_string(&document) {
+                    table.insert("ftml".to_string(), Value::from(ftml));
+                }
+            }
+            Value::Table(table)
+        })
+        .collect::<Vec<_>>();
+    let mut table = Table::new();
+    table.insert("entries".to_string(), Value::Array(entries));
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    save::write_privately(file, table.to_string().as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::TempDir;
+
+    fn contents(text: &str) -> ClipboardContents {
+        ClipboardContents {
+            text: text.to_string(),
+            fragment: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn history_is_bounded_and_newest_first() {
+        let mut history = ClipboardHistory::default();
+        for index in 0..CAPACITY + 5 {
+            history.push(contents(&format!("entry {index}")));
+        }
+        assert_eq!(history.len(), CAPACITY);
+        assert_eq!(
+            history.get(0).map(|entry| entry.text.as_str()),
+            Some("entry 24")
+        );
+
+        // Copying something again moves it to the front.
+        history.push(contents("entry 10"));
+        assert_eq!(history.len(), CAPACITY);
+        assert_eq!(
+            history.get(0).map(|entry| entry.text.as_str()),
+            Some("entry 10")
+        );
+        assert_eq!(
+            history
+                .iter()
+                .filter(|entry| entry.text == "entry 10")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn history_is_written_with_its_formatting() {
+        let dir = TempDir::new("clipboard");
+        let file = dir.join("clipboard.toml");
+        assert!(read(&file).is_empty());
+
+        let document = tdoc::ftml! { h1 { "Plan" } p { "Go " b { "now" } } };
+        let mut history = ClipboardHistory::default();
+        history.push(contents("plain \"quoted\"\nlines"));
+        history.push(ClipboardContents {
+            text: "Plan\n\nGo now".to_string(),
+            fragment: document.paragraphs,
+        });
+        write(&file, &history).expect("write history");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&file).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let read_back = read(&file);
+        assert_eq!(
+            read_back.iter().collect::<Vec<_>>(),
+            history.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn previews_fit_on_one_line() {
+        assert_eq!(contents("One\n\n  two ").preview(), "One two");
+        let preview = contents(&"word ".repeat(30)).preview();
+        assert_eq!(preview.chars().count(), PREVIEW_LEN);
+        assert!(preview.ends_with('…'));
+    }
+}
@@ -0,0 +1,302 @@
+//! Command palette: every editor command in one searchable list.
+//!
+//! The palette lists the menu bar's actions and the formatting actions of the
+//! context menu, each with the key bound to it and whether it can be used at
+//! the cursor right now. Typing narrows the list with a fuzzy match, so `hd2`
+//! finds "Paragraph: Heading 2"; Up/Down pick an entry and Enter runs it. The
+//! surrounding [`crate::app::App`] builds the entries and runs the chosen
+//! command.
+//!
+//! The buffer switcher is the same list under another title, with one entry
+//! per open buffer.
+
+use tdoc::{InlineStyle, ParagraphType};
+
+use crate::app::MenuAction;
+use crate::keymap::Command;
+use crate::text_field::TextField;
+
+/// The context menu's actions with their palette labels. Cut, copy and paste
+/// are left out, as the menu bar's Edit menu has them already.
+pub const MENU_ACTIONS: &[(&str, MenuAction)] = &[
+    (
+        "Paragraph: Text",
+        MenuAction::SetParagraphType(ParagraphType::Text),
+    ),
+    (
+        "Paragraph: Heading 1",
+        MenuAction::SetParagraphType(ParagraphType::Header1),
+    ),
+    (
+        "Paragraph: Heading 2",
+        MenuAction::SetParagraphType(ParagraphType::Header2),
+    ),
+    (
+        "Paragraph: Heading 3",
+        MenuAction::SetParagraphType(ParagraphType::Header3),
+    ),
+    (
+        "Paragraph: Quote",
+        MenuAction::SetParagraphType(ParagraphType::Quote),
+    ),
+    (
+        "Paragraph: Code",
+        MenuAction::SetParagraphType(ParagraphType::CodeBlock),
+    ),
+    (
+        "Paragraph: Numbered List",
+        MenuAction::SetParagraphType(ParagraphType::OrderedList),
+    ),
+    (
+        "Paragraph: Bullet List",
+        MenuAction::SetParagraphType(ParagraphType::UnorderedList),
+    ),
+    (
+        "Paragraph: Checklist",
+        MenuAction::SetParagraphType(ParagraphType::Checklist),
+    ),
+    (
+        "Style: Bold",
+        MenuAction::ApplyInlineStyle(InlineStyle::Bold),
+    ),
+    (
+        "Style: Italic",
+        MenuAction::ApplyInlineStyle(InlineStyle::Italic),
+    ),
+    (
+        "Style: Underline",
+        MenuAction::ApplyInlineStyle(InlineStyle::Underline),
+    ),
+    (
+        "Style: Code",
+        MenuAction::ApplyInlineStyle(InlineStyle::Code),
+    ),
+    (
+        "Style: Highlight",
+        MenuAction::ApplyInlineStyle(InlineStyle::Highlight),
+    ),
+    (
+        "Style: Strikethrough",
+        MenuAction::ApplyInlineStyle(InlineStyle::Strike),
+    ),
+    (
+        "Style: Clear Formatting",
+        MenuAction::ApplyInlineStyle(InlineStyle::None),
+    ),
+    ("Style: Edit Link", MenuAction::EditLink),
+    ("Structure: Indent More", MenuAction::IndentMore),
+    ("Structure: Indent Less", MenuAction::IndentLess),
+    (
+        "Structure: Check Item",
+        MenuAction::SetChecklistItemChecked(true),
+    ),
+    (
+        "Structure: Uncheck Item",
+        MenuAction::SetChecklistItemChecked(false),
+    ),
+    ("Table: Insert Row Above", MenuAction::InsertTableRow(false)),
+    ("Table: Insert Row Below", MenuAction::InsertTableRow(true)),
+    (
+        "Table: Insert Column Left",
+        MenuAction::InsertTableColumn(false),
+    ),
+    (
+        "Table: Insert Column Right",
+        MenuAction::InsertTableColumn(true),
+    ),
+    ("Table: Delete Row", MenuAction::DeleteTableRow),
+    ("Table: Delete Column", MenuAction::DeleteTableColumn),
+];
+
+/// One command in the palette.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaletteEntry {
+    pub label: String,
+    pub command: Command,
+    /// The key bound to the command, as shown in menus.
+    pub shortcut: Option<String>,
+    /// Whether the command applies at the cursor; disabled entries are
+    /// listed, but cannot be run.
+    pub enabled: bool,
+}
+
+pub struct CommandPaletteState {
+    title: &'static str,
+    query: TextField,
+    entries: Vec<PaletteEntry>,
+    /// Indices into `entries` of the entries matching the query, best match
+    /// first.
+    matches: Vec<usize>,
+    /// Index into `matches` of the highlighted entry.
+    selected: usize,
+}
+
+impl CommandPaletteState {
+    pub fn new(title: &'static str, entries: Vec<PaletteEntry>) -> Self {
+        let mut palette = Self {
+            title,
+            query: TextField::default(),
+            entries,
+            matches: Vec::new(),
+            selected: 0,
+        };
+        palette.refresh();
+        palette
+    }
+
+    pub fn title(&self) -> &'static str {
+        self.title
+    }
+
+    pub fn query(&self) -> &TextField {
+        &self.query
+    }
+
+    /// The query, for editing; call [`Self::refresh`] afterwards.
+    pub fn query_mut(&mut self) -> &mut TextField {
+        &mut self.query
+    }
+
+    /// Filter the entries by the query again. The best match is
+    /// highlighted; without a query, the first enabled entry is.
+    pub fn refresh(&mut self) {
+        let query = self.query.text();
+        let mut scored: Vec<(i32, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| Some((fuzzy_score(query, &entry.label)?, index)))
+            .collect();
+        // Stable, so equally good matches keep the palette's order.
+        scored.sort_by_key(|(score, _)| -score);
+        self.matches = scored.into_iter().map(|(_, index)| index).collect();
+        self.selected = if query.is_empty() {
+            self.matches
+                .iter()
+                .position(|index| self.entries[*index].enabled)
+                .unwrap_or(0)
+        } else {
+            0
+        };
+    }
+
+    /// The entries matching the query, best match first.
+    pub fn matches(&self) -> impl Iterator<Item = &PaletteEntry> {
+        self.matches.iter().map(|index| &self.entries[*index])
+    }
+
+    pub fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Position of the highlighted entry among the matches.
+    pub fn selected(&self) -> Option<usize> {
+        (!self.matches.is_empty()).then_some(self.selected)
+    }
+
+    pub fn selected_entry(&self) -> Option<&PaletteEntry> {
+        let index = self.matches.get(self.selected)?;
+        Some(&self.entries[*index])
+    }
+
+    /// Move the highlight by `delta` entries, stopping at either end.
+    pub fn move_selection(&mut self, delta: isize) {
+        let last = self.matches.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+}
+
+/// How well `query` matches `candidate`, or `None` if it does not: every
+/// character of the query has to appear in the candidate in order, ignoring
+/// case and spaces. Matches at the start of words and runs of consecutive
+/// characters score higher, so `ins row` prefers "Insert Row" to a label that
+/// merely contains those letters.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    let candidate: Vec<char> = candidate.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query
+        .chars()
+        .filter(|ch| !ch.is_whitespace())
+        .flat_map(char::to_lowercase)
+    {
+        let found = position + candidate[position..].iter().position(|ch| *ch == wanted)?;
+        let word_start = found == 0 || !candidate[found - 1].is_alphanumeric();
+        if word_start {
+            score += 10;
+        }
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        }
+        // Letters skipped over make a match slightly worse.
+        score -= (found - position).min(10) as i32;
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu_bar::AppAction;
+
+    fn entry(label: &str, enabled: bool) -> PaletteEntry {
+        PaletteEntry {
+            label: label.to_string(),
+            command: Command::App(AppAction::New),
+            shortcut: None,
+            enabled,
+        }
+    }
+
+    fn labels(palette: &CommandPaletteState) -> Vec<&str> {
+        palette
+            .matches()
+            .map(|entry| entry.label.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn fuzzy_matches_need_the_query_in_order() {
+        assert!(fuzzy_score("hd2", "Paragraph: Heading 2").is_some());
+        assert!(fuzzy_score("SAVE", "File: Save As").is_some());
+        assert!(fuzzy_score("2hd", "Paragraph: Heading 2").is_none());
+        assert!(fuzzy_score("", "anything").is_some());
+        assert!(
+            fuzzy_score("ins row", "Table: Insert Row Above")
+                > fuzzy_score("ins row", "Structure: Indent More Rows")
+        );
+    }
+
+    #[test]
+    fn typing_filters_and_ranks_the_entries() {
+        let mut palette = CommandPaletteState::new(
+            "Command Palette",
+            vec![
+                entry("Edit: Cut", false),
+                entry("File: Save", true),
+                entry("File: Save As", true),
+                entry("Style: Strikethrough", true),
+            ],
+        );
+        assert_eq!(palette.match_count(), 4);
+        assert_eq!(
+            palette.selected_entry().map(|entry| entry.label.as_str()),
+            Some("File: Save"),
+            "the first enabled entry is highlighted"
+        );
+
+        palette.query_mut().insert_str("sa");
+        palette.refresh();
+        assert_eq!(labels(&palette), ["File: Save", "File: Save As"]);
+        palette.move_selection(5);
+        assert_eq!(palette.selected(), Some(1));
+
+        palette.query_mut().insert_str("xyz");
+        palette.refresh();
+        assert_eq!(palette.selected(), None);
+        assert!(palette.selected_entry().is_none());
+    }
+}
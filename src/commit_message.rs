@@ -0,0 +1,185 @@
+//! Git commit messages, for using Pure as `$EDITOR`.
+//!
+//! Git hands the editor a file such as `COMMIT_EDITMSG` holding room for the
+//! message, followed by lines starting with `#` that tell what is being
+//! committed; with `git commit --verbose`, a scissors line and the diff come
+//! last. Git drops the comment lines, and everything from the scissors line
+//! on, once the editor exits. Pure reads such a file as plain text, putting
+//! each run of comment lines — and the scissors line with all that follows —
+//! in a code block of its own. Those blocks cannot be edited, so they are
+//! written back the way they were read.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use tdoc::{Document, Paragraph, Span};
+
+use crate::app::{DocumentFormat, LoadedDocument};
+use crate::plain_text;
+
+/// The files Git asks the editor to fill in with a message.
+const FILE_NAMES: [&str; 3] = ["COMMIT_EDITMSG", "MERGE_MSG", "TAG_EDITMSG"];
+
+/// The line below which Git ignores everything.
+const SCISSORS: &str = "# ------------------------ >8 ------------------------";
+
+/// Whether `path` is a message Git waits for.
+pub fn is_commit_message(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| FILE_NAMES.contains(&name))
+}
+
+/// Read the commit message at `path`, which Git may not have written yet.
+/// Recovery files are not offered: they would come from another commit.
+pub fn load(path: &Path) -> Result<LoadedDocument> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read {}", path.display()));
+        }
+    };
+    Ok(LoadedDocument {
+        document: parse(&text),
+        format: DocumentFormat::PlainText,
+        status: Some("Save and quit to commit, or quit without saving to abort".to_string()),
+        recovery: None,
+        parse_failure: None,
+    })
+}
+
+/// Read a commit message: the message as plain text, the comments as code
+/// blocks. A message that is still empty gets an empty paragraph to type it
+/// in.
+pub fn parse(text: &str) -> Document {
+    let text = text.replace("\r\n", "\n");
+    let text = text.strip_suffix('\n').unwrap_or(&text);
+    let mut paragraphs = Vec::new();
+    let mut message: Vec<&str> = Vec::new();
+    let mut comment: Vec<&str> = Vec::new();
+    let mut lines = text.split('\n');
+    while let Some(line) = lines.next() {
+        if line.starts_with('#') {
+            flush_message(&mut message, &mut paragraphs);
+            comment.push(line);
+            if line == SCISSORS {
+                comment.extend(lines.by_ref());
+            }
+        } else {
+            flush_comment(&mut comment, &mut paragraphs);
+            message.push(line);
+        }
+    }
+    flush_message(&mut message, &mut paragraphs);
+    flush_comment(&mut comment, &mut paragraphs);
+
+    if !matches!(paragraphs.first(), Some(Paragraph::Text { .. })) {
+        paragraphs.insert(
+            0,
+            Paragraph::new_text().with_content(vec![Span::new_text("")]),
+        );
+    }
+    Document::new().with_paragraphs(paragraphs)
+}
+
+fn flush_message(lines: &mut Vec<&str>, paragraphs: &mut Vec<Paragraph>) {
+    if !lines.is_empty() {
+        paragraphs.extend(plain_text::parse(&lines.join("\n")).paragraphs);
+        lines.clear();
+    }
+}
+
+fn flush_comment(lines: &mut Vec<&str>, paragraphs: &mut Vec<Paragraph>) {
+    if !lines.is_empty() {
+        paragraphs
+            .push(Paragraph::new_code_block().with_content(vec![Span::new_text(lines.join("\n"))]));
+        lines.clear();
+    }
+}
+
+/// A commit message being edited.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommitMessage {
+    /// The text of each comment block, as [`parse`] made them.
+    comments: Vec<String>,
+    /// Whether the message has been saved; quitting without saving aborts
+    /// the commit.
+    pub saved: bool,
+}
+
+impl CommitMessage {
+    /// Track the comment blocks of `document`, as read by [`parse`].
+    pub fn new(document: &Document) -> Self {
+        let comments = document
+            .paragraphs
+            .iter()
+            .filter_map(|paragraph| match paragraph {
+                Paragraph::CodeBlock { content } => Some(plain_text::spans_text(content)),
+                _ => None,
+            })
+            .collect();
+        Self {
+            comments,
+            saved: false,
+        }
+    }
+
+    /// Whether `paragraph` is one of the comment blocks.
+    pub fn is_comment(&self, paragraph: &Paragraph) -> bool {
+        match paragraph {
+            Paragraph::CodeBlock { content } => {
+                self.comments.contains(&plain_text::spans_text(content))
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written(document: &Document) -> String {
+        let mut out = Vec::new();
+        plain_text::write(&mut out, document, None).expect("write plain text");
+        String::from_utf8(out).expect("utf-8")
+    }
+
+    #[test]
+    fn recognizes_the_files_git_asks_for() {
+        assert!(is_commit_message(Path::new(".git/COMMIT_EDITMSG")));
+        assert!(is_commit_message(Path::new("MERGE_MSG")));
+        assert!(!is_commit_message(Path::new("notes.txt")));
+    }
+
+    #[test]
+    fn comments_become_code_blocks_kept_as_they_were() {
+        let template = "\n# Please enter the commit message for your changes.\n#\n\
+                        # On branch main\n#\tmodified:   src/app.rs\n#\n";
+        let document = parse(template);
+        assert_eq!(document.paragraphs.len(), 2);
+        assert!(matches!(document.paragraphs[0], Paragraph::Text { .. }));
+        let message = CommitMessage::new(&document);
+        assert!(!message.is_comment(&document.paragraphs[0]));
+        assert!(message.is_comment(&document.paragraphs[1]));
+        assert_eq!(written(&document), template);
+    }
+
+    #[test]
+    fn everything_below_the_scissors_line_is_one_comment() {
+        let text = format!(
+            "Fix the frobnicator\n\nIt was broken.\n# Comment\n{SCISSORS}\n\
+             # Do not modify or remove the line above.\ndiff --git a/x b/x\n\n+added\n"
+        );
+        let document = parse(&text);
+        assert_eq!(document.paragraphs.len(), 3);
+        let Paragraph::CodeBlock { content } = &document.paragraphs[2] else {
+            panic!("expected the scissors block");
+        };
+        assert!(plain_text::spans_text(content).ends_with("b/x\n\n+added"));
+        assert_eq!(written(&document), text.replace("broken.\n", "broken.\n\n"));
+    }
+}
@@ -0,0 +1,465 @@
+//! User configuration, read from `$XDG_CONFIG_HOME/pure/config.toml`
+//! (falling back to `~/.config/pure/config.toml`).
+//!
+//! The file has three optional tables:
+//!
+//! ```toml
+//! [editor]
+//! max_text_width = 80       # widest a line of text gets on wide terminals
+//! status_timeout = 6        # seconds a status message stays visible
+//! mouse_scroll_lines = 5    # lines per mouse wheel step
+//! backup = "tilde"          # keep the previous version: "none", "tilde", "bak"
+//! keymap = "emacs"          # key binding preset: "default", "emacs", "wordstar"
+//! plain_text_wrap = 72      # hard-wrap plain-text files when saving (0: don't)
+//! remember_folds = true     # fold the same parts when a file is opened again
+//! remember_clipboard = true # keep the clipboard history for the next session
+//! autoformat = false        # leave Markdown typed into documents as it is
+//! clipboard = ["wl-paste"]  # paste from: commands, "osc52" for the terminal
+//!
+//! [theme]
+//! status_bar_bg = "#005f87" # any `Theme` field; names, indices or #rrggbb
+//!
+//! [keys]
+//! "ctrl+b" = "bold"         # a key chord and a command name
+//! "ctrl+x ctrl+b" = "save"  # a key sequence
+//! "ctrl+q" = "none"         # remove the preset's binding
+//! ```
+//!
+//! The `[keys]` table changes the chosen preset, wherever `keymap` appears
+//! in the file.
+//!
+//! A missing file means the defaults. Problems never stop Pure from
+//! starting: an entry that cannot be used is skipped and described in
+//! [`Config::problems`], which the app shows in the status line.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use ratatui::style::Color;
+use toml::{Table, Value};
+
+use crate::clipboard::{self, ClipboardSource};
+use crate::keymap::{Command, KeyChord, Keymap, KeymapPreset};
+use crate::save::Backup;
+use crate::theme::Theme;
+
+pub const DEFAULT_MAX_TEXT_WIDTH: usize = 92;
+pub const DEFAULT_STATUS_TIMEOUT: Duration = Duration::from_secs(4);
+pub const DEFAULT_MOUSE_SCROLL_LINES: usize = 3;
+
+/// Narrower text widths make for unreadably short lines.
+const MIN_MAX_TEXT_WIDTH: usize = 20;
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub theme: Theme,
+    /// The preset's bindings with the user's changes applied.
+    pub keymap: Keymap,
+    /// The widest the text column gets; wider terminals add margins.
+    pub max_text_width: usize,
+    pub status_timeout: Duration,
+    pub mouse_scroll_lines: usize,
+    pub backup: Backup,
+    /// The width plain-text files are hard-wrapped at when saved; `None`
+    /// keeps lines as they are.
+    pub plain_text_wrap: Option<usize>,
+    /// Whether the folds of each file are kept for the next time it is
+    /// opened; see [`crate::fold_state`].
+    pub remember_folds: bool,
+    /// Whether the clipboard history is kept for the next session; see
+    /// [`crate::clipboard_history`].
+    pub remember_clipboard: bool,
+    /// Whether Markdown typed into a document turns into formatting; see
+    /// [`crate::editor::Autoformat`].
+    pub autoformat: bool,
+    /// Where the system clipboard is read from, in order; see
+    /// [`crate::clipboard`].
+    pub clipboard: Vec<ClipboardSource>,
+    /// Entries that were skipped, described for the user.
+    pub problems: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            keymap: Keymap::default(),
+            max_text_width: DEFAULT_MAX_TEXT_WIDTH,
+            status_timeout: DEFAULT_STATUS_TIMEOUT,
+            mouse_scroll_lines: DEFAULT_MOUSE_SCROLL_LINES,
+            backup: Backup::None,
+            plain_text_wrap: None,
+            remember_folds: false,
+            remember_clipboard: false,
+            autoformat: true,
+            clipboard: clipboard::default_sources(),
+            problems: Vec::new(),
+        }
+    }
+}
+
+/// Where the configuration file lives.
+pub fn path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("pure").join("config.toml"))
+}
+
+/// Read the user's configuration file.
+pub fn load() -> Config {
+    let Some(path) = path() else {
+        return Config::default();
+    };
+    match fs::read_to_string(&path) {
+        Ok(source) => parse(&source),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Config::default(),
+        Err(err) => Config {
+            problems: vec![format!("failed to read {}: {err}", path.display())],
+            ..Config::default()
+        },
+    }
+}
+
+/// Read the configuration file at `path`, given in place of the user's. A
+/// file that cannot be read is an error rather than a problem to show, since
+/// it was asked for by name.
+pub fn load_from(path: &Path) -> io::Result<Config> {
+    fs::read_to_string(path).map(|source| parse(&source))
+}
+
+/// Build a configuration from the contents of a configuration file.
+pub fn parse(source: &str) -> Config {
+    let mut config = Config::default();
+    let table = match source.parse::<Table>() {
+        Ok(table) => table,
+        Err(err) => {
+            let message = err.message().to_string();
+            let line = err
+                .span()
+                .map(|span| source[..span.start].matches('\n').count() + 1);
+            config.problems.push(match line {
+                Some(line) => format!("config.toml line {line}: {message}"),
+                None => format!("config.toml: {message}"),
+            });
+            return config;
+        }
+    };
+
+    let mut keys = None;
+    for (key, value) in &table {
+        match (key.as_str(), value) {
+            ("editor", Value::Table(editor)) => config.apply_editor(editor),
+            ("theme", Value::Table(theme)) => config.apply_theme(theme),
+            ("keys", Value::Table(table)) => keys = Some(table),
+            ("editor" | "theme" | "keys", _) => {
+                config.problems.push(format!("`{key}` must be a table"));
+            }
+            _ => config.problems.push(format!("unknown setting `{key}`")),
+        }
+    }
+    if let Some(keys) = keys {
+        config.apply_keys(keys);
+    }
+    config
+}
+
+impl Config {
+    fn apply_editor(&mut self, editor: &Table) {
+        for (key, value) in editor {
+            let applied = match key.as_str() {
+                "max_text_width" => match value.as_integer() {
+                    Some(width) if width >= MIN_MAX_TEXT_WIDTH as i64 => {
+                        self.max_text_width = width as usize;
+                        Ok(())
+                    }
+                    _ => Err(format!("a number of at least {MIN_MAX_TEXT_WIDTH}")),
+                },
+                "status_timeout" => {
+                    let seconds = value
+                        .as_float()
+                        .or_else(|| value.as_integer().map(|seconds| seconds as f64));
+                    match seconds.and_then(|seconds| Duration::try_from_secs_f64(seconds).ok()) {
+                        Some(timeout) => {
+                            self.status_timeout = timeout;
+                            Ok(())
+                        }
+                        None => Err("a number of seconds".to_string()),
+                    }
+                }
+                "mouse_scroll_lines" => match value.as_integer() {
+                    Some(lines) if lines >= 1 => {
+                        self.mouse_scroll_lines = lines as usize;
+                        Ok(())
+                    }
+                    _ => Err("a positive number".to_string()),
+                },
+                "backup" => match value.as_str() {
+                    Some("none") => {
+                        self.backup = Backup::None;
+                        Ok(())
+                    }
+                    Some("tilde") => {
+                        self.backup = Backup::Tilde;
+                        Ok(())
+                    }
+                    Some("bak") => {
+                        self.backup = Backup::Bak;
+                        Ok(())
+                    }
+                    _ => Err(r#""none", "tilde" or "bak""#.to_string()),
+                },
+                "plain_text_wrap" => match value.as_integer() {
+                    Some(0) => {
+                        self.plain_text_wrap = None;
+                        Ok(())
+                    }
+                    Some(width) if width >= MIN_MAX_TEXT_WIDTH as i64 => {
+                        self.plain_text_wrap = Some(width as usize);
+                        Ok(())
+                    }
+                    _ => Err(format!("0 or a number of at least {MIN_MAX_TEXT_WIDTH}")),
+                },
+                "remember_folds" => match value.as_bool() {
+                    Some(remember) => {
+                        self.remember_folds = remember;
+                        Ok(())
+                    }
+                    None => Err("true or false".to_string()),
+                },
+                "remember_clipboard" => match value.as_bool() {
+                    Some(remember) => {
+                        self.remember_clipboard = remember;
+                        Ok(())
+                    }
+                    None => Err("true or false".to_string()),
+                },
+                "autoformat" => match value.as_bool() {
+                    Some(autoformat) => {
+                        self.autoformat = autoformat;
+                        Ok(())
+                    }
+                    None => Err("true or false".to_string()),
+                },
+                "clipboard" => {
+                    let sources = value.as_array().and_then(|names| {
+                        names
+                            .iter()
+                            .map(|name| name.as_str().and_then(ClipboardSource::from_name))
+                            .collect::<Option<Vec<_>>>()
+                    });
+                    match sources {
+                        Some(sources) => {
+                            self.clipboard = sources;
+                            Ok(())
+                        }
+                        None => Err(format!(
+                            "a list of commands or \"{}\"",
+                            clipboard::TERMINAL_SOURCE
+                        )),
+                    }
+                }
+                "keymap" => match value.as_str().and_then(KeymapPreset::from_name) {
+                    Some(preset) => {
+                        self.keymap = Keymap::preset(preset);
+                        Ok(())
+                    }
+                    None => Err(r#""default", "emacs" or "wordstar""#.to_string()),
+                },
+                _ => {
+                    self.problems
+                        .push(format!("unknown setting `editor.{key}`"));
+                    continue;
+                }
+            };
+            if let Err(expected) = applied {
+                self.problems
+                    .push(format!("`editor.{key}` must be {expected}"));
+            }
+        }
+    }
+
+    fn apply_theme(&mut self, theme: &Table) {
+        for (key, value) in theme {
+            let color = value.as_str().and_then(|text| text.parse::<Color>().ok());
+            match (self.theme.color_mut(key), color) {
+                (Some(slot), Some(color)) => *slot = color,
+                (Some(_), None) => self
+                    .problems
+                    .push(format!("`theme.{key}` is not a color: {value}")),
+                (None, _) => self.problems.push(format!("unknown theme color `{key}`")),
+            }
+        }
+    }
+
+    fn apply_keys(&mut self, keys: &Table) {
+        for (key, value) in keys {
+            let sequence = match KeyChord::parse_sequence(key) {
+                Ok(sequence) => sequence,
+                Err(err) => {
+                    self.problems.push(err);
+                    continue;
+                }
+            };
+            if value.as_str() == Some("none") {
+                self.keymap.unbind(&sequence);
+                continue;
+            }
+            match value.as_str().and_then(Command::from_name) {
+                Some(command) => self.keymap.bind(sequence, command),
+                None => self
+                    .problems
+                    .push(format!("unknown command for `{key}`: {value}")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::Lookup;
+    use crate::menu_bar::AppAction;
+
+    fn lookup(config: &Config, keys: &str) -> Lookup {
+        config
+            .keymap
+            .lookup(&KeyChord::parse_sequence(keys).expect("valid key sequence"))
+    }
+
+    #[test]
+    fn settings_override_the_defaults() {
+        let config = parse(
+            r##"
+            [editor]
+            max_text_width = 72
+            status_timeout = 1.5
+            mouse_scroll_lines = 1
+            backup = "bak"
+            plain_text_wrap = 72
+            remember_folds = true
+            remember_clipboard = true
+            autoformat = false
+            clipboard = ["osc52", "wl-paste"]
+
+            [theme]
+            status_bar_bg = "#102030"
+            menu_fg = "light-cyan"
+
+            [keys]
+            "ctrl+g" = "find"
+            "##,
+        );
+        assert!(config.problems.is_empty(), "{:?}", config.problems);
+        assert_eq!(config.max_text_width, 72);
+        assert_eq!(config.status_timeout, Duration::from_millis(1500));
+        assert_eq!(config.mouse_scroll_lines, 1);
+        assert_eq!(config.backup, Backup::Bak);
+        assert_eq!(config.plain_text_wrap, Some(72));
+        assert!(config.remember_folds);
+        assert!(config.remember_clipboard);
+        assert!(!config.autoformat);
+        assert_eq!(
+            config.clipboard,
+            [
+                ClipboardSource::Terminal,
+                ClipboardSource::Command("wl-paste".to_string())
+            ]
+        );
+        assert_eq!(config.theme.status_bar_bg, Color::Rgb(0x10, 0x20, 0x30));
+        assert_eq!(config.theme.menu_fg, Color::LightCyan);
+        assert_eq!(
+            lookup(&config, "ctrl+g"),
+            Lookup::Command(Command::App(AppAction::Find))
+        );
+    }
+
+    #[test]
+    fn keys_change_the_chosen_preset() {
+        let config = parse(
+            r#"
+            [keys]
+            "ctrl+x ctrl+b" = "bold"
+            "ctrl+x ctrl+c" = "none"
+
+            [editor]
+            keymap = "emacs"
+            "#,
+        );
+        assert!(config.problems.is_empty(), "{:?}", config.problems);
+        assert_eq!(
+            lookup(&config, "ctrl+x ctrl+s"),
+            Lookup::Command(Command::App(AppAction::Save))
+        );
+        assert!(matches!(
+            lookup(&config, "ctrl+x ctrl+b"),
+            Lookup::Command(Command::Menu(_))
+        ));
+        assert_eq!(lookup(&config, "ctrl+x ctrl+c"), Lookup::Unbound);
+        assert_eq!(
+            config.keymap.shortcut_label(Command::App(AppAction::Quit)),
+            None
+        );
+    }
+
+    #[test]
+    fn invalid_entries_are_reported_and_skipped() {
+        let config = parse(
+            r#"
+            [editor]
+            max_text_width = 5
+            tab_width = 4
+            mouse_scroll_lines = 2
+            keymap = "vi"
+
+            [theme]
+            menu_bg = "plaid"
+            sparkles = "red"
+
+            [keys]
+            "ctrl+g" = "launch_rockets"
+            "hyper+x" = "save"
+            "#,
+        );
+        assert_eq!(
+            config.problems,
+            [
+                "`editor.keymap` must be \"default\", \"emacs\" or \"wordstar\"",
+                "`editor.max_text_width` must be a number of at least 20",
+                "unknown setting `editor.tab_width`",
+                "`theme.menu_bg` is not a color: \"plaid\"",
+                "unknown theme color `sparkles`",
+                "unknown command for `ctrl+g`: \"launch_rockets\"",
+                "unknown modifier `hyper` in `hyper+x`",
+            ]
+        );
+        assert_eq!(config.max_text_width, DEFAULT_MAX_TEXT_WIDTH);
+        // Valid entries next to invalid ones still apply.
+        assert_eq!(config.mouse_scroll_lines, 2);
+        assert_eq!(
+            lookup(&config, "ctrl+g"),
+            Lookup::Unbound,
+            "the default keymap stays in place"
+        );
+        assert_eq!(
+            lookup(&config, "ctrl+s"),
+            Lookup::Command(Command::App(AppAction::Save))
+        );
+    }
+
+    #[test]
+    fn syntax_errors_fall_back_to_the_defaults() {
+        let config = parse("[editor]\nmax_text_width = 80\nstatus_timeout = \n");
+        assert_eq!(config.problems.len(), 1);
+        assert!(
+            config.problems[0].starts_with("config.toml line 3:"),
+            "{:?}",
+            config.problems
+        );
+        assert_eq!(config.max_text_width, DEFAULT_MAX_TEXT_WIDTH);
+    }
+}
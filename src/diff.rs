@@ -0,0 +1,194 @@
+//! Line-by-line differences between two versions of a text, as shown when
+//! the file of an open document changed on disk.
+//!
+//! Lines both versions start and end with are matched right away; the
+//! lines in between are compared as a longest common subsequence. Should
+//! that part be too large to compare in reasonable time, it is shown as
+//! removed and added wholesale.
+
+/// Largest number of line pairs compared for a common subsequence.
+const COMPARISON_LIMIT: usize = 4_000_000;
+
+/// What became of a line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineKind {
+    /// In both versions.
+    Same,
+    /// Only in the old version.
+    Removed,
+    /// Only in the new version.
+    Added,
+    /// Unchanged lines left out between two changes.
+    Gap,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffLine {
+    pub kind: LineKind,
+    pub text: String,
+}
+
+impl DiffLine {
+    fn new(kind: LineKind, text: &str) -> Self {
+        Self {
+            kind,
+            text: text.to_string(),
+        }
+    }
+}
+
+/// The changes turning `old` into `new`, each with up to `context`
+/// unchanged lines around it. Longer runs of unchanged lines are cut down
+/// to a [`LineKind::Gap`]. Identical texts give no lines at all.
+pub fn unified(old: &str, new: &str, context: usize) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let lines = compare(&old, &new);
+
+    let changed: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.kind != LineKind::Same)
+        .map(|(index, _)| index)
+        .collect();
+    if changed.is_empty() {
+        return Vec::new();
+    }
+    let near_change = |index: usize| {
+        let first = changed.partition_point(|&change| change + context < index);
+        changed
+            .get(first)
+            .is_some_and(|&change| change <= index + context)
+    };
+
+    let mut out = Vec::new();
+    let mut skipped = false;
+    for (index, line) in lines.into_iter().enumerate() {
+        if near_change(index) {
+            if skipped && !out.is_empty() {
+                out.push(DiffLine::new(LineKind::Gap, ""));
+            }
+            skipped = false;
+            out.push(line);
+        } else {
+            skipped = true;
+        }
+    }
+    out
+}
+
+/// Every line of `old` and `new`, matched up.
+fn compare(old: &[&str], new: &[&str]) -> Vec<DiffLine> {
+    let prefix = old
+        .iter()
+        .zip(new)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let mut lines: Vec<DiffLine> = old[..prefix]
+        .iter()
+        .map(|line| DiffLine::new(LineKind::Same, line))
+        .collect();
+    if old_middle.len().saturating_mul(new_middle.len()) > COMPARISON_LIMIT {
+        lines.extend(
+            old_middle
+                .iter()
+                .map(|line| DiffLine::new(LineKind::Removed, line)),
+        );
+        lines.extend(
+            new_middle
+                .iter()
+                .map(|line| DiffLine::new(LineKind::Added, line)),
+        );
+    } else {
+        lines.extend(common_subsequence(old_middle, new_middle));
+    }
+    lines.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|line| DiffLine::new(LineKind::Same, line)),
+    );
+    lines
+}
+
+fn common_subsequence(old: &[&str], new: &[&str]) -> Vec<DiffLine> {
+    // lengths[i][j]: the longest common subsequence of old[i..] and new[j..].
+    let width = new.len() + 1;
+    let mut lengths = vec![0usize; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * width + j] = if old[i] == new[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(DiffLine::new(LineKind::Same, old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len()
+            && (j == new.len() || lengths[(i + 1) * width + j] >= lengths[i * width + j + 1])
+        {
+            lines.push(DiffLine::new(LineKind::Removed, old[i]));
+            i += 1;
+        } else {
+            lines.push(DiffLine::new(LineKind::Added, new[j]));
+            j += 1;
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(lines: &[DiffLine]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| match line.kind {
+                LineKind::Same => format!(" {}", line.text),
+                LineKind::Removed => format!("-{}", line.text),
+                LineKind::Added => format!("+{}", line.text),
+                LineKind::Gap => "…".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn identical_texts_have_no_differences() {
+        assert!(unified("a\nb\n", "a\nb\n", 2).is_empty());
+    }
+
+    #[test]
+    fn changes_come_with_context_and_gaps() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+        let new = "1\nTWO\n3\n4\n5\n6\n7\n8\n9\nten\n";
+        assert_eq!(
+            render(&unified(old, new, 1)),
+            vec![" 1", "-2", "+TWO", " 3", "…", " 9", "+ten"]
+        );
+    }
+
+    #[test]
+    fn moved_lines_are_removed_and_added() {
+        assert_eq!(
+            render(&unified("a\nb\nc\n", "b\nc\na\n", 3)),
+            vec!["-a", " b", " c", "+a"]
+        );
+    }
+}
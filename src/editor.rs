@@ -1,36 +1,50 @@
 const MARKER_POINTER_PREFIX: &str = "1337;M";
 const MARKER_REVEAL_PREFIX: &str = "1337;R";
 use std::cmp::Ordering;
+use std::ops::Range;
 use tdoc::{ChecklistItem, Document, InlineStyle, Paragraph, ParagraphType, Span};
 
 use content::{insert_char_at, prune_and_merge_spans, remove_char_at};
 
+mod autoformat;
 pub mod content;
 pub mod cursor;
+mod folds;
 pub mod inspect;
 mod links;
+mod search;
 mod structure;
 mod styles;
+mod tables;
 
+pub use autoformat::Autoformat;
+pub use folds::FoldRef;
 pub use links::LinkAtCursor;
+pub use search::{SearchHighlight, SearchMatch, SearchOptions, SearchQuery};
 pub(crate) use styles::inline_style_label;
 
-use inspect::{checklist_item_ref, paragraph_ref, span_ref, span_ref_from_item};
+use inspect::{checklist_item_ref, content_spans_ref, paragraph_ref, span_ref};
 use structure::{
     EntryContext, IndentTarget, IndentTargetKind, ParentRelation,
     append_paragraph_as_checklist_child, append_paragraph_to_entry, append_paragraph_to_list,
     append_paragraph_to_quote, break_list_entry_for_non_list_target, checklist_item_mut,
-    convert_paragraph_into_list, determine_parent_scope, ensure_checklist_item_has_content,
-    ensure_document_initialized, ensure_list_entry_has_paragraph, entry_has_multiple_paragraphs,
-    extract_checklist_item_context, extract_entry_context, find_container_indent_target,
-    find_indent_target, find_list_ancestor_path, indent_checklist_item_into_item,
-    indent_list_entry_into_entry, indent_list_entry_into_foreign_list,
-    indent_paragraph_within_entry, insert_paragraph_after_parent, is_list_type,
-    is_single_paragraph_entry, list_entry_append_target, merge_adjacent_lists, paragraph_is_empty,
-    paragraph_mut, parent_paragraph_path, promote_list_entry_to_parent,
-    promote_single_child_into_parent, remove_paragraph_by_path, span_mut, span_mut_from_item,
-    split_list_entries_after, split_paragraph_break, take_checklist_item_at, take_list_entry,
-    take_paragraph_at, unindent_checklist_item, update_existing_list_type, update_paragraph_type,
+    content_spans_mut, convert_paragraph_into_list, determine_parent_scope,
+    ensure_checklist_item_has_content, ensure_document_initialized,
+    ensure_list_entry_has_paragraph, ensure_table_cell_has_content, entry_has_multiple_paragraphs,
+    extract_checklist_item_context, extract_entry_context, extract_table_cell_context,
+    find_container_indent_target, find_indent_target, find_list_ancestor_path,
+    indent_checklist_item_into_item, indent_list_entry_into_entry,
+    indent_list_entry_into_foreign_list, indent_paragraph_within_entry,
+    insert_paragraph_after_parent, is_list_type, is_single_paragraph_entry,
+    list_entry_append_target, merge_adjacent_lists, paragraph_is_empty, paragraph_mut,
+    parent_paragraph_path, promote_list_entry_to_parent, promote_single_child_into_parent,
+    remove_paragraph_by_path, span_mut, split_list_entries_after, split_paragraph_break,
+    take_checklist_item_at, take_list_entry, take_paragraph_at, unindent_checklist_item,
+    update_existing_list_type, update_paragraph_type,
+};
+use structure::{
+    can_shift_heading_levels, next_sibling_section, previous_sibling_section, section_containing,
+    shift_heading_levels, swap_root_ranges,
 };
 
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
@@ -49,6 +63,10 @@ enum PathStep {
     ChecklistItem {
         indices: Vec<usize>,
     },
+    TableCell {
+        row: usize,
+        column: usize,
+    },
 }
 
 impl ParagraphPath {
@@ -77,6 +95,10 @@ impl ParagraphPath {
         self.steps.push(PathStep::ChecklistItem { indices });
     }
 
+    pub fn push_table_cell(&mut self, row: usize, column: usize) {
+        self.steps.push(PathStep::TableCell { row, column });
+    }
+
     pub fn pop(&mut self) {
         if self.steps.len() > 1 {
             self.steps.pop();
@@ -106,6 +128,10 @@ impl ParagraphPath {
                         nums.push(*idx);
                     }
                 }
+                PathStep::TableCell { row, column } => {
+                    nums.push(*row);
+                    nums.push(*column);
+                }
             }
         }
         nums
@@ -123,6 +149,12 @@ impl ParagraphPath {
         }
     }
 
+    fn set_root_index(&mut self, index: usize) {
+        if let Some(PathStep::Root(idx)) = self.steps.first_mut() {
+            *idx = index;
+        }
+    }
+
     /// Shift the root index down by `base`, e.g. to re-anchor the path in a
     /// document that contains only a slice of the original root paragraphs.
     fn rebase_root(&mut self, base: usize) {
@@ -259,16 +291,10 @@ pub struct DocumentEditor {
     cursor: CursorPointer,
     cursor_segment: usize,
     reveal_codes: bool,
-    /// Content width (in columns) the read-only blocks — currently tables —
-    /// are laid out at when building their navigable per-line segments. Kept in
-    /// sync with the renderer's wrap width by [`EditorDisplay`] so the segments
-    /// match what is drawn. See [`DocumentEditor::set_layout_width`].
-    layout_width: usize,
+    /// Heads of the folded parts, see [`folds`].
+    folds: Vec<ParagraphPath>,
 }
 
-/// Default content width used before the display reports its real wrap width.
-const DEFAULT_LAYOUT_WIDTH: usize = 80;
-
 impl DocumentEditor {
     fn normalize_selection(
         &self,
@@ -581,33 +607,99 @@ impl DocumentEditor {
             return false;
         }
 
-        // Refuse to delete a selection that touches a read-only paragraph (e.g.
-        // a table), so its content cannot be removed even as part of a larger
-        // multi-paragraph selection.
-        if let Some(targets) = self.selection_paragraph_targets(selection)
-            && targets
-                .iter()
-                .any(|target| self.is_readonly_paragraph(&target.paragraph_path))
-        {
-            return false;
-        }
-
         let (start, end) = match self.normalize_selection(selection) {
             Some(bounds) => bounds,
             None => return false,
         };
 
-        let mut moved = self.move_to_pointer(&end);
+        // Table cells are removed one at a time so that a selection spanning
+        // several cells (or reaching into a table from outside) clears their
+        // text but keeps the grid intact. Regions are processed back to front
+        // so the pointers of the earlier ones stay valid.
+        let Some(regions) = self.selection_regions(&start, &end) else {
+            return false;
+        };
+        let mut removed = false;
+        for (region_start, region_end) in regions.iter().rev() {
+            if self.compare_pointers(region_start, region_end) != Some(Ordering::Less) {
+                continue;
+            }
+            if !self.remove_range(region_start, region_end) {
+                return false;
+            }
+            removed = true;
+        }
+        if !removed {
+            return false;
+        }
+        if !self.move_to_pointer(&start) {
+            let _ = self.fallback_move_to_text(&start, false)
+                || self.fallback_move_to_text(&start, true);
+        }
+        true
+    }
+
+    /// Split the range `start..end` into the runs that can be removed
+    /// independently: every table cell forms its own run, and consecutive
+    /// segments outside of tables are grouped together.
+    fn selection_regions(
+        &self,
+        start: &CursorPointer,
+        end: &CursorPointer,
+    ) -> Option<Vec<(CursorPointer, CursorPointer)>> {
+        let start_key = self.pointer_key(start)?;
+        let end_key = self.pointer_key(end)?;
+
+        let mut regions: Vec<(CursorPointer, CursorPointer)> = Vec::new();
+        let mut current_region: Option<Option<ParagraphPath>> = None;
+        for segment_index in start_key.segment_index..=end_key.segment_index {
+            let segment = self.segments.get(segment_index)?;
+            let region = self
+                .is_table_path(&segment.paragraph_path)
+                .then(|| segment.paragraph_path.clone());
+            let region_start = if segment_index == start_key.segment_index {
+                start.clone()
+            } else {
+                CursorPointer {
+                    paragraph_path: segment.paragraph_path.clone(),
+                    span_path: segment.span_path.clone(),
+                    offset: 0,
+                    segment_kind: segment.kind,
+                }
+            };
+            let region_end = if segment_index == end_key.segment_index {
+                end.clone()
+            } else {
+                CursorPointer {
+                    paragraph_path: segment.paragraph_path.clone(),
+                    span_path: segment.span_path.clone(),
+                    offset: segment.len,
+                    segment_kind: segment.kind,
+                }
+            };
+            if current_region.as_ref() == Some(&region) {
+                if let Some(last) = regions.last_mut() {
+                    last.1 = region_end;
+                }
+            } else {
+                regions.push((region_start, region_end));
+                current_region = Some(region);
+            }
+        }
+        Some(regions)
+    }
+
+    fn remove_range(&mut self, start: &CursorPointer, end: &CursorPointer) -> bool {
+        let mut moved = self.move_to_pointer(end);
         if !moved {
-            moved =
-                self.fallback_move_to_text(&end, false) || self.fallback_move_to_text(&end, true);
+            moved = self.fallback_move_to_text(end, false) || self.fallback_move_to_text(end, true);
         }
         if !moved {
             return false;
         }
 
         loop {
-            match self.compare_pointers(&self.cursor, &start) {
+            match self.compare_pointers(&self.cursor, start) {
                 Some(Ordering::Greater) => {
                     if !self.backspace() {
                         return false;
@@ -615,10 +707,10 @@ impl DocumentEditor {
                 }
                 Some(Ordering::Equal) => break,
                 Some(Ordering::Less) => {
-                    let mut moved_to_start = self.move_to_pointer(&start);
+                    let mut moved_to_start = self.move_to_pointer(start);
                     if !moved_to_start {
-                        moved_to_start = self.fallback_move_to_text(&start, false)
-                            || self.fallback_move_to_text(&start, true);
+                        moved_to_start = self.fallback_move_to_text(start, false)
+                            || self.fallback_move_to_text(start, true);
                     }
                     if !moved_to_start {
                         return false;
@@ -640,7 +732,7 @@ impl DocumentEditor {
             cursor: CursorPointer::default(),
             cursor_segment: 0,
             reveal_codes: false,
-            layout_width: DEFAULT_LAYOUT_WIDTH,
+            folds: Vec::new(),
         };
         editor.rebuild_segments();
         editor.ensure_cursor_selectable();
@@ -651,18 +743,25 @@ impl DocumentEditor {
         &self.document
     }
 
-    /// Whether the paragraph at `path` is read-only (currently: tables). The
-    /// editor refuses to mutate read-only paragraphs, but the cursor may still
-    /// be positioned within them.
-    pub fn is_readonly_paragraph(&self, path: &ParagraphPath) -> bool {
-        paragraph_ref(&self.document, path)
-            .map(|paragraph| paragraph.paragraph_type() == ParagraphType::Table)
-            .unwrap_or(false)
+    /// Whether `path` points at a table or into one of its cells. Text inside
+    /// a cell is editable, but the grid itself never takes part in paragraph
+    /// operations (type changes, splits, merges, indentation).
+    pub fn is_table_path(&self, path: &ParagraphPath) -> bool {
+        extract_table_cell_context(path).is_some()
+            || paragraph_ref(&self.document, path)
+                .map(|paragraph| paragraph.paragraph_type() == ParagraphType::Table)
+                .unwrap_or(false)
+    }
+
+    /// Whether the cursor currently sits inside a table.
+    pub fn cursor_in_table(&self) -> bool {
+        self.is_table_path(&self.cursor.paragraph_path)
     }
 
-    /// Whether the cursor currently sits inside a read-only paragraph.
-    pub fn cursor_in_readonly(&self) -> bool {
-        self.is_readonly_paragraph(&self.cursor.paragraph_path)
+    /// Whether moving between `a` and `b` would leave (or enter) a table cell.
+    /// Editing operations stop at such boundaries instead of merging content.
+    fn crosses_table_boundary(&self, a: &ParagraphPath, b: &ParagraphPath) -> bool {
+        a != b && (self.is_table_path(a) || self.is_table_path(b))
     }
 
     /// Replace the document wholesale and move the cursor as close as
@@ -670,6 +769,25 @@ impl DocumentEditor {
     pub fn restore_document(&mut self, document: Document, cursor: &CursorPointer) {
         self.document = document;
         self.rebuild_segments();
+        self.move_near_pointer(cursor);
+    }
+
+    /// Replace the top-level paragraph at `index`, keeping the cursor as
+    /// close to where it was as possible. Lets a second view of the
+    /// document take over another editor's edits paragraph by paragraph.
+    pub fn replace_root_paragraph(&mut self, index: usize, paragraph: Paragraph) {
+        let Some(slot) = self.document.paragraphs.get_mut(index) else {
+            return;
+        };
+        *slot = paragraph;
+        let cursor = self.cursor.clone();
+        self.update_segments_for_paragraph(&ParagraphPath::new_root(index));
+        if cursor.paragraph_path.root_index() == Some(index) {
+            self.move_near_pointer(&cursor);
+        }
+    }
+
+    fn move_near_pointer(&mut self, cursor: &CursorPointer) {
         if !self.move_to_pointer(cursor)
             && !self.fallback_move_to_text(cursor, false)
             && !self.fallback_move_to_text(cursor, true)
@@ -678,17 +796,6 @@ impl DocumentEditor {
         }
     }
 
-    /// Update the content width used to lay out read-only blocks (tables) when
-    /// building their navigable segments. Rebuilds segments if the width
-    /// actually changed so the table's per-line cursor stops track the viewport.
-    pub fn set_layout_width(&mut self, width: usize) {
-        let width = width.max(1);
-        if width != self.layout_width {
-            self.layout_width = width;
-            self.rebuild_segments();
-        }
-    }
-
     pub fn reveal_codes(&self) -> bool {
         self.reveal_codes
     }
@@ -729,7 +836,6 @@ impl DocumentEditor {
             start: Option<String>,
             text: Option<String>,
             end: Option<String>,
-            from_checklist: bool,
         }
 
         let mut clone = self.document.clone();
@@ -756,37 +862,20 @@ impl DocumentEditor {
             }) {
                 existing
             } else {
-                if let Some(item) = checklist_item_ref(&self.document, &segment.paragraph_path) {
-                    let Some(span) = span_ref_from_item(item, &segment.span_path) else {
-                        continue;
-                    };
-                    assemblies.push(SpanAssembly {
-                        paragraph_path: segment.paragraph_path.clone(),
-                        span_path: segment.span_path.clone(),
-                        original_text: span.text.clone(),
-                        start: None,
-                        text: None,
-                        end: None,
-                        from_checklist: true,
-                    });
-                } else {
-                    let Some(paragraph) = paragraph_ref(&self.document, &segment.paragraph_path)
-                    else {
-                        continue;
-                    };
-                    let Some(span) = span_ref(paragraph, &segment.span_path) else {
-                        continue;
-                    };
-                    assemblies.push(SpanAssembly {
-                        paragraph_path: segment.paragraph_path.clone(),
-                        span_path: segment.span_path.clone(),
-                        original_text: span.text.clone(),
-                        start: None,
-                        text: None,
-                        end: None,
-                        from_checklist: false,
-                    });
-                }
+                let Some(spans) = content_spans_ref(&self.document, &segment.paragraph_path) else {
+                    continue;
+                };
+                let Some(span) = span_ref(spans, &segment.span_path) else {
+                    continue;
+                };
+                assemblies.push(SpanAssembly {
+                    paragraph_path: segment.paragraph_path.clone(),
+                    span_path: segment.span_path.clone(),
+                    original_text: span.text.clone(),
+                    start: None,
+                    text: None,
+                    end: None,
+                });
                 assemblies.last_mut().unwrap()
             };
 
@@ -949,23 +1038,13 @@ impl DocumentEditor {
             if let Some(end) = assembly.end {
                 combined.push_str(&end);
             }
-            if assembly.from_checklist {
-                let Some(item) = checklist_item_mut(&mut clone, &assembly.paragraph_path) else {
-                    continue;
-                };
-                let Some(span) = span_mut_from_item(item, &assembly.span_path) else {
-                    continue;
-                };
-                span.text = combined;
-            } else {
-                let Some(paragraph) = paragraph_mut(&mut clone, &assembly.paragraph_path) else {
-                    continue;
-                };
-                let Some(span) = span_mut(paragraph, &assembly.span_path) else {
-                    continue;
-                };
-                span.text = combined;
-            }
+            let Some(spans) = content_spans_mut(&mut clone, &assembly.paragraph_path) else {
+                continue;
+            };
+            let Some(span) = span_mut(spans, &assembly.span_path) else {
+                continue;
+            };
+            span.text = combined;
         }
 
         (clone, markers, reveal_tags, inserted_cursor)
@@ -994,6 +1073,9 @@ impl DocumentEditor {
     /// `can_indent_more` and `indent_current_paragraph` so the advertised
     /// state stays exact.
     fn indent_target_for_cursor(&self) -> Option<IndentTarget> {
+        if self.cursor_in_table() {
+            return None;
+        }
         let path = &self.cursor.paragraph_path;
         let mut target = find_indent_target(&self.document, path);
         if target.is_none()
@@ -1033,6 +1115,9 @@ impl DocumentEditor {
     }
 
     pub fn can_indent_less(&self) -> bool {
+        if self.cursor_in_table() {
+            return false;
+        }
         if let Some(ctx) = extract_checklist_item_context(&self.cursor.paragraph_path) {
             return ctx.indices.len() > 1;
         }
@@ -1040,6 +1125,9 @@ impl DocumentEditor {
     }
 
     pub fn can_change_paragraph_type(&self) -> bool {
+        if self.cursor_in_table() {
+            return false;
+        }
         if let Some(ctx) = extract_checklist_item_context(&self.cursor.paragraph_path) {
             return ctx.indices.len() <= 1;
         }
@@ -1047,9 +1135,6 @@ impl DocumentEditor {
     }
 
     pub fn indent_current_paragraph(&mut self) -> bool {
-        if self.cursor_in_readonly() {
-            return false;
-        }
         let Some(target) = self.indent_target_for_cursor() else {
             return false;
         };
@@ -1176,7 +1261,7 @@ impl DocumentEditor {
     }
 
     pub fn unindent_current_paragraph(&mut self) -> bool {
-        if self.cursor.paragraph_path.steps().len() <= 1 {
+        if self.cursor_in_table() || self.cursor.paragraph_path.steps().len() <= 1 {
             return false;
         }
         let pointer = self.cursor_stable_pointer();
@@ -1305,7 +1390,7 @@ impl DocumentEditor {
     }
 
     pub fn set_paragraph_type(&mut self, target: ParagraphType) -> bool {
-        if self.cursor_in_readonly() {
+        if self.cursor_in_table() {
             return false;
         }
         let current_pointer = self.cursor.clone();
@@ -1460,8 +1545,131 @@ impl DocumentEditor {
         true
     }
 
+    /// The root paragraphs of the innermost section the cursor is in: its
+    /// heading and everything up to the next heading of the same or a
+    /// higher level.
+    pub fn current_section(&self) -> Option<Range<usize>> {
+        let index = self.cursor.paragraph_path.root_index()?;
+        section_containing(&self.document, index)
+    }
+
+    /// The current section and the sibling section it trades places with
+    /// when moved up (or down), in document order.
+    pub fn section_swap(&self, up: bool) -> Option<(Range<usize>, Range<usize>)> {
+        let section = self.current_section()?;
+        if up {
+            let previous = previous_sibling_section(&self.document, &section)?;
+            Some((previous, section))
+        } else {
+            let next = next_sibling_section(&self.document, &section)?;
+            Some((section, next))
+        }
+    }
+
+    /// Move the current section up (or down) past its sibling section. The
+    /// cursor moves along with it.
+    pub fn move_current_section(&mut self, up: bool) -> bool {
+        let Some((first, second)) = self.section_swap(up) else {
+            return false;
+        };
+        let mut pointer = self.cursor.clone();
+        let Some(root) = pointer.paragraph_path.root_index() else {
+            return false;
+        };
+        if !swap_root_ranges(&mut self.document, first.clone(), second.clone()) {
+            return false;
+        }
+        let root = if up {
+            root - first.len()
+        } else {
+            root + second.len()
+        };
+        pointer.paragraph_path.set_root_index(root);
+        self.rebuild_segments();
+        if !self.move_to_pointer(&pointer) && !self.fallback_move_to_text(&pointer, false) {
+            self.ensure_cursor_selectable();
+        }
+        true
+    }
+
+    /// Remove the top-level paragraphs `range`, as a section, leaving the
+    /// cursor at the start of what follows. A document left without
+    /// paragraphs gets an empty one.
+    pub fn remove_root_paragraphs(&mut self, range: Range<usize>) -> bool {
+        if range.is_empty() || range.end > self.document.paragraphs.len() {
+            return false;
+        }
+        let start = ParagraphPath::new_root(range.start);
+        for _ in range.clone() {
+            if take_paragraph_at(&mut self.document, &start).is_none() {
+                return false;
+            }
+        }
+        if self.document.paragraphs.is_empty() {
+            self.document.paragraphs.push(Paragraph::new_text());
+        }
+        let root = range.start.min(self.document.paragraphs.len() - 1);
+        self.rebuild_segments();
+        self.move_to_paragraph_char_offset(&ParagraphPath::new_root(root), 0);
+        true
+    }
+
+    /// Whether every heading of the current section can be raised
+    /// (`delta` < 0) or lowered (`delta` > 0) by `delta` levels.
+    pub fn can_shift_current_section(&self, delta: isize) -> bool {
+        self.current_section()
+            .is_some_and(|section| can_shift_heading_levels(&self.document, section, delta))
+    }
+
+    /// Promote (`delta` < 0) or demote (`delta` > 0) every heading of the
+    /// current section, e.g. Heading 2 to Heading 1 and Heading 3 to
+    /// Heading 2.
+    pub fn shift_current_section(&mut self, delta: isize) -> bool {
+        let Some(section) = self.current_section() else {
+            return false;
+        };
+        if !shift_heading_levels(&mut self.document, section, delta) {
+            return false;
+        }
+        let pointer = self.cursor.clone();
+        self.rebuild_segments();
+        if !self.move_to_pointer(&pointer) && !self.fallback_move_to_text(&pointer, false) {
+            self.ensure_cursor_selectable();
+        }
+        true
+    }
+
+    /// The start of the current section's first paragraph and the end of
+    /// its last, for selecting the whole section.
+    pub fn current_section_bounds(&self) -> Option<(CursorPointer, CursorPointer)> {
+        let section = self.current_section()?;
+        let in_section = |segment: &&SegmentRef| {
+            segment.kind == SegmentKind::Text
+                && segment
+                    .paragraph_path
+                    .root_index()
+                    .is_some_and(|root| section.contains(&root))
+        };
+        let first = self.segments.iter().find(in_section)?;
+        let last = self.segments.iter().rev().find(in_section)?;
+        Some((
+            CursorPointer {
+                paragraph_path: first.paragraph_path.clone(),
+                span_path: first.span_path.clone(),
+                offset: 0,
+                segment_kind: SegmentKind::Text,
+            },
+            CursorPointer {
+                paragraph_path: last.paragraph_path.clone(),
+                span_path: last.span_path.clone(),
+                offset: last.len,
+                segment_kind: SegmentKind::Text,
+            },
+        ))
+    }
+
     pub fn insert_paragraph_break(&mut self) -> bool {
-        if self.cursor_in_readonly() {
+        if self.cursor_in_table() {
             return false;
         }
         if !self.cursor.is_valid() {
@@ -1482,7 +1690,7 @@ impl DocumentEditor {
     }
 
     pub fn insert_paragraph_break_as_sibling(&mut self) -> bool {
-        if self.cursor_in_readonly() {
+        if self.cursor_in_table() {
             return false;
         }
         if !self.cursor.is_valid() {
@@ -1503,9 +1711,6 @@ impl DocumentEditor {
     }
 
     pub fn insert_char(&mut self, ch: char) -> bool {
-        if self.cursor_in_readonly() {
-            return false;
-        }
         let pointer = self.cursor.clone();
 
         // Check if we're trying to insert into an empty structure and populate it if needed
@@ -1523,9 +1728,24 @@ impl DocumentEditor {
             needs_rebuild = true;
         }
 
+        // Check for empty table cells
+        if ensure_table_cell_has_content(&mut self.document, &pointer.paragraph_path) {
+            needs_rebuild = true;
+        }
+
         if needs_rebuild {
             // Content was added, so we need to rebuild segments
             self.rebuild_segments();
+            // The placeholder span replaces the empty content root's stop,
+            // which the cursor pointed at; put the cursor back into it.
+            if self.cursor.paragraph_path != pointer.paragraph_path
+                && let Some((index, segment)) =
+                    select_text_in_paragraph(&self.segments, &pointer.paragraph_path, false)
+            {
+                self.cursor_segment = index;
+                self.cursor.update_from_segment(&segment);
+                self.cursor.offset = 0;
+            }
         }
 
         if !self.prepare_cursor_for_text_insertion() {
@@ -1582,13 +1802,11 @@ impl DocumentEditor {
     }
 
     pub fn backspace(&mut self) -> bool {
-        if self.cursor_in_readonly() {
-            return false;
-        }
         if self.segments.is_empty() {
             return false;
         }
-        if self.current_paragraph_is_empty()
+        if !self.cursor_in_table()
+            && self.current_paragraph_is_empty()
             && self.remove_current_paragraph(RemovalDirection::Backward)
         {
             return true;
@@ -1599,12 +1817,9 @@ impl DocumentEditor {
                 let prev_segment = &self.segments[self.cursor_segment - 1];
                 let prev_para_path = &prev_segment.paragraph_path;
 
-                // At the boundary of a read-only paragraph (a table), backspace
-                // is a no-op: it must neither remove/merge the block nor pull the
-                // cursor into it.
-                if prev_para_path != &self.cursor.paragraph_path
-                    && self.is_readonly_paragraph(prev_para_path)
-                {
+                // At a table cell boundary backspace is a no-op: it must
+                // neither merge cells nor pull surrounding text into the grid.
+                if self.crosses_table_boundary(prev_para_path, &self.cursor.paragraph_path) {
                     return false;
                 }
 
@@ -1997,7 +2212,7 @@ impl DocumentEditor {
         None
     }
 
-    fn pointer_at_paragraph_char_offset(
+    pub(crate) fn pointer_at_paragraph_char_offset(
         &self,
         path: &ParagraphPath,
         mut char_offset: usize,
@@ -2068,9 +2283,12 @@ impl DocumentEditor {
 
         let prev_segment = self.segments[prev_segment_idx].clone();
 
-        // Never merge into a read-only paragraph (e.g. a table); its content is
-        // immutable, so the merge would either panic or destroy the block.
-        if self.is_readonly_paragraph(&prev_segment.paragraph_path) {
+        // Never merge across a table cell boundary; doing so would either
+        // destroy the grid or pull a paragraph's text into a cell.
+        if self.crosses_table_boundary(
+            &prev_segment.paragraph_path,
+            &current_segment.paragraph_path,
+        ) {
             return false;
         }
 
@@ -2245,7 +2463,7 @@ impl DocumentEditor {
                 return false;
             }
             Paragraph::Table { .. } => {
-                // Tables are read-only; never merge their content.
+                // Tables keep their grid; never merge their content.
                 return false;
             }
         };
@@ -2335,9 +2553,11 @@ impl DocumentEditor {
 
         let next_segment = self.segments[next_segment_idx].clone();
 
-        // Never merge a read-only paragraph (e.g. a table) into the current one;
-        // its content is immutable and must not be consumed by the merge.
-        if self.is_readonly_paragraph(&next_segment.paragraph_path) {
+        // Never merge across a table cell boundary (see the backward case).
+        if self.crosses_table_boundary(
+            &next_segment.paragraph_path,
+            &current_segment.paragraph_path,
+        ) {
             return false;
         }
 
@@ -2598,7 +2818,7 @@ impl DocumentEditor {
                     return false;
                 }
                 Paragraph::Table { .. } => {
-                    // Tables are read-only; never merge their content.
+                    // Tables keep their grid; never merge their content.
                     return false;
                 }
             };
@@ -2658,13 +2878,11 @@ impl DocumentEditor {
     }
 
     pub fn delete(&mut self) -> bool {
-        if self.cursor_in_readonly() {
-            return false;
-        }
         if self.segments.is_empty() {
             return false;
         }
-        if self.current_paragraph_is_empty()
+        if !self.cursor_in_table()
+            && self.current_paragraph_is_empty()
             && self.remove_current_paragraph(RemovalDirection::Forward)
         {
             return true;
@@ -2714,6 +2932,10 @@ impl DocumentEditor {
         let current_para = &self.segments[self.cursor_segment].paragraph_path;
         let next_para = &next_segment.paragraph_path;
 
+        if self.crosses_table_boundary(current_para, next_para) {
+            return false;
+        }
+
         if current_para != next_para {
             // Different paragraphs: merge them
             return self.try_merge_with_next_paragraph();
@@ -2736,9 +2958,6 @@ impl DocumentEditor {
     }
 
     pub fn delete_word_backward(&mut self) -> bool {
-        if self.cursor_in_readonly() {
-            return false;
-        }
         if self.segments.is_empty() {
             return false;
         }
@@ -2746,6 +2965,10 @@ impl DocumentEditor {
         let Some((target_segment, target_pointer)) = self.previous_word_position() else {
             return false;
         };
+        if self.crosses_table_boundary(&target_pointer.paragraph_path, &self.cursor.paragraph_path)
+        {
+            return false;
+        }
 
         let target_offset = target_pointer.offset;
         let steps = self.count_backward_steps(target_segment, target_offset);
@@ -2784,9 +3007,6 @@ impl DocumentEditor {
     }
 
     pub fn delete_word_forward(&mut self) -> bool {
-        if self.cursor_in_readonly() {
-            return false;
-        }
         if self.segments.is_empty() {
             return false;
         }
@@ -2796,6 +3016,12 @@ impl DocumentEditor {
         let Some((target_segment, target_pointer)) = self.next_word_position() else {
             return false;
         };
+        if self.crosses_table_boundary(
+            &target_pointer.paragraph_path,
+            &start_pointer.paragraph_path,
+        ) {
+            return false;
+        }
 
         let target_offset = target_pointer.offset;
         let steps = self.count_forward_steps(target_segment, target_offset);
@@ -2864,15 +3090,15 @@ impl DocumentEditor {
             })
             .map(|other| other.len)
             .sum();
-        let paragraph = paragraph_mut(&mut self.document, &segment.paragraph_path)?;
-        let span = span_mut(paragraph, &segment.span_path)?;
+        let spans = content_spans_mut(&mut self.document, &segment.paragraph_path)?;
+        let span = span_mut(spans, &segment.span_path)?;
         if span.style != style {
             // Style mismatch indicates the structure changed; treat as no-op.
             return None;
         }
         span.style = InlineStyle::None;
         span.link_target = None;
-        prune_and_merge_spans(paragraph.content_mut());
+        prune_and_merge_spans(spans);
         Some((segment.paragraph_path, char_offset))
     }
 
@@ -3052,3 +3278,7 @@ mod style_tests;
 #[cfg(test)]
 #[path = "editor/link_tests.rs"]
 mod link_tests;
+
+#[cfg(test)]
+#[path = "editor/search_tests.rs"]
+mod search_tests;
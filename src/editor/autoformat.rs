@@ -0,0 +1,298 @@
+//! Markdown-style autoformat: Markdown typed into the document turns into
+//! the formatting it stands for.
+//!
+//! A marker typed at the start of a top-level Text paragraph changes the
+//! paragraph's type — `## ` makes a Heading 2, `- ` a bullet list — and a
+//! paragraph holding nothing but a code fence becomes a code block when
+//! Enter is pressed in it. Inline markers become a style once the closing
+//! marker is typed: `**bold**`, `_italic_` and `` `code` ``. The markers
+//! themselves are removed. [`crate::editor_display::EditorDisplay`] records
+//! the change as an edit of its own, so undo brings the typed markers back.
+
+use tdoc::{InlineStyle, ParagraphType, Span};
+
+use super::content::{apply_style_to_content_range, char_to_byte_idx, prune_and_merge_spans};
+use super::inspect::{content_spans_ref, paragraph_ref, span_ref};
+use super::{
+    CursorPointer, DocumentEditor, ParagraphPath, SegmentKind, SpanPath, content_spans_mut,
+    span_mut,
+};
+
+/// Markers that change the type of the paragraph they start, and whether
+/// a checklist item starts out checked.
+const BLOCK_MARKERS: &[(&str, ParagraphType, bool)] = &[
+    ("# ", ParagraphType::Header1, false),
+    ("## ", ParagraphType::Header2, false),
+    ("### ", ParagraphType::Header3, false),
+    ("- ", ParagraphType::UnorderedList, false),
+    ("* ", ParagraphType::UnorderedList, false),
+    ("1. ", ParagraphType::OrderedList, false),
+    ("[ ] ", ParagraphType::Checklist, false),
+    ("[x] ", ParagraphType::Checklist, true),
+    ("[X] ", ParagraphType::Checklist, true),
+    ("> ", ParagraphType::Quote, false),
+];
+
+const CODE_FENCE: &str = "```";
+
+/// Markers that style the text between an opening and a closing one.
+const INLINE_MARKERS: &[(&str, InlineStyle)] = &[
+    ("**", InlineStyle::Bold),
+    ("_", InlineStyle::Italic),
+    ("`", InlineStyle::Code),
+];
+
+/// A change asked for by Markdown typed at the cursor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Autoformat {
+    /// Remove the first `marker_len` characters of the paragraph and make
+    /// it a `target`.
+    Block {
+        marker_len: usize,
+        target: ParagraphType,
+        checked: bool,
+    },
+    /// Style the `len` characters following an opening marker at `start`
+    /// in the cursor's span, which ends with the closing marker.
+    Inline {
+        start: usize,
+        len: usize,
+        marker_len: usize,
+        style: InlineStyle,
+    },
+}
+
+impl DocumentEditor {
+    /// The autoformat asked for by the character just typed, if any.
+    pub fn markdown_autoformat(&self) -> Option<Autoformat> {
+        let before: String = self
+            .cursor_span_text()?
+            .chars()
+            .take(self.cursor.offset)
+            .collect();
+        if self.cursor_at_text_paragraph_start(self.cursor.offset)
+            && let Some(&(marker, target, checked)) =
+                BLOCK_MARKERS.iter().find(|(marker, ..)| *marker == before)
+        {
+            return Some(Autoformat::Block {
+                marker_len: marker.chars().count(),
+                target,
+                checked,
+            });
+        }
+        if self.cursor_in_code() {
+            return None;
+        }
+        INLINE_MARKERS.iter().find_map(|&(marker, style)| {
+            let (start, len) = closed_inline_marker(&before, marker)?;
+            Some(Autoformat::Inline {
+                start,
+                len,
+                marker_len: marker.chars().count(),
+                style,
+            })
+        })
+    }
+
+    /// The code block asked for by pressing Enter at the end of a paragraph
+    /// holding only a code fence.
+    pub fn code_fence_autoformat(&self) -> Option<Autoformat> {
+        let text = self.cursor_span_text()?;
+        let marker_len = CODE_FENCE.chars().count();
+        (text == CODE_FENCE
+            && self.cursor.offset == marker_len
+            && self.cursor_at_text_paragraph_start(marker_len)
+            && self.paragraph_text_len() == marker_len)
+            .then_some(Autoformat::Block {
+                marker_len,
+                target: ParagraphType::CodeBlock,
+                checked: false,
+            })
+    }
+
+    /// Carry out `autoformat`, as returned for the current cursor position.
+    pub fn apply_autoformat(&mut self, autoformat: Autoformat) -> bool {
+        let pointer = self.cursor.clone();
+        match autoformat {
+            Autoformat::Block {
+                marker_len,
+                target,
+                checked,
+            } => {
+                let Some(span) = content_spans_mut(&mut self.document, &pointer.paragraph_path)
+                    .and_then(|spans| span_mut(spans, &pointer.span_path))
+                else {
+                    return false;
+                };
+                remove_chars(&mut span.text, 0, marker_len);
+                self.update_segments_for_paragraph(&pointer.paragraph_path);
+                self.move_to_paragraph_char_offset(&pointer.paragraph_path, 0);
+                // Turning a top-level Text paragraph into another type does
+                // not fail; the markers are gone either way.
+                self.set_paragraph_type(target);
+                if checked {
+                    self.set_current_checklist_item_checked(true);
+                }
+                true
+            }
+            Autoformat::Inline {
+                start,
+                len,
+                marker_len,
+                style,
+            } => {
+                let Some(paragraph_offset) = self.paragraph_char_offset_of_pointer(&pointer) else {
+                    return false;
+                };
+                let Some(spans) = content_spans_mut(&mut self.document, &pointer.paragraph_path)
+                else {
+                    return false;
+                };
+                let Some(span) = span_mut(spans, &pointer.span_path) else {
+                    return false;
+                };
+                // The closing marker first, so `start` stays where it is.
+                remove_chars(&mut span.text, start + marker_len + len, marker_len);
+                remove_chars(&mut span.text, start, marker_len);
+                let path = pointer.span_path.indices();
+                apply_style_to_content_range(spans, path, start, path, start + len, style);
+                prune_and_merge_spans(spans);
+                self.update_segments_for_paragraph(&pointer.paragraph_path);
+                self.move_past_styled_text(
+                    &pointer.paragraph_path,
+                    paragraph_offset.saturating_sub(2 * marker_len),
+                );
+                true
+            }
+        }
+    }
+
+    /// Put the cursor right after the text just styled, `char_offset`
+    /// characters into the paragraph at `path`, so typing goes on unstyled:
+    /// at the start of the text that follows, or else in an empty span
+    /// added for it.
+    fn move_past_styled_text(&mut self, path: &ParagraphPath, char_offset: usize) {
+        self.move_to_paragraph_char_offset(path, char_offset);
+        if let Some(next) = self.segments.get(self.cursor_segment + 1)
+            && next.paragraph_path == *path
+            && next.kind == SegmentKind::Text
+        {
+            let pointer = CursorPointer {
+                paragraph_path: path.clone(),
+                span_path: next.span_path.clone(),
+                offset: 0,
+                segment_kind: SegmentKind::Text,
+            };
+            self.move_to_pointer(&pointer);
+            return;
+        }
+        let mut indices = self.cursor.span_path.indices().to_vec();
+        let Some((&index, parents)) = indices.split_last() else {
+            return;
+        };
+        let Some(mut siblings) = content_spans_mut(&mut self.document, path) else {
+            return;
+        };
+        for &parent in parents {
+            let Some(span) = siblings.get_mut(parent) else {
+                return;
+            };
+            siblings = &mut span.children;
+        }
+        siblings.insert(index + 1, Span::new_text(""));
+        if let Some(last) = indices.last_mut() {
+            *last += 1;
+        }
+        self.update_segments_for_paragraph(path);
+        self.move_to_pointer(&CursorPointer {
+            paragraph_path: path.clone(),
+            span_path: SpanPath::new(indices),
+            offset: 0,
+            segment_kind: SegmentKind::Text,
+        });
+    }
+
+    fn cursor_span_text(&self) -> Option<&str> {
+        if self.cursor.segment_kind != SegmentKind::Text {
+            return None;
+        }
+        let spans = content_spans_ref(&self.document, &self.cursor.paragraph_path)?;
+        span_ref(spans, &self.cursor.span_path).map(|span| span.text.as_str())
+    }
+
+    /// Whether the cursor is `offset` characters into a top-level Text
+    /// paragraph, all of them in the cursor's span.
+    fn cursor_at_text_paragraph_start(&self, offset: usize) -> bool {
+        let path = &self.cursor.paragraph_path;
+        path.steps().len() == 1
+            && paragraph_ref(&self.document, path)
+                .is_some_and(|paragraph| paragraph.paragraph_type() == ParagraphType::Text)
+            && self.paragraph_char_offset_of_pointer(&self.cursor) == Some(offset)
+    }
+
+    /// The number of characters in the cursor's paragraph.
+    fn paragraph_text_len(&self) -> usize {
+        self.segments
+            .iter()
+            .filter(|segment| {
+                segment.paragraph_path == self.cursor.paragraph_path
+                    && segment.kind == SegmentKind::Text
+            })
+            .map(|segment| segment.len)
+            .sum()
+    }
+
+    /// Whether the cursor is in a code block or inline code, where Markdown
+    /// markers are meant literally.
+    pub fn cursor_in_code(&self) -> bool {
+        if paragraph_ref(&self.document, &self.cursor.paragraph_path)
+            .is_some_and(|paragraph| paragraph.paragraph_type() == ParagraphType::CodeBlock)
+        {
+            return true;
+        }
+        let Some(mut spans) = content_spans_ref(&self.document, &self.cursor.paragraph_path) else {
+            return false;
+        };
+        for &index in self.cursor.span_path.indices() {
+            let Some(span) = spans.get(index) else {
+                return false;
+            };
+            if span.style == InlineStyle::Code {
+                return true;
+            }
+            spans = &span.children;
+        }
+        false
+    }
+}
+
+/// Where the text closed by `marker` at the end of `before` starts, and its
+/// length, both in characters. The styled text must not be empty or start
+/// or end with a space, and an underscore within a word, as in snake_case,
+/// opens nothing.
+fn closed_inline_marker(before: &str, marker: &str) -> Option<(usize, usize)> {
+    let inner_end = before.strip_suffix(marker)?;
+    let open = inner_end.rfind(marker)?;
+    let inner = &inner_end[open + marker.len()..];
+    let first = inner.chars().next()?;
+    let last = inner.chars().next_back()?;
+    if first.is_whitespace() || last.is_whitespace() {
+        return None;
+    }
+    let preceding = &inner_end[..open];
+    if marker == "_"
+        && preceding
+            .chars()
+            .next_back()
+            .is_some_and(char::is_alphanumeric)
+    {
+        return None;
+    }
+    Some((preceding.chars().count(), inner.chars().count()))
+}
+
+fn remove_chars(text: &mut String, start: usize, count: usize) {
+    let from = char_to_byte_idx(text, start);
+    let to = char_to_byte_idx(text, start + count);
+    text.drain(from..to);
+}
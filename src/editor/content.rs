@@ -1,4 +1,4 @@
-use super::{CursorPointer, checklist_item_mut, paragraph_mut, span_mut, span_mut_from_item};
+use super::{CursorPointer, content_spans_mut, span_mut};
 use tdoc::{ChecklistItem, Document, InlineStyle, Span};
 
 pub(crate) fn insert_char_at(
@@ -7,21 +7,10 @@ pub(crate) fn insert_char_at(
     offset: usize,
     ch: char,
 ) -> bool {
-    if let Some(item) = checklist_item_mut(document, &pointer.paragraph_path) {
-        let Some(span) = span_mut_from_item(item, &pointer.span_path) else {
-            return false;
-        };
-        let char_len = span.text.chars().count();
-        let clamped_offset = offset.min(char_len);
-        let byte_idx = char_to_byte_idx(&span.text, clamped_offset);
-        span.text.insert(byte_idx, ch);
-        return true;
-    }
-
-    let Some(paragraph) = paragraph_mut(document, &pointer.paragraph_path) else {
+    let Some(spans) = content_spans_mut(document, &pointer.paragraph_path) else {
         return false;
     };
-    let Some(span) = span_mut(paragraph, &pointer.span_path) else {
+    let Some(span) = span_mut(spans, &pointer.span_path) else {
         return false;
     };
     let char_len = span.text.chars().count();
@@ -36,17 +25,10 @@ pub(crate) fn remove_char_at(
     pointer: &CursorPointer,
     offset: usize,
 ) -> bool {
-    if let Some(item) = checklist_item_mut(document, &pointer.paragraph_path) {
-        let Some(span) = span_mut_from_item(item, &pointer.span_path) else {
-            return false;
-        };
-        return remove_char_from_text(&mut span.text, offset);
-    }
-
-    let Some(paragraph) = paragraph_mut(document, &pointer.paragraph_path) else {
+    let Some(spans) = content_spans_mut(document, &pointer.paragraph_path) else {
         return false;
     };
-    let Some(span) = span_mut(paragraph, &pointer.span_path) else {
+    let Some(span) = span_mut(spans, &pointer.span_path) else {
         return false;
     };
     remove_char_from_text(&mut span.text, offset)
@@ -3,8 +3,8 @@ use super::content::{
     word_start_boundary,
 };
 use super::inspect::{
-    breadcrumbs_for_pointer, checklist_item_ref, collect_segments, paragraph_path_is_prefix,
-    paragraph_ref, span_path_is_prefix, span_ref, span_ref_from_item,
+    breadcrumbs_for_pointer, collect_segments, content_spans_ref, paragraph_path_is_prefix,
+    span_path_is_prefix, span_ref,
 };
 use super::{
     CursorPointer, DocumentEditor, ParagraphPath, PointerKey, SegmentKind, SegmentRef,
@@ -87,13 +87,8 @@ impl DocumentEditor {
     }
 
     pub(crate) fn span_text_for_pointer<'a>(&'a self, pointer: &CursorPointer) -> Option<&'a str> {
-        if let Some(item) = checklist_item_ref(&self.document, &pointer.paragraph_path) {
-            let span = span_ref_from_item(item, &pointer.span_path)?;
-            return Some(span.text.as_str());
-        }
-
-        let paragraph = paragraph_ref(&self.document, &pointer.paragraph_path)?;
-        let span = span_ref(paragraph, &pointer.span_path)?;
+        let spans = content_spans_ref(&self.document, &pointer.paragraph_path)?;
+        let span = span_ref(spans, &pointer.span_path)?;
         Some(span.text.as_str())
     }
 
@@ -592,12 +587,8 @@ impl DocumentEditor {
         if segment.kind != SegmentKind::Text {
             return None;
         }
-        if let Some(item) = checklist_item_ref(&self.document, &self.cursor.paragraph_path) {
-            let span = span_ref_from_item(item, &self.cursor.span_path)?;
-            return Some(span.text.as_str());
-        }
-        let paragraph = paragraph_ref(&self.document, &self.cursor.paragraph_path)?;
-        let span = span_ref(paragraph, &self.cursor.span_path)?;
+        let spans = content_spans_ref(&self.document, &self.cursor.paragraph_path)?;
+        let span = span_ref(spans, &self.cursor.span_path)?;
         Some(span.text.as_str())
     }
 
@@ -605,12 +596,8 @@ impl DocumentEditor {
         if segment.kind != SegmentKind::Text {
             return None;
         }
-        if let Some(item) = checklist_item_ref(&self.document, &segment.paragraph_path) {
-            let span = span_ref_from_item(item, &segment.span_path)?;
-            return Some(span.text.as_str());
-        }
-        let paragraph = paragraph_ref(&self.document, &segment.paragraph_path)?;
-        let span = span_ref(paragraph, &segment.span_path)?;
+        let spans = content_spans_ref(&self.document, &segment.paragraph_path)?;
+        let span = span_ref(spans, &segment.span_path)?;
         Some(span.text.as_str())
     }
 
@@ -766,10 +753,10 @@ impl DocumentEditor {
     }
 
     pub(crate) fn rebuild_segments(&mut self) {
-        self.segments = collect_segments(&self.document, self.reveal_codes, self.layout_width);
+        self.segments = collect_segments(&self.document, self.reveal_codes);
         if self.segments.is_empty() {
             self.ensure_placeholder_segment();
-            self.segments = collect_segments(&self.document, self.reveal_codes, self.layout_width);
+            self.segments = collect_segments(&self.document, self.reveal_codes);
         }
         if self.segments.is_empty() {
             self.cursor = CursorPointer::default();
@@ -791,7 +778,6 @@ impl DocumentEditor {
             &self.document,
             root_path,
             self.reveal_codes,
-            self.layout_width,
         );
 
         // Replace the old segment range with new segments
@@ -800,7 +786,7 @@ impl DocumentEditor {
         // If segments are now empty, ensure we have a placeholder
         if self.segments.is_empty() {
             self.ensure_placeholder_segment();
-            self.segments = collect_segments(&self.document, self.reveal_codes, self.layout_width);
+            self.segments = collect_segments(&self.document, self.reveal_codes);
         }
 
         if self.segments.is_empty() {
@@ -0,0 +1,438 @@
+//! Folding: showing a section, list entry, checklist item or quote as a
+//! single summary line.
+//!
+//! A fold is named by the path of the part that stays visible, its head:
+//!
+//! - a top-level heading, whose section below it is hidden;
+//! - the first paragraph of a list entry, whose other paragraphs are hidden;
+//! - a checklist item, whose nested items are hidden;
+//! - a quote, of which only the first paragraph is shown.
+//!
+//! Folding only changes what is shown: the document keeps the folded
+//! content and saves it as usual. The renderer draws each head with a ▸
+//! marker and a count of what is hidden, the cursor moves past folded
+//! content, and a fold opens again when the cursor lands inside it some
+//! other way, as by a search or an undo.
+
+use std::ops::Range;
+
+use tdoc::{ChecklistItem, Document, Paragraph};
+
+use super::inspect::{checklist_item_ref, paragraph_ref};
+use super::structure::{heading_level, section_containing, section_range};
+use super::{CursorPointer, DocumentEditor, ParagraphPath, PathStep, SegmentKind, SegmentRef};
+
+/// A fold as the renderer draws it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FoldRef {
+    /// The head, which stays visible.
+    pub path: ParagraphPath,
+    /// What the fold hides, e.g. "12 paragraphs".
+    pub summary: String,
+    /// For a folded heading, the top-level paragraphs below it; empty
+    /// otherwise.
+    pub hidden_roots: Range<usize>,
+}
+
+fn count(number: usize, unit: &str) -> String {
+    if number == 1 {
+        format!("1 {unit}")
+    } else {
+        format!("{number} {unit}s")
+    }
+}
+
+fn nested_items(item: &ChecklistItem) -> usize {
+    item.children
+        .iter()
+        .map(|child| 1 + nested_items(child))
+        .sum()
+}
+
+/// The paragraphs a quote shows as separate blocks: its own text, if any,
+/// and each of its children.
+fn quote_blocks(paragraph: &Paragraph) -> usize {
+    usize::from(!paragraph.content().is_empty()) + paragraph.children().len()
+}
+
+/// The list entry `path` ends in, as the path of the list and the entry's
+/// index.
+fn entry_step(path: &ParagraphPath) -> Option<(&[PathStep], usize, usize)> {
+    match path.steps().split_last()? {
+        (
+            PathStep::Entry {
+                entry_index,
+                paragraph_index,
+            },
+            list,
+        ) => Some((list, *entry_index, *paragraph_index)),
+        _ => None,
+    }
+}
+
+/// `path` as a fold of `document`, if there is anything to hide below it.
+fn resolve(document: &Document, path: &ParagraphPath) -> Option<FoldRef> {
+    let steps = path.steps();
+    let (hidden, unit, hidden_roots) = if let [PathStep::Root(index)] = steps
+        && document
+            .paragraphs
+            .get(*index)
+            .and_then(heading_level)
+            .is_some()
+    {
+        let section = section_range(document, *index)?;
+        let hidden_roots = section.start + 1..section.end;
+        (hidden_roots.len(), "paragraph", hidden_roots)
+    } else if let Some((list, entry_index, 0)) = entry_step(path)
+        && let Some(entry) = paragraph_ref(document, &ParagraphPath::from_steps(list.to_vec()))
+            .and_then(|list| list.entries().get(entry_index))
+        && entry.len() > 1
+    {
+        (entry.len() - 1, "paragraph", 0..0)
+    } else if let Some(PathStep::ChecklistItem { .. }) = steps.last() {
+        let item = checklist_item_ref(document, path)?;
+        (nested_items(item), "item", 0..0)
+    } else if let Some(quote @ Paragraph::Quote { .. }) = paragraph_ref(document, path) {
+        (quote_blocks(quote).saturating_sub(1), "paragraph", 0..0)
+    } else {
+        return None;
+    };
+    (hidden > 0).then(|| FoldRef {
+        path: path.clone(),
+        summary: count(hidden, unit),
+        hidden_roots,
+    })
+}
+
+/// Whether the fold `fold` hides the paragraph at `path`.
+fn hides(document: &Document, fold: &FoldRef, path: &ParagraphPath) -> bool {
+    if path
+        .root_index()
+        .is_some_and(|root| fold.hidden_roots.contains(&root))
+    {
+        return true;
+    }
+    let head = fold.path.steps();
+    let steps = path.steps();
+    if steps.len() < head.len() || steps[..head.len() - 1] != head[..head.len() - 1] {
+        return false;
+    }
+    match (&head[head.len() - 1], &steps[head.len() - 1]) {
+        (
+            PathStep::Entry {
+                entry_index: folded,
+                ..
+            },
+            PathStep::Entry {
+                entry_index,
+                paragraph_index,
+            },
+        ) => entry_index == folded && *paragraph_index > 0,
+        (PathStep::ChecklistItem { indices: folded }, PathStep::ChecklistItem { indices }) => {
+            indices.len() > folded.len() && indices.starts_with(folded)
+        }
+        _ if steps.len() > head.len() && steps[..head.len()] == *head => {
+            // Inside a folded quote, everything but the first block.
+            let Some(quote @ Paragraph::Quote { .. }) = paragraph_ref(document, &fold.path) else {
+                return false;
+            };
+            match &steps[head.len()] {
+                PathStep::Child(index) => !quote.content().is_empty() || *index > 0,
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Whether `path` starts with all the steps of `prefix`.
+fn starts_with(path: &ParagraphPath, prefix: &ParagraphPath) -> bool {
+    path.steps().starts_with(prefix.steps())
+}
+
+/// The folds that could be made around the paragraph at `path`, innermost
+/// first.
+fn candidates(document: &Document, path: &ParagraphPath) -> Vec<FoldRef> {
+    let steps = path.steps();
+    let mut paths = Vec::new();
+    for end in (1..=steps.len()).rev() {
+        let prefix = ParagraphPath::from_steps(steps[..end].to_vec());
+        if let Some(Paragraph::Quote { .. }) = paragraph_ref(document, &prefix) {
+            paths.push(prefix.clone());
+        }
+        match &steps[end - 1] {
+            PathStep::Entry { entry_index, .. } => {
+                let mut entry = steps[..end - 1].to_vec();
+                entry.push(PathStep::Entry {
+                    entry_index: *entry_index,
+                    paragraph_index: 0,
+                });
+                paths.push(ParagraphPath::from_steps(entry));
+            }
+            PathStep::ChecklistItem { indices } => {
+                for depth in (1..=indices.len()).rev() {
+                    let mut item = steps[..end - 1].to_vec();
+                    item.push(PathStep::ChecklistItem {
+                        indices: indices[..depth].to_vec(),
+                    });
+                    paths.push(ParagraphPath::from_steps(item));
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(section) = path
+        .root_index()
+        .and_then(|root| section_containing(document, root))
+    {
+        paths.push(ParagraphPath::new_root(section.start));
+    }
+    let mut folds: Vec<FoldRef> = Vec::new();
+    for path in paths {
+        if let Some(fold) = resolve(document, &path)
+            && !folds.iter().any(|known| known.path == fold.path)
+        {
+            folds.push(fold);
+        }
+    }
+    folds
+}
+
+/// The fold at `path` in `previous`, moved along with its paragraph into
+/// `current`. Top-level paragraphs before and after the part that changed
+/// are matched up; a fold within the changed part keeps its position.
+fn remap(previous: &Document, current: &Document, path: &ParagraphPath) -> ParagraphPath {
+    let (old, new) = (&previous.paragraphs, &current.paragraphs);
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let limit = old.len().min(new.len()) - prefix;
+    let suffix = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take(limit)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut path = path.clone();
+    if let Some(root) = path.root_index()
+        && root >= old.len() - suffix
+    {
+        path.set_root_index(root + new.len() - old.len());
+    }
+    path
+}
+
+/// A path written as text, for remembering folds across sessions: its
+/// steps separated by `/`, such as `3/e1.0/i0.2`.
+fn path_key(path: &ParagraphPath) -> String {
+    let join = |indices: &[usize]| {
+        indices
+            .iter()
+            .map(usize::to_string)
+            .collect::<Vec<_>>()
+            .join(".")
+    };
+    path.steps()
+        .iter()
+        .map(|step| match step {
+            PathStep::Root(index) => index.to_string(),
+            PathStep::Child(index) => format!("c{index}"),
+            PathStep::Entry {
+                entry_index,
+                paragraph_index,
+            } => format!("e{entry_index}.{paragraph_index}"),
+            PathStep::ChecklistItem { indices } => format!("i{}", join(indices)),
+            PathStep::TableCell { row, column } => format!("t{row}.{column}"),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn parse_path_key(key: &str) -> Option<ParagraphPath> {
+    let numbers = |text: &str| -> Option<Vec<usize>> {
+        text.split('.').map(|part| part.parse().ok()).collect()
+    };
+    let mut parts = key.trim().split('/');
+    let mut steps = vec![PathStep::Root(parts.next()?.parse().ok()?)];
+    for part in parts {
+        let (kind, rest) = part.split_at(part.char_indices().nth(1)?.0);
+        steps.push(match (kind, numbers(rest)?.as_slice()) {
+            ("c", [index]) => PathStep::Child(*index),
+            ("e", [entry_index, paragraph_index]) => PathStep::Entry {
+                entry_index: *entry_index,
+                paragraph_index: *paragraph_index,
+            },
+            ("i", indices) if !indices.is_empty() => PathStep::ChecklistItem {
+                indices: indices.to_vec(),
+            },
+            ("t", [row, column]) => PathStep::TableCell {
+                row: *row,
+                column: *column,
+            },
+            _ => return None,
+        });
+    }
+    Some(ParagraphPath::from_steps(steps))
+}
+
+impl DocumentEditor {
+    /// The folds to draw, in the order they were made.
+    pub fn fold_refs(&self) -> Vec<FoldRef> {
+        self.folds
+            .iter()
+            .filter_map(|path| resolve(&self.document, path))
+            .collect()
+    }
+
+    pub fn has_folds(&self) -> bool {
+        !self.folds.is_empty()
+    }
+
+    /// Whether folded content hides `pointer`.
+    pub fn is_folded_away(&self, pointer: &CursorPointer) -> bool {
+        self.fold_refs()
+            .iter()
+            .any(|fold| hides(&self.document, fold, &pointer.paragraph_path))
+    }
+
+    /// Whether there is something to fold or unfold at the cursor.
+    pub fn can_toggle_fold(&self) -> bool {
+        !candidates(&self.document, &self.cursor.paragraph_path).is_empty()
+    }
+
+    /// Fold the innermost section, list entry, checklist item or quote
+    /// around the cursor, or unfold it if it is folded already. Returns
+    /// whether it is folded now, or `None` if there is nothing to fold.
+    pub fn toggle_fold(&mut self) -> Option<bool> {
+        let fold = candidates(&self.document, &self.cursor.paragraph_path)
+            .into_iter()
+            .next()?;
+        if let Some(index) = self.folds.iter().position(|path| *path == fold.path) {
+            self.folds.remove(index);
+            return Some(false);
+        }
+        self.folds.push(fold.path);
+        self.leave_folded_content();
+        Some(true)
+    }
+
+    /// Fold every section. Returns whether anything was folded.
+    pub fn fold_all_sections(&mut self) -> bool {
+        let mut folded = false;
+        for index in 0..self.document.paragraphs.len() {
+            let path = ParagraphPath::new_root(index);
+            if !self.folds.contains(&path) && resolve(&self.document, &path).is_some() {
+                self.folds.push(path);
+                folded = true;
+            }
+        }
+        self.leave_folded_content();
+        folded
+    }
+
+    /// Unfold everything. Returns whether anything was folded.
+    pub fn unfold_all(&mut self) -> bool {
+        let folded = self.has_folds();
+        self.folds.clear();
+        folded
+    }
+
+    /// Unfold whatever hides the cursor. Returns whether anything did.
+    pub fn unfold_at_cursor(&mut self) -> bool {
+        let hiding: Vec<ParagraphPath> = self
+            .fold_refs()
+            .into_iter()
+            .filter(|fold| hides(&self.document, fold, &self.cursor.paragraph_path))
+            .map(|fold| fold.path)
+            .collect();
+        self.folds.retain(|path| !hiding.contains(path));
+        !hiding.is_empty()
+    }
+
+    /// Carry the cursor on past the folded content it moved into: forward
+    /// to the start of the first segment after it, or back to the end of
+    /// the last segment before it. Returns false, leaving the cursor where
+    /// it is, if there is no such segment.
+    pub fn skip_folded_content(&mut self, forward: bool) -> bool {
+        let folds = self.fold_refs();
+        let visible = |segment: &&SegmentRef| {
+            !folds
+                .iter()
+                .any(|fold| hides(&self.document, fold, &segment.paragraph_path))
+        };
+        let index = self.cursor_segment.min(self.segments.len());
+        let target = if forward {
+            self.segments[index..].iter().find(visible)
+        } else {
+            self.segments[..index].iter().rev().find(visible)
+        };
+        let Some(target) = target else {
+            return false;
+        };
+        let pointer = CursorPointer {
+            paragraph_path: target.paragraph_path.clone(),
+            span_path: target.span_path.clone(),
+            offset: if forward { 0 } else { target.len },
+            segment_kind: target.kind,
+        };
+        self.move_to_pointer(&pointer)
+    }
+
+    /// Move the cursor out of folded content to the start of the head of
+    /// the fold that hides it.
+    fn leave_folded_content(&mut self) {
+        for _ in 0..=self.folds.len() {
+            let Some(fold) = self
+                .fold_refs()
+                .into_iter()
+                .find(|fold| hides(&self.document, fold, &self.cursor.paragraph_path))
+            else {
+                return;
+            };
+            let head = self.segments.iter().find(|segment| {
+                segment.kind == SegmentKind::Text
+                    && starts_with(&segment.paragraph_path, &fold.path)
+                    && !hides(&self.document, &fold, &segment.paragraph_path)
+            });
+            let Some(head) = head else {
+                return;
+            };
+            let pointer = CursorPointer {
+                paragraph_path: head.paragraph_path.clone(),
+                span_path: head.span_path.clone(),
+                offset: 0,
+                segment_kind: SegmentKind::Text,
+            };
+            self.move_to_pointer(&pointer);
+        }
+    }
+
+    /// Carry the folds over from `previous`, the document before an edit,
+    /// to the current one, dropping those with nothing left to fold.
+    pub fn remap_folds(&mut self, previous: &Document) {
+        let mut folds: Vec<ParagraphPath> = Vec::new();
+        for path in &self.folds {
+            let path = remap(previous, &self.document, path);
+            if resolve(&self.document, &path).is_some() && !folds.contains(&path) {
+                folds.push(path);
+            }
+        }
+        self.folds = folds;
+    }
+
+    /// The folds as text, one per fold, for [`Self::restore_folds`].
+    pub fn fold_keys(&self) -> Vec<String> {
+        self.folds.iter().map(path_key).collect()
+    }
+
+    /// Fold what `keys` from [`Self::fold_keys`] name, skipping those that
+    /// no longer fit the document.
+    pub fn restore_folds(&mut self, keys: &[String]) {
+        self.folds.clear();
+        for path in keys.iter().filter_map(|key| parse_path_key(key)) {
+            if resolve(&self.document, &path).is_some() && !self.folds.contains(&path) {
+                self.folds.push(path);
+            }
+        }
+        self.leave_folded_content();
+    }
+}
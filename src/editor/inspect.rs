@@ -1,23 +1,13 @@
 use super::{
     CursorPointer, ParagraphPath, PathStep, SegmentKind, SegmentRef, SpanPath, inline_style_label,
 };
-use tdoc::{ChecklistItem, Document, InlineStyle, Paragraph, ParagraphType, Span};
+use tdoc::{ChecklistItem, Document, InlineStyle, Paragraph, ParagraphType, Span, TableCell};
 
-pub fn collect_segments(
-    document: &Document,
-    reveal_codes: bool,
-    layout_width: usize,
-) -> Vec<SegmentRef> {
+pub fn collect_segments(document: &Document, reveal_codes: bool) -> Vec<SegmentRef> {
     let mut result = Vec::new();
     for (idx, paragraph) in document.paragraphs.iter().enumerate() {
         let mut path = ParagraphPath::new_root(idx);
-        collect_paragraph_segments(
-            paragraph,
-            &mut path,
-            reveal_codes,
-            layout_width,
-            &mut result,
-        );
+        collect_paragraph_segments(paragraph, &mut path, reveal_codes, &mut result);
     }
     result
 }
@@ -28,7 +18,6 @@ pub fn collect_segments_for_paragraph_tree(
     document: &Document,
     root_path: &ParagraphPath,
     reveal_codes: bool,
-    layout_width: usize,
 ) -> Vec<SegmentRef> {
     let mut result = Vec::new();
 
@@ -52,15 +41,11 @@ pub fn collect_segments_for_paragraph_tree(
             let mut path = parent_path;
             collect_checklist_item_segments(item, &mut path, indices, reveal_codes, &mut result);
         }
+    } else if let Some(cell) = table_cell_ref(document, root_path) {
+        collect_content_segments(&cell.content, root_path, reveal_codes, &mut result);
     } else if let Some(paragraph) = paragraph_ref(document, root_path) {
         let mut path = root_path.clone();
-        collect_paragraph_segments(
-            paragraph,
-            &mut path,
-            reveal_codes,
-            layout_width,
-            &mut result,
-        );
+        collect_paragraph_segments(paragraph, &mut path, reveal_codes, &mut result);
     }
     result
 }
@@ -72,29 +57,20 @@ pub fn breadcrumbs_for_pointer(
     if pointer.paragraph_path.is_empty() {
         return None;
     }
-    let (mut labels, target) = collect_paragraph_labels(document, &pointer.paragraph_path)?;
-    let inline_labels = match target {
-        LabelTarget::Paragraph(paragraph) => collect_inline_labels(paragraph, &pointer.span_path)?,
-        LabelTarget::ChecklistItem(item) => {
-            collect_inline_labels_from_item(item, &pointer.span_path)?
-        }
-    };
-    labels.extend(inline_labels);
+    let (mut labels, spans) = collect_paragraph_labels(document, &pointer.paragraph_path)?;
+    labels.extend(collect_inline_labels(spans, &pointer.span_path)?);
     Some(labels)
 }
 
-enum LabelTarget<'a> {
-    Paragraph(&'a Paragraph),
-    ChecklistItem(&'a ChecklistItem),
-}
-
+/// Labels for the block structure along `path`, together with the inline
+/// spans of the content root it ends in.
 fn collect_paragraph_labels<'a>(
     document: &'a Document,
     path: &ParagraphPath,
-) -> Option<(Vec<String>, LabelTarget<'a>)> {
+) -> Option<(Vec<String>, &'a [Span])> {
     let mut labels = Vec::new();
     let mut current: Option<&'a Paragraph> = None;
-    let mut current_item: Option<&'a ChecklistItem> = None;
+    let mut current_spans: Option<&'a [Span]> = None;
     let mut traversed = Vec::new();
 
     for step in path.steps() {
@@ -123,7 +99,14 @@ fn collect_paragraph_labels<'a>(
                     labels.push("Checklist".to_string());
                 }
                 let current_path = ParagraphPath::from_steps(traversed.clone());
-                current_item = checklist_item_ref(document, &current_path);
+                current_spans =
+                    checklist_item_ref(document, &current_path).map(|item| &item.content[..]);
+                continue;
+            }
+            PathStep::TableCell { .. } => {
+                let current_path = ParagraphPath::from_steps(traversed.clone());
+                current_spans =
+                    table_cell_ref(document, &current_path).map(|cell| &cell.content[..]);
                 continue;
             }
         };
@@ -133,45 +116,14 @@ fn collect_paragraph_labels<'a>(
             labels.push(paragraph.paragraph_type().to_string());
         }
         current = Some(paragraph);
-        current_item = None;
-    }
-
-    if let Some(item) = current_item {
-        Some((labels, LabelTarget::ChecklistItem(item)))
-    } else {
-        let paragraph = current?;
-        Some((labels, LabelTarget::Paragraph(paragraph)))
-    }
-}
-
-fn collect_inline_labels(paragraph: &Paragraph, span_path: &SpanPath) -> Option<Vec<String>> {
-    let mut labels = Vec::new();
-    if span_path.is_empty() {
-        return Some(labels);
-    }
-
-    let mut spans = paragraph.content();
-    for &idx in span_path.indices() {
-        let span = spans.get(idx)?;
-        if let Some(label) = inline_style_label(span.style) {
-            labels.push(label.to_string());
-        }
-        spans = &span.children;
+        current_spans = Some(paragraph.content());
     }
 
-    Some(labels)
+    Some((labels, current_spans?))
 }
 
-fn collect_inline_labels_from_item(
-    item: &ChecklistItem,
-    span_path: &SpanPath,
-) -> Option<Vec<String>> {
+fn collect_inline_labels(mut spans: &[Span], span_path: &SpanPath) -> Option<Vec<String>> {
     let mut labels = Vec::new();
-    if span_path.is_empty() {
-        return Some(labels);
-    }
-
-    let mut spans = &item.content;
     for &idx in span_path.indices() {
         let span = spans.get(idx)?;
         if let Some(label) = inline_style_label(span.style) {
@@ -179,7 +131,6 @@ fn collect_inline_labels_from_item(
         }
         spans = &span.children;
     }
-
     Some(labels)
 }
 
@@ -213,7 +164,7 @@ fn text_effective_relation(
             .entries()
             .get(entry_index)
             .and_then(|entry| (entry.len() == 1).then_some(TextEffectiveRelation::Entry)),
-        PathStep::Root(_) | PathStep::ChecklistItem { .. } => None,
+        PathStep::Root(_) | PathStep::ChecklistItem { .. } | PathStep::TableCell { .. } => None,
     }
 }
 
@@ -328,6 +279,18 @@ fn dump_paragraph(
                 rows.len(),
                 columns
             ));
+            for (row_index, row) in rows.iter().enumerate() {
+                for (column_index, cell) in row.cells.iter().enumerate() {
+                    path.push_table_cell(row_index, column_index);
+                    let marker = cursor_marker(path, cursor);
+                    path.pop();
+                    let kind = if cell.is_header { "header" } else { "cell" };
+                    out.push_str(&format!(
+                        "{indent}  {kind} [{row_index}, {column_index}] {}{marker}\n",
+                        dump_spans(&cell.content)
+                    ));
+                }
+            }
         }
     }
 }
@@ -415,7 +378,7 @@ pub fn paragraph_ref<'a>(document: &'a Document, path: &ParagraphPath) -> Option
                 }
                 _ => return None,
             },
-            PathStep::ChecklistItem { .. } => return None,
+            PathStep::ChecklistItem { .. } | PathStep::TableCell { .. } => return None,
             PathStep::Root(_) => return None,
         };
     }
@@ -446,21 +409,34 @@ pub fn checklist_item_ref<'a>(
     Some(item)
 }
 
-pub fn span_ref<'a>(paragraph: &'a Paragraph, path: &SpanPath) -> Option<&'a Span> {
-    let mut iter = path.indices().iter();
-    let first = iter.next()?;
-    let spans = paragraph.content();
-    let mut span = spans.get(*first)?;
-    for idx in iter {
-        span = span.children.get(*idx)?;
+pub fn table_cell_ref<'a>(document: &'a Document, path: &ParagraphPath) -> Option<&'a TableCell> {
+    let (PathStep::TableCell { row, column }, table_steps) = path.steps().split_last()? else {
+        return None;
+    };
+    let table_path = ParagraphPath::from_steps(table_steps.to_vec());
+    paragraph_ref(document, &table_path)?
+        .rows()
+        .get(*row)?
+        .cells
+        .get(*column)
+}
+
+/// The inline spans of the content root at `path`: a leaf paragraph, a
+/// checklist item, or a table cell.
+pub fn content_spans_ref<'a>(document: &'a Document, path: &ParagraphPath) -> Option<&'a [Span]> {
+    match path.steps().last()? {
+        PathStep::TableCell { .. } => table_cell_ref(document, path).map(|cell| &cell.content[..]),
+        PathStep::ChecklistItem { .. } => {
+            checklist_item_ref(document, path).map(|item| &item.content[..])
+        }
+        _ => paragraph_ref(document, path).map(|paragraph| paragraph.content()),
     }
-    Some(span)
 }
 
-pub fn span_ref_from_item<'a>(item: &'a ChecklistItem, path: &SpanPath) -> Option<&'a Span> {
+pub fn span_ref<'a>(spans: &'a [Span], path: &SpanPath) -> Option<&'a Span> {
     let mut iter = path.indices().iter();
     let first = iter.next()?;
-    let mut span = item.content.get(*first)?;
+    let mut span = spans.get(*first)?;
     for idx in iter {
         span = span.children.get(*idx)?;
     }
@@ -471,13 +447,12 @@ fn collect_paragraph_segments(
     paragraph: &Paragraph,
     path: &mut ParagraphPath,
     reveal_codes: bool,
-    layout_width: usize,
     segments: &mut Vec<SegmentRef>,
 ) {
     collect_span_segments(paragraph, path, reveal_codes, segments);
     for (child_index, child) in paragraph.children().iter().enumerate() {
         path.push_child(child_index);
-        collect_paragraph_segments(child, path, reveal_codes, layout_width, segments);
+        collect_paragraph_segments(child, path, reveal_codes, segments);
         path.pop();
     }
     for (entry_index, entry) in paragraph.entries().iter().enumerate() {
@@ -494,7 +469,7 @@ fn collect_paragraph_segments(
         } else {
             for (child_index, child) in entry.iter().enumerate() {
                 path.push_entry(entry_index, child_index);
-                collect_paragraph_segments(child, path, reveal_codes, layout_width, segments);
+                collect_paragraph_segments(child, path, reveal_codes, segments);
                 path.pop();
             }
         }
@@ -505,39 +480,32 @@ fn collect_paragraph_segments(
         }
     }
     if paragraph.paragraph_type() == ParagraphType::Table {
-        collect_table_segments(paragraph, path, layout_width, segments);
+        collect_table_segments(paragraph, path, reveal_codes, segments);
     }
 }
 
-/// Tables are read-only and laid out entirely by the renderer. We mirror that
-/// layout here by asking the renderer for the per-line character counts at the
-/// current content width, then emit one navigable `Text` segment per rendered
-/// line (span path = line index, len = character count). This lines up exactly
-/// with the per-line cursor stops `DirectRenderer::render_table` emits, so the
-/// cursor can be positioned anywhere in the grid while edits stay blocked. A
-/// degenerate/empty table still gets a single zero-length stop so it remains
-/// selectable.
+/// Table cells are content roots of their own, visited row by row. A table
+/// without any cells still gets a zero-length stop on the table itself so it
+/// remains selectable (and removable).
 fn collect_table_segments(
     paragraph: &Paragraph,
-    path: &ParagraphPath,
-    layout_width: usize,
+    path: &mut ParagraphPath,
+    reveal_codes: bool,
     segments: &mut Vec<SegmentRef>,
 ) {
-    let line_lengths = crate::render::table_line_char_counts(paragraph, layout_width);
-    if line_lengths.is_empty() {
-        segments.push(SegmentRef {
-            paragraph_path: path.clone(),
-            span_path: SpanPath::new(vec![0]),
-            len: 0,
-            kind: SegmentKind::Text,
-        });
-        return;
+    let segments_before = segments.len();
+    for (row_index, row) in paragraph.rows().iter().enumerate() {
+        for (column_index, cell) in row.cells.iter().enumerate() {
+            path.push_table_cell(row_index, column_index);
+            collect_content_segments(&cell.content, path, reveal_codes, segments);
+            path.pop();
+        }
     }
-    for (line_index, len) in line_lengths.into_iter().enumerate() {
+    if segments.len() == segments_before {
         segments.push(SegmentRef {
             paragraph_path: path.clone(),
-            span_path: SpanPath::new(vec![line_index]),
-            len,
+            span_path: SpanPath::new(Vec::new()),
+            len: 0,
             kind: SegmentKind::Text,
         });
     }
@@ -551,7 +519,7 @@ fn collect_checklist_item_segments(
     segments: &mut Vec<SegmentRef>,
 ) {
     path.push_checklist_item(indices.to_vec());
-    collect_span_segments_from_item(item, path, reveal_codes, segments);
+    collect_content_segments(&item.content, path, reveal_codes, segments);
     path.pop();
 
     for (child_index, child) in item.children.iter().enumerate() {
@@ -586,20 +554,22 @@ fn collect_span_segments(
     }
 }
 
-fn collect_span_segments_from_item(
-    item: &ChecklistItem,
+/// Segments for the spans of a checklist item or table cell. Unlike a
+/// paragraph, these always get a stop, even when their content is empty.
+fn collect_content_segments(
+    spans: &[Span],
     path: &ParagraphPath,
     reveal_codes: bool,
     segments: &mut Vec<SegmentRef>,
 ) {
     let segments_before = segments.len();
-    for (index, span) in item.content.iter().enumerate() {
+    for (index, span) in spans.iter().enumerate() {
         let mut span_path = SpanPath::new(vec![index]);
         collect_span_rec(span, path, &mut span_path, reveal_codes, segments);
     }
 
     // If no segments were added (empty content), add a zero-length segment
-    // This ensures empty checklist items can still be navigated to
+    // This ensures empty checklist items and table cells can still be navigated to
     if segments.len() == segments_before {
         segments.push(SegmentRef {
             paragraph_path: path.clone(),
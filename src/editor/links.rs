@@ -8,10 +8,8 @@
 //! plain text when the target is cleared (unlinking).
 
 use super::content::{prune_and_merge_spans, replace_range_with_link};
-use super::inspect::{checklist_item_ref, paragraph_ref};
-use super::{
-    CursorPointer, DocumentEditor, ParagraphPath, SegmentKind, checklist_item_mut, paragraph_mut,
-};
+use super::inspect::content_spans_ref;
+use super::{CursorPointer, DocumentEditor, ParagraphPath, SegmentKind, content_spans_mut};
 use std::cmp::Ordering;
 use tdoc::{InlineStyle, Span};
 
@@ -29,14 +27,7 @@ impl DocumentEditor {
     /// is reported so editing affects the whole visible link.
     pub fn link_at_cursor(&self) -> Option<LinkAtCursor> {
         let pointer = self.cursor_pointer();
-        let spans: &[Span] =
-            if let Some(item) = checklist_item_ref(&self.document, &pointer.paragraph_path) {
-                &item.content
-            } else if let Some(paragraph) = paragraph_ref(&self.document, &pointer.paragraph_path) {
-                paragraph.content()
-            } else {
-                return None;
-            };
+        let spans = content_spans_ref(&self.document, &pointer.paragraph_path)?;
 
         // Walk the cursor's span path from the root, stopping at the first
         // (outermost) link ancestor — including the leaf span itself.
@@ -121,53 +112,33 @@ impl DocumentEditor {
             Some(_) => {}
             None => return false,
         }
-        // Hyperlinks live within a single paragraph or checklist item.
+        // Hyperlinks live within a single paragraph, checklist item or table
+        // cell.
         if start.paragraph_path != end.paragraph_path {
             return false;
         }
         let path = start.paragraph_path.clone();
 
-        // Read-only paragraphs (tables) cannot have their content rewritten.
-        if self.is_readonly_paragraph(&path) {
-            return false;
-        }
-
         // The character offset of the range start within its content root is
         // stable across the edit (text before it is untouched); remember it to
         // place the cursor at the end of the new link afterwards.
         let start_char = self.paragraph_char_offset_of_pointer(&start).unwrap_or(0);
 
-        let changed = if let Some(item) = checklist_item_mut(&mut self.document, &path) {
-            let changed = replace_range_with_link(
-                &mut item.content,
-                start.span_path.indices(),
-                start.offset,
-                end.span_path.indices(),
-                end.offset,
-                text,
-                target,
-            );
-            if changed {
-                prune_and_merge_spans(&mut item.content);
-            }
-            changed
-        } else if let Some(paragraph) = paragraph_mut(&mut self.document, &path) {
-            let changed = replace_range_with_link(
-                paragraph.content_mut(),
-                start.span_path.indices(),
-                start.offset,
-                end.span_path.indices(),
-                end.offset,
-                text,
-                target,
-            );
-            if changed {
-                prune_and_merge_spans(paragraph.content_mut());
-            }
-            changed
-        } else {
-            false
+        let Some(spans) = content_spans_mut(&mut self.document, &path) else {
+            return false;
         };
+        let changed = replace_range_with_link(
+            spans,
+            start.span_path.indices(),
+            start.offset,
+            end.span_path.indices(),
+            end.offset,
+            text,
+            target,
+        );
+        if changed {
+            prune_and_merge_spans(spans);
+        }
 
         if changed {
             if let Some(first_step) = path.steps().first() {
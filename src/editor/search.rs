@@ -0,0 +1,317 @@
+//! Finding and replacing text.
+//!
+//! A search runs over every content root — paragraphs, list entries,
+//! checklist items and table cells — one root at a time, so a match never
+//! spans two of them. Within a root, the text of all its leaf spans is
+//! searched as one string, which lets a match cross style boundaries.
+//! Replacing writes the new text into the leaf span the match starts in, so
+//! it keeps that span's [`InlineStyle`](tdoc::InlineStyle).
+
+use regex::{Regex, RegexBuilder};
+use tdoc::Span;
+
+use super::content::{char_to_byte_idx, prune_and_merge_spans};
+use super::{
+    CursorPointer, DocumentEditor, ParagraphPath, SegmentKind, SpanPath, content_spans_mut,
+    span_mut,
+};
+
+/// How a search query is interpreted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub regex: bool,
+}
+
+/// A compiled search query. Literal queries are escaped and run through the
+/// same engine as regular expressions.
+#[derive(Clone, Debug)]
+pub struct SearchQuery {
+    regex: Regex,
+    /// Whether replacements expand `$1`-style capture group references.
+    expand: bool,
+}
+
+impl SearchQuery {
+    pub fn new(pattern: &str, options: SearchOptions) -> Result<Self, regex::Error> {
+        let mut source = if options.regex {
+            pattern.to_string()
+        } else {
+            regex::escape(pattern)
+        };
+        if options.whole_word {
+            source = format!(r"\b(?:{source})\b");
+        }
+        let regex = RegexBuilder::new(&source)
+            .case_insensitive(!options.case_sensitive)
+            .build()?;
+        Ok(Self {
+            regex,
+            expand: options.regex,
+        })
+    }
+
+    /// The non-empty matches in `text`, as char ranges plus the replacement
+    /// text each one would receive.
+    fn matches(&self, text: &str, replacement: &str) -> Vec<(usize, usize, String)> {
+        let mut result = Vec::new();
+        let mut char_offset = 0;
+        let mut byte_offset = 0;
+        for captures in self.regex.captures_iter(text) {
+            let whole = captures.get(0).expect("capture group 0 is the match");
+            if whole.is_empty() {
+                continue;
+            }
+            char_offset += text[byte_offset..whole.start()].chars().count();
+            let start = char_offset;
+            char_offset += whole.as_str().chars().count();
+            byte_offset = whole.end();
+
+            let mut replaced = String::new();
+            if self.expand {
+                captures.expand(replacement, &mut replaced);
+            } else {
+                replaced.push_str(replacement);
+            }
+            result.push((start, char_offset, replaced));
+        }
+        result
+    }
+}
+
+/// A match, delimited by two leaf positions of the same content root.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchMatch {
+    pub start: CursorPointer,
+    pub end: CursorPointer,
+}
+
+/// The part of a match that falls into one leaf span, as a char range of the
+/// span's text. The renderer paints these.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchHighlight {
+    pub paragraph_path: ParagraphPath,
+    pub span_path: SpanPath,
+    pub start: usize,
+    pub end: usize,
+    /// Whether this belongs to the match the cursor is on.
+    pub current: bool,
+}
+
+/// The text of one content root with the leaf spans it is made of.
+struct TextRun {
+    paragraph_path: ParagraphPath,
+    text: String,
+    /// Each leaf span's path, and the char range its text occupies in `text`.
+    pieces: Vec<(SpanPath, usize, usize)>,
+}
+
+impl TextRun {
+    fn pointer_at(&self, offset: usize, prefer_end: bool) -> Option<CursorPointer> {
+        let (span_path, start, _) = self.pieces.iter().find(|(_, start, end)| {
+            if prefer_end {
+                *start < offset && offset <= *end
+            } else {
+                *start <= offset && offset < *end
+            }
+        })?;
+        Some(CursorPointer {
+            paragraph_path: self.paragraph_path.clone(),
+            span_path: span_path.clone(),
+            offset: offset - start,
+            segment_kind: SegmentKind::Text,
+        })
+    }
+}
+
+impl DocumentEditor {
+    /// All matches of `query` in document order.
+    pub fn find_all(&self, query: &SearchQuery) -> Vec<SearchMatch> {
+        let mut matches = Vec::new();
+        for run in self.text_runs() {
+            for (start, end, _) in query.matches(&run.text, "") {
+                if let (Some(start), Some(end)) =
+                    (run.pointer_at(start, false), run.pointer_at(end, true))
+                {
+                    matches.push(SearchMatch { start, end });
+                }
+            }
+        }
+        matches
+    }
+
+    /// Splits each match into its per-span parts, flagging those of the match
+    /// at index `current`.
+    pub fn search_highlights(
+        &self,
+        matches: &[SearchMatch],
+        current: Option<usize>,
+    ) -> Vec<SearchHighlight> {
+        let mut highlights = Vec::new();
+        for (index, search_match) in matches.iter().enumerate() {
+            let (Some(start), Some(end)) = (
+                self.pointer_key(&search_match.start),
+                self.pointer_key(&search_match.end),
+            ) else {
+                continue;
+            };
+            for segment_index in start.segment_index..=end.segment_index {
+                let Some(segment) = self.segments.get(segment_index) else {
+                    continue;
+                };
+                if segment.kind != SegmentKind::Text {
+                    continue;
+                }
+                let from = if segment_index == start.segment_index {
+                    start.offset
+                } else {
+                    0
+                };
+                let to = if segment_index == end.segment_index {
+                    end.offset
+                } else {
+                    segment.len
+                };
+                if from < to {
+                    highlights.push(SearchHighlight {
+                        paragraph_path: segment.paragraph_path.clone(),
+                        span_path: segment.span_path.clone(),
+                        start: from,
+                        end: to,
+                        current: current == Some(index),
+                    });
+                }
+            }
+        }
+        highlights
+    }
+
+    /// Replaces one match found by [`DocumentEditor::find_all`] and leaves the
+    /// cursor after the inserted text. Returns `false` when the document no
+    /// longer holds a match of `query` at that position.
+    pub fn replace_match(
+        &mut self,
+        query: &SearchQuery,
+        search_match: &SearchMatch,
+        replacement: &str,
+    ) -> bool {
+        let path = search_match.start.paragraph_path.clone();
+        let (Some(start), Some(end)) = (
+            self.paragraph_char_offset_of_pointer(&search_match.start),
+            self.paragraph_char_offset_of_pointer(&search_match.end),
+        ) else {
+            return false;
+        };
+        let Some(run) = self
+            .text_runs()
+            .into_iter()
+            .find(|run| run.paragraph_path == path)
+        else {
+            return false;
+        };
+        let Some((_, _, replaced)) = query
+            .matches(&run.text, replacement)
+            .into_iter()
+            .find(|(from, to, _)| *from == start && *to == end)
+        else {
+            return false;
+        };
+
+        let inserted = replaced.chars().count();
+        if !self.replace_in_run(&run, &[(start, end, replaced)]) {
+            return false;
+        }
+        self.rebuild_segments();
+        self.move_to_paragraph_char_offset(&path, start + inserted);
+        true
+    }
+
+    /// Replaces every match of `query` and returns how many were replaced.
+    /// The cursor keeps its character position within its content root.
+    pub fn replace_all(&mut self, query: &SearchQuery, replacement: &str) -> usize {
+        let cursor_position = self
+            .paragraph_char_offset_of_pointer(&self.cursor)
+            .map(|char_offset| (self.cursor.paragraph_path.clone(), char_offset));
+
+        let mut count = 0;
+        for run in self.text_runs() {
+            let matches = query.matches(&run.text, replacement);
+            if !matches.is_empty() && self.replace_in_run(&run, &matches) {
+                count += matches.len();
+            }
+        }
+
+        if count > 0 {
+            self.rebuild_segments();
+            if let Some((paragraph_path, char_offset)) = cursor_position {
+                self.move_to_paragraph_char_offset(&paragraph_path, char_offset);
+            }
+        }
+        count
+    }
+
+    /// The searchable text of every content root, in document order.
+    fn text_runs(&self) -> Vec<TextRun> {
+        let mut runs: Vec<TextRun> = Vec::new();
+        for segment in &self.segments {
+            if segment.kind != SegmentKind::Text || segment.span_path.is_empty() {
+                continue;
+            }
+            let Some(text) = self.segment_text(segment) else {
+                continue;
+            };
+            if runs
+                .last()
+                .is_none_or(|run| run.paragraph_path != segment.paragraph_path)
+            {
+                runs.push(TextRun {
+                    paragraph_path: segment.paragraph_path.clone(),
+                    text: String::new(),
+                    pieces: Vec::new(),
+                });
+            }
+            let run = runs.last_mut().expect("run was just pushed");
+            let start = run.text.chars().count();
+            run.text.push_str(text);
+            run.pieces
+                .push((segment.span_path.clone(), start, start + segment.len));
+        }
+        runs
+    }
+
+    /// Applies `replacements` — non-overlapping char ranges of `run.text` in
+    /// ascending order, each with its new text — to the spans of the run's
+    /// content root.
+    fn replace_in_run(&mut self, run: &TextRun, replacements: &[(usize, usize, String)]) -> bool {
+        let Some(spans) = content_spans_mut(&mut self.document, &run.paragraph_path) else {
+            return false;
+        };
+        // Working backwards keeps the char ranges of earlier matches valid.
+        for (start, end, text) in replacements.iter().rev() {
+            for (span_path, piece_start, piece_end) in run.pieces.iter().rev() {
+                let from = (*start).max(*piece_start);
+                let to = (*end).min(*piece_end);
+                let holds_start = *piece_start <= *start && *start < *piece_end;
+                if from >= to && !holds_start {
+                    continue;
+                }
+                let Some(span) = span_mut(spans, span_path) else {
+                    continue;
+                };
+                let byte_from = char_to_byte_idx(&span.text, from - piece_start);
+                let byte_to = char_to_byte_idx(&span.text, to - piece_start);
+                if holds_start {
+                    span.text.replace_range(byte_from..byte_to, text);
+                } else {
+                    span.text.replace_range(byte_from..byte_to, "");
+                }
+            }
+        }
+        prune_and_merge_spans(spans);
+        if spans.is_empty() {
+            spans.push(Span::new_text(String::new()));
+        }
+        true
+    }
+}
@@ -0,0 +1,177 @@
+use super::*;
+use tdoc::{ChecklistItem, TableCell, TableRow};
+
+fn text_paragraph(text: &str) -> Paragraph {
+    Paragraph::new_text().with_content(vec![Span::new_text(text)])
+}
+
+fn query(pattern: &str, options: SearchOptions) -> SearchQuery {
+    SearchQuery::new(pattern, options).expect("valid query")
+}
+
+fn literal(pattern: &str) -> SearchQuery {
+    query(pattern, SearchOptions::default())
+}
+
+fn root_text(editor: &DocumentEditor, root: usize) -> String {
+    fn collect(spans: &[Span], out: &mut String) {
+        for span in spans {
+            out.push_str(&span.text);
+            collect(&span.children, out);
+        }
+    }
+    let mut out = String::new();
+    collect(editor.document().paragraphs[root].content(), &mut out);
+    out
+}
+
+#[test]
+fn find_all_ignores_case_by_default() {
+    let document = Document::new().with_paragraphs(vec![
+        text_paragraph("Apple pie and apple juice"),
+        text_paragraph("no fruit here"),
+        text_paragraph("APPLE"),
+    ]);
+    let editor = DocumentEditor::new(document);
+
+    let matches = editor.find_all(&literal("apple"));
+    let found: Vec<(usize, usize)> = matches
+        .iter()
+        .map(|m| (m.start.paragraph_path.root_index().unwrap(), m.start.offset))
+        .collect();
+    assert_eq!(found, vec![(0, 0), (0, 14), (2, 0)]);
+
+    let case_sensitive = SearchOptions {
+        case_sensitive: true,
+        ..SearchOptions::default()
+    };
+    assert_eq!(editor.find_all(&query("apple", case_sensitive)).len(), 1);
+}
+
+#[test]
+fn whole_word_and_regex_options_narrow_matches() {
+    let document =
+        Document::new().with_paragraphs(vec![text_paragraph("cat catalog scat cat2 (cat)")]);
+    let editor = DocumentEditor::new(document);
+
+    let whole_word = SearchOptions {
+        whole_word: true,
+        ..SearchOptions::default()
+    };
+    assert_eq!(editor.find_all(&query("cat", whole_word)).len(), 2);
+
+    let regex = SearchOptions {
+        regex: true,
+        ..SearchOptions::default()
+    };
+    assert_eq!(editor.find_all(&query(r"cat\d", regex)).len(), 1);
+    // Without the regex option, metacharacters match literally.
+    assert_eq!(editor.find_all(&literal("(cat)")).len(), 1);
+    assert!(SearchQuery::new("(cat", regex).is_err());
+}
+
+#[test]
+fn matches_cross_style_boundaries() {
+    let document = Document::new().with_paragraphs(vec![Paragraph::new_text().with_content(vec![
+        Span::new_text("hello "),
+        Span::new_styled(InlineStyle::Bold).with_children(vec![Span::new_text("wor")]),
+        Span::new_text("ld"),
+    ])]);
+    let editor = DocumentEditor::new(document);
+
+    let matches = editor.find_all(&literal("world"));
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].start.span_path.indices(), &[1, 0]);
+    assert_eq!(matches[0].start.offset, 0);
+    assert_eq!(matches[0].end.span_path.indices(), &[2]);
+    assert_eq!(matches[0].end.offset, 2);
+
+    let highlights = editor.search_highlights(&matches, Some(0));
+    let parts: Vec<(usize, usize)> = highlights.iter().map(|h| (h.start, h.end)).collect();
+    assert_eq!(parts, vec![(0, 3), (0, 2)]);
+    assert!(highlights.iter().all(|h| h.current));
+}
+
+#[test]
+fn find_all_searches_list_entries_checklist_items_and_table_cells() {
+    let document = Document::new().with_paragraphs(vec![
+        Paragraph::new_unordered_list().with_entries(vec![vec![text_paragraph("a needle")]]),
+        Paragraph::new_checklist().with_checklist_items(vec![
+            ChecklistItem::new(false).with_content(vec![Span::new_text("needle task")]),
+        ]),
+        Paragraph::new_table().with_rows(vec![TableRow::new().with_cells(vec![
+            TableCell::new_data().with_content(vec![Span::new_text("hay")]),
+            TableCell::new_data().with_content(vec![Span::new_text("needle")]),
+        ])]),
+    ]);
+    let editor = DocumentEditor::new(document);
+
+    let matches = editor.find_all(&literal("needle"));
+    assert_eq!(matches.len(), 3);
+    let roots: Vec<usize> = matches
+        .iter()
+        .map(|m| m.start.paragraph_path.root_index().unwrap())
+        .collect();
+    assert_eq!(roots, vec![0, 1, 2]);
+}
+
+#[test]
+fn replace_match_keeps_the_inline_style() {
+    let document = Document::new().with_paragraphs(vec![Paragraph::new_text().with_content(vec![
+        Span::new_text("say "),
+        Span::new_styled(InlineStyle::Bold).with_children(vec![Span::new_text("hello")]),
+        Span::new_text(" now"),
+    ])]);
+    let mut editor = DocumentEditor::new(document);
+    let search = literal("hello");
+    let matches = editor.find_all(&search);
+
+    assert!(editor.replace_match(&search, &matches[0], "goodbye"));
+    assert_eq!(root_text(&editor, 0), "say goodbye now");
+    let bold = &editor.document().paragraphs[0].content()[1];
+    assert_eq!(bold.style, InlineStyle::Bold);
+    assert_eq!(bold.text, "goodbye");
+    // The cursor ends up after the inserted text.
+    assert_eq!(editor.cursor_pointer().span_path.indices(), &[1]);
+    assert_eq!(editor.cursor_pointer().offset, 7);
+
+    // A stale match no longer applies.
+    assert!(!editor.replace_match(&search, &matches[0], "x"));
+}
+
+#[test]
+fn replace_all_counts_replacements_and_expands_captures() {
+    let document = Document::new().with_paragraphs(vec![
+        text_paragraph("2024-01-05 and 2025-12-31"),
+        text_paragraph("none"),
+    ]);
+    let mut editor = DocumentEditor::new(document);
+    let regex = SearchOptions {
+        regex: true,
+        ..SearchOptions::default()
+    };
+
+    let count = editor.replace_all(&query(r"(\d+)-(\d+)-(\d+)", regex), "$3.$2.$1");
+    assert_eq!(count, 2);
+    assert_eq!(root_text(&editor, 0), "05.01.2024 and 31.12.2025");
+
+    // Literal replacements are not expanded.
+    let count = editor.replace_all(&literal("and"), "$1");
+    assert_eq!(count, 1);
+    assert_eq!(root_text(&editor, 0), "05.01.2024 $1 31.12.2025");
+}
+
+#[test]
+fn replacing_a_match_across_spans_drops_the_emptied_spans() {
+    let document = Document::new().with_paragraphs(vec![Paragraph::new_text().with_content(vec![
+        Span::new_text("ab"),
+        Span::new_styled(InlineStyle::Italic).with_children(vec![Span::new_text("cd")]),
+    ])]);
+    let mut editor = DocumentEditor::new(document);
+
+    assert_eq!(editor.replace_all(&literal("bcd"), "X"), 1);
+    let content = editor.document().paragraphs[0].content();
+    assert_eq!(content.len(), 1);
+    assert_eq!(content[0].text, "aX");
+    assert_eq!(content[0].style, InlineStyle::None);
+}
@@ -2,7 +2,8 @@ use crate::editor::content::{
     checklist_item_is_empty, span_is_empty as content_span_is_empty, split_spans,
 };
 use std::mem;
-use tdoc::{ChecklistItem, Document, Paragraph, ParagraphType, Span};
+use std::ops::Range;
+use tdoc::{ChecklistItem, Document, Paragraph, ParagraphType, Span, TableCell};
 
 use super::inspect::paragraph_ref;
 use super::{CursorPointer, ParagraphPath, PathStep, SegmentKind, SpanPath};
@@ -47,7 +48,7 @@ pub(crate) fn paragraph_mut<'a>(
                 }
                 _ => return None,
             },
-            PathStep::ChecklistItem { .. } => return None,
+            PathStep::ChecklistItem { .. } | PathStep::TableCell { .. } => return None,
             PathStep::Root(_) => return None,
         };
     }
@@ -82,29 +83,47 @@ pub(crate) fn checklist_item_mut<'a>(
     Some(item)
 }
 
-pub(crate) fn span_mut<'a>(paragraph: &'a mut Paragraph, path: &SpanPath) -> Option<&'a mut Span> {
-    // Only leaf paragraphs hold inline spans; `content_mut` panics otherwise.
-    // Non-leaf paragraphs (notably read-only tables, whose synthetic cursor
-    // segments carry a line-index span path) simply have no editable span here.
-    if !paragraph.paragraph_type().is_leaf() {
+pub(crate) fn table_cell_mut<'a>(
+    document: &'a mut Document,
+    path: &ParagraphPath,
+) -> Option<&'a mut TableCell> {
+    let (PathStep::TableCell { row, column }, table_steps) = path.steps().split_last()? else {
         return None;
+    };
+    let table_path = ParagraphPath::from_steps(table_steps.to_vec());
+    let Paragraph::Table { rows } = paragraph_mut(document, &table_path)? else {
+        return None;
+    };
+    rows.get_mut(*row)?.cells.get_mut(*column)
+}
+
+/// The inline spans of the content root at `path`: a leaf paragraph, a
+/// checklist item, or a table cell.
+pub(crate) fn content_spans_mut<'a>(
+    document: &'a mut Document,
+    path: &ParagraphPath,
+) -> Option<&'a mut Vec<Span>> {
+    match path.steps().last()? {
+        PathStep::TableCell { .. } => table_cell_mut(document, path).map(|cell| &mut cell.content),
+        PathStep::ChecklistItem { .. } => {
+            checklist_item_mut(document, path).map(|item| &mut item.content)
+        }
+        _ => {
+            let paragraph = paragraph_mut(document, path)?;
+            // Only leaf paragraphs hold inline spans; `content_mut` panics
+            // otherwise.
+            if !paragraph.paragraph_type().is_leaf() {
+                return None;
+            }
+            Some(paragraph.content_mut())
+        }
     }
-    let mut iter = path.indices().iter();
-    let first = iter.next()?;
-    let mut span = paragraph.content_mut().get_mut(*first)?;
-    for idx in iter {
-        span = span.children.get_mut(*idx)?;
-    }
-    Some(span)
 }
 
-pub(crate) fn span_mut_from_item<'a>(
-    item: &'a mut ChecklistItem,
-    path: &SpanPath,
-) -> Option<&'a mut Span> {
+pub(crate) fn span_mut<'a>(spans: &'a mut [Span], path: &SpanPath) -> Option<&'a mut Span> {
     let mut iter = path.indices().iter();
     let first = iter.next()?;
-    let mut span = item.content.get_mut(*first)?;
+    let mut span = spans.get_mut(*first)?;
     for idx in iter {
         span = span.children.get_mut(*idx)?;
     }
@@ -349,7 +368,7 @@ pub(crate) fn determine_parent_scope(
                 None
             }
         }
-        PathStep::Root(_) => None,
+        PathStep::Root(_) | PathStep::TableCell { .. } => None,
     }
 }
 
@@ -996,6 +1015,23 @@ pub(crate) fn extract_checklist_item_context(path: &ParagraphPath) -> Option<Che
     None
 }
 
+pub(crate) struct TableCellContext {
+    pub table_path: ParagraphPath,
+    pub row: usize,
+    pub column: usize,
+}
+
+pub(crate) fn extract_table_cell_context(path: &ParagraphPath) -> Option<TableCellContext> {
+    let (PathStep::TableCell { row, column }, table_steps) = path.steps().split_last()? else {
+        return None;
+    };
+    Some(TableCellContext {
+        table_path: ParagraphPath::from_steps(table_steps.to_vec()),
+        row: *row,
+        column: *column,
+    })
+}
+
 fn checklist_items_container_mut<'a>(
     document: &'a mut Document,
     checklist_path: &ParagraphPath,
@@ -1499,6 +1535,17 @@ pub(crate) fn ensure_checklist_item_has_content(
     false
 }
 
+pub(crate) fn ensure_table_cell_has_content(document: &mut Document, path: &ParagraphPath) -> bool {
+    let Some(cell) = table_cell_mut(document, path) else {
+        return false;
+    };
+    if cell.content.is_empty() {
+        cell.content.push(Span::new_text(""));
+        return true;
+    }
+    false
+}
+
 pub(crate) fn split_paragraph_break(
     document: &mut Document,
     pointer: &CursorPointer,
@@ -2632,6 +2679,7 @@ pub(crate) fn take_paragraph_at(
             // TODO: Implement checklist item removal and return as paragraph
             None
         }
+        PathStep::TableCell { .. } => None,
     }
 }
 
@@ -2763,5 +2811,151 @@ pub(crate) fn remove_paragraph_by_path(document: &mut Document, path: &Paragraph
             // TODO: Implement checklist item removal
             false
         }
+        PathStep::TableCell { .. } => false,
     }
 }
+
+// ============================================================================
+// Sections: a top-level heading and everything up to the next heading of the
+// same or a higher level
+// ============================================================================
+
+/// 1 to 3 for Heading 1 to Heading 3; `None` for other paragraphs.
+pub(crate) fn heading_level(paragraph: &Paragraph) -> Option<usize> {
+    match paragraph {
+        Paragraph::Header1 { .. } => Some(1),
+        Paragraph::Header2 { .. } => Some(2),
+        Paragraph::Header3 { .. } => Some(3),
+        _ => None,
+    }
+}
+
+fn root_heading_level(document: &Document, index: usize) -> Option<usize> {
+    document.paragraphs.get(index).and_then(heading_level)
+}
+
+/// The root paragraphs of the section headed by root paragraph `start`.
+pub(crate) fn section_range(document: &Document, start: usize) -> Option<Range<usize>> {
+    let level = root_heading_level(document, start)?;
+    let end = (start + 1..document.paragraphs.len())
+        .find(|&index| root_heading_level(document, index).is_some_and(|other| other <= level))
+        .unwrap_or(document.paragraphs.len());
+    Some(start..end)
+}
+
+/// The innermost section containing root paragraph `index`: the one headed
+/// by the nearest heading at or before it.
+pub(crate) fn section_containing(document: &Document, index: usize) -> Option<Range<usize>> {
+    let start = (0..=index.min(document.paragraphs.len().checked_sub(1)?))
+        .rev()
+        .find(|&index| root_heading_level(document, index).is_some())?;
+    section_range(document, start)
+}
+
+/// The section of the same level right before `section`, within the same
+/// parent section.
+pub(crate) fn previous_sibling_section(
+    document: &Document,
+    section: &Range<usize>,
+) -> Option<Range<usize>> {
+    let level = root_heading_level(document, section.start)?;
+    for index in (0..section.start).rev() {
+        match root_heading_level(document, index) {
+            Some(other) if other == level => return Some(index..section.start),
+            // The parent's heading: there is no sibling before.
+            Some(other) if other < level => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The section of the same level right after `section`, within the same
+/// parent section.
+pub(crate) fn next_sibling_section(
+    document: &Document,
+    section: &Range<usize>,
+) -> Option<Range<usize>> {
+    let level = root_heading_level(document, section.start)?;
+    if root_heading_level(document, section.end) != Some(level) {
+        return None;
+    }
+    section_range(document, section.end)
+}
+
+/// Swap two adjacent runs of root paragraphs, `first` directly followed by
+/// `second`. The paragraphs of `first` are taken out one by one and put back
+/// after the last paragraph of `second`.
+pub(crate) fn swap_root_ranges(
+    document: &mut Document,
+    first: Range<usize>,
+    second: Range<usize>,
+) -> bool {
+    if first.is_empty() || second.is_empty() || first.end != second.start {
+        return false;
+    }
+    if second.end > document.paragraphs.len() {
+        return false;
+    }
+    // With one paragraph of `first` out, `second` ends one place earlier.
+    let last = ParagraphPath::new_root(second.end - 2);
+    let start = ParagraphPath::new_root(first.start);
+    for _ in first {
+        let Some(paragraph) = take_paragraph_at(document, &start) else {
+            return false;
+        };
+        if insert_paragraph_after_parent(document, &last, paragraph).is_none() {
+            return false;
+        }
+    }
+    true
+}
+
+/// The level `level` is raised (`delta` < 0) or lowered (`delta` > 0) to,
+/// unless that is above Heading 1 or below Heading 3.
+fn shifted_level(level: usize, delta: isize) -> Option<usize> {
+    level
+        .checked_add_signed(delta)
+        .filter(|level| (1..=3).contains(level))
+}
+
+/// Whether every heading in the root paragraphs `section` can be raised or
+/// lowered by `delta` levels.
+pub(crate) fn can_shift_heading_levels(
+    document: &Document,
+    section: Range<usize>,
+    delta: isize,
+) -> bool {
+    document.paragraphs.get(section).is_some_and(|paragraphs| {
+        paragraphs
+            .iter()
+            .filter_map(heading_level)
+            .all(|level| shifted_level(level, delta).is_some())
+    })
+}
+
+/// Raise (`delta` < 0) or lower (`delta` > 0) the level of every heading in
+/// the root paragraphs `section`. Refuses when a heading would end up above
+/// Heading 1 or below Heading 3.
+pub(crate) fn shift_heading_levels(
+    document: &mut Document,
+    section: Range<usize>,
+    delta: isize,
+) -> bool {
+    if !can_shift_heading_levels(document, section.clone(), delta) {
+        return false;
+    }
+    for paragraph in &mut document.paragraphs[section] {
+        let Some(level) = heading_level(paragraph).and_then(|level| shifted_level(level, delta))
+        else {
+            continue;
+        };
+        let target = match level {
+            1 => ParagraphType::Header1,
+            2 => ParagraphType::Header2,
+            _ => ParagraphType::Header3,
+        };
+        apply_paragraph_type_in_place(paragraph, target);
+    }
+    true
+}
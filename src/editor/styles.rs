@@ -1,16 +1,15 @@
 use super::content::{apply_style_to_content_range, prune_and_merge_spans};
-use super::inspect::{checklist_item_ref, paragraph_ref};
+use super::inspect::content_spans_ref;
 use super::{
     CursorPointer, DocumentEditor, ParagraphPath, SegmentKind, SegmentRef, SpanPath,
-    checklist_item_mut, paragraph_mut,
+    content_spans_mut,
 };
-use tdoc::{InlineStyle, Span};
+use tdoc::InlineStyle;
 
-/// The portion of one content root (paragraph or checklist item) covered by
-/// a selection, delimited by leaf span positions.
+/// The portion of one content root (paragraph, checklist item or table cell)
+/// covered by a selection, delimited by leaf span positions.
 struct StyledRange {
     paragraph_path: ParagraphPath,
-    is_checklist_item: bool,
     start: (SpanPath, usize),
     end: (SpanPath, usize),
 }
@@ -49,16 +48,6 @@ impl DocumentEditor {
             return false;
         }
 
-        // Refuse to restyle a selection that touches a read-only paragraph (a
-        // table): its content is immutable.
-        for segment_index in start_key.segment_index..=end_key.segment_index {
-            if let Some(segment) = self.segments.get(segment_index)
-                && self.is_readonly_paragraph(&segment.paragraph_path)
-            {
-                return false;
-            }
-        }
-
         // Collect the selected text ranges grouped by content root. While at
         // it, detect when the whole selection already carries the requested
         // style (or is plain already, when clearing) so re-applying it does
@@ -96,8 +85,6 @@ impl DocumentEditor {
                 }
                 _ => ranges.push(StyledRange {
                     paragraph_path: segment.paragraph_path.clone(),
-                    is_checklist_item: checklist_item_ref(&self.document, &segment.paragraph_path)
-                        .is_some(),
                     start: (segment.span_path.clone(), seg_start),
                     end: (segment.span_path.clone(), seg_end),
                 }),
@@ -116,42 +103,22 @@ impl DocumentEditor {
             .map(|char_offset| (self.cursor.paragraph_path.clone(), char_offset));
 
         let mut changed = false;
-        let mut touched_paragraphs: Vec<ParagraphPath> = Vec::new();
-        let mut touched_checklists: Vec<ParagraphPath> = Vec::new();
+        let mut touched_paths: Vec<ParagraphPath> = Vec::new();
 
         for range in ranges.iter().rev() {
-            if range.is_checklist_item {
-                let Some(item) = checklist_item_mut(&mut self.document, &range.paragraph_path)
-                else {
-                    continue;
-                };
-                if apply_style_to_content_range(
-                    &mut item.content,
-                    range.start.0.indices(),
-                    range.start.1,
-                    range.end.0.indices(),
-                    range.end.1,
-                    style,
-                ) {
-                    changed = true;
-                    touched_checklists.push(range.paragraph_path.clone());
-                }
-            } else {
-                let Some(paragraph) = paragraph_mut(&mut self.document, &range.paragraph_path)
-                else {
-                    continue;
-                };
-                if apply_style_to_content_range(
-                    paragraph.content_mut(),
-                    range.start.0.indices(),
-                    range.start.1,
-                    range.end.0.indices(),
-                    range.end.1,
-                    style,
-                ) {
-                    changed = true;
-                    touched_paragraphs.push(range.paragraph_path.clone());
-                }
+            let Some(spans) = content_spans_mut(&mut self.document, &range.paragraph_path) else {
+                continue;
+            };
+            if apply_style_to_content_range(
+                spans,
+                range.start.0.indices(),
+                range.start.1,
+                range.end.0.indices(),
+                range.end.1,
+                style,
+            ) {
+                changed = true;
+                touched_paths.push(range.paragraph_path.clone());
             }
         }
 
@@ -159,23 +126,11 @@ impl DocumentEditor {
             // Collect all unique root paths that need updating
             let mut unique_paths = Vec::new();
 
-            for path in touched_paragraphs {
-                if let Some(paragraph) = paragraph_mut(&mut self.document, &path) {
-                    prune_and_merge_spans(paragraph.content_mut());
+            for path in touched_paths {
+                if let Some(spans) = content_spans_mut(&mut self.document, &path) {
+                    prune_and_merge_spans(spans);
                 }
-                // Get the root path for this paragraph (first step in path)
-                if let Some(first_step) = path.steps().first() {
-                    let root_path = super::ParagraphPath::from_steps(vec![first_step.clone()]);
-                    if !unique_paths.iter().any(|p| p == &root_path) {
-                        unique_paths.push(root_path);
-                    }
-                }
-            }
-            for path in touched_checklists {
-                if let Some(item) = checklist_item_mut(&mut self.document, &path) {
-                    prune_and_merge_spans(&mut item.content);
-                }
-                // Get the root path for this checklist (first step in path)
+                // Get the root path for this content (first step in path)
                 if let Some(first_step) = path.steps().first() {
                     let root_path = super::ParagraphPath::from_steps(vec![first_step.clone()]);
                     if !unique_paths.iter().any(|p| p == &root_path) {
@@ -206,14 +161,9 @@ impl DocumentEditor {
     /// ancestors — already provides `style`. For `InlineStyle::None` this
     /// checks that the chain is entirely unstyled (nothing to clear).
     fn segment_already_styled(&self, segment: &SegmentRef, style: InlineStyle) -> bool {
-        let spans: &[Span] =
-            if let Some(item) = checklist_item_ref(&self.document, &segment.paragraph_path) {
-                &item.content
-            } else if let Some(paragraph) = paragraph_ref(&self.document, &segment.paragraph_path) {
-                paragraph.content()
-            } else {
-                return false;
-            };
+        let Some(spans) = content_spans_ref(&self.document, &segment.paragraph_path) else {
+            return false;
+        };
 
         let mut current = spans;
         let mut chain_styles = Vec::new();
@@ -0,0 +1,220 @@
+//! Navigating and reshaping tables.
+//!
+//! Every [`TableCell`] is a content root of its own, addressed by a
+//! [`PathStep::TableCell`] step below the table paragraph. Typing and inline
+//! styling inside a cell go through the regular editing paths; this module
+//! adds moving between cells and inserting or deleting whole rows and columns.
+//! Operations never leave a table without at least one row and one column.
+
+use super::structure::{TableCellContext, extract_table_cell_context};
+use super::{
+    DocumentEditor, ParagraphPath, paragraph_mut, paragraph_ref, select_text_in_paragraph,
+};
+use tdoc::{Paragraph, TableCell, TableRow};
+
+impl DocumentEditor {
+    /// The `(row, column)` of the table cell holding the cursor, if any.
+    pub fn table_cell_position(&self) -> Option<(usize, usize)> {
+        let ctx = extract_table_cell_context(&self.cursor.paragraph_path)?;
+        Some((ctx.row, ctx.column))
+    }
+
+    /// Moves the cursor to the end of the text in the given cell of the table
+    /// currently holding the cursor.
+    pub fn move_to_table_cell(&mut self, row: usize, column: usize) -> bool {
+        let Some(ctx) = extract_table_cell_context(&self.cursor.paragraph_path) else {
+            return false;
+        };
+        self.move_to_cell_in_table(&ctx.table_path, row, column)
+    }
+
+    /// Moves the cursor to the next cell, continuing on the following row
+    /// after the last column. Returns `false` in the table's last cell.
+    pub fn move_to_next_table_cell(&mut self) -> bool {
+        let Some(ctx) = extract_table_cell_context(&self.cursor.paragraph_path) else {
+            return false;
+        };
+        let cells = self.table_cell_order(&ctx.table_path);
+        let Some(index) = cells.iter().position(|&cell| cell == (ctx.row, ctx.column)) else {
+            return false;
+        };
+        let Some(&(row, column)) = cells.get(index + 1) else {
+            return false;
+        };
+        self.move_to_cell_in_table(&ctx.table_path, row, column)
+    }
+
+    /// Moves the cursor to the previous cell, wrapping to the end of the
+    /// preceding row. Returns `false` in the table's first cell.
+    pub fn move_to_previous_table_cell(&mut self) -> bool {
+        let Some(ctx) = extract_table_cell_context(&self.cursor.paragraph_path) else {
+            return false;
+        };
+        let cells = self.table_cell_order(&ctx.table_path);
+        let Some(index) = cells.iter().position(|&cell| cell == (ctx.row, ctx.column)) else {
+            return false;
+        };
+        if index == 0 {
+            return false;
+        }
+        let (row, column) = cells[index - 1];
+        self.move_to_cell_in_table(&ctx.table_path, row, column)
+    }
+
+    /// Inserts an empty row above (or below) the cursor's row and moves the
+    /// cursor into it, keeping the column. A row inserted above a header row
+    /// becomes a header row itself; rows inserted below are data rows.
+    pub fn insert_table_row(&mut self, below: bool) -> bool {
+        let Some(ctx) = extract_table_cell_context(&self.cursor.paragraph_path) else {
+            return false;
+        };
+        let Some(Paragraph::Table { rows }) = paragraph_mut(&mut self.document, &ctx.table_path)
+        else {
+            return false;
+        };
+        if ctx.row >= rows.len() {
+            return false;
+        }
+        let columns = rows
+            .iter()
+            .map(|row| row.cells.len())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let cells = (0..columns)
+            .map(|column| {
+                let is_header = !below
+                    && rows[ctx.row]
+                        .cells
+                        .get(column)
+                        .is_some_and(|cell| cell.is_header);
+                TableCell::new(is_header)
+            })
+            .collect();
+        let row = if below { ctx.row + 1 } else { ctx.row };
+        rows.insert(row, TableRow::new().with_cells(cells));
+        self.finish_table_edit(&ctx.table_path, row, ctx.column)
+    }
+
+    /// Inserts an empty column left (or right) of the cursor's column and
+    /// moves the cursor into it. Each new cell takes the header flag of its
+    /// neighbour in the cursor's column.
+    pub fn insert_table_column(&mut self, right: bool) -> bool {
+        let Some(ctx) = extract_table_cell_context(&self.cursor.paragraph_path) else {
+            return false;
+        };
+        let Some(Paragraph::Table { rows }) = paragraph_mut(&mut self.document, &ctx.table_path)
+        else {
+            return false;
+        };
+        let column = if right { ctx.column + 1 } else { ctx.column };
+        for row in rows.iter_mut() {
+            let is_header = row
+                .cells
+                .get(ctx.column)
+                .or_else(|| row.cells.last())
+                .is_some_and(|cell| cell.is_header);
+            let index = column.min(row.cells.len());
+            row.cells.insert(index, TableCell::new(is_header));
+        }
+        self.finish_table_edit(&ctx.table_path, ctx.row, column)
+    }
+
+    /// Deletes the cursor's row. Refuses to remove the only row of a table.
+    pub fn delete_table_row(&mut self) -> bool {
+        let Some(ctx) = extract_table_cell_context(&self.cursor.paragraph_path) else {
+            return false;
+        };
+        let Some(Paragraph::Table { rows }) = paragraph_mut(&mut self.document, &ctx.table_path)
+        else {
+            return false;
+        };
+        if rows.len() <= 1 || ctx.row >= rows.len() {
+            return false;
+        }
+        rows.remove(ctx.row);
+        let row = ctx.row.min(rows.len() - 1);
+        self.finish_table_edit(&ctx.table_path, row, ctx.column)
+    }
+
+    /// Deletes the cursor's column. Refuses to remove the only column of a
+    /// table.
+    pub fn delete_table_column(&mut self) -> bool {
+        let Some(ctx) = extract_table_cell_context(&self.cursor.paragraph_path) else {
+            return false;
+        };
+        let Some(Paragraph::Table { rows }) = paragraph_mut(&mut self.document, &ctx.table_path)
+        else {
+            return false;
+        };
+        let columns = rows.iter().map(|row| row.cells.len()).max().unwrap_or(0);
+        if columns <= 1 {
+            return false;
+        }
+        for row in rows.iter_mut() {
+            if ctx.column < row.cells.len() {
+                row.cells.remove(ctx.column);
+            }
+        }
+        self.finish_table_edit(&ctx.table_path, ctx.row, ctx.column)
+    }
+
+    /// Whether the cursor sits in the last cell of its table.
+    pub fn cursor_in_last_table_cell(&self) -> bool {
+        let Some(TableCellContext {
+            table_path,
+            row,
+            column,
+        }) = extract_table_cell_context(&self.cursor.paragraph_path)
+        else {
+            return false;
+        };
+        self.table_cell_order(&table_path).last() == Some(&(row, column))
+    }
+
+    /// The `(row, column)` coordinates of all cells of the table at
+    /// `table_path`, in reading order.
+    fn table_cell_order(&self, table_path: &ParagraphPath) -> Vec<(usize, usize)> {
+        let Some(table) = paragraph_ref(&self.document, table_path) else {
+            return Vec::new();
+        };
+        table
+            .rows()
+            .iter()
+            .enumerate()
+            .flat_map(|(row, cells)| (0..cells.cells.len()).map(move |column| (row, column)))
+            .collect()
+    }
+
+    fn move_to_cell_in_table(
+        &mut self,
+        table_path: &ParagraphPath,
+        row: usize,
+        column: usize,
+    ) -> bool {
+        let mut cell_path = table_path.clone();
+        cell_path.push_table_cell(row, column);
+        let Some((index, segment)) = select_text_in_paragraph(&self.segments, &cell_path, true)
+        else {
+            return false;
+        };
+        self.cursor_segment = index;
+        self.cursor.update_from_segment(&segment);
+        self.cursor.offset = segment.len;
+        true
+    }
+
+    /// Refreshes the segments after the grid of the table at `table_path`
+    /// changed and places the cursor in the cell closest to `(row, column)`.
+    fn finish_table_edit(&mut self, table_path: &ParagraphPath, row: usize, column: usize) -> bool {
+        self.rebuild_segments();
+        let columns = paragraph_ref(&self.document, table_path)
+            .and_then(|table| table.rows().get(row))
+            .map(|row| row.cells.len())
+            .unwrap_or(0);
+        if columns == 0 || !self.move_to_cell_in_table(table_path, row, column.min(columns - 1)) {
+            self.ensure_cursor_selectable();
+        }
+        true
+    }
+}
@@ -1,10 +1,15 @@
-use std::ops::{Deref, DerefMut};
+use std::collections::BTreeSet;
+use std::mem;
+use std::ops::{Deref, DerefMut, Range};
 
 use ratatui::layout::Rect;
 use ratatui::text::Line;
 use unicode_width::UnicodeWidthStr;
 
-use crate::editor::{CursorPointer, DocumentEditor, RevealTagKind, SegmentKind};
+use crate::editor::{
+    Autoformat, CursorPointer, DocumentEditor, RevealTagKind, SearchHighlight, SearchMatch,
+    SearchQuery, SegmentKind,
+};
 use crate::render::{
     CursorVisualPosition, DirectCursorTracking, ParagraphLineInfo, RenderResult, layout_paragraph,
     render_document_direct,
@@ -33,6 +38,18 @@ struct UndoSnapshot {
     cursor: CursorPointer,
 }
 
+/// What changed in a display's document since another display of it last
+/// took the changes over; see [`EditorDisplay::sync_with`].
+#[derive(Debug, Default)]
+enum UnsyncedEdits {
+    #[default]
+    None,
+    /// Only these top-level paragraphs changed; none were added or removed.
+    Paragraphs(BTreeSet<usize>),
+    /// Anything may have changed.
+    All,
+}
+
 /// EditorDisplay wraps a DocumentEditor and manages all visual/rendering concerns.
 /// This includes cursor movement in visual space, wrapping, and rendering.
 #[derive(Debug)]
@@ -59,17 +76,27 @@ pub struct EditorDisplay {
     last_modified_paragraphs: Vec<usize>,
     /// Track the last selection to detect selection changes
     last_selection: Option<(CursorPointer, CursorPointer)>,
+    /// Search matches painted by the renderer
+    search_highlights: Vec<SearchHighlight>,
     /// Theme for rendering
     theme: Theme,
     /// Snapshots that undo restores (oldest first)
     undo_stack: Vec<UndoSnapshot>,
     /// Snapshots that redo restores (populated by undo, cleared on edit)
     redo_stack: Vec<UndoSnapshot>,
+    /// Edits, undos and redos so far, so callers can tell whether the
+    /// document changed since they last looked
+    edit_count: usize,
     /// Kind of the last recorded edit, used to coalesce edit runs
     last_edit_kind: Option<UndoEditKind>,
     /// Cursor position right after the last recorded edit; coalescing breaks
     /// when the cursor moved away between edits
     last_edit_cursor: Option<CursorPointer>,
+    /// The document before the current edit, to carry the folds over to
+    /// the edited one; only kept while something is folded
+    fold_base: Option<Document>,
+    /// Changes a second view of the document has yet to take over
+    unsynced: UnsyncedEdits,
 }
 
 enum SelectionIterationOrder {
@@ -98,11 +125,16 @@ impl EditorDisplay {
             layout_dirty: true,
             last_modified_paragraphs: Vec::new(),
             last_selection: None,
+            search_highlights: Vec::new(),
             theme: Theme::default(),
             undo_stack: Vec::new(),
             redo_stack: Vec::new(),
+            edit_count: 0,
             last_edit_kind: None,
             last_edit_cursor: None,
+            fold_base: None,
+            // A new display may hold any document.
+            unsynced: UnsyncedEdits::All,
         }
     }
 
@@ -255,6 +287,7 @@ impl EditorDisplay {
         let prefix = ""; // For text paragraphs; other types would need their specific prefixes
 
         // Re-layout just this paragraph with full position tracking
+        let folds = self.editor.fold_refs();
         let para_layout = crate::render::layout_paragraph(
             paragraph,
             paragraph_index,
@@ -268,6 +301,8 @@ impl EditorDisplay {
                 cursor: Some(&self.editor.cursor_pointer()),
                 selection: None,
                 track_all_positions: true,
+                search_highlights: &[],
+                folds: &folds,
             },
             &self.theme,
         );
@@ -419,7 +454,10 @@ impl EditorDisplay {
         }
 
         // Try incremental update if we know which paragraphs changed
-        if !self.last_modified_paragraphs.is_empty() {
+        if self.last_modified_paragraphs.is_empty() {
+            // Nothing tells which paragraphs changed.
+            self.unsynced = UnsyncedEdits::All;
+        } else {
             let paragraphs_to_update = std::mem::take(&mut self.last_modified_paragraphs);
             let mut all_succeeded = true;
 
@@ -448,11 +486,107 @@ impl EditorDisplay {
         if !self.last_modified_paragraphs.contains(&paragraph_index) {
             self.last_modified_paragraphs.push(paragraph_index);
         }
+        match &mut self.unsynced {
+            UnsyncedEdits::None => {
+                self.unsynced = UnsyncedEdits::Paragraphs(BTreeSet::from([paragraph_index]));
+            }
+            UnsyncedEdits::Paragraphs(changed) => {
+                changed.insert(paragraph_index);
+            }
+            UnsyncedEdits::All => {}
+        }
     }
 
     fn force_full_relayout(&mut self) {
         self.last_modified_paragraphs.clear();
         self.layout_dirty = true;
+        self.unsynced = UnsyncedEdits::All;
+    }
+
+    /// Take over the edits made to `source`, another display of the same
+    /// document, since this display last did, keeping the cursor as close
+    /// to where it was as possible.
+    ///
+    /// Only the top-level paragraphs the edits changed are copied and laid
+    /// out again; when paragraphs were added or removed, the whole document
+    /// is. Returns false if there was nothing to take over.
+    pub fn sync_with(&mut self, source: &mut EditorDisplay) -> bool {
+        let unsynced = mem::take(&mut source.unsynced);
+        if matches!(unsynced, UnsyncedEdits::None) {
+            return false;
+        }
+        let previous = self
+            .editor
+            .has_folds()
+            .then(|| self.editor.document().clone());
+        let document = source.editor.document();
+        match unsynced {
+            UnsyncedEdits::Paragraphs(changed)
+                if self.editor.document().paragraphs.len() == document.paragraphs.len() =>
+            {
+                for index in changed {
+                    if let Some(paragraph) = document.paragraphs.get(index) {
+                        self.editor.replace_root_paragraph(index, paragraph.clone());
+                        self.mark_paragraph_modified(index);
+                    }
+                }
+            }
+            _ => {
+                let cursor = self.editor.cursor_pointer();
+                self.editor.restore_document(document.clone(), &cursor);
+                self.force_full_relayout();
+            }
+        }
+        if let Some(previous) = previous {
+            self.carry_folds_over(&previous);
+        }
+        self.clear_render_cache();
+        true
+    }
+
+    /// Move the folds along with the edit that turned `previous` into the
+    /// current document, and open those the cursor ended up in.
+    fn carry_folds_over(&mut self, previous: &Document) {
+        let before = self.editor.fold_refs();
+        self.editor.remap_folds(previous);
+        self.editor.unfold_at_cursor();
+        if self.editor.fold_refs() != before {
+            self.force_full_relayout();
+        }
+    }
+
+    /// Fold or unfold around the cursor, see
+    /// [`DocumentEditor::toggle_fold`].
+    pub fn toggle_fold(&mut self) -> Option<bool> {
+        let folded = self.editor.toggle_fold();
+        if folded.is_some() {
+            self.force_full_relayout();
+        }
+        folded
+    }
+
+    /// Fold every section. Returns whether anything was folded.
+    pub fn fold_all_sections(&mut self) -> bool {
+        let folded = self.editor.fold_all_sections();
+        if folded {
+            self.force_full_relayout();
+        }
+        folded
+    }
+
+    /// Fold what `keys` name, see [`DocumentEditor::restore_folds`].
+    pub fn restore_folds(&mut self, keys: &[String]) {
+        self.editor.restore_folds(keys);
+        self.force_full_relayout();
+    }
+
+    /// Unfold everything. Returns whether anything was folded.
+    pub fn unfold_all(&mut self) -> bool {
+        let unfolded = self.editor.unfold_all();
+        if unfolded {
+            self.force_full_relayout();
+        }
+        unfolded
     }
 
     /// Update layout for a single paragraph (incremental update)
@@ -479,6 +613,11 @@ impl EditorDisplay {
             return false;
         };
 
+        // A paragraph in a folded section has no lines to update.
+        if para_info.end_line < para_info.start_line {
+            return false;
+        }
+
         let old_start_line = para_info.start_line;
         let old_end_line = para_info.end_line;
         let old_line_count = old_end_line - old_start_line + 1;
@@ -494,6 +633,7 @@ impl EditorDisplay {
 
         // Layout the paragraph
         let cursor_pointer = self.editor.cursor_pointer();
+        let folds = self.editor.fold_refs();
         let layout = layout_paragraph(
             paragraph,
             paragraph_index,
@@ -507,6 +647,8 @@ impl EditorDisplay {
                 cursor: Some(&cursor_pointer),
                 selection: None,
                 track_all_positions: true,
+                search_highlights: &self.search_highlights,
+                folds: &folds,
             },
             &self.theme,
         );
@@ -592,6 +734,52 @@ impl EditorDisplay {
         true
     }
 
+    /// Paint the given search matches, marking the one at index `current`
+    pub fn set_search_matches(&mut self, matches: &[SearchMatch], current: Option<usize>) {
+        let highlights = self.editor.search_highlights(matches, current);
+        if highlights != self.search_highlights {
+            self.search_highlights = highlights;
+            self.layout_dirty = true;
+        }
+    }
+
+    /// Stop painting search matches
+    pub fn clear_search_matches(&mut self) {
+        if !self.search_highlights.is_empty() {
+            self.search_highlights.clear();
+            self.layout_dirty = true;
+        }
+    }
+
+    /// Replace a single search match with undo support
+    pub fn replace_match(
+        &mut self,
+        query: &SearchQuery,
+        search_match: &SearchMatch,
+        replacement: &str,
+    ) -> bool {
+        let undo = self.begin_edit(UndoEditKind::Other);
+        let result = self.editor.replace_match(query, search_match, replacement);
+        if result {
+            self.force_full_relayout();
+            self.clear_render_cache();
+            self.commit_edit(UndoEditKind::Other, undo);
+        }
+        result
+    }
+
+    /// Replace every search match as a single undo step
+    pub fn replace_all(&mut self, query: &SearchQuery, replacement: &str) -> usize {
+        let undo = self.begin_edit(UndoEditKind::Other);
+        let count = self.editor.replace_all(query, replacement);
+        if count > 0 {
+            self.force_full_relayout();
+            self.clear_render_cache();
+            self.commit_edit(UndoEditKind::Other, undo);
+        }
+        count
+    }
+
     /// Set reveal codes mode and clear cache
     /// This overrides the Deref implementation to ensure cache is cleared
     pub fn set_reveal_codes(&mut self, enabled: bool) {
@@ -618,8 +806,12 @@ impl EditorDisplay {
         // Check if selection has changed
         let selection_changed = self.last_selection != selection;
 
+        // The cursor may have landed in folded content, as by a search
+        let unfolded = self.editor.unfold_at_cursor();
+
         // Check if we can reuse cached layout
         let needs_rerender = self.layout_dirty
+            || unfolded
             || self.layout.is_none()
             || self.wrap_width != wrap_width
             || self.left_padding != left_padding
@@ -654,6 +846,7 @@ impl EditorDisplay {
         selection: Option<(CursorPointer, CursorPointer)>,
     ) {
         // Force re-render
+        self.editor.unfold_at_cursor();
         self.render_document_internal(wrap_width, left_padding, selection.clone(), true);
         self.wrap_width = wrap_width;
         self.left_padding = left_padding;
@@ -669,13 +862,9 @@ impl EditorDisplay {
         selection: Option<(CursorPointer, CursorPointer)>,
         track_all_positions: bool,
     ) {
-        // Keep the editor's read-only-block (table) layout width in sync with
-        // the width we're about to render at, so the navigable per-line table
-        // segments match what gets drawn. Rebuilds segments only if it changed.
-        self.editor.set_layout_width(wrap_width.max(1));
-
         // Use direct rendering - no document cloning needed!
         let cursor_pointer = self.editor.cursor_pointer();
+        let folds = self.editor.fold_refs();
 
         // Get reveal tags for reveal codes mode
         let reveal_tags = if self.editor.reveal_codes() {
@@ -693,6 +882,8 @@ impl EditorDisplay {
                 cursor: Some(&cursor_pointer),
                 selection: selection.as_ref().map(|(start, end)| (start, end)),
                 track_all_positions,
+                search_highlights: &self.search_highlights,
+                folds: &folds,
             },
             &self.theme,
         );
@@ -732,6 +923,7 @@ impl EditorDisplay {
         }
 
         // Use layout.cursor as the source of truth for current position
+        let start = self.editor.cursor_pointer();
         let Some(current) = self.cursor_visual() else {
             // Fallback to logical cursor movement when visual position isn't available
             if delta < 0 {
@@ -739,6 +931,7 @@ impl EditorDisplay {
             } else if delta > 0 {
                 self.editor.move_down();
             }
+            self.step_past_folds(&start, delta > 0);
             return;
         };
 
@@ -794,6 +987,7 @@ impl EditorDisplay {
             }
             self.preferred_column = None;
         }
+        self.step_past_folds(&start, delta > 0);
 
         // Update the cached cursor visual position after movement
         self.update_cursor_visual_position();
@@ -883,7 +1077,8 @@ impl EditorDisplay {
 
     /// Move cursor left by one character
     pub fn move_left(&mut self) -> bool {
-        let result = self.editor.move_left();
+        let start = self.editor.cursor_pointer();
+        let result = self.editor.move_left() && self.step_past_folds(&start, false);
         if result {
             self.update_cursor_visual_position();
         }
@@ -892,7 +1087,8 @@ impl EditorDisplay {
 
     /// Move cursor right by one character
     pub fn move_right(&mut self) -> bool {
-        let result = self.editor.move_right();
+        let start = self.editor.cursor_pointer();
+        let result = self.editor.move_right() && self.step_past_folds(&start, true);
         if result {
             self.update_cursor_visual_position();
         }
@@ -901,7 +1097,8 @@ impl EditorDisplay {
 
     /// Move cursor left by one word
     pub fn move_word_left(&mut self) -> bool {
-        let result = self.editor.move_word_left();
+        let start = self.editor.cursor_pointer();
+        let result = self.editor.move_word_left() && self.step_past_folds(&start, false);
         if result {
             self.update_cursor_visual_position();
         }
@@ -910,13 +1107,27 @@ impl EditorDisplay {
 
     /// Move cursor right by one word
     pub fn move_word_right(&mut self) -> bool {
-        let result = self.editor.move_word_right();
+        let start = self.editor.cursor_pointer();
+        let result = self.editor.move_word_right() && self.step_past_folds(&start, true);
         if result {
             self.update_cursor_visual_position();
         }
         result
     }
 
+    /// After moving the cursor from `start` into folded content, carry it on
+    /// past that content, or back to `start` if there is nothing beyond it.
+    /// Returns whether the cursor left `start`.
+    fn step_past_folds(&mut self, start: &CursorPointer, forward: bool) -> bool {
+        if self.editor.is_folded_away(&self.editor.cursor_pointer())
+            && !self.editor.skip_folded_content(forward)
+        {
+            self.editor.move_to_pointer(start);
+            return false;
+        }
+        true
+    }
+
     /// Find the closest pointer on a given line to a target column
     /// Uses content_column (without left padding) for comparison
     fn closest_pointer_on_line(&mut self, line: usize, column: u16) -> Option<CursorDisplay> {
@@ -1294,6 +1505,10 @@ impl EditorDisplay {
     /// same kind at the cursor position left by the previous edit, in which
     /// case the run's existing snapshot already covers it.
     fn begin_edit(&mut self, kind: UndoEditKind) -> Option<UndoSnapshot> {
+        self.fold_base = self
+            .editor
+            .has_folds()
+            .then(|| self.editor.document().clone());
         let coalesce = kind != UndoEditKind::Other
             && self.last_edit_kind == Some(kind)
             && self.last_edit_cursor.as_ref() == Some(&self.editor.cursor_pointer())
@@ -1315,10 +1530,18 @@ impl EditorDisplay {
             self.undo_stack.push(snapshot);
         }
         self.redo_stack.clear();
+        self.edit_count += 1;
+        if let Some(previous) = self.fold_base.take() {
+            self.carry_folds_over(&previous);
+        }
         self.last_edit_kind = Some(kind);
         self.last_edit_cursor = Some(self.editor.cursor_pointer());
     }
 
+    pub fn edit_count(&self) -> usize {
+        self.edit_count
+    }
+
     pub fn can_undo(&self) -> bool {
         !self.undo_stack.is_empty()
     }
@@ -1355,8 +1578,16 @@ impl EditorDisplay {
     }
 
     fn restore_snapshot(&mut self, snapshot: UndoSnapshot) {
+        self.edit_count += 1;
+        let previous = self
+            .editor
+            .has_folds()
+            .then(|| self.editor.document().clone());
         self.editor
             .restore_document(snapshot.document, &snapshot.cursor);
+        if let Some(previous) = previous {
+            self.carry_folds_over(&previous);
+        }
         self.last_edit_kind = None;
         self.last_edit_cursor = None;
         self.force_full_relayout();
@@ -1421,14 +1652,17 @@ impl EditorDisplay {
         match newline_count {
             0 => false,
             1 => self.editor.insert_char('\n'),
+            // A table cell cannot be split into paragraphs; keep the text
+            // within the cell instead.
+            _ if self.editor.cursor_in_table() => self.editor.insert_char('\n'),
             _ => self.editor.insert_paragraph_break(),
         }
     }
 
     /// Insert a document fragment (as produced by
     /// [`DocumentEditor::selection_fragment`]) at the cursor as a single
-    /// undoable edit, restoring its formatting: inline styles always, and
-    /// paragraph types where a pasted paragraph becomes a root-level
+    /// undoable edit, restoring its formatting: inline styles and links
+    /// always, and paragraph types where a pasted paragraph becomes a root-level
     /// paragraph of its own. The first block merges into the paragraph at
     /// the cursor (keeping that paragraph's type, unless it was empty), and
     /// inside lists or checklists new blocks become sibling entries.
@@ -1447,32 +1681,49 @@ impl EditorDisplay {
         // Style runs as global character offsets, which restyling and
         // paragraph type changes below cannot invalidate.
         let mut styled_runs: Vec<(usize, usize, Vec<InlineStyle>)> = Vec::new();
+        // Linked runs likewise, with their text and target.
+        let mut linked_runs: Vec<(usize, usize, String, String)> = Vec::new();
 
         if self.editor.current_paragraph_is_empty() {
             typed_blocks.push((self.editor.cursor_pointer(), blocks[0].paragraph_type));
         }
         for (index, block) in blocks.iter().enumerate() {
             if index > 0 {
-                if !self.editor.insert_paragraph_break() {
-                    break;
+                if self.editor.cursor_in_table() {
+                    // A table cell cannot be split into paragraphs; put
+                    // each further block on a line of its own instead.
+                    if self.editor.insert_char('\n') {
+                        inserted = true;
+                    }
+                } else {
+                    if !self.editor.insert_paragraph_break() {
+                        break;
+                    }
+                    inserted = true;
+                    typed_blocks.push((self.editor.cursor_pointer(), block.paragraph_type));
                 }
-                inserted = true;
-                typed_blocks.push((self.editor.cursor_pointer(), block.paragraph_type));
             }
             for run in &block.runs {
                 let start = self.editor.cursor_global_char_offset();
+                let mut text = String::new();
                 for ch in run.text.chars() {
                     if ch.is_control() && ch != '\t' && ch != '\n' {
                         continue;
                     }
                     if self.editor.insert_char(ch) {
                         inserted = true;
+                        text.push(ch);
                     }
                 }
                 let end = self.editor.cursor_global_char_offset();
                 if end > start && !run.styles.is_empty() {
                     styled_runs.push((start, end, run.styles.clone()));
                 }
+                if end > start
+                    && let Some(target) = &run.link_target
+                {
+                    linked_runs.push((start, end, text, target.clone()));
+                }
             }
         }
         let final_offset = self.editor.cursor_global_char_offset();
@@ -1500,6 +1751,19 @@ impl EditorDisplay {
             self.editor.set_paragraph_type(*paragraph_type);
         }
 
+        // Links first: setting one replaces the text it covers, styles and
+        // all.
+        for (start, end, text, target) in &linked_runs {
+            let (Some(run_start), Some(run_end)) = (
+                self.editor.pointer_at_global_char_offset(*start),
+                self.editor.pointer_at_global_char_offset_end(*end),
+            ) else {
+                continue;
+            };
+            self.editor
+                .set_link(&(run_start, run_end), text, Some(target.as_str()));
+        }
+
         for (start, end, styles) in &styled_runs {
             for style in styles {
                 let (Some(run_start), Some(run_end)) = (
@@ -1737,6 +2001,45 @@ impl EditorDisplay {
         result
     }
 
+    /// Move the current section past its sibling section as a single undo
+    /// step
+    pub fn move_current_section(&mut self, up: bool) -> bool {
+        let undo = self.begin_edit(UndoEditKind::Other);
+        let result = self.editor.move_current_section(up);
+        if result {
+            // Whole runs of paragraphs trade places
+            self.force_full_relayout();
+            self.clear_render_cache();
+            self.commit_edit(UndoEditKind::Other, undo);
+        }
+        result
+    }
+
+    /// Remove the top-level paragraphs `range` as a single undo step
+    pub fn remove_root_paragraphs(&mut self, range: Range<usize>) -> bool {
+        let undo = self.begin_edit(UndoEditKind::Other);
+        let result = self.editor.remove_root_paragraphs(range);
+        if result {
+            self.force_full_relayout();
+            self.clear_render_cache();
+            self.commit_edit(UndoEditKind::Other, undo);
+        }
+        result
+    }
+
+    /// Promote or demote the current section's headings as a single undo
+    /// step
+    pub fn shift_current_section(&mut self, delta: isize) -> bool {
+        let undo = self.begin_edit(UndoEditKind::Other);
+        let result = self.editor.shift_current_section(delta);
+        if result {
+            self.force_full_relayout();
+            self.clear_render_cache();
+            self.commit_edit(UndoEditKind::Other, undo);
+        }
+        result
+    }
+
     fn apply_selection_paragraph_operation<F>(
         &mut self,
         selection: &(CursorPointer, CursorPointer),
@@ -1849,6 +2152,75 @@ impl EditorDisplay {
         result
     }
 
+    /// Move to the next table cell. In the table's last cell a new row is
+    /// appended first, so Tab keeps growing the table as you type.
+    pub fn next_table_cell(&mut self) -> bool {
+        if self.editor.move_to_next_table_cell() {
+            self.update_cursor_visual_position();
+            return true;
+        }
+        if !self.editor.cursor_in_last_table_cell() {
+            return false;
+        }
+        let Some((row, _)) = self.editor.table_cell_position() else {
+            return false;
+        };
+        if !self.insert_table_row(true) {
+            return false;
+        }
+        let moved = self.editor.move_to_table_cell(row + 1, 0);
+        self.update_cursor_visual_position();
+        moved
+    }
+
+    /// Move to the previous table cell.
+    pub fn previous_table_cell(&mut self) -> bool {
+        let result = self.editor.move_to_previous_table_cell();
+        if result {
+            self.update_cursor_visual_position();
+        }
+        result
+    }
+
+    /// Insert a table row above or below the cursor with layout update
+    pub fn insert_table_row(&mut self, below: bool) -> bool {
+        self.apply_table_edit(|editor| editor.insert_table_row(below))
+    }
+
+    /// Insert a table column left or right of the cursor with layout update
+    pub fn insert_table_column(&mut self, right: bool) -> bool {
+        self.apply_table_edit(|editor| editor.insert_table_column(right))
+    }
+
+    /// Delete the cursor's table row with layout update
+    pub fn delete_table_row(&mut self) -> bool {
+        self.apply_table_edit(|editor| editor.delete_table_row())
+    }
+
+    /// Delete the cursor's table column with layout update
+    pub fn delete_table_column(&mut self) -> bool {
+        self.apply_table_edit(|editor| editor.delete_table_column())
+    }
+
+    fn apply_table_edit<F>(&mut self, edit: F) -> bool
+    where
+        F: FnOnce(&mut DocumentEditor) -> bool,
+    {
+        let undo = self.begin_edit(UndoEditKind::Other);
+        let paragraph_index = self.editor.cursor_pointer().paragraph_path.root_index();
+
+        let result = edit(&mut self.editor);
+        if result {
+            // Reshaping a table only affects the table's own root paragraph
+            if let Some(para_idx) = paragraph_index {
+                self.mark_paragraph_modified(para_idx);
+            }
+            self.clear_render_cache();
+            self.commit_edit(UndoEditKind::Other, undo);
+        }
+        result
+    }
+
     /// Set paragraph type with layout update
     pub fn set_paragraph_type(&mut self, target: tdoc::ParagraphType) -> bool {
         let undo = self.begin_edit(UndoEditKind::Other);
@@ -1975,6 +2347,36 @@ impl EditorDisplay {
         result
     }
 
+    /// Turn Markdown just typed at the cursor into formatting, if it asks
+    /// for any. This is an edit of its own, so undo brings the typed
+    /// characters back.
+    pub fn autoformat(&mut self) -> bool {
+        match self.editor.markdown_autoformat() {
+            Some(autoformat) => self.commit_autoformat(autoformat),
+            None => false,
+        }
+    }
+
+    /// Turn a paragraph holding only a code fence into a code block, as
+    /// Enter at its end asks for.
+    pub fn autoformat_code_fence(&mut self) -> bool {
+        match self.editor.code_fence_autoformat() {
+            Some(autoformat) => self.commit_autoformat(autoformat),
+            None => false,
+        }
+    }
+
+    fn commit_autoformat(&mut self, autoformat: Autoformat) -> bool {
+        let undo = self.begin_edit(UndoEditKind::Other);
+        if !self.editor.apply_autoformat(autoformat) {
+            return false;
+        }
+        self.force_full_relayout();
+        self.clear_render_cache();
+        self.commit_edit(UndoEditKind::Other, undo);
+        true
+    }
+
     /// Create, retarget, or remove a hyperlink over `range`.
     ///
     /// Shadows the `Deref` access to [`DocumentEditor::set_link`] so the edit
@@ -2022,6 +2424,7 @@ struct PasteBlock {
 struct PasteRun {
     text: String,
     styles: Vec<InlineStyle>,
+    link_target: Option<String>,
 }
 
 fn flatten_fragment(fragment: &[Paragraph]) -> Vec<PasteBlock> {
@@ -2045,7 +2448,7 @@ fn flatten_paragraph(
         | Paragraph::Header3 { .. }
         | Paragraph::CodeBlock { .. } => {
             let mut runs = Vec::new();
-            flatten_spans(paragraph.content(), &[], &mut runs);
+            flatten_spans(paragraph.content(), &[], None, &mut runs);
             blocks.push(PasteBlock {
                 paragraph_type,
                 runs,
@@ -2072,16 +2475,27 @@ fn flatten_paragraph(
         Paragraph::Checklist { items } => {
             flatten_checklist_items(items, blocks);
         }
-        // Tables are read-only; they carry no editable runs to flatten for
-        // copy/paste, so they contribute no paste blocks for now.
-        Paragraph::Table { .. } => {}
+        // Copied table content is pasted as one text paragraph per
+        // non-empty cell; the grid itself is not reconstructed.
+        Paragraph::Table { rows } => {
+            for cell in rows.iter().flat_map(|row| &row.cells) {
+                let mut runs = Vec::new();
+                flatten_spans(&cell.content, &[], None, &mut runs);
+                if runs.iter().any(|run| !run.text.is_empty()) {
+                    blocks.push(PasteBlock {
+                        paragraph_type: ParagraphType::Text,
+                        runs,
+                    });
+                }
+            }
+        }
     }
 }
 
 fn flatten_checklist_items(items: &[ChecklistItem], blocks: &mut Vec<PasteBlock>) {
     for item in items {
         let mut runs = Vec::new();
-        flatten_spans(&item.content, &[], &mut runs);
+        flatten_spans(&item.content, &[], None, &mut runs);
         blocks.push(PasteBlock {
             paragraph_type: ParagraphType::Checklist,
             runs,
@@ -2090,11 +2504,19 @@ fn flatten_checklist_items(items: &[ChecklistItem], blocks: &mut Vec<PasteBlock>
     }
 }
 
-fn flatten_spans(spans: &[Span], inherited: &[InlineStyle], runs: &mut Vec<PasteRun>) {
+fn flatten_spans(
+    spans: &[Span],
+    inherited: &[InlineStyle],
+    link_target: Option<&str>,
+    runs: &mut Vec<PasteRun>,
+) {
     for span in spans {
         let mut styles = inherited.to_vec();
-        // Links are skipped: re-applying the style could not carry the link
-        // target over, so linked text is pasted with its remaining styles.
+        // Links are not a style to re-apply: their runs carry the target.
+        let link_target = match span.style {
+            InlineStyle::Link => span.link_target.as_deref().or(link_target),
+            _ => link_target,
+        };
         if span.style != InlineStyle::None
             && span.style != InlineStyle::Link
             && !styles.contains(&span.style)
@@ -2105,9 +2527,10 @@ fn flatten_spans(spans: &[Span], inherited: &[InlineStyle], runs: &mut Vec<Paste
             runs.push(PasteRun {
                 text: span.text.clone(),
                 styles: styles.clone(),
+                link_target: link_target.map(str::to_string),
             });
         }
-        flatten_spans(&span.children, &styles, runs);
+        flatten_spans(&span.children, &styles, link_target, runs);
     }
 }
 
@@ -4202,6 +4625,28 @@ mod tests {
         assert_eq!(styled_texts(&entries[2][0])[0].0, "Last");
     }
 
+    #[test]
+    fn insert_fragment_keeps_link_targets() {
+        let doc = ftml! { p { "See " } };
+        let mut display = EditorDisplay::new(DocumentEditor::new(doc));
+        display.render_document_with_positions(80, 0, None);
+        assert!(display.move_to_pointer(&pointer_for_path(ParagraphPath::new_root(0), 4)));
+
+        let mut link = Span::new_styled(InlineStyle::Link);
+        link.text = "the docs".to_string();
+        link.link_target = Some("https://example.com".to_string());
+        let fragment = vec![Paragraph::new_text().with_content(vec![link])];
+        assert!(display.insert_fragment(&fragment));
+
+        let content = display.document().paragraphs[0].content();
+        let link = content
+            .iter()
+            .find(|span| span.style == InlineStyle::Link)
+            .expect("the pasted link");
+        assert_eq!(link.text, "the docs");
+        assert_eq!(link.link_target.as_deref(), Some("https://example.com"));
+    }
+
     fn document_with_table() -> Document {
         use tdoc::{TableCell, TableRow};
         let table = Paragraph::new_table().with_rows(vec![
@@ -4231,7 +4676,7 @@ mod tests {
             display.cursor_pointer().paragraph_path.root_index(),
             Some(0)
         );
-        assert!(!display.cursor_in_readonly());
+        assert!(!display.cursor_in_table());
 
         // Pressing down should land the cursor inside the table (root index 1).
         let mut entered_table = false;
@@ -4248,8 +4693,8 @@ mod tests {
             "cursor should enter the table when moving down"
         );
         assert!(
-            display.cursor_in_readonly(),
-            "the cursor should report being in a read-only block while inside the table"
+            display.cursor_in_table(),
+            "the cursor should report being inside the table"
         );
 
         // Continuing down eventually exits past the table to the trailing paragraph.
@@ -4266,40 +4711,205 @@ mod tests {
             exited_table,
             "cursor should move past the table to the next paragraph"
         );
-        assert!(!display.cursor_in_readonly());
+        assert!(!display.cursor_in_table());
+    }
+
+    fn cell_path(row: usize, column: usize) -> ParagraphPath {
+        let mut path = ParagraphPath::new_root(1);
+        path.push_table_cell(row, column);
+        path
+    }
+
+    fn cell_texts(display: &EditorDisplay) -> Vec<Vec<String>> {
+        display.document().paragraphs[1]
+            .rows()
+            .iter()
+            .map(|row| {
+                row.cells
+                    .iter()
+                    .map(|cell| cell.content.iter().map(|span| span.text.as_str()).collect())
+                    .collect()
+            })
+            .collect()
     }
 
     #[test]
-    fn table_content_cannot_be_edited() {
+    fn table_cell_text_can_be_edited() {
         let mut display = EditorDisplay::new(DocumentEditor::new(document_with_table()));
         display.render_document_with_positions(60, 0, None);
+        assert!(display.move_to_pointer(&pointer_for_path(cell_path(1, 0), 5)));
 
-        // Move the cursor into the table.
-        let mut tries = 0;
-        while display.cursor_pointer().paragraph_path.root_index() != Some(1) && tries < 5 {
-            display.move_cursor_vertical(1);
-            display.render_document_with_positions(60, 0, None);
-            tries += 1;
-        }
-        assert_eq!(
-            display.cursor_pointer().paragraph_path.root_index(),
-            Some(1)
-        );
+        assert!(display.insert_char('!'));
+        assert!(display.backspace());
+        assert!(display.backspace());
+        assert_eq!(cell_texts(&display)[1][0], "Alic");
 
-        let before = display.document().clone();
+        // The grid itself is not subject to paragraph operations.
+        assert!(!display.insert_paragraph_break());
+        assert!(!display.set_paragraph_type(tdoc::ParagraphType::Header1));
+        assert!(!display.can_change_paragraph_type());
+        assert!(!display.can_indent_more());
+        assert!(!display.can_indent_less());
+        assert_eq!(display.document().paragraphs.len(), 3);
+    }
 
-        // None of the editing operations should mutate the table.
-        assert!(!display.insert_char('x'));
+    #[test]
+    fn editing_stops_at_table_cell_boundaries() {
+        let mut display = EditorDisplay::new(DocumentEditor::new(document_with_table()));
+        display.render_document_with_positions(60, 0, None);
+        let before = cell_texts(&display);
+
+        assert!(display.move_to_pointer(&pointer_for_path(cell_path(1, 1), 0)));
         assert!(!display.backspace());
+        assert!(!display.delete_word_backward());
+        assert!(display.move_to_pointer(&pointer_for_path(cell_path(1, 0), 5)));
         assert!(!display.delete());
-        assert!(!display.insert_paragraph_break());
-        assert!(!display.set_paragraph_type(tdoc::ParagraphType::Header1));
+        assert!(!display.delete_word_forward());
+
+        assert_eq!(cell_texts(&display), before);
+        assert_eq!(display.cursor_pointer().paragraph_path, cell_path(1, 0));
+    }
+
+    #[test]
+    fn typing_into_an_empty_table_cell() {
+        let mut display = EditorDisplay::new(DocumentEditor::new(document_with_table()));
+        display.render_document_with_positions(60, 0, None);
+        assert!(display.move_to_pointer(&pointer_for_path(cell_path(1, 1), 0)));
+        assert!(display.insert_table_column(true));
+        assert_eq!(display.cursor_pointer().paragraph_path, cell_path(1, 2));
+
+        assert!(display.insert_char('o'));
+        assert!(display.insert_char('k'));
+        assert_eq!(cell_texts(&display)[1][2], "ok");
+    }
+
+    #[test]
+    fn inline_style_applies_inside_a_table_cell() {
+        let mut display = EditorDisplay::new(DocumentEditor::new(document_with_table()));
+        display.render_document_with_positions(60, 0, None);
+        let selection = (
+            pointer_for_path(cell_path(1, 1), 0),
+            pointer_for_path(cell_path(1, 1), 3),
+        );
+        assert!(display.apply_inline_style_to_selection(&selection, InlineStyle::Bold));
+
+        let cell = &display.document().paragraphs[1].rows()[1].cells[1];
+        assert_eq!(cell.content[0].style, InlineStyle::Bold);
+        assert_eq!(cell.content[0].text, "Dev");
+        assert_eq!(cell.content[1].style, InlineStyle::None);
+        assert_eq!(cell.content[1].text, "eloper");
+    }
+
+    #[test]
+    fn removing_a_selection_across_cells_keeps_the_grid() {
+        let mut display = EditorDisplay::new(DocumentEditor::new(document_with_table()));
+        display.render_document_with_positions(60, 0, None);
+        let selection = (
+            pointer_for_path(cell_path(0, 1), 2),
+            pointer_for_path(cell_path(1, 1), 3),
+        );
+        assert!(display.remove_selection(&selection));
 
         assert_eq!(
-            display.document().paragraphs,
-            before.paragraphs,
-            "read-only table content must be unchanged after edit attempts"
+            cell_texts(&display),
+            vec![vec!["Name", "Ro"], vec!["", "eloper"]]
         );
+        assert_eq!(display.cursor_pointer().paragraph_path, cell_path(0, 1));
+        assert_eq!(display.cursor_pointer().offset, 2);
+    }
+
+    #[test]
+    fn tab_moves_between_table_cells_and_appends_rows() {
+        let mut display = EditorDisplay::new(DocumentEditor::new(document_with_table()));
+        display.render_document_with_positions(60, 0, None);
+        assert!(display.move_to_pointer(&pointer_for_path(cell_path(0, 0), 0)));
+
+        assert!(display.next_table_cell());
+        assert_eq!(display.cursor_pointer().paragraph_path, cell_path(0, 1));
+        assert_eq!(display.cursor_pointer().offset, 4);
+        assert!(display.next_table_cell());
+        assert_eq!(display.cursor_pointer().paragraph_path, cell_path(1, 0));
+        assert!(display.previous_table_cell());
+        assert_eq!(display.cursor_pointer().paragraph_path, cell_path(0, 1));
+
+        // Tab in the last cell grows the table by a data row.
+        assert!(display.move_to_pointer(&pointer_for_path(cell_path(1, 1), 0)));
+        assert!(display.next_table_cell());
+        assert_eq!(display.cursor_pointer().paragraph_path, cell_path(2, 0));
+        // The cursor is drawn in the new row right away.
+        let shown = display
+            .cursor_visual()
+            .map(|cursor| (cursor.line, cursor.column));
+        display.render_document_with_positions(60, 0, None);
+        assert_eq!(
+            shown,
+            display
+                .cursor_visual()
+                .map(|cursor| (cursor.line, cursor.column))
+        );
+        let rows = display.document().paragraphs[1].rows();
+        assert_eq!(rows.len(), 3);
+        assert!(rows[2].cells.iter().all(|cell| !cell.is_header));
+
+        assert!(display.move_to_pointer(&pointer_for_path(cell_path(0, 0), 0)));
+        assert!(!display.previous_table_cell());
+    }
+
+    #[test]
+    fn table_rows_and_columns_can_be_inserted_and_deleted() {
+        let mut display = EditorDisplay::new(DocumentEditor::new(document_with_table()));
+        display.render_document_with_positions(60, 0, None);
+        assert!(display.move_to_pointer(&pointer_for_path(cell_path(0, 1), 0)));
+
+        assert!(display.insert_table_row(false));
+        assert_eq!(display.cursor_pointer().paragraph_path, cell_path(0, 1));
+        assert!(display.document().paragraphs[1].rows()[0].cells[0].is_header);
+        assert!(display.insert_table_column(false));
+        assert_eq!(
+            cell_texts(&display),
+            vec![
+                vec!["", "", ""],
+                vec!["Name", "", "Role"],
+                vec!["Alice", "", "Developer"],
+            ]
+        );
+
+        assert!(display.delete_table_column());
+        assert!(display.delete_table_row());
+        assert_eq!(
+            cell_texts(&display),
+            vec![vec!["Name", "Role"], vec!["Alice", "Developer"]]
+        );
+
+        // Every reshaping step is its own undo entry.
+        assert!(display.undo());
+        assert_eq!(cell_texts(&display).len(), 3);
+        assert!(display.undo());
+        assert!(display.undo());
+        assert!(display.undo());
+        assert_eq!(
+            cell_texts(&display),
+            vec![vec!["Name", "Role"], vec!["Alice", "Developer"]]
+        );
+    }
+
+    #[test]
+    fn the_last_table_row_and_column_cannot_be_deleted() {
+        use tdoc::{TableCell, TableRow};
+        let table = Paragraph::new_table().with_rows(vec![TableRow::new().with_cells(vec![
+            TableCell::new_data().with_content(vec![Span::new_text("Only")]),
+        ])]);
+        let document = Document::new().with_paragraphs(vec![
+            Paragraph::new_text().with_content(vec![Span::new_text("Before")]),
+            table,
+        ]);
+        let mut display = EditorDisplay::new(DocumentEditor::new(document));
+        display.render_document_with_positions(60, 0, None);
+        assert!(display.move_to_pointer(&pointer_for_path(cell_path(0, 0), 0)));
+
+        assert!(!display.delete_table_row());
+        assert!(!display.delete_table_column());
+        assert!(!display.can_undo());
     }
 
     #[test]
@@ -4317,7 +4927,7 @@ mod tests {
         assert!(display.move_to_pointer(&pointer));
 
         let before = display.document().clone();
-        // Backspace here must not consume the (read-only) table that precedes it.
+        // Backspace here must not consume the table that precedes it.
         display.backspace();
         assert_eq!(
             display.document().paragraphs.len(),
@@ -4389,4 +4999,45 @@ mod tests {
         );
         assert!(display.move_left());
     }
+
+    fn line_text(display: &EditorDisplay, line: usize) -> String {
+        display.get_lines().expect("rendered")[line]
+            .spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn sync_with_takes_over_edits_and_keeps_the_cursor() {
+        let mut source = create_test_display();
+        let mut mirror = create_test_display();
+        // A new display may hold any document, so it is taken over whole.
+        assert!(mirror.sync_with(&mut source));
+        assert!(!mirror.sync_with(&mut source));
+        source.render_document(80, 0, None);
+        mirror.render_document(80, 0, None);
+        mirror.move_cursor_vertical(2);
+        let cursor = mirror.cursor_pointer();
+
+        for ch in "Edited ".chars() {
+            source.insert_char(ch);
+        }
+        assert!(mirror.sync_with(&mut source));
+        assert_eq!(mirror.document(), source.document());
+        assert_eq!(mirror.cursor_pointer(), cursor);
+        // The changed paragraph was laid out again without a full render.
+        assert!(!mirror.layout_dirty);
+        assert!(line_text(&mirror, 0).contains("Edited First line"));
+        assert!(!mirror.sync_with(&mut source));
+
+        // A new paragraph shifts the others, so the layout is rebuilt.
+        source.insert_paragraph_break();
+        assert!(mirror.sync_with(&mut source));
+        assert_eq!(mirror.document(), source.document());
+        assert!(mirror.layout_dirty);
+        mirror.render_document(80, 0, None);
+        source.render_document(80, 0, None);
+        assert_eq!(mirror.get_total_lines(), source.get_total_lines());
+    }
 }
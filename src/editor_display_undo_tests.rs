@@ -1,7 +1,9 @@
 use tdoc::{InlineStyle, ftml};
 
 use super::EditorDisplay;
-use crate::editor::{CursorPointer, DocumentEditor, ParagraphPath, SegmentKind, SpanPath};
+use crate::editor::{
+    CursorPointer, DocumentEditor, ParagraphPath, SearchOptions, SearchQuery, SegmentKind, SpanPath,
+};
 
 fn display_from(doc: tdoc::Document) -> EditorDisplay {
     EditorDisplay::new(DocumentEditor::new(doc))
@@ -178,3 +180,140 @@ fn undo_depth_is_capped() {
     }
     assert_eq!(undo_steps, super::MAX_UNDO_DEPTH);
 }
+
+#[test]
+fn replace_all_is_a_single_undo_step() {
+    let mut display = display_from(ftml! { p { "one fish two fish" } p { "red fish" } });
+    let query = SearchQuery::new("fish", SearchOptions::default()).unwrap();
+    assert_eq!(display.replace_all(&query, "cat"), 3);
+    assert_eq!(paragraph_text(&display, 0), "one cat two cat");
+    assert_eq!(paragraph_text(&display, 1), "red cat");
+
+    assert!(display.undo());
+    assert_eq!(paragraph_text(&display, 0), "one fish two fish");
+    assert_eq!(paragraph_text(&display, 1), "red fish");
+    assert!(!display.can_undo());
+}
+
+#[test]
+fn section_operations_are_single_undo_steps() {
+    let original = ftml! {
+        h2 { "One" }
+        p { "First" }
+        h3 { "Detail" }
+        h2 { "Two" }
+        p { "Second" }
+    };
+    let mut display = display_from(original.clone());
+    assert!(display.move_current_section(false));
+    assert!(display.shift_current_section(-1));
+    assert_eq!(paragraph_text(&display, 2), "One");
+    assert_eq!(
+        display.document().paragraphs[2].paragraph_type(),
+        tdoc::ParagraphType::Header1
+    );
+
+    assert!(display.undo());
+    assert_eq!(
+        display.document().paragraphs[2].paragraph_type(),
+        tdoc::ParagraphType::Header2
+    );
+    assert!(display.undo());
+    assert_eq!(display.document().clone(), original);
+    assert_eq!(display.cursor_pointer(), pointer(0, 0));
+}
+
+#[test]
+fn folds_follow_edits_and_open_when_undo_lands_inside() {
+    let mut display = display_from(ftml! {
+        h2 { "One" }
+        p { "First" }
+        h2 { "Two" }
+        p { "Second" }
+    });
+    display.focus_pointer(&pointer(1, 0));
+    assert!(display.insert_char('x'));
+
+    display.focus_pointer(&pointer(3, 0));
+    assert_eq!(display.toggle_fold(), Some(true));
+    display.focus_pointer(&pointer(0, 0));
+    assert!(display.insert_paragraph_break());
+    assert_eq!(display.fold_keys(), vec!["3"]);
+
+    // Undoing the break keeps the fold where it belongs.
+    assert!(display.undo());
+    assert_eq!(display.fold_keys(), vec!["2"]);
+
+    display.focus_pointer(&pointer(0, 0));
+    assert_eq!(display.toggle_fold(), Some(true));
+    assert_eq!(display.fold_keys(), vec!["2", "0"]);
+    // The typing undone was in the first section.
+    assert!(display.undo());
+    assert_eq!(paragraph_text(&display, 1), "First");
+    assert_eq!(display.fold_keys(), vec!["2"]);
+}
+
+#[test]
+fn autoformat_is_undone_on_its_own() {
+    let mut display = display_from(ftml! { p { "Shopping" } });
+    for ch in ['-', ' '] {
+        assert!(display.insert_char(ch));
+        display.autoformat();
+    }
+    let list = &display.document().paragraphs[0];
+    assert_eq!(list.paragraph_type(), tdoc::ParagraphType::UnorderedList);
+    // Typing on after the autoformat is a step of its own.
+    assert!(display.insert_char('x'));
+    assert!(display.undo());
+    assert!(display.undo());
+    assert_eq!(
+        display.document().paragraphs[0].paragraph_type(),
+        tdoc::ParagraphType::Text
+    );
+    assert_eq!(paragraph_text(&display, 0), "- Shopping");
+    assert!(display.undo());
+    assert_eq!(paragraph_text(&display, 0), "Shopping");
+}
+
+#[test]
+fn closing_inline_markers_style_the_text_between_them() {
+    let mut display = display_from(ftml! { p { "Say " } });
+    display.focus_pointer(&pointer(0, 4));
+    for ch in "**hi** to snake_case_".chars() {
+        assert!(display.insert_char(ch));
+        display.autoformat();
+    }
+    let content = display.document().paragraphs[0].content();
+    assert_eq!(content[1].style, InlineStyle::Bold);
+    assert_eq!(content[1].text, "hi");
+    // Typing goes on unstyled, and an underscore within a word opens nothing.
+    assert_eq!(content[2].style, InlineStyle::None);
+    assert_eq!(content[2].text, " to snake_case_");
+
+    // Undo brings the markers back, then the typing before them.
+    while paragraph_text(&display, 0) != "Say **hi**" {
+        assert!(display.undo());
+    }
+    assert!(
+        display.document().paragraphs[0]
+            .content()
+            .iter()
+            .all(|span| span.style == InlineStyle::None)
+    );
+}
+
+#[test]
+fn enter_on_a_code_fence_makes_a_code_block() {
+    let mut display = display_from(ftml! { p { "``" } });
+    display.focus_pointer(&pointer(0, 2));
+    assert!(display.insert_char('`'));
+    assert!(!display.autoformat());
+    assert!(display.autoformat_code_fence());
+    assert_eq!(
+        display.document().paragraphs[0].paragraph_type(),
+        tdoc::ParagraphType::CodeBlock
+    );
+    assert_eq!(paragraph_text(&display, 0), "");
+    assert!(display.undo());
+    assert_eq!(paragraph_text(&display, 0), "```");
+}
@@ -3784,7 +3784,7 @@ fn indent_unindent_sequences_keep_tree_sound() {
             "seed '{seed_name}' is already unsound: {start_problems:?}"
         );
 
-        let segments = inspect::collect_segments(&seed, false, 80);
+        let segments = inspect::collect_segments(&seed, false);
         for segment in &segments {
             for ops in indent_op_sequences(4) {
                 let mut editor = DocumentEditor::new(seed.clone());
@@ -4055,3 +4055,222 @@ fn ctrl_p_then_unindent_on_empty_item_splits_list_in_place() {
     );
     assert_eq!(editor.cursor_pointer().paragraph_path.root_index(), Some(1));
 }
+
+fn sections_document() -> Document {
+    ftml! {
+        h1 { "Trip" }
+        h2 { "Route" }
+        p { "North along the coast." }
+        h3 { "Stops" }
+        p { "Two nights each." }
+        h2 { "Packing" }
+        p { "Travel light." }
+        h1 { "Budget" }
+    }
+}
+
+#[test]
+fn sections_run_to_the_next_heading_of_the_same_or_a_higher_level() {
+    let mut editor = DocumentEditor::new(sections_document());
+    assert!(editor.move_to_pointer(&pointer_to_root_span(2)));
+    assert_eq!(editor.current_section(), Some(1..5));
+    assert_eq!(editor.section_swap(true), None);
+    assert_eq!(editor.section_swap(false), Some((1..5, 5..7)));
+
+    assert!(editor.move_to_pointer(&pointer_to_root_span(4)));
+    assert_eq!(editor.current_section(), Some(3..5));
+    // The only Heading 3 of its parent section.
+    assert_eq!(editor.section_swap(false), None);
+}
+
+#[test]
+fn moving_a_section_takes_its_subsections_and_the_cursor_along() {
+    let mut editor = DocumentEditor::new(sections_document());
+    let mut pointer = pointer_to_root_span(2);
+    pointer.offset = 6;
+    assert!(editor.move_to_pointer(&pointer));
+
+    assert!(editor.move_current_section(false));
+    let expected = ftml! {
+        h1 { "Trip" }
+        h2 { "Packing" }
+        p { "Travel light." }
+        h2 { "Route" }
+        p { "North along the coast." }
+        h3 { "Stops" }
+        p { "Two nights each." }
+        h1 { "Budget" }
+    };
+    assert_eq!(editor.document().clone(), expected);
+    assert_eq!(editor.cursor_pointer().paragraph_path.root_index(), Some(4));
+    assert_eq!(editor.cursor_pointer().offset, 6);
+
+    // Not past the parent's heading.
+    assert!(!editor.move_current_section(false));
+    assert!(editor.move_current_section(true));
+    assert_eq!(editor.document().clone(), sections_document());
+    assert_eq!(editor.cursor_pointer().paragraph_path.root_index(), Some(2));
+}
+
+#[test]
+fn promoting_a_section_raises_every_heading_in_it() {
+    let mut editor = DocumentEditor::new(sections_document());
+    assert!(editor.move_to_pointer(&pointer_to_root_span(1)));
+    assert!(editor.can_shift_current_section(-1));
+    // Stops is a Heading 3 already.
+    assert!(!editor.can_shift_current_section(1));
+    assert!(!editor.shift_current_section(1));
+
+    assert!(editor.shift_current_section(-1));
+    let types: Vec<ParagraphType> = editor
+        .document()
+        .paragraphs
+        .iter()
+        .map(Paragraph::paragraph_type)
+        .collect();
+    assert_eq!(
+        types,
+        vec![
+            ParagraphType::Header1,
+            ParagraphType::Header1,
+            ParagraphType::Text,
+            ParagraphType::Header2,
+            ParagraphType::Text,
+            ParagraphType::Header2,
+            ParagraphType::Text,
+            ParagraphType::Header1,
+        ]
+    );
+    // Route now heads a section next to Trip, and Packing, still a
+    // Heading 2, belongs to it.
+    assert_eq!(editor.current_section(), Some(1..7));
+}
+
+#[test]
+fn folding_a_section_hides_everything_below_its_heading() {
+    let mut editor = DocumentEditor::new(sections_document());
+    assert!(editor.move_to_pointer(&pointer_to_root_span(4)));
+
+    // The innermost section around the cursor is the Heading 3 one, and
+    // the cursor moves out of it to the heading.
+    assert_eq!(editor.toggle_fold(), Some(true));
+    assert_eq!(
+        editor.fold_refs(),
+        vec![FoldRef {
+            path: ParagraphPath::new_root(3),
+            summary: "1 paragraph".to_string(),
+            hidden_roots: 4..5,
+        }]
+    );
+    assert_eq!(editor.cursor_pointer().paragraph_path.root_index(), Some(3));
+    assert!(editor.is_folded_away(&pointer_to_root_span(4)));
+    assert_eq!(editor.toggle_fold(), Some(false));
+    assert!(!editor.has_folds());
+
+    // Budget has nothing below it to fold.
+    assert!(editor.fold_all_sections());
+    assert_eq!(editor.fold_keys(), vec!["0", "1", "3", "5"]);
+    assert_eq!(editor.fold_refs()[0].summary, "6 paragraphs");
+    assert!(editor.unfold_all());
+    assert!(!editor.unfold_all());
+}
+
+#[test]
+fn moving_into_a_folded_section_skips_past_it() {
+    let mut editor = DocumentEditor::new(sections_document());
+    assert!(editor.move_to_pointer(&pointer_to_root_span(2)));
+    assert_eq!(editor.toggle_fold(), Some(true));
+    assert_eq!(editor.cursor_pointer().paragraph_path.root_index(), Some(1));
+
+    editor.move_to_segment_end();
+    assert!(editor.move_right());
+    assert!(editor.is_folded_away(&editor.cursor_pointer()));
+    assert!(editor.skip_folded_content(true));
+    assert_eq!(editor.cursor_pointer().paragraph_path.root_index(), Some(5));
+    assert_eq!(editor.cursor_pointer().offset, 0);
+
+    assert!(editor.move_left());
+    assert!(editor.skip_folded_content(false));
+    assert_eq!(editor.cursor_pointer().paragraph_path.root_index(), Some(1));
+    assert!(!editor.is_folded_away(&editor.cursor_pointer()));
+}
+
+fn nested_folding_document() -> Document {
+    let mut document = ftml! {
+        ul {
+            li { p { "One" } p { "More on one" } }
+            li { p { "Two" } }
+        }
+        quote {
+            p { "Said" }
+            p { "And more" }
+        }
+    };
+    let item = |text: &str| ChecklistItem::new(false).with_content(vec![Span::new_text(text)]);
+    let checklist = Paragraph::new_checklist().with_checklist_items(vec![
+        item("Pack").with_children(vec![item("Tent"), item("Stove")]),
+    ]);
+    document.paragraphs.insert(1, checklist);
+    document
+}
+
+#[test]
+fn list_entries_checklist_items_and_quotes_fold_to_their_first_line() {
+    let mut editor = DocumentEditor::new(nested_folding_document());
+
+    let mut more = ParagraphPath::new_root(0);
+    more.push_entry(0, 1);
+    let mut pointer = pointer_to_root_span(0);
+    pointer.paragraph_path = more;
+    assert!(editor.move_to_pointer(&pointer));
+    assert_eq!(editor.toggle_fold(), Some(true));
+
+    let mut stove = ParagraphPath::new_root(1);
+    stove.push_checklist_item(vec![0, 1]);
+    pointer.paragraph_path = stove.clone();
+    assert!(editor.move_to_pointer(&pointer));
+    assert_eq!(editor.toggle_fold(), Some(true));
+    assert!(editor.is_folded_away(&pointer));
+
+    assert!(editor.move_to_pointer(&pointer_to_child_span(2, 1)));
+    assert_eq!(editor.toggle_fold(), Some(true));
+    assert_eq!(
+        editor.cursor_pointer().paragraph_path,
+        pointer_to_child_span(2, 0).paragraph_path
+    );
+
+    let summaries: Vec<String> = editor
+        .fold_refs()
+        .into_iter()
+        .map(|fold| fold.summary)
+        .collect();
+    assert_eq!(summaries, vec!["1 paragraph", "2 items", "1 paragraph"]);
+    assert!(editor.is_folded_away(&pointer_to_child_span(2, 1)));
+    assert!(!editor.is_folded_away(&pointer_to_child_span(2, 0)));
+
+    // Folds are remembered as text and restored from it.
+    let keys = editor.fold_keys();
+    assert_eq!(keys, vec!["0/e0.0", "1/i0", "2"]);
+    let mut reopened = DocumentEditor::new(nested_folding_document());
+    reopened.restore_folds(&keys);
+    assert_eq!(reopened.fold_refs(), editor.fold_refs());
+}
+
+#[test]
+fn folds_move_along_with_the_paragraphs_around_them() {
+    let mut editor = DocumentEditor::new(sections_document());
+    assert!(editor.move_to_pointer(&pointer_to_root_span(6)));
+    assert_eq!(editor.toggle_fold(), Some(true));
+    assert!(editor.move_to_pointer(&pointer_to_root_span(2)));
+
+    let previous = editor.document().clone();
+    assert!(editor.remove_root_paragraphs(2..3));
+    editor.remap_folds(&previous);
+    assert_eq!(editor.fold_keys(), vec!["4"]);
+
+    // A fold with nothing left to hide is dropped.
+    let previous = editor.document().clone();
+    assert!(editor.remove_root_paragraphs(5..6));
+    editor.remap_folds(&previous);
+    assert!(!editor.has_folds());
+}
@@ -0,0 +1,204 @@
+//! Noticing when another program changes the file of an open document.
+//!
+//! When a document is read or saved, its file's modification time, size and
+//! a hash of its contents are kept as a [`FileStamp`]. The file is compared
+//! against it while the document is shown, and once more right before
+//! saving, so a newer version written by another editor or a `git checkout`
+//! is never overwritten unnoticed. A changed modification time alone, as
+//! from `touch`, does not count: the contents are compared too.
+//!
+//! The module also holds the buttons and state of the prompt shown when the
+//! document has unsaved changes of its own, a
+//! [`crate::choice_prompt::ChoicePrompt`] offering Reload, Keep Mine, and
+//! Show Differences. Keep Mine has focus, so a hasty Enter loses nothing.
+//! Show Differences opens the differences between the file and the document
+//! within the prompt, scrolled with Up / Down and Page Up / Page Down.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::choice_prompt::Choice;
+use crate::diff::DiffLine;
+
+/// What a file looked like when it was last read or written.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+    hash: u64,
+}
+
+/// How the file at a document's path compares to its [`FileStamp`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiskState {
+    Unchanged,
+    /// Written again with the same contents; the new stamp replaces the
+    /// old one.
+    Touched(FileStamp),
+    /// Holds other contents now.
+    Changed(FileStamp),
+    Removed,
+}
+
+impl FileStamp {
+    /// The stamp of the file at `path` as it is now; `None` if it cannot be
+    /// read, for instance because it does not exist yet.
+    pub fn read(path: &Path) -> Option<Self> {
+        let contents = fs::read(path).ok()?;
+        Self::written(path, &contents)
+    }
+
+    /// The stamp of the file at `path`, which was just written with
+    /// `contents`.
+    pub fn written(path: &Path, contents: &[u8]) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            hash: hash(contents),
+        })
+    }
+
+    /// Compare the file at `path` against the stamp. Only a changed
+    /// modification time or size has the contents read. A file that cannot
+    /// be looked at for other reasons than being gone counts as unchanged.
+    pub fn check(&self, path: &Path) -> DiskState {
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return DiskState::Removed,
+            Err(_) => return DiskState::Unchanged,
+        };
+        if metadata.modified().ok() == self.modified && metadata.len() == self.len {
+            return DiskState::Unchanged;
+        }
+        match Self::read(path) {
+            Some(stamp) if stamp.hash == self.hash && stamp.len == self.len => {
+                DiskState::Touched(stamp)
+            }
+            Some(stamp) => DiskState::Changed(stamp),
+            None => DiskState::Unchanged,
+        }
+    }
+}
+
+fn hash(contents: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExternalChangeChoice {
+    Reload,
+    KeepMine,
+    ShowDifferences,
+}
+
+/// The prompt's buttons, in display order, chosen by their initials.
+pub const CHOICES: [Choice<ExternalChangeChoice>; 3] = [
+    Choice {
+        value: ExternalChangeChoice::Reload,
+        label: "Reload",
+        accelerators: "r",
+    },
+    Choice {
+        value: ExternalChangeChoice::KeepMine,
+        label: "Keep Mine",
+        accelerators: "k",
+    },
+    Choice {
+        value: ExternalChangeChoice::ShowDifferences,
+        label: "Show Differences",
+        accelerators: "s",
+    },
+];
+
+/// What the prompt knows about the change it asks about.
+pub struct ExternalChange {
+    /// The file as it is now.
+    stamp: FileStamp,
+    /// Whether the prompt interrupted saving, which Keep Mine completes.
+    saving: bool,
+    /// The differences between the file and the document, once shown.
+    differences: Option<Vec<DiffLine>>,
+    scroll: usize,
+}
+
+impl ExternalChange {
+    pub fn new(stamp: FileStamp, saving: bool) -> Self {
+        Self {
+            stamp,
+            saving,
+            differences: None,
+            scroll: 0,
+        }
+    }
+
+    pub fn stamp(&self) -> &FileStamp {
+        &self.stamp
+    }
+
+    pub fn saving(&self) -> bool {
+        self.saving
+    }
+
+    pub fn differences(&self) -> Option<&[DiffLine]> {
+        self.differences.as_deref()
+    }
+
+    /// Show `differences`, or hide them again with `None`.
+    pub fn set_differences(&mut self, differences: Option<Vec<DiffLine>>) {
+        self.differences = differences;
+        self.scroll = 0;
+    }
+
+    /// The first difference line in view.
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    /// Scroll the differences by `delta` lines, keeping `visible` lines in
+    /// view.
+    pub fn scroll_by(&mut self, delta: isize, visible: usize) {
+        let count = self.differences.as_ref().map_or(0, Vec::len);
+        let max = count.saturating_sub(visible);
+        self.scroll = self.scroll.saturating_add_signed(delta).min(max);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::TempDir;
+
+    fn temp_file(name: &str, contents: &str) -> (TempDir, std::path::PathBuf) {
+        let dir = TempDir::new(name);
+        let path = dir.join("notes.md");
+        fs::write(&path, contents).expect("write file");
+        (dir, path)
+    }
+
+    #[test]
+    fn stamps_tell_changed_contents_from_a_touch() {
+        let (_dir, path) = temp_file("stamp", "one\n");
+        let stamp = FileStamp::read(&path).expect("stamp");
+        assert_eq!(stamp.check(&path), DiskState::Unchanged);
+
+        // Same contents with another modification time.
+        let touched = FileStamp {
+            modified: None,
+            ..stamp.clone()
+        };
+        assert!(matches!(touched.check(&path), DiskState::Touched(_)));
+
+        fs::write(&path, "one and two\n").unwrap();
+        assert!(matches!(stamp.check(&path), DiskState::Changed(_)));
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(stamp.check(&path), DiskState::Removed);
+    }
+}
@@ -8,7 +8,7 @@
 //! either descends into the selected directory or accepts a file. The
 //! surrounding [`crate::app::App`] decides what accepting a path means and
 //! uses [`FileDialogState::pending_confirm`] to require a second Enter for
-//! destructive accepts (overwriting a file, discarding unsaved changes).
+//! destructive accepts (overwriting a file).
 
 use std::fs;
 use std::path::{Path, PathBuf};
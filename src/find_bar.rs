@@ -0,0 +1,237 @@
+//! Find & replace bar, docked above the status line.
+//!
+//! Ctrl+F opens the bar with a single query field; Ctrl+H adds a replacement
+//! field below it. The bar keeps the query, the replacement, the search
+//! options and the matches of the last search, with the one the cursor is on
+//! marked as current. The surrounding [`crate::app::App`] runs the searches,
+//! moves the cursor between matches and performs the replacements.
+
+use crate::editor::{SearchMatch, SearchOptions};
+use crate::text_field::TextField;
+
+/// Which of the bar's input fields has focus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FindField {
+    Query,
+    Replacement,
+}
+
+pub struct FindBarState {
+    query: TextField,
+    replacement: TextField,
+    focus: FindField,
+    /// Whether the replacement field is shown.
+    replacing: bool,
+    options: SearchOptions,
+    matches: Vec<SearchMatch>,
+    /// Index into `matches` of the match the cursor is on.
+    current: Option<usize>,
+    /// Why the query could not be used, e.g. an invalid regular expression.
+    error: Option<String>,
+}
+
+impl FindBarState {
+    /// A bar searching for `query`. With `replacing`, the replacement field
+    /// is shown as well and gets focus, unless there is no query yet.
+    pub fn new(query: String, replacing: bool) -> Self {
+        let mut bar = Self {
+            query: TextField::new(query),
+            replacement: TextField::default(),
+            focus: FindField::Query,
+            replacing: false,
+            options: SearchOptions::default(),
+            matches: Vec::new(),
+            current: None,
+            error: None,
+        };
+        bar.set_replacing(replacing);
+        bar
+    }
+
+    pub fn query(&self) -> &TextField {
+        &self.query
+    }
+
+    pub fn replacement(&self) -> &TextField {
+        &self.replacement
+    }
+
+    pub fn focus(&self) -> FindField {
+        self.focus
+    }
+
+    pub fn replacing(&self) -> bool {
+        self.replacing
+    }
+
+    /// Show or hide the replacement field, focusing the field that was asked
+    /// for. The query keeps focus while it is empty.
+    pub fn set_replacing(&mut self, replacing: bool) {
+        self.replacing = replacing;
+        self.focus = if replacing && !self.query.text().is_empty() {
+            FindField::Replacement
+        } else {
+            FindField::Query
+        };
+    }
+
+    /// Move focus between the query and replacement fields.
+    pub fn toggle_focus(&mut self) {
+        if self.replacing {
+            self.focus = match self.focus {
+                FindField::Query => FindField::Replacement,
+                FindField::Replacement => FindField::Query,
+            };
+        }
+    }
+
+    pub fn options(&self) -> SearchOptions {
+        self.options
+    }
+
+    pub fn toggle_case_sensitive(&mut self) {
+        self.options.case_sensitive = !self.options.case_sensitive;
+    }
+
+    pub fn toggle_whole_word(&mut self) {
+        self.options.whole_word = !self.options.whole_word;
+    }
+
+    pub fn toggle_regex(&mut self) {
+        self.options.regex = !self.options.regex;
+    }
+
+    pub fn focused_field_mut(&mut self) -> &mut TextField {
+        match self.focus {
+            FindField::Query => &mut self.query,
+            FindField::Replacement => &mut self.replacement,
+        }
+    }
+
+    pub fn matches(&self) -> &[SearchMatch] {
+        &self.matches
+    }
+
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    pub fn current_match(&self) -> Option<&SearchMatch> {
+        self.matches.get(self.current?)
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Store the outcome of a search.
+    pub fn set_results(&mut self, matches: Vec<SearchMatch>, current: Option<usize>) {
+        self.current = current.filter(|&index| index < matches.len());
+        self.matches = matches;
+        self.error = None;
+    }
+
+    /// Record that the query could not be searched for.
+    pub fn set_error(&mut self, error: String) {
+        self.matches.clear();
+        self.current = None;
+        self.error = Some(error);
+    }
+
+    /// Make the next (or, with `backward`, previous) match current, wrapping
+    /// around the ends of the document.
+    pub fn step(&mut self, backward: bool) -> Option<&SearchMatch> {
+        let count = self.matches.len();
+        if count == 0 {
+            return None;
+        }
+        let next = match (self.current, backward) {
+            (None, false) => 0,
+            (None, true) => count - 1,
+            (Some(index), false) => (index + 1) % count,
+            (Some(index), true) => (index + count - 1) % count,
+        };
+        self.current = Some(next);
+        self.matches.get(next)
+    }
+
+    /// Summary shown next to the query, e.g. "3 of 12".
+    pub fn status_text(&self) -> String {
+        if let Some(error) = &self.error {
+            return error.clone();
+        }
+        if self.query.text().is_empty() {
+            return String::new();
+        }
+        match (self.current, self.matches.len()) {
+            (_, 0) => "No matches".to_string(),
+            (Some(index), count) => format!("{} of {}", index + 1, count),
+            (None, 1) => "1 match".to_string(),
+            (None, count) => format!("{count} matches"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::{CursorPointer, SearchMatch};
+
+    fn matches(count: usize) -> Vec<SearchMatch> {
+        (0..count)
+            .map(|offset| {
+                let start = CursorPointer {
+                    offset,
+                    ..CursorPointer::default()
+                };
+                let end = CursorPointer {
+                    offset: offset + 1,
+                    ..CursorPointer::default()
+                };
+                SearchMatch { start, end }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stepping_wraps_around_both_ends() {
+        let mut bar = FindBarState::new("x".to_string(), false);
+        bar.set_results(matches(3), Some(2));
+        assert_eq!(bar.status_text(), "3 of 3");
+
+        bar.step(false);
+        assert_eq!(bar.current(), Some(0));
+        bar.step(true);
+        assert_eq!(bar.current(), Some(2));
+        bar.step(true);
+        assert_eq!(bar.status_text(), "2 of 3");
+    }
+
+    #[test]
+    fn focus_only_moves_to_the_replacement_when_replacing() {
+        let mut bar = FindBarState::new("x".to_string(), false);
+        bar.toggle_focus();
+        assert_eq!(bar.focus(), FindField::Query);
+
+        bar.set_replacing(true);
+        assert_eq!(bar.focus(), FindField::Replacement);
+        bar.toggle_focus();
+        assert_eq!(bar.focus(), FindField::Query);
+    }
+
+    #[test]
+    fn replacing_without_a_query_focuses_the_query() {
+        let bar = FindBarState::new(String::new(), true);
+        assert!(bar.replacing());
+        assert_eq!(bar.focus(), FindField::Query);
+    }
+
+    #[test]
+    fn errors_replace_the_match_summary() {
+        let mut bar = FindBarState::new("(".to_string(), false);
+        bar.set_error("Invalid pattern".to_string());
+        assert_eq!(bar.status_text(), "Invalid pattern");
+        bar.set_results(Vec::new(), None);
+        assert_eq!(bar.status_text(), "No matches");
+    }
+}
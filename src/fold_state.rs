@@ -0,0 +1,78 @@
+//! Remembering folds across sessions.
+//!
+//! With `remember_folds` set in the configuration, the folds of a document
+//! are written to Pure's state directory (`$XDG_STATE_HOME/pure/folds`,
+//! falling back to `~/.local/state/pure/folds`) whenever the document is
+//! saved, and whenever something is folded or unfolded while it has no
+//! unsaved changes — so they always fit the file as it is on disk. Opening
+//! the file again folds the same parts, skipping those that no longer fit.
+//! A document without folds has no file there.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::recovery;
+
+/// The file holding the folds of the document at `path`.
+fn fold_file(path: &Path) -> Option<PathBuf> {
+    let name = recovery::state_file_name(path)?;
+    Some(recovery::state_home()?.join("folds").join(name))
+}
+
+/// The folds remembered for the document at `path`, as written by
+/// [`store`]; none if there are none or they cannot be read.
+pub fn load(path: &Path) -> Vec<String> {
+    fold_file(path).map(|file| read(&file)).unwrap_or_default()
+}
+
+/// Remember `folds` for the document at `path`, or forget its folds if
+/// there are none.
+pub fn store(path: &Path, folds: &[String]) -> io::Result<()> {
+    match fold_file(path) {
+        Some(file) => write(&file, folds),
+        None => Ok(()),
+    }
+}
+
+fn read(file: &Path) -> Vec<String> {
+    fs::read_to_string(file)
+        .map(|text| text.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn write(file: &Path, folds: &[String]) -> io::Result<()> {
+    if folds.is_empty() {
+        return match fs::remove_file(file) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        };
+    }
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut text = folds.join("\n");
+    text.push('\n');
+    fs::write(file, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::TempDir;
+
+    #[test]
+    fn folds_are_written_one_per_line_and_forgotten_when_gone() {
+        let dir = TempDir::new("folds");
+        let file = dir.join("folds").join("%notes.md");
+        assert!(read(&file).is_empty());
+
+        let folds = vec!["0".to_string(), "4/e1.0".to_string()];
+        write(&file, &folds).expect("write folds");
+        assert_eq!(read(&file), folds);
+
+        write(&file, &[]).expect("forget folds");
+        assert!(!file.exists());
+        write(&file, &[]).expect("nothing to forget");
+    }
+}
@@ -0,0 +1,224 @@
+//! Subcommands that run without the editor, for scripts and CI.
+//!
+//! `pure convert <input> <output>` reads a document and writes it in the
+//! format the output's extension names, the way saving would. `pure cat
+//! <file>` prints a document laid out as the editor shows it — wrapped,
+//! with bullets, quote bars and table borders — either with the theme's
+//! colors as ANSI escape sequences or as plain text.
+
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use tdoc::Document;
+
+use crate::app::{DocumentFormat, load_document_as, write_document};
+use crate::render::{DirectCursorTracking, render_document_direct};
+use crate::save::{self, Backup};
+use crate::theme::Theme;
+
+/// Convert the document at `input` — read as `input_format`, if given — to
+/// the format of `output`'s extension. Plain text is hard-wrapped at
+/// `plain_text_wrap` columns, if given.
+pub fn convert(
+    input: &Path,
+    input_format: Option<DocumentFormat>,
+    output: &Path,
+    plain_text_wrap: Option<usize>,
+) -> Result<()> {
+    let Some(format) = DocumentFormat::from_extension(output) else {
+        bail!(
+            "cannot tell the format of {} from its extension; use .ftml, .md, .html, .gmi or .txt",
+            output.display()
+        );
+    };
+    let document = read(input, input_format)?;
+    let contents = write_document(&document, format, plain_text_wrap)?;
+    save::write_atomically(output, &contents, Backup::None)
+        .with_context(|| format!("failed to write {}", output.display()))
+}
+
+/// Write the document at `path` — read as `format`, if given — to `out` as
+/// the editor lays it out, `width` columns wide. With `ansi`, styles are kept
+/// as escape sequences in `theme`'s colors; without, trailing spaces are
+/// trimmed.
+pub fn cat(
+    path: &Path,
+    format: Option<DocumentFormat>,
+    width: usize,
+    ansi: bool,
+    theme: &Theme,
+    out: &mut impl Write,
+) -> Result<()> {
+    let document = read(path, format)?;
+    let lines = render_lines(&document, width, theme);
+    // Headings keep space around them in the editor; the output starts and
+    // ends with text.
+    let is_blank = |line: &Line| line.spans.iter().all(|span| span.content.trim().is_empty());
+    let first = lines
+        .iter()
+        .position(|line| !is_blank(line))
+        .unwrap_or(lines.len());
+    let last = lines
+        .iter()
+        .rposition(|line| !is_blank(line))
+        .map_or(first, |last| last + 1);
+    for line in &lines[first..last] {
+        if ansi {
+            writeln!(out, "{}", ansi_line(line))?;
+        } else {
+            let text: String = line
+                .spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect();
+            writeln!(out, "{}", text.trim_end())?;
+        }
+    }
+    Ok(())
+}
+
+/// Read a document that has to exist and parse.
+fn read(path: &Path, format: Option<DocumentFormat>) -> Result<Document> {
+    if !path.exists() {
+        bail!("{} does not exist", path.display());
+    }
+    let loaded = load_document_as(&path.to_path_buf(), format)?;
+    if let Some(failure) = loaded.parse_failure {
+        bail!("{}: {}", path.display(), failure.error);
+    }
+    Ok(loaded.document)
+}
+
+fn render_lines(document: &Document, width: usize, theme: &Theme) -> Vec<Line<'static>> {
+    let tracking = DirectCursorTracking {
+        cursor: None,
+        selection: None,
+        track_all_positions: false,
+        search_highlights: &[],
+        folds: &[],
+    };
+    render_document_direct(document, width, 0, &[], tracking, theme).lines
+}
+
+/// A rendered line with its styles as SGR escape sequences.
+fn ansi_line(line: &Line) -> String {
+    let mut out = String::new();
+    for span in &line.spans {
+        let codes = sgr_codes(line.style.patch(span.style));
+        if codes.is_empty() {
+            out.push_str(&span.content);
+        } else {
+            out.push_str(&format!("\x1b[{}m{}\x1b[0m", codes.join(";"), span.content));
+        }
+    }
+    out
+}
+
+fn sgr_codes(style: Style) -> Vec<String> {
+    const MODIFIERS: [(Modifier, &str); 6] = [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::CROSSED_OUT, "9"),
+    ];
+    let mut codes: Vec<String> = MODIFIERS
+        .iter()
+        .filter(|(modifier, _)| style.add_modifier.contains(*modifier))
+        .map(|(_, code)| code.to_string())
+        .collect();
+    codes.extend(style.fg.and_then(|color| color_code(color, false)));
+    codes.extend(style.bg.and_then(|color| color_code(color, true)));
+    codes
+}
+
+/// The SGR parameters selecting `color` as foreground or background;
+/// `None` for the terminal's default.
+fn color_code(color: Color, background: bool) -> Option<String> {
+    let offset = if background { 10 } else { 0 };
+    let basic = match color {
+        Color::Reset => return None,
+        Color::Black => 30,
+        Color::Red => 31,
+        Color::Green => 32,
+        Color::Yellow => 33,
+        Color::Blue => 34,
+        Color::Magenta => 35,
+        Color::Cyan => 36,
+        Color::Gray => 37,
+        Color::DarkGray => 90,
+        Color::LightRed => 91,
+        Color::LightGreen => 92,
+        Color::LightYellow => 93,
+        Color::LightBlue => 94,
+        Color::LightMagenta => 95,
+        Color::LightCyan => 96,
+        Color::White => 97,
+        Color::Rgb(r, g, b) => return Some(format!("{};2;{r};{g};{b}", 38 + offset)),
+        Color::Indexed(index) => return Some(format!("{};5;{index}", 38 + offset)),
+    };
+    Some((basic + offset).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::TempDir;
+
+    fn cat_to_string(path: &Path, width: usize, ansi: bool) -> String {
+        let mut out = Vec::new();
+        cat(path, None, width, ansi, &Theme::default(), &mut out).expect("cat");
+        String::from_utf8(out).expect("utf-8")
+    }
+
+    #[test]
+    fn convert_writes_the_format_of_the_output_extension() {
+        let dir = TempDir::new("convert");
+        let input = dir.join("in.md");
+        std::fs::write(&input, "# Title\n\nSome **bold** text.\n").unwrap();
+
+        convert(&input, None, &dir.join("out.ftml"), None).expect("convert to FTML");
+        let ftml = std::fs::read_to_string(dir.join("out.ftml")).unwrap();
+        assert!(ftml.contains("<h1>Title</h1>"), "got: {ftml}");
+        assert!(ftml.contains("<b>bold</b>"), "got: {ftml}");
+
+        let err = convert(&input, None, &dir.join("out.xyz"), None).unwrap_err();
+        assert!(err.to_string().contains("cannot tell the format"));
+        let err = convert(&dir.join("missing.md"), None, &dir.join("out.ftml"), None).unwrap_err();
+        assert!(err.to_string().contains("does not exist"));
+    }
+
+    #[test]
+    fn convert_refuses_files_that_fail_to_parse() {
+        let dir = TempDir::new("convert-broken");
+        let input = dir.join("broken.ftml");
+        std::fs::write(&input, "<p>Fine</p>\nstray text\n").unwrap();
+        let output = dir.join("out.md");
+
+        let err = convert(&input, None, &output, None).unwrap_err();
+        assert!(err.to_string().contains("line 2"), "got: {err}");
+        assert!(!output.exists());
+    }
+
+    #[test]
+    fn cat_wraps_plain_text_and_styles_ansi() {
+        let dir = TempDir::new("cat");
+        let input = dir.join("notes.md");
+        std::fs::write(
+            &input,
+            "# Notes\n\nSome **bold** words that wrap.\n\n- One\n- Two\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            cat_to_string(&input, 16, false),
+            "     Notes\n\n\n\nSome bold words\nthat wrap.\n\n• One\n\n• Two\n"
+        );
+        let ansi = cat_to_string(&input, 16, true);
+        assert!(ansi.contains("\x1b[1mbold\x1b[0m"), "got: {ansi:?}");
+    }
+}
@@ -0,0 +1,1008 @@
+//! Key bindings: key chords, the commands they trigger, and the keymaps
+//! that tie the two together.
+//!
+//! A [`KeyChord`] is a key together with its modifiers, written as in
+//! `ctrl+shift+s` or `alt+f9`; a binding may also be a sequence of chords
+//! such as `ctrl+x ctrl+s`. A [`Command`] is anything a key can trigger: a
+//! menu bar action ([`AppAction`]), a formatting action from the context menu
+//! ([`MenuAction`]) or an editing command such as a cursor motion
+//! ([`EditCommand`]). Commands have textual names, so the configuration file
+//! can pick one of the built-in [`KeymapPreset`]s and remap keys on top of it.
+
+use std::fmt;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use tdoc::{InlineStyle, ParagraphType};
+
+use crate::app::MenuAction;
+use crate::menu_bar::AppAction;
+
+/// A key press with its modifiers, normalized so that equal chords compare
+/// equal however the terminal reports them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// The chord of a key event. Letters are stored in lowercase, with an
+    /// uppercase letter turning into Shift; other characters already tell
+    /// whether Shift was held, so it is dropped for them, as are modifiers
+    /// other than Ctrl, Alt and Shift.
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers =
+            modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        let code = match code {
+            KeyCode::Char(ch) if ch.is_uppercase() => {
+                modifiers |= KeyModifiers::SHIFT;
+                KeyCode::Char(ch.to_lowercase().next().unwrap_or(ch))
+            }
+            KeyCode::Char(ch) if !ch.is_alphabetic() => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                code
+            }
+            KeyCode::BackTab => {
+                modifiers |= KeyModifiers::SHIFT;
+                KeyCode::Tab
+            }
+            code => code,
+        };
+        Self { code, modifiers }
+    }
+
+    /// Parse a chord such as `ctrl+s`, `alt+shift+left` or `f9`. Modifier
+    /// and key names are case-insensitive; `+` separates them, and a chord
+    /// ending in `+` binds the plus key itself.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let (prefix, key) = match text.strip_suffix("++") {
+            Some(prefix) => (prefix, "+"),
+            None if text == "+" => ("", "+"),
+            None => match text.rsplit_once('+') {
+                Some((prefix, key)) => (prefix, key),
+                None => ("", text),
+            },
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for name in prefix.split('+').filter(|name| !name.is_empty()) {
+            modifiers |= match name.trim().to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" | "option" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier `{name}` in `{text}`")),
+            };
+        }
+
+        let code =
+            parse_key(key.trim()).ok_or_else(|| format!("unknown key `{key}` in `{text}`"))?;
+        Ok(Self::new(code, modifiers))
+    }
+
+    /// Parse a sequence of chords separated by spaces, such as
+    /// `ctrl+x ctrl+s`.
+    pub fn parse_sequence(text: &str) -> Result<Vec<Self>, String> {
+        let keys = text
+            .split_whitespace()
+            .map(Self::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        if keys.is_empty() {
+            return Err("empty key sequence".to_string());
+        }
+        Ok(keys)
+    }
+
+    /// Whether only terminals speaking the kitty keyboard protocol can send
+    /// the chord. Others send Ctrl+Shift with a letter like Ctrl alone,
+    /// Ctrl+[ like Esc, Shift+Enter or Ctrl+Enter like Enter, and Ctrl+\,
+    /// Ctrl+], Ctrl+/ and Ctrl+_ as control codes read as Ctrl+4, Ctrl+5
+    /// and Ctrl+7.
+    pub fn needs_enhanced_keyboard(&self) -> bool {
+        let ctrl = self.modifiers.contains(KeyModifiers::CONTROL);
+        match self.code {
+            KeyCode::Char(ch) if ch.is_alphabetic() => {
+                ctrl && self.modifiers.contains(KeyModifiers::SHIFT)
+            }
+            KeyCode::Char('[' | ']' | '\\' | '/' | '_') => ctrl,
+            KeyCode::Enter => ctrl || self.modifiers.contains(KeyModifiers::SHIFT),
+            _ => false,
+        }
+    }
+
+    /// The chord as shown next to menu items: `^S` for a plain Ctrl chord,
+    /// the full name (`Alt+W`, `F9`) otherwise.
+    pub fn short_label(&self) -> String {
+        match self.code {
+            KeyCode::Char(_) if self.modifiers == KeyModifiers::CONTROL => {
+                let key = Self::new(self.code, KeyModifiers::NONE).to_string();
+                format!("^{key}")
+            }
+            _ => self.to_string(),
+        }
+    }
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(ch), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(ch));
+    }
+    let lower = name.to_ascii_lowercase();
+    let code = match lower.as_str() {
+        "esc" | "escape" => KeyCode::Esc,
+        "enter" | "return" => KeyCode::Enter,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" | "ins" => KeyCode::Insert,
+        "space" => KeyCode::Char(' '),
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        _ => {
+            let number = lower.strip_prefix('f')?.parse::<u8>().ok()?;
+            if !(1..=24).contains(&number) {
+                return None;
+            }
+            KeyCode::F(number)
+        }
+    };
+    Some(code)
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            f.write_str("Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(ch) => write!(f, "{}", ch.to_uppercase()),
+            KeyCode::F(number) => write!(f, "F{number}"),
+            KeyCode::PageUp => f.write_str("PgUp"),
+            KeyCode::PageDown => f.write_str("PgDn"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+/// A key sequence written out for the status line, e.g. `Ctrl+X Ctrl+S`.
+pub fn sequence_text(keys: &[KeyChord]) -> String {
+    keys.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Where a cursor motion goes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Motion {
+    Left,
+    Right,
+    WordLeft,
+    WordRight,
+    Up,
+    Down,
+    LineStart,
+    LineEnd,
+    PageUp,
+    PageDown,
+}
+
+/// An editing command that is only reachable through keys.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditCommand {
+    /// Move the cursor, dropping the selection.
+    Move(Motion),
+    /// Move the cursor, extending the selection.
+    Select(Motion),
+    /// Scroll the view by a page without moving the cursor.
+    ScrollUp,
+    ScrollDown,
+    Backspace,
+    Delete,
+    DeleteWordBackward,
+    DeleteWordForward,
+    InsertParagraphBreak,
+    /// Move to the next table cell (appending a row after the last one), or
+    /// insert a tab outside of tables.
+    Tab,
+    PreviousCell,
+}
+
+/// Something a key can be bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    App(AppAction),
+    Menu(MenuAction),
+    Edit(EditCommand),
+}
+
+/// Every command with its name, as used in the configuration file.
+pub const COMMANDS: &[(&str, Command)] = &[
+    ("new", Command::App(AppAction::New)),
+    ("open", Command::App(AppAction::Open)),
+    (
+        "open_as_plain_text",
+        Command::App(AppAction::OpenAsPlainText),
+    ),
+    ("save", Command::App(AppAction::Save)),
+    ("save_as", Command::App(AppAction::SaveAs)),
+    ("quit", Command::App(AppAction::Quit)),
+    ("undo", Command::App(AppAction::Undo)),
+    ("redo", Command::App(AppAction::Redo)),
+    ("cut", Command::App(AppAction::Cut)),
+    ("copy", Command::App(AppAction::Copy)),
+    ("copy_as_markdown", Command::App(AppAction::CopyAsMarkdown)),
+    ("copy_as_html", Command::App(AppAction::CopyAsHtml)),
+    ("paste", Command::App(AppAction::Paste)),
+    (
+        "paste_as_plain_text",
+        Command::App(AppAction::PasteAsPlainText),
+    ),
+    (
+        "paste_from_system",
+        Command::App(AppAction::PasteFromSystem),
+    ),
+    ("paste_previous", Command::App(AppAction::PastePrevious)),
+    (
+        "clipboard_history",
+        Command::App(AppAction::ClipboardHistory),
+    ),
+    ("find", Command::App(AppAction::Find)),
+    ("replace", Command::App(AppAction::Replace)),
+    (
+        "insert_line_break",
+        Command::App(AppAction::InsertLineBreak),
+    ),
+    (
+        "insert_sibling_paragraph",
+        Command::App(AppAction::InsertSiblingParagraph),
+    ),
+    ("formatting_menu", Command::App(AppAction::FormattingMenu)),
+    ("select_section", Command::App(AppAction::SelectSection)),
+    ("cut_section", Command::App(AppAction::CutSection)),
+    ("copy_section", Command::App(AppAction::CopySection)),
+    ("move_section_up", Command::App(AppAction::MoveSectionUp)),
+    (
+        "move_section_down",
+        Command::App(AppAction::MoveSectionDown),
+    ),
+    ("promote_section", Command::App(AppAction::PromoteSection)),
+    ("demote_section", Command::App(AppAction::DemoteSection)),
+    (
+        "toggle_reveal_codes",
+        Command::App(AppAction::ToggleRevealCodes),
+    ),
+    ("toggle_outline", Command::App(AppAction::ToggleOutline)),
+    ("toggle_fold", Command::App(AppAction::ToggleFold)),
+    ("fold_all", Command::App(AppAction::FoldAllSections)),
+    ("unfold_all", Command::App(AppAction::UnfoldAll)),
+    ("command_palette", Command::App(AppAction::CommandPalette)),
+    (
+        "split_horizontally",
+        Command::App(AppAction::SplitHorizontally),
+    ),
+    ("split_vertically", Command::App(AppAction::SplitVertically)),
+    ("other_pane", Command::App(AppAction::OtherPane)),
+    ("unsplit", Command::App(AppAction::Unsplit)),
+    ("next_buffer", Command::App(AppAction::NextBuffer)),
+    ("previous_buffer", Command::App(AppAction::PreviousBuffer)),
+    ("buffer_list", Command::App(AppAction::BufferList)),
+    ("close_buffer", Command::App(AppAction::CloseBuffer)),
+    #[cfg(debug_assertions)]
+    (
+        "dump_document_tree",
+        Command::App(AppAction::DumpDocumentTree),
+    ),
+    (
+        "text",
+        Command::Menu(MenuAction::SetParagraphType(ParagraphType::Text)),
+    ),
+    (
+        "heading1",
+        Command::Menu(MenuAction::SetParagraphType(ParagraphType::Header1)),
+    ),
+    (
+        "heading2",
+        Command::Menu(MenuAction::SetParagraphType(ParagraphType::Header2)),
+    ),
+    (
+        "heading3",
+        Command::Menu(MenuAction::SetParagraphType(ParagraphType::Header3)),
+    ),
+    (
+        "quote",
+        Command::Menu(MenuAction::SetParagraphType(ParagraphType::Quote)),
+    ),
+    (
+        "code_block",
+        Command::Menu(MenuAction::SetParagraphType(ParagraphType::CodeBlock)),
+    ),
+    (
+        "numbered_list",
+        Command::Menu(MenuAction::SetParagraphType(ParagraphType::OrderedList)),
+    ),
+    (
+        "bullet_list",
+        Command::Menu(MenuAction::SetParagraphType(ParagraphType::UnorderedList)),
+    ),
+    (
+        "checklist",
+        Command::Menu(MenuAction::SetParagraphType(ParagraphType::Checklist)),
+    ),
+    (
+        "bold",
+        Command::Menu(MenuAction::ApplyInlineStyle(InlineStyle::Bold)),
+    ),
+    (
+        "italic",
+        Command::Menu(MenuAction::ApplyInlineStyle(InlineStyle::Italic)),
+    ),
+    (
+        "underline",
+        Command::Menu(MenuAction::ApplyInlineStyle(InlineStyle::Underline)),
+    ),
+    (
+        "inline_code",
+        Command::Menu(MenuAction::ApplyInlineStyle(InlineStyle::Code)),
+    ),
+    (
+        "highlight",
+        Command::Menu(MenuAction::ApplyInlineStyle(InlineStyle::Highlight)),
+    ),
+    (
+        "strikethrough",
+        Command::Menu(MenuAction::ApplyInlineStyle(InlineStyle::Strike)),
+    ),
+    (
+        "clear_formatting",
+        Command::Menu(MenuAction::ApplyInlineStyle(InlineStyle::None)),
+    ),
+    ("edit_link", Command::Menu(MenuAction::EditLink)),
+    ("indent", Command::Menu(MenuAction::IndentMore)),
+    ("unindent", Command::Menu(MenuAction::IndentLess)),
+    (
+        "check_item",
+        Command::Menu(MenuAction::SetChecklistItemChecked(true)),
+    ),
+    (
+        "uncheck_item",
+        Command::Menu(MenuAction::SetChecklistItemChecked(false)),
+    ),
+    (
+        "insert_row_above",
+        Command::Menu(MenuAction::InsertTableRow(false)),
+    ),
+    (
+        "insert_row_below",
+        Command::Menu(MenuAction::InsertTableRow(true)),
+    ),
+    (
+        "insert_column_left",
+        Command::Menu(MenuAction::InsertTableColumn(false)),
+    ),
+    (
+        "insert_column_right",
+        Command::Menu(MenuAction::InsertTableColumn(true)),
+    ),
+    ("delete_row", Command::Menu(MenuAction::DeleteTableRow)),
+    (
+        "delete_column",
+        Command::Menu(MenuAction::DeleteTableColumn),
+    ),
+    ("move_left", Command::Edit(EditCommand::Move(Motion::Left))),
+    (
+        "move_right",
+        Command::Edit(EditCommand::Move(Motion::Right)),
+    ),
+    (
+        "move_word_left",
+        Command::Edit(EditCommand::Move(Motion::WordLeft)),
+    ),
+    (
+        "move_word_right",
+        Command::Edit(EditCommand::Move(Motion::WordRight)),
+    ),
+    ("move_up", Command::Edit(EditCommand::Move(Motion::Up))),
+    ("move_down", Command::Edit(EditCommand::Move(Motion::Down))),
+    (
+        "move_line_start",
+        Command::Edit(EditCommand::Move(Motion::LineStart)),
+    ),
+    (
+        "move_line_end",
+        Command::Edit(EditCommand::Move(Motion::LineEnd)),
+    ),
+    (
+        "move_page_up",
+        Command::Edit(EditCommand::Move(Motion::PageUp)),
+    ),
+    (
+        "move_page_down",
+        Command::Edit(EditCommand::Move(Motion::PageDown)),
+    ),
+    (
+        "select_left",
+        Command::Edit(EditCommand::Select(Motion::Left)),
+    ),
+    (
+        "select_right",
+        Command::Edit(EditCommand::Select(Motion::Right)),
+    ),
+    (
+        "select_word_left",
+        Command::Edit(EditCommand::Select(Motion::WordLeft)),
+    ),
+    (
+        "select_word_right",
+        Command::Edit(EditCommand::Select(Motion::WordRight)),
+    ),
+    ("select_up", Command::Edit(EditCommand::Select(Motion::Up))),
+    (
+        "select_down",
+        Command::Edit(EditCommand::Select(Motion::Down)),
+    ),
+    (
+        "select_line_start",
+        Command::Edit(EditCommand::Select(Motion::LineStart)),
+    ),
+    (
+        "select_line_end",
+        Command::Edit(EditCommand::Select(Motion::LineEnd)),
+    ),
+    (
+        "select_page_up",
+        Command::Edit(EditCommand::Select(Motion::PageUp)),
+    ),
+    (
+        "select_page_down",
+        Command::Edit(EditCommand::Select(Motion::PageDown)),
+    ),
+    ("scroll_up", Command::Edit(EditCommand::ScrollUp)),
+    ("scroll_down", Command::Edit(EditCommand::ScrollDown)),
+    ("backspace", Command::Edit(EditCommand::Backspace)),
+    ("delete", Command::Edit(EditCommand::Delete)),
+    (
+        "delete_word_backward",
+        Command::Edit(EditCommand::DeleteWordBackward),
+    ),
+    (
+        "delete_word_forward",
+        Command::Edit(EditCommand::DeleteWordForward),
+    ),
+    (
+        "insert_paragraph_break",
+        Command::Edit(EditCommand::InsertParagraphBreak),
+    ),
+    ("tab", Command::Edit(EditCommand::Tab)),
+    ("previous_cell", Command::Edit(EditCommand::PreviousCell)),
+];
+
+impl Command {
+    /// The command called `name`; dashes may stand in for underscores.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_ascii_lowercase().replace('-', "_");
+        COMMANDS
+            .iter()
+            .find(|(candidate, _)| *candidate == name)
+            .map(|(_, command)| *command)
+    }
+}
+
+/// A built-in set of key bindings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeymapPreset {
+    /// Ctrl+S to save, Ctrl+C / Ctrl+V for the clipboard and so on.
+    #[default]
+    Default,
+    /// Ctrl+F / Ctrl+B to move, Ctrl+X Ctrl+S to save, Ctrl+Y to paste.
+    Emacs,
+    /// The Ctrl+E / S / D / X diamond and Ctrl+K block commands.
+    WordStar,
+}
+
+impl KeymapPreset {
+    pub const ALL: [KeymapPreset; 3] = [
+        KeymapPreset::Default,
+        KeymapPreset::Emacs,
+        KeymapPreset::WordStar,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KeymapPreset::Default => "default",
+            KeymapPreset::Emacs => "emacs",
+            KeymapPreset::WordStar => "wordstar",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_ascii_lowercase();
+        Self::ALL.into_iter().find(|preset| preset.name() == name)
+    }
+
+    fn bindings(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            KeymapPreset::Default => DEFAULT_BINDINGS,
+            KeymapPreset::Emacs => EMACS_BINDINGS,
+            KeymapPreset::WordStar => WORDSTAR_BINDINGS,
+        }
+    }
+}
+
+const DEFAULT_BINDINGS: &[(&str, &str)] = &[
+    ("ctrl+n", "new"),
+    ("ctrl+o", "open"),
+    ("ctrl+s", "save"),
+    ("ctrl+q", "quit"),
+    ("ctrl+z", "undo"),
+    ("ctrl+y", "redo"),
+    ("ctrl+shift+z", "redo"),
+    ("ctrl+shift+s", "save_as"),
+    ("ctrl+x", "cut"),
+    ("ctrl+c", "copy"),
+    ("ctrl+v", "paste"),
+    ("ctrl+f", "find"),
+    ("ctrl+h", "replace"),
+    ("ctrl+j", "insert_line_break"),
+    ("ctrl+p", "insert_sibling_paragraph"),
+    ("ctrl+space", "formatting_menu"),
+    ("ctrl+k", "edit_link"),
+    ("ctrl+]", "indent"),
+    ("ctrl+[", "unindent"),
+    ("ctrl+a", "move_line_start"),
+    ("ctrl+e", "move_line_end"),
+    ("ctrl+w", "delete_word_backward"),
+];
+
+const EMACS_BINDINGS: &[(&str, &str)] = &[
+    ("ctrl+x ctrl+f", "open"),
+    ("ctrl+x ctrl+s", "save"),
+    ("ctrl+x ctrl+w", "save_as"),
+    ("ctrl+x ctrl+c", "quit"),
+    ("ctrl+/", "undo"),
+    ("ctrl+_", "undo"),
+    ("ctrl+x u", "undo"),
+    ("ctrl+w", "cut"),
+    ("alt+w", "copy"),
+    ("ctrl+y", "paste"),
+    ("alt+y", "paste_previous"),
+    ("ctrl+s", "find"),
+    ("alt+%", "replace"),
+    ("ctrl+j", "insert_line_break"),
+    ("ctrl+f", "move_right"),
+    ("ctrl+b", "move_left"),
+    ("ctrl+n", "move_down"),
+    ("ctrl+p", "move_up"),
+    ("alt+f", "move_word_right"),
+    ("alt+b", "move_word_left"),
+    ("ctrl+a", "move_line_start"),
+    ("ctrl+e", "move_line_end"),
+    ("ctrl+v", "move_page_down"),
+    ("alt+v", "move_page_up"),
+    ("ctrl+d", "delete"),
+    ("alt+d", "delete_word_forward"),
+    ("alt+x", "command_palette"),
+    ("ctrl+x b", "buffer_list"),
+    ("ctrl+x k", "close_buffer"),
+    ("ctrl+x right", "next_buffer"),
+    ("ctrl+x left", "previous_buffer"),
+    ("ctrl+x 2", "split_horizontally"),
+    ("ctrl+x 3", "split_vertically"),
+    ("ctrl+x o", "other_pane"),
+    ("ctrl+x 1", "unsplit"),
+];
+
+/// The second key of a WordStar command may be typed with or without Ctrl;
+/// the plain form comes first so that menus show `^K S`.
+const WORDSTAR_BINDINGS: &[(&str, &str)] = &[
+    ("ctrl+k s", "save"),
+    ("ctrl+k ctrl+s", "save"),
+    ("ctrl+k d", "save"),
+    ("ctrl+k ctrl+d", "save"),
+    ("ctrl+k x", "quit"),
+    ("ctrl+k ctrl+x", "quit"),
+    ("ctrl+k q", "quit"),
+    ("ctrl+k ctrl+q", "quit"),
+    ("ctrl+k r", "open"),
+    ("ctrl+k ctrl+r", "open"),
+    ("ctrl+k c", "copy"),
+    ("ctrl+k ctrl+c", "copy"),
+    ("ctrl+k y", "cut"),
+    ("ctrl+k ctrl+y", "cut"),
+    ("ctrl+k v", "paste"),
+    ("ctrl+k ctrl+v", "paste"),
+    ("ctrl+q f", "find"),
+    ("ctrl+q ctrl+f", "find"),
+    ("ctrl+q a", "replace"),
+    ("ctrl+q ctrl+a", "replace"),
+    ("ctrl+q s", "move_line_start"),
+    ("ctrl+q ctrl+s", "move_line_start"),
+    ("ctrl+q d", "move_line_end"),
+    ("ctrl+q ctrl+d", "move_line_end"),
+    ("ctrl+p b", "bold"),
+    ("ctrl+p ctrl+b", "bold"),
+    ("ctrl+p y", "italic"),
+    ("ctrl+p ctrl+y", "italic"),
+    ("ctrl+p s", "underline"),
+    ("ctrl+p ctrl+s", "underline"),
+    ("ctrl+p x", "strikethrough"),
+    ("ctrl+p ctrl+x", "strikethrough"),
+    ("ctrl+u", "undo"),
+    ("ctrl+o", "formatting_menu"),
+    ("ctrl+s", "move_left"),
+    ("ctrl+d", "move_right"),
+    ("ctrl+e", "move_up"),
+    ("ctrl+x", "move_down"),
+    ("ctrl+a", "move_word_left"),
+    ("ctrl+f", "move_word_right"),
+    ("ctrl+r", "move_page_up"),
+    ("ctrl+c", "move_page_down"),
+    ("ctrl+w", "scroll_up"),
+    ("ctrl+z", "scroll_down"),
+    ("ctrl+g", "delete"),
+    ("ctrl+h", "backspace"),
+    ("ctrl+t", "delete_word_forward"),
+];
+
+/// Bindings every preset shares, for keys that mean the same everywhere. A
+/// preset's own binding for one of these keys wins.
+const COMMON_BINDINGS: &[(&str, &str)] = &[
+    ("left", "move_left"),
+    ("right", "move_right"),
+    ("up", "move_up"),
+    ("down", "move_down"),
+    ("home", "move_line_start"),
+    ("end", "move_line_end"),
+    ("pageup", "move_page_up"),
+    ("pagedown", "move_page_down"),
+    ("ctrl+left", "move_word_left"),
+    ("ctrl+right", "move_word_right"),
+    ("shift+left", "select_left"),
+    ("shift+right", "select_right"),
+    ("shift+up", "select_up"),
+    ("shift+down", "select_down"),
+    ("shift+home", "select_line_start"),
+    ("shift+end", "select_line_end"),
+    ("shift+pageup", "select_page_up"),
+    ("shift+pagedown", "select_page_down"),
+    ("ctrl+shift+left", "select_word_left"),
+    ("ctrl+shift+right", "select_word_right"),
+    ("ctrl+up", "scroll_up"),
+    ("ctrl+down", "scroll_down"),
+    ("backspace", "backspace"),
+    ("ctrl+backspace", "delete_word_backward"),
+    ("alt+backspace", "delete_word_backward"),
+    ("delete", "delete"),
+    ("ctrl+delete", "delete_word_forward"),
+    ("alt+delete", "delete_word_forward"),
+    ("enter", "insert_paragraph_break"),
+    ("shift+enter", "insert_line_break"),
+    ("ctrl+enter", "insert_line_break"),
+    ("tab", "tab"),
+    ("shift+tab", "previous_cell"),
+    ("esc", "formatting_menu"),
+    ("f9", "toggle_reveal_codes"),
+    ("f7", "toggle_outline"),
+    ("f8", "toggle_fold"),
+    ("alt+shift+up", "move_section_up"),
+    ("alt+shift+down", "move_section_down"),
+    ("alt+shift+left", "promote_section"),
+    ("alt+shift+right", "demote_section"),
+    ("ctrl+shift+p", "command_palette"),
+    ("f6", "other_pane"),
+    ("ctrl+pagedown", "next_buffer"),
+    ("ctrl+pageup", "previous_buffer"),
+    #[cfg(debug_assertions)]
+    ("f12", "dump_document_tree"),
+];
+
+/// What a sequence of key presses amounts to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lookup {
+    Command(Command),
+    /// The keys start a longer binding; wait for the next one.
+    Prefix,
+    Unbound,
+}
+
+/// Bindings from key sequences to commands. Earlier bindings of a command
+/// are the ones shown in menus.
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(Vec<KeyChord>, Command)>,
+    /// Whether the terminal speaks the kitty keyboard protocol. Without it,
+    /// bindings the terminal cannot send are left out of lookups and
+    /// menus.
+    enhanced_keyboard: bool,
+}
+
+impl Keymap {
+    /// A keymap without any bindings.
+    pub fn empty() -> Self {
+        Self {
+            bindings: Vec::new(),
+            enhanced_keyboard: false,
+        }
+    }
+
+    pub fn set_enhanced_keyboard(&mut self, enhanced: bool) {
+        self.enhanced_keyboard = enhanced;
+    }
+
+    /// The bindings the terminal can send.
+    fn reachable(&self) -> impl Iterator<Item = &(Vec<KeyChord>, Command)> {
+        self.bindings.iter().filter(|(keys, _)| {
+            self.enhanced_keyboard || !keys.iter().any(KeyChord::needs_enhanced_keyboard)
+        })
+    }
+
+    pub fn preset(preset: KeymapPreset) -> Self {
+        let mut keymap = Self::empty();
+        for (keys, command) in preset.bindings().iter().chain(COMMON_BINDINGS) {
+            let keys = KeyChord::parse_sequence(keys).expect("valid preset key sequence");
+            let command = Command::from_name(command).expect("known preset command");
+            if !keymap.conflicts(&keys) {
+                keymap.bindings.push((keys, command));
+            }
+        }
+        keymap
+    }
+
+    /// Bind `keys` to `command`, replacing the bindings it conflicts with:
+    /// the same keys, a prefix of them, or sequences starting with them.
+    pub fn bind(&mut self, keys: Vec<KeyChord>, command: Command) {
+        if let Some(binding) = self.bindings.iter_mut().find(|(bound, _)| *bound == keys) {
+            binding.1 = command;
+        } else {
+            self.bindings.push((keys.clone(), command));
+        }
+        self.bindings
+            .retain(|(bound, _)| *bound == keys || !overlaps(bound, &keys));
+    }
+
+    /// Remove the binding of `keys`, and of any sequence starting with them.
+    pub fn unbind(&mut self, keys: &[KeyChord]) {
+        self.bindings.retain(|(bound, _)| !bound.starts_with(keys));
+    }
+
+    /// What the keys pressed so far are bound to.
+    pub fn lookup(&self, keys: &[KeyChord]) -> Lookup {
+        let mut lookup = Lookup::Unbound;
+        for (bound, command) in self.reachable() {
+            if bound == keys {
+                return Lookup::Command(*command);
+            }
+            if bound.starts_with(keys) {
+                lookup = Lookup::Prefix;
+            }
+        }
+        lookup
+    }
+
+    /// The command for a special key pressed with modifiers nothing is bound
+    /// to: the key with Shift only, or on its own. This way Alt+Left still
+    /// moves left and Ctrl+Shift+Up still selects, as terminals and users
+    /// add modifiers liberally.
+    pub fn fallback(&self, chord: KeyChord) -> Option<Command> {
+        if matches!(chord.code, KeyCode::Char(_)) {
+            return None;
+        }
+        [chord.modifiers & KeyModifiers::SHIFT, KeyModifiers::NONE]
+            .into_iter()
+            .filter(|modifiers| *modifiers != chord.modifiers)
+            .find_map(
+                |modifiers| match self.lookup(&[KeyChord::new(chord.code, modifiers)]) {
+                    Lookup::Command(command) => Some(command),
+                    _ => None,
+                },
+            )
+    }
+
+    /// The label of the first binding of `command`, as shown in menus:
+    /// `^S`, or `^X ^S` for a sequence.
+    pub fn shortcut_label(&self, command: Command) -> Option<String> {
+        let (keys, _) = self.reachable().find(|(_, bound)| *bound == command)?;
+        Some(
+            keys.iter()
+                .map(KeyChord::short_label)
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
+
+    fn conflicts(&self, keys: &[KeyChord]) -> bool {
+        self.bindings.iter().any(|(bound, _)| overlaps(bound, keys))
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::preset(KeymapPreset::Default)
+    }
+}
+
+/// Whether one of two key sequences starts with the other, so that binding
+/// both would leave one unreachable.
+fn overlaps(a: &[KeyChord], b: &[KeyChord]) -> bool {
+    a.starts_with(b) || b.starts_with(a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(text: &str) -> Vec<KeyChord> {
+        KeyChord::parse_sequence(text).expect("valid key sequence")
+    }
+
+    #[test]
+    fn chords_parse_modifiers_and_named_keys() {
+        let chord = KeyChord::parse("Ctrl+Shift+S").expect("valid chord");
+        assert_eq!(chord.code, KeyCode::Char('s'));
+        assert_eq!(chord.modifiers, KeyModifiers::CONTROL | KeyModifiers::SHIFT);
+        assert_eq!(chord.to_string(), "Ctrl+Shift+S");
+
+        assert_eq!(KeyChord::parse("f9").unwrap().code, KeyCode::F(9));
+        assert_eq!(
+            KeyChord::parse("alt+pagedown").unwrap().to_string(),
+            "Alt+PgDn"
+        );
+        assert_eq!(KeyChord::parse("ctrl++").unwrap().code, KeyCode::Char('+'));
+        assert!(KeyChord::parse("hyper+s").is_err());
+        assert!(KeyChord::parse("ctrl+nope").is_err());
+        assert!(KeyChord::parse("f99").is_err());
+        assert_eq!(keys("ctrl+x  ctrl+s").len(), 2);
+        assert!(KeyChord::parse_sequence("  ").is_err());
+    }
+
+    #[test]
+    fn lookups_normalize_how_terminals_report_keys() {
+        let mut keymap = Keymap::empty();
+        keymap.set_enhanced_keyboard(true);
+        keymap.bind(keys("ctrl+shift+b"), Command::from_name("bold").unwrap());
+        keymap.bind(keys("alt+%"), Command::from_name("replace").unwrap());
+        // Some terminals report Shift only through the uppercase letter,
+        // others add it to punctuation typed with Shift.
+        let bold = Command::Menu(MenuAction::ApplyInlineStyle(InlineStyle::Bold));
+        let pressed = KeyChord::new(KeyCode::Char('B'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.lookup(&[pressed]), Lookup::Command(bold));
+        let pressed = KeyChord::new(KeyCode::Char('b'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.lookup(&[pressed]), Lookup::Unbound);
+        let pressed = KeyChord::new(KeyCode::Char('%'), KeyModifiers::ALT | KeyModifiers::SHIFT);
+        assert_eq!(
+            keymap.lookup(&[pressed]),
+            Lookup::Command(Command::App(AppAction::Replace))
+        );
+        assert_eq!(
+            Command::from_name("Save-As"),
+            Some(Command::App(AppAction::SaveAs))
+        );
+    }
+
+    #[test]
+    fn sequences_wait_for_their_last_key() {
+        let keymap = Keymap::preset(KeymapPreset::Emacs);
+        assert_eq!(keymap.lookup(&keys("ctrl+x")), Lookup::Prefix);
+        assert_eq!(
+            keymap.lookup(&keys("ctrl+x ctrl+s")),
+            Lookup::Command(Command::App(AppAction::Save))
+        );
+        assert_eq!(keymap.lookup(&keys("ctrl+x ctrl+z")), Lookup::Unbound);
+
+        let mut keymap = keymap;
+        // Binding a prefix on its own replaces the sequences behind it.
+        keymap.bind(keys("ctrl+x"), Command::App(AppAction::Cut));
+        assert_eq!(
+            keymap.lookup(&keys("ctrl+x")),
+            Lookup::Command(Command::App(AppAction::Cut))
+        );
+        assert_eq!(keymap.shortcut_label(Command::App(AppAction::Save)), None);
+        keymap.unbind(&keys("ctrl+x"));
+        assert_eq!(keymap.lookup(&keys("ctrl+x")), Lookup::Unbound);
+    }
+
+    #[test]
+    fn legacy_terminals_get_no_bindings_they_cannot_send() {
+        let default = Keymap::preset(KeymapPreset::Default);
+        for chord in ["ctrl+]", "ctrl+[", "ctrl+shift+z"] {
+            assert_eq!(default.lookup(&keys(chord)), Lookup::Unbound, "{chord}");
+        }
+        let indent = Command::Menu(MenuAction::IndentMore);
+        assert_eq!(default.shortcut_label(indent), None);
+
+        let mut emacs = Keymap::preset(KeymapPreset::Emacs);
+        for chord in ["ctrl+/", "ctrl+_"] {
+            assert_eq!(emacs.lookup(&keys(chord)), Lookup::Unbound, "{chord}");
+        }
+        let undo = Command::App(AppAction::Undo);
+        assert_eq!(emacs.shortcut_label(undo).as_deref(), Some("^X U"));
+        emacs.set_enhanced_keyboard(true);
+        assert_eq!(emacs.lookup(&keys("ctrl+_")), Lookup::Command(undo));
+        assert_eq!(emacs.shortcut_label(undo).as_deref(), Some("^/"));
+    }
+
+    #[test]
+    fn presets_label_menu_items_with_their_first_binding() {
+        let label = |preset, action| Keymap::preset(preset).shortcut_label(Command::App(action));
+        assert_eq!(
+            label(KeymapPreset::Default, AppAction::Save).as_deref(),
+            Some("^S")
+        );
+        assert_eq!(
+            label(KeymapPreset::Default, AppAction::FormattingMenu).as_deref(),
+            Some("^Space")
+        );
+        // Ctrl+Shift chords reach Pure only from terminals speaking the
+        // kitty keyboard protocol; elsewhere they arrive as plain Ctrl ones.
+        assert_eq!(label(KeymapPreset::Default, AppAction::SaveAs), None);
+        let mut keymap = Keymap::preset(KeymapPreset::Default);
+        assert_eq!(keymap.lookup(&keys("ctrl+shift+s")), Lookup::Unbound);
+        keymap.set_enhanced_keyboard(true);
+        assert_eq!(
+            keymap
+                .shortcut_label(Command::App(AppAction::SaveAs))
+                .as_deref(),
+            Some("Ctrl+Shift+S")
+        );
+        assert_eq!(
+            label(KeymapPreset::Emacs, AppAction::Save).as_deref(),
+            Some("^X ^S")
+        );
+        assert_eq!(
+            label(KeymapPreset::Emacs, AppAction::Copy).as_deref(),
+            Some("Alt+W")
+        );
+        assert_eq!(
+            label(KeymapPreset::WordStar, AppAction::Quit).as_deref(),
+            Some("^K X")
+        );
+        assert_eq!(
+            label(KeymapPreset::WordStar, AppAction::ToggleRevealCodes).as_deref(),
+            Some("F9")
+        );
+    }
+
+    #[test]
+    fn presets_keep_their_own_bindings_over_the_common_ones() {
+        let wordstar = Keymap::preset(KeymapPreset::WordStar);
+        assert_eq!(
+            wordstar.lookup(&keys("ctrl+s")),
+            Lookup::Command(Command::Edit(EditCommand::Move(Motion::Left)))
+        );
+        assert_eq!(
+            wordstar.lookup(&keys("ctrl+k ctrl+s")),
+            Lookup::Command(Command::App(AppAction::Save))
+        );
+        // Special keys with extra modifiers fall back to their plain binding.
+        let alt_left = KeyChord::new(KeyCode::Left, KeyModifiers::ALT);
+        assert_eq!(wordstar.lookup(&[alt_left]), Lookup::Unbound);
+        assert_eq!(
+            wordstar.fallback(alt_left),
+            Some(Command::Edit(EditCommand::Move(Motion::Left)))
+        );
+        let ctrl_shift_up = KeyChord::new(KeyCode::Up, KeyModifiers::CONTROL | KeyModifiers::SHIFT);
+        assert_eq!(
+            wordstar.fallback(ctrl_shift_up),
+            Some(Command::Edit(EditCommand::Select(Motion::Up)))
+        );
+        assert_eq!(
+            KeymapPreset::from_name("WordStar"),
+            Some(KeymapPreset::WordStar)
+        );
+    }
+}
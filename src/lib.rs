@@ -2,12 +2,34 @@
 // This exposes internal modules for testing and benchmarking
 
 pub mod app;
+pub mod choice_prompt;
+pub mod cli;
+pub mod clipboard;
+pub mod clipboard_history;
+pub mod command_palette;
+pub mod commit_message;
+pub mod config;
+pub mod diff;
 pub mod editor;
 pub mod editor_display;
+pub mod external_change;
 pub mod file_dialog;
+pub mod fold_state;
+pub mod find_bar;
+pub mod headless;
+pub mod keymap;
 pub mod link_dialog;
+pub mod lock;
 pub mod menu_bar;
+pub mod outline;
+pub mod overwrite_prompt;
+pub mod plain_text;
+pub mod quit_prompt;
+pub mod recovery;
 pub mod render;
+pub mod save;
+pub mod terminal;
+pub mod text_field;
 pub mod theme;
 
 #[cfg(any(test, feature = "recorder"))]
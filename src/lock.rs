@@ -0,0 +1,298 @@
+//! Lock files, so two running Pures do not overwrite each other's changes.
+//!
+//! While a document is open, a lock file sits next to it: `.<name>.pure-lock`,
+//! holding `user@host.pid` of the Pure that edits it. Another Pure opening
+//! the document finds the lock and offers to open it read-only instead. The
+//! lock is advisory — nothing keeps other programs from writing the file —
+//! and is removed again when the document is closed or Pure quits. A lock
+//! whose process no longer runs on this host was left behind by a crash and
+//! is cleaned up; so is one that cannot be read. A directory Pure cannot
+//! write to gets no lock at all.
+//!
+//! The module also holds the buttons of the prompt shown for a locked
+//! document; the surrounding [`crate::app::App`] shows it and carries out the
+//! choice.
+
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::choice_prompt::Choice;
+
+const SUFFIX: &str = ".pure-lock";
+
+/// The lock file for the document at `path`.
+fn lock_path(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_string_lossy();
+    Some(path.with_file_name(format!(".{name}{SUFFIX}")))
+}
+
+/// The Pure a lock file belongs to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LockOwner {
+    pub user: String,
+    pub host: String,
+    pub pid: u32,
+}
+
+impl LockOwner {
+    /// This Pure.
+    fn current() -> Self {
+        Self {
+            user: std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .unwrap_or_else(|_| "unknown".to_string()),
+            host: host_name(),
+            pid: std::process::id(),
+        }
+    }
+
+    /// Read `user@host.pid`.
+    fn parse(text: &str) -> Option<Self> {
+        let (name, pid) = text.trim().rsplit_once('.')?;
+        let (user, host) = name.split_once('@')?;
+        Some(Self {
+            user: user.to_string(),
+            host: host.to_string(),
+            pid: pid.parse().ok()?,
+        })
+    }
+
+    /// Whether the owner is known to have ended: it ran on this host, and
+    /// no process with its id is left.
+    fn is_gone(&self) -> bool {
+        self.host == host_name() && !process_exists(self.pid)
+    }
+}
+
+impl fmt::Display for LockOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{} (process {})", self.user, self.host, self.pid)
+    }
+}
+
+#[cfg(unix)]
+fn host_name() -> String {
+    let mut buf = [0u8; 256];
+    // SAFETY: the buffer is valid for its whole length.
+    let result = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+    let len = buf.iter().position(|&byte| byte == 0).unwrap_or(buf.len());
+    match result {
+        0 if len > 0 => String::from_utf8_lossy(&buf[..len]).into_owned(),
+        _ => "localhost".to_string(),
+    }
+}
+
+#[cfg(not(unix))]
+fn host_name() -> String {
+    std::env::var("COMPUTERNAME").unwrap_or_else(|_| "localhost".to_string())
+}
+
+#[cfg(unix)]
+pub(crate) fn process_exists(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    if pid <= 0 {
+        return false;
+    }
+    // Signal 0 only checks whether the process could be signalled. A
+    // process of another user exists all the same.
+    // SAFETY: no signal is sent.
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Without a way to tell, every owner counts as running; Edit Anyway takes
+/// over a lock left behind.
+#[cfg(not(unix))]
+pub(crate) fn process_exists(_pid: u32) -> bool {
+    true
+}
+
+/// The outcome of locking a document.
+#[derive(Debug)]
+pub enum Acquisition {
+    Locked(Lock),
+    /// Another Pure has the document open.
+    HeldBy(LockOwner),
+    /// This Pure has the document open already, in another buffer.
+    AlreadyHeld,
+    /// No lock could be written, as in a read-only directory.
+    Unavailable,
+}
+
+/// Lock the document at `path`, cleaning up a lock left behind by a Pure
+/// that is gone.
+pub fn acquire(path: &Path) -> Acquisition {
+    let Some(lock_path) = lock_path(path) else {
+        return Acquisition::Unavailable;
+    };
+    let owner = LockOwner::current();
+    for _ in 0..2 {
+        match write_new(&lock_path, &owner) {
+            Ok(()) => {
+                return Acquisition::Locked(Lock {
+                    path: lock_path,
+                    owner,
+                });
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(_) => return Acquisition::Unavailable,
+        }
+        match read_owner(&lock_path) {
+            Some(holder) if holder == owner => return Acquisition::AlreadyHeld,
+            Some(holder) if !holder.is_gone() => return Acquisition::HeldBy(holder),
+            _ => {
+                let _ = fs::remove_file(&lock_path);
+            }
+        }
+    }
+    Acquisition::Unavailable
+}
+
+/// Lock the document at `path` whoever holds it now. The previous owner
+/// leaves the lock in place when it closes the document.
+pub fn take_over(path: &Path) -> Option<Lock> {
+    let lock_path = lock_path(path)?;
+    let owner = LockOwner::current();
+    fs::write(&lock_path, format!("{}\n", owner_line(&owner))).ok()?;
+    Some(Lock {
+        path: lock_path,
+        owner,
+    })
+}
+
+fn owner_line(owner: &LockOwner) -> String {
+    format!("{}@{}.{}", owner.user, owner.host, owner.pid)
+}
+
+fn write_new(lock_path: &Path, owner: &LockOwner) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(lock_path)?;
+    writeln!(file, "{}", owner_line(owner))
+}
+
+fn read_owner(lock_path: &Path) -> Option<LockOwner> {
+    LockOwner::parse(&fs::read_to_string(lock_path).ok()?)
+}
+
+/// A lock held on a document, removed again when dropped — unless another
+/// Pure has taken it over.
+#[derive(Debug)]
+pub struct Lock {
+    path: PathBuf,
+    owner: LockOwner,
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        if read_owner(&self.path).as_ref() == Some(&self.owner) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// What to do with a document locked by another Pure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockChoice {
+    ReadOnly,
+    EditAnyway,
+}
+
+/// The buttons of the prompt shown when the current document is locked by
+/// another Pure, in display order, chosen by their initials. Read-Only has
+/// focus, so a hasty Enter changes nothing.
+pub const CHOICES: [Choice<LockChoice>; 2] = [
+    Choice {
+        value: LockChoice::ReadOnly,
+        label: "Read-Only",
+        accelerators: "r",
+    },
+    Choice {
+        value: LockChoice::EditAnyway,
+        label: "Edit Anyway",
+        accelerators: "e",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::TempDir;
+
+    fn temp_document(name: &str) -> (TempDir, PathBuf) {
+        let dir = TempDir::new(name);
+        let path = dir.join("notes.md");
+        fs::write(&path, "# Notes\n").expect("write document");
+        (dir, path)
+    }
+
+    /// A lock file as another Pure on this host would write it.
+    fn foreign_lock(path: &Path, pid: u32) -> LockOwner {
+        let owner = LockOwner {
+            pid,
+            ..LockOwner::current()
+        };
+        fs::write(lock_path(path).unwrap(), owner_line(&owner)).unwrap();
+        owner
+    }
+
+    #[test]
+    fn locks_are_released_when_dropped() {
+        let (_dir, path) = temp_document("lock");
+        let lock = match acquire(&path) {
+            Acquisition::Locked(lock) => lock,
+            other => panic!("expected a lock, got {other:?}"),
+        };
+        let lock_file = lock_path(&path).unwrap();
+        assert_eq!(read_owner(&lock_file), Some(LockOwner::current()));
+        drop(lock);
+        assert!(!lock_file.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn running_owners_keep_their_lock_and_gone_ones_do_not() {
+        let (_dir, path) = temp_document("lock-held");
+        // Process 1 always runs.
+        let owner = foreign_lock(&path, 1);
+        match acquire(&path) {
+            Acquisition::HeldBy(holder) => assert_eq!(holder, owner),
+            other => panic!("expected the lock to be held, got {other:?}"),
+        }
+
+        // Beyond any process id the kernel hands out.
+        foreign_lock(&path, 999_999_999);
+        assert!(matches!(acquire(&path), Acquisition::Locked(_)));
+    }
+
+    #[test]
+    fn a_lock_of_this_pure_is_already_held() {
+        let (_dir, path) = temp_document("lock-own");
+        let lock = match acquire(&path) {
+            Acquisition::Locked(lock) => lock,
+            other => panic!("expected a lock, got {other:?}"),
+        };
+        assert!(matches!(acquire(&path), Acquisition::AlreadyHeld));
+        drop(lock);
+    }
+
+    #[test]
+    fn a_lock_taken_over_stays_with_the_new_owner() {
+        let (_dir, path) = temp_document("lock-take-over");
+        let lock_file = lock_path(&path).unwrap();
+        let stale = Lock {
+            path: lock_file.clone(),
+            owner: foreign_lock(&path, 1),
+        };
+        let lock = take_over(&path).expect("take over");
+        drop(stale);
+        assert!(lock_file.exists());
+        drop(lock);
+        assert!(!lock_file.exists());
+    }
+}
@@ -2,15 +2,19 @@
 //!
 //! The bar is hidden during normal editing and overlays the top row of the
 //! screen while active. It is activated with F10 (bar only) or with a menu's
-//! Alt accelerator (bar plus open drop-down), and driven entirely with the
-//! keyboard: Left/Right switch menus, Up/Down move within a drop-down,
-//! Return activates the selected item, Esc closes the bar.
+//! Alt accelerator (bar plus open drop-down) unless the keymap binds that
+//! key, and driven entirely with the keyboard: Left/Right switch menus,
+//! Up/Down move within a drop-down, Return activates the selected item, Esc
+//! closes the bar. The shortcuts shown next to items come from the keymap.
 
 /// An application-level command reachable from the menu bar.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum AppAction {
     New,
     Open,
+    /// Read a file that could not be parsed as plain text, so it can be
+    /// edited.
+    OpenAsPlainText,
     Save,
     SaveAs,
     Quit,
@@ -18,17 +22,76 @@ pub enum AppAction {
     Redo,
     Cut,
     Copy,
+    /// Copy the selection as Markdown, for pasting it into chat or mail.
+    CopyAsMarkdown,
+    /// Copy the selection as an HTML fragment.
+    CopyAsHtml,
     Paste,
+    /// Paste the clipboard's text without its formatting, and without
+    /// reading Markdown or HTML in it.
+    PasteAsPlainText,
+    /// Paste what another application copied, even after a cut or copy in
+    /// Pure.
+    PasteFromSystem,
+    /// Right after a paste, replace the pasted text with the entry of the
+    /// clipboard history before it.
+    PastePrevious,
+    /// Pick an earlier cut or copy to paste.
+    ClipboardHistory,
+    /// Paste the entry at this position in the clipboard history; chosen in
+    /// the clipboard history.
+    PasteFromHistory(usize),
+    Find,
+    Replace,
     InsertLineBreak,
     InsertSiblingParagraph,
     FormattingMenu,
+    /// Select the section at the cursor: its heading and everything up to
+    /// the next heading of the same or a higher level.
+    SelectSection,
+    CutSection,
+    CopySection,
+    /// Move the section at the cursor before the previous section of the
+    /// same level.
+    MoveSectionUp,
+    /// Move the section at the cursor after the next section of the same
+    /// level.
+    MoveSectionDown,
+    /// Raise every heading of the section at the cursor by one level.
+    PromoteSection,
+    /// Lower every heading of the section at the cursor by one level.
+    DemoteSection,
     ToggleRevealCodes,
+    /// Show or hide the outline, or move the focus to it.
+    ToggleOutline,
+    /// Fold or unfold the innermost section, list entry, checklist item or
+    /// quote around the cursor.
+    ToggleFold,
+    FoldAllSections,
+    UnfoldAll,
+    CommandPalette,
+    /// Split the editor area into panes one above the other.
+    SplitHorizontally,
+    /// Split the editor area into panes side by side.
+    SplitVertically,
+    OtherPane,
+    Unsplit,
+    NextBuffer,
+    PreviousBuffer,
+    /// Open the buffer switcher, listing every open buffer.
+    BufferList,
+    CloseBuffer,
+    /// Make the buffer at this position in the buffer list the current one;
+    /// chosen in the buffer switcher.
+    SwitchToBuffer(usize),
+    /// Append the document tree to a file in the temp directory; a
+    /// diagnostic in debug builds, listed only in the command palette.
+    #[cfg(debug_assertions)]
+    DumpDocumentTree,
 }
 
 pub struct MenuBarItem {
     pub label: &'static str,
-    /// Shortcut text shown right-aligned in the drop-down (display only).
-    pub shortcut: Option<&'static str>,
     /// `None` marks the item as disabled (shown, but not activatable).
     pub action: Option<AppAction>,
 }
@@ -55,14 +118,11 @@ impl MenuDef {
     }
 }
 
-const fn item(
-    label: &'static str,
-    shortcut: Option<&'static str>,
-    action: AppAction,
-) -> MenuBarEntry {
+/// A menu item. Its shortcut, shown right-aligned in the drop-down, is
+/// looked up in the active keymap.
+const fn item(label: &'static str, action: AppAction) -> MenuBarEntry {
     MenuBarEntry::Item(MenuBarItem {
         label,
-        shortcut,
         action: Some(action),
     })
 }
@@ -72,56 +132,89 @@ pub const MENU_BAR: &[MenuDef] = &[
         title: "File",
         accel_index: 0,
         entries: &[
-            item("New", Some("^N"), AppAction::New),
-            item("Open...", Some("^O"), AppAction::Open),
+            item("New", AppAction::New),
+            item("Open...", AppAction::Open),
+            item("Open as Plain Text", AppAction::OpenAsPlainText),
             MenuBarEntry::Separator,
-            item("Save", Some("^S"), AppAction::Save),
-            item("Save As...", None, AppAction::SaveAs),
+            item("Save", AppAction::Save),
+            item("Save As...", AppAction::SaveAs),
             MenuBarEntry::Separator,
-            item("Quit", Some("^Q"), AppAction::Quit),
+            item("Quit", AppAction::Quit),
         ],
     },
     MenuDef {
         title: "Edit",
         accel_index: 0,
         entries: &[
-            item("Undo", Some("^Z"), AppAction::Undo),
-            item("Redo", Some("^Y"), AppAction::Redo),
+            item("Undo", AppAction::Undo),
+            item("Redo", AppAction::Redo),
             MenuBarEntry::Separator,
-            item("Cut", Some("^X"), AppAction::Cut),
-            item("Copy", Some("^C"), AppAction::Copy),
-            item("Paste", Some("^V"), AppAction::Paste),
+            item("Cut", AppAction::Cut),
+            item("Copy", AppAction::Copy),
+            item("Copy as Markdown", AppAction::CopyAsMarkdown),
+            item("Copy as HTML", AppAction::CopyAsHtml),
+            item("Paste", AppAction::Paste),
+            item("Paste as Plain Text", AppAction::PasteAsPlainText),
+            item("Paste from System Clipboard", AppAction::PasteFromSystem),
+            item("Paste Previous", AppAction::PastePrevious),
+            item("Clipboard History...", AppAction::ClipboardHistory),
+            MenuBarEntry::Separator,
+            item("Select Section", AppAction::SelectSection),
+            item("Cut Section", AppAction::CutSection),
+            item("Copy Section", AppAction::CopySection),
+            MenuBarEntry::Separator,
+            item("Find...", AppAction::Find),
+            item("Replace...", AppAction::Replace),
         ],
     },
     MenuDef {
         title: "Insert",
         accel_index: 0,
         entries: &[
-            item("Line Break", Some("^J"), AppAction::InsertLineBreak),
-            item(
-                "Sibling Paragraph",
-                Some("^P"),
-                AppAction::InsertSiblingParagraph,
-            ),
+            item("Line Break", AppAction::InsertLineBreak),
+            item("Sibling Paragraph", AppAction::InsertSiblingParagraph),
         ],
     },
     MenuDef {
         title: "Format",
         accel_index: 1,
-        entries: &[item(
-            "Formatting Menu...",
-            Some("^Space"),
-            AppAction::FormattingMenu,
-        )],
+        entries: &[
+            item("Formatting Menu...", AppAction::FormattingMenu),
+            MenuBarEntry::Separator,
+            item("Move Section Up", AppAction::MoveSectionUp),
+            item("Move Section Down", AppAction::MoveSectionDown),
+            item("Promote Section", AppAction::PromoteSection),
+            item("Demote Section", AppAction::DemoteSection),
+        ],
     },
     MenuDef {
         title: "View",
         accel_index: 0,
-        entries: &[item(
-            "Reveal Codes",
-            Some("F9"),
-            AppAction::ToggleRevealCodes,
-        )],
+        entries: &[
+            item("Reveal Codes", AppAction::ToggleRevealCodes),
+            item("Outline", AppAction::ToggleOutline),
+            item("Command Palette...", AppAction::CommandPalette),
+            MenuBarEntry::Separator,
+            item("Fold/Unfold", AppAction::ToggleFold),
+            item("Fold All Sections", AppAction::FoldAllSections),
+            item("Unfold All", AppAction::UnfoldAll),
+            MenuBarEntry::Separator,
+            item("Split Horizontally", AppAction::SplitHorizontally),
+            item("Split Vertically", AppAction::SplitVertically),
+            item("Other Pane", AppAction::OtherPane),
+            item("Unsplit", AppAction::Unsplit),
+        ],
+    },
+    MenuDef {
+        title: "Buffers",
+        accel_index: 0,
+        entries: &[
+            item("Next Buffer", AppAction::NextBuffer),
+            item("Previous Buffer", AppAction::PreviousBuffer),
+            item("Buffer List...", AppAction::BufferList),
+            MenuBarEntry::Separator,
+            item("Close Buffer", AppAction::CloseBuffer),
+        ],
     },
 ];
 
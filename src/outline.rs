@@ -0,0 +1,222 @@
+//! The outline: a pane left of the editor listing the document's headings.
+//!
+//! The outline is built from the top-level Heading 1–3 paragraphs and
+//! rebuilt each time it is drawn, so it follows the headings as they are
+//! edited. The section the cursor is in is marked. While the outline has
+//! focus, Up / Down, Page Up / Page Down and Home / End select a heading,
+//! Enter moves the cursor there, and Esc returns to the document; a click
+//! on a heading moves the cursor there as well. The surrounding
+//! [`crate::app::App`] draws the pane and moves the cursor.
+
+use ratatui::layout::Rect;
+use tdoc::{Document, Paragraph};
+
+use crate::plain_text;
+
+/// A heading listed in the outline.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutlineEntry {
+    /// 1 to 3, as in Heading 1 to Heading 3.
+    pub level: usize,
+    pub title: String,
+    /// Position of the heading among the document's top-level paragraphs.
+    pub paragraph: usize,
+}
+
+/// The headings of `document`, in document order.
+pub fn entries(document: &Document) -> Vec<OutlineEntry> {
+    document
+        .paragraphs
+        .iter()
+        .enumerate()
+        .filter_map(|(paragraph, content)| {
+            let (level, content) = match content {
+                Paragraph::Header1 { content } => (1, content),
+                Paragraph::Header2 { content } => (2, content),
+                Paragraph::Header3 { content } => (3, content),
+                _ => return None,
+            };
+            Some(OutlineEntry {
+                level,
+                title: plain_text::spans_text(content).trim().to_string(),
+                paragraph,
+            })
+        })
+        .collect()
+}
+
+pub struct OutlineState {
+    entries: Vec<OutlineEntry>,
+    selected: usize,
+    /// Whether keys go to the outline rather than the document.
+    focused: bool,
+    /// The first entry in view.
+    scroll: usize,
+    /// The current section's entry when last drawn. Without focus, the
+    /// outline scrolls to the current section only when it changes, so the
+    /// mouse wheel can look around.
+    followed: Option<usize>,
+    /// Where the entries were last drawn, for the mouse.
+    area: Rect,
+}
+
+impl OutlineState {
+    /// An outline of `document`, with focus and the heading of the section
+    /// containing `cursor_paragraph` selected.
+    pub fn new(document: &Document, cursor_paragraph: usize) -> Self {
+        let mut outline = Self {
+            entries: Vec::new(),
+            selected: 0,
+            focused: true,
+            scroll: 0,
+            followed: None,
+            area: Rect::default(),
+        };
+        outline.refresh(document);
+        outline.selected = outline.current(cursor_paragraph).unwrap_or(0);
+        outline
+    }
+
+    /// Rebuild the entries from `document`, keeping the selection in range.
+    pub fn refresh(&mut self, document: &Document) {
+        self.entries = entries(document);
+        self.selected = self.selected.min(self.entries.len().saturating_sub(1));
+    }
+
+    pub fn entries(&self) -> &[OutlineEntry] {
+        &self.entries
+    }
+
+    /// The entry of the section containing the top-level paragraph
+    /// `paragraph`: the last heading at or before it.
+    pub fn current(&self, paragraph: usize) -> Option<usize> {
+        self.entries
+            .partition_point(|entry| entry.paragraph <= paragraph)
+            .checked_sub(1)
+    }
+
+    pub fn selected(&self) -> Option<&OutlineEntry> {
+        self.entries.get(self.selected)
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.entries.len().saturating_sub(1));
+    }
+
+    /// Move the selection by `delta` entries, stopping at either end.
+    pub fn move_selection(&mut self, delta: isize) {
+        self.select(self.selected.saturating_add_signed(delta));
+    }
+
+    pub fn focused(&self) -> bool {
+        self.focused
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    /// Scroll so that entry `index` is among the `visible` entries in view.
+    fn scroll_to(&mut self, index: usize, visible: usize) {
+        if index < self.scroll {
+            self.scroll = index;
+        } else if visible > 0 && index >= self.scroll + visible {
+            self.scroll = index + 1 - visible;
+        }
+        let max = self.entries.len().saturating_sub(visible);
+        self.scroll = self.scroll.min(max);
+    }
+
+    /// Scroll so that the selection is in view while the outline has focus,
+    /// or else the entry `current` once it becomes the current section.
+    pub fn keep_in_view(&mut self, current: Option<usize>, visible: usize) {
+        if self.focused {
+            self.scroll_to(self.selected, visible);
+        } else if current != self.followed {
+            self.scroll_to(current.unwrap_or(0), visible);
+        }
+        self.followed = current;
+    }
+
+    /// Scroll by `delta` entries, as with the mouse wheel.
+    pub fn scroll_by(&mut self, delta: isize) {
+        let visible = self.area.height as usize;
+        let max = self.entries.len().saturating_sub(visible);
+        self.scroll = self.scroll.saturating_add_signed(delta).min(max);
+    }
+
+    pub fn area(&self) -> Rect {
+        self.area
+    }
+
+    pub fn set_area(&mut self, area: Rect) {
+        self.area = area;
+    }
+
+    /// The entry drawn on screen row `row`, if any.
+    pub fn entry_at_row(&self, row: u16) -> Option<usize> {
+        if row < self.area.y || row >= self.area.bottom() {
+            return None;
+        }
+        let index = self.scroll + (row - self.area.y) as usize;
+        (index < self.entries.len()).then_some(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tdoc::Span;
+
+    fn heading(level: usize, text: &str) -> Paragraph {
+        let paragraph = match level {
+            1 => Paragraph::new_header1(),
+            2 => Paragraph::new_header2(),
+            _ => Paragraph::new_header3(),
+        };
+        paragraph.with_content(vec![Span::new_text(text)])
+    }
+
+    fn document() -> Document {
+        Document::new().with_paragraphs(vec![
+            Paragraph::new_text().with_content(vec![Span::new_text("Intro")]),
+            heading(1, "One"),
+            Paragraph::new_text().with_content(vec![Span::new_text("Body")]),
+            heading(2, " Two "),
+            heading(3, "Three"),
+        ])
+    }
+
+    #[test]
+    fn headings_make_up_the_outline() {
+        let titles: Vec<(usize, String, usize)> = entries(&document())
+            .into_iter()
+            .map(|entry| (entry.level, entry.title, entry.paragraph))
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                (1, "One".to_string(), 1),
+                (2, "Two".to_string(), 3),
+                (3, "Three".to_string(), 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn the_section_containing_the_cursor_is_current() {
+        let outline = OutlineState::new(&document(), 2);
+        assert_eq!(outline.current(0), None);
+        assert_eq!(outline.current(2), Some(0));
+        assert_eq!(outline.current(3), Some(1));
+        assert_eq!(outline.selected_index(), 0);
+    }
+}
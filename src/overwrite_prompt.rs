@@ -0,0 +1,56 @@
+//! Modal prompt shown before saving over a file that could not be parsed.
+//!
+//! Such a file opens read-only, showing its raw text, or as plain text on
+//! request; either way the document is not what the file holds, and saving
+//! would replace the original. The prompt is a
+//! [`crate::choice_prompt::ChoicePrompt`] offering three buttons —
+//! Overwrite, Save As, and Cancel. Cancel has focus, so a hasty Enter
+//! leaves the file alone.
+
+use crate::choice_prompt::Choice;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverwriteChoice {
+    Overwrite,
+    SaveAs,
+    Cancel,
+}
+
+/// The prompt's buttons, in display order. Besides the initials, `y` and
+/// `n` answer the "Overwrite?" question.
+pub const CHOICES: [Choice<OverwriteChoice>; 3] = [
+    Choice {
+        value: OverwriteChoice::Overwrite,
+        label: "Overwrite",
+        accelerators: "oy",
+    },
+    Choice {
+        value: OverwriteChoice::SaveAs,
+        label: "Save As",
+        accelerators: "s",
+    },
+    Choice {
+        value: OverwriteChoice::Cancel,
+        label: "Cancel",
+        accelerators: "cn",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::choice_prompt::ChoicePrompt;
+
+    #[test]
+    fn accelerators_include_yes_and_no() {
+        let prompt = ChoicePrompt::new(&CHOICES, OverwriteChoice::Cancel, ());
+        assert_eq!(prompt.focus(), OverwriteChoice::Cancel);
+        assert_eq!(
+            prompt.from_accelerator('Y'),
+            Some(OverwriteChoice::Overwrite)
+        );
+        assert_eq!(prompt.from_accelerator('s'), Some(OverwriteChoice::SaveAs));
+        assert_eq!(prompt.from_accelerator('n'), Some(OverwriteChoice::Cancel));
+        assert_eq!(prompt.from_accelerator('x'), None);
+    }
+}
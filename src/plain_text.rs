@@ -0,0 +1,286 @@
+//! Plain text, for files without markup such as `notes.txt` or `README`.
+//!
+//! Paragraphs are separated by blank lines, and a single newline within a
+//! paragraph is a line break. Every further blank line is an empty
+//! paragraph, so a file saved without changes stays as it was. Plain text has no styles, so they are dropped
+//! when writing, and links keep only their text. Structure is spelled out
+//! the way people type it: `- ` and `1. ` before list items, `[ ]` and `[x]`
+//! before checklist items, `> ` before quoted lines, and table cells
+//! separated by ` | `. Reading such a file back gives plain paragraphs.
+
+use std::io::{self, Write};
+
+use tdoc::{ChecklistItem, Document, Paragraph, Span};
+use unicode_width::UnicodeWidthStr;
+
+/// Read plain text: every run of lines between blank lines becomes a
+/// paragraph, keeping its line breaks. Blank lines beyond the one between
+/// two paragraphs, and those before the first or after the last, become
+/// empty paragraphs.
+pub fn parse(text: &str) -> Document {
+    let text = text.replace("\r\n", "\n");
+    let text = text.strip_suffix('\n').unwrap_or(&text);
+    let mut document = Document::new();
+    let mut lines: Vec<&str> = Vec::new();
+    let mut blank_lines = 0usize;
+    for line in text.split('\n') {
+        if !line.trim().is_empty() {
+            if lines.is_empty() {
+                let separator = usize::from(!document.paragraphs.is_empty());
+                push_empty_paragraphs(&mut document, blank_lines.saturating_sub(separator));
+                blank_lines = 0;
+            }
+            lines.push(line);
+            continue;
+        }
+        if !lines.is_empty() {
+            document
+                .paragraphs
+                .push(Paragraph::new_text().with_content(vec![Span::new_text(lines.join("\n"))]));
+            lines.clear();
+        }
+        blank_lines += 1;
+    }
+    if !lines.is_empty() {
+        document
+            .paragraphs
+            .push(Paragraph::new_text().with_content(vec![Span::new_text(lines.join("\n"))]));
+    } else if !document.paragraphs.is_empty() {
+        push_empty_paragraphs(&mut document, blank_lines);
+    }
+    document
+}
+
+fn push_empty_paragraphs(document: &mut Document, count: usize) {
+    for _ in 0..count {
+        document.paragraphs.push(Paragraph::new_text());
+    }
+}
+
+/// Whether `paragraph` is a text paragraph without any text, written as
+/// nothing but the blank line before it.
+fn is_empty_text(paragraph: &Paragraph) -> bool {
+    matches!(paragraph, Paragraph::Text { content } if spans_text(content).is_empty())
+}
+
+/// Write `document` as plain text. With a `wrap_width`, lines longer than
+/// that many columns are broken between words; code blocks and tables are
+/// left as they are.
+pub fn write<W: Write>(
+    writer: &mut W,
+    document: &Document,
+    wrap_width: Option<usize>,
+) -> io::Result<()> {
+    for line in blocks_lines(&document.paragraphs, wrap_width, true) {
+        writeln!(writer, "{line}")?;
+    }
+    Ok(())
+}
+
+/// The lines of consecutive paragraphs, with a blank line between them if
+/// `spaced`; an empty paragraph then adds just that blank line.
+fn blocks_lines(paragraphs: &[Paragraph], width: Option<usize>, spaced: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (index, paragraph) in paragraphs.iter().enumerate() {
+        if spaced && index > 0 {
+            lines.push(String::new());
+        }
+        if spaced && is_empty_text(paragraph) {
+            continue;
+        }
+        lines.extend(paragraph_lines(paragraph, width));
+    }
+    lines
+}
+
+fn paragraph_lines(paragraph: &Paragraph, width: Option<usize>) -> Vec<String> {
+    match paragraph {
+        Paragraph::Text { content }
+        | Paragraph::Header1 { content }
+        | Paragraph::Header2 { content }
+        | Paragraph::Header3 { content } => wrap(&spans_text(content), width),
+        Paragraph::CodeBlock { content } => wrap(&spans_text(content), None),
+        Paragraph::UnorderedList { entries } => entries
+            .iter()
+            .flat_map(|entry| item_lines("- ", entry, width))
+            .collect(),
+        Paragraph::OrderedList { entries } => entries
+            .iter()
+            .enumerate()
+            .flat_map(|(index, entry)| item_lines(&format!("{}. ", index + 1), entry, width))
+            .collect(),
+        Paragraph::Checklist { items } => checklist_lines(items, width),
+        Paragraph::Quote { children } => {
+            prefixed(blocks_lines(children, narrower(width, 2), true), "> ", "> ")
+        }
+        Paragraph::Table { rows } => rows
+            .iter()
+            .map(|row| {
+                row.cells
+                    .iter()
+                    .map(|cell| spans_text(&cell.content).replace('\n', " "))
+                    .collect::<Vec<_>>()
+                    .join(" | ")
+            })
+            .collect(),
+    }
+}
+
+/// A list item: the marker before its first line, the following lines
+/// indented to match.
+fn item_lines(marker: &str, paragraphs: &[Paragraph], width: Option<usize>) -> Vec<String> {
+    let indent = " ".repeat(marker.width());
+    let lines = blocks_lines(paragraphs, narrower(width, indent.len()), false);
+    prefixed(lines, marker, &indent)
+}
+
+fn checklist_lines(items: &[ChecklistItem], width: Option<usize>) -> Vec<String> {
+    let mut lines = Vec::new();
+    for item in items {
+        let marker = if item.checked { "[x] " } else { "[ ] " };
+        let text = wrap(&spans_text(&item.content), narrower(width, marker.len()));
+        lines.extend(prefixed(text, marker, "    "));
+        lines.extend(prefixed(
+            checklist_lines(&item.children, narrower(width, 4)),
+            "    ",
+            "    ",
+        ));
+    }
+    lines
+}
+
+/// Put `first` before the first line and `rest` before the others, leaving
+/// blank lines free of trailing spaces.
+fn prefixed(lines: Vec<String>, first: &str, rest: &str) -> Vec<String> {
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let prefix = if index == 0 { first } else { rest };
+            if line.is_empty() {
+                prefix.trim_end().to_string()
+            } else {
+                format!("{prefix}{line}")
+            }
+        })
+        .collect()
+}
+
+fn narrower(width: Option<usize>, by: usize) -> Option<usize> {
+    width.map(|width| width.saturating_sub(by).max(1))
+}
+
+/// The text of `spans` without styles.
+pub(crate) fn spans_text(spans: &[Span]) -> String {
+    fn collect(spans: &[Span], out: &mut String) {
+        for span in spans {
+            out.push_str(&span.text);
+            collect(&span.children, out);
+        }
+    }
+    let mut out = String::new();
+    collect(spans, &mut out);
+    out
+}
+
+/// Split `text` at its line breaks and, with a `width`, break longer lines
+/// between words.
+fn wrap(text: &str, width: Option<usize>) -> Vec<String> {
+    let mut lines = Vec::new();
+    for line in text.split('\n') {
+        let Some(width) = width.filter(|width| line.width() > *width) else {
+            lines.push(line.to_string());
+            continue;
+        };
+        let mut current = String::new();
+        for (index, word) in line.split(' ').enumerate() {
+            if index == 0 {
+                current.push_str(word);
+            } else if current.width() + 1 + word.width() <= width || current.trim().is_empty() {
+                current.push(' ');
+                current.push_str(word);
+            } else {
+                lines.push(std::mem::take(&mut current));
+                current.push_str(word);
+            }
+        }
+        lines.push(current);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tdoc::ftml;
+
+    fn written(document: &Document, wrap_width: Option<usize>) -> String {
+        let mut out = Vec::new();
+        write(&mut out, document, wrap_width).expect("write plain text");
+        String::from_utf8(out).expect("utf-8")
+    }
+
+    #[test]
+    fn blank_lines_separate_paragraphs() {
+        let document = parse("First line\nsecond line\n\n  Indented\r\nparagraph\n");
+        assert_eq!(
+            document,
+            Document::new().with_paragraphs(vec![
+                Paragraph::new_text().with_content(vec![Span::new_text("First line\nsecond line")]),
+                Paragraph::new_text().with_content(vec![Span::new_text("  Indented\nparagraph")]),
+            ])
+        );
+        assert_eq!(
+            written(&document, None),
+            "First line\nsecond line\n\n  Indented\nparagraph\n"
+        );
+        assert!(parse("\n \n").paragraphs.is_empty());
+    }
+
+    #[test]
+    fn further_blank_lines_survive_a_round_trip() {
+        for text in [
+            "One\n\n\nTwo\n",
+            "\n\nAfter two blank lines\n\n\n\nand three\n\n",
+            "Line\nbreak\n\n\n\n\n\n",
+        ] {
+            assert_eq!(written(&parse(text), None), text);
+        }
+        assert_eq!(parse("One\n\n\nTwo\n").paragraphs[1], Paragraph::new_text());
+    }
+
+    #[test]
+    fn structure_is_spelled_out() {
+        let document = ftml! {
+            h1 { "Packing " b { "List" } }
+            ul {
+                li { p { "Passport" } }
+                li { p { "Tickets" } }
+            }
+            ol {
+                li { p { "Pack" } }
+                li { p { "Go" } }
+            }
+            quote { p { "Travel light." } p { "Really." } }
+        };
+        assert_eq!(
+            written(&document, None),
+            "Packing List\n\n- Passport\n- Tickets\n\n1. Pack\n2. Go\n\n\
+             > Travel light.\n>\n> Really.\n"
+        );
+    }
+
+    #[test]
+    fn long_lines_are_wrapped_between_words() {
+        let document = ftml! {
+            p { "The quick brown fox jumps over the lazy dog." }
+            ul { li { p { "A list item that is too long" } } }
+        };
+        assert_eq!(
+            written(&document, Some(16)),
+            "The quick brown\nfox jumps over\nthe lazy dog.\n\n- A list item\n  that is too\n  long\n"
+        );
+        // Words longer than the width stay whole.
+        assert_eq!(wrap("unbreakable words", Some(4)), ["unbreakable", "words"]);
+    }
+}
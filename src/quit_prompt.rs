@@ -0,0 +1,54 @@
+//! Modal prompt shown when quitting, or closing a buffer, with unsaved
+//! changes.
+//!
+//! The prompt is a [`crate::choice_prompt::ChoicePrompt`] offering three
+//! buttons — Save, Discard, and Cancel — with Save focused, so Enter keeps
+//! the user's work. The surrounding [`crate::app::App`] carries out the
+//! choice: saving (through the Save As dialog for untitled documents) before
+//! quitting or closing, doing so right away, or returning to the document.
+//! When quitting, the prompt is shown once for every buffer with unsaved
+//! changes.
+
+use crate::choice_prompt::Choice;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuitChoice {
+    Save,
+    Discard,
+    Cancel,
+}
+
+/// The prompt's buttons, in display order. Besides the initials, `y` and
+/// `n` answer the "Save changes?" question.
+pub const CHOICES: [Choice<QuitChoice>; 3] = [
+    Choice {
+        value: QuitChoice::Save,
+        label: "Save",
+        accelerators: "sy",
+    },
+    Choice {
+        value: QuitChoice::Discard,
+        label: "Discard",
+        accelerators: "dn",
+    },
+    Choice {
+        value: QuitChoice::Cancel,
+        label: "Cancel",
+        accelerators: "c",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::choice_prompt::ChoicePrompt;
+
+    #[test]
+    fn accelerators_include_yes_and_no() {
+        let prompt = ChoicePrompt::new(&CHOICES, QuitChoice::Save, ());
+        assert_eq!(prompt.from_accelerator('Y'), Some(QuitChoice::Save));
+        assert_eq!(prompt.from_accelerator('n'), Some(QuitChoice::Discard));
+        assert_eq!(prompt.from_accelerator('c'), Some(QuitChoice::Cancel));
+        assert_eq!(prompt.from_accelerator('x'), None);
+    }
+}
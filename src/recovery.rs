@@ -0,0 +1,268 @@
+//! Crash recovery for unsaved changes.
+//!
+//! While a document has unsaved changes, the app periodically writes it as
+//! FTML to a recovery file: `.<name>.pure-recovery` next to the document or,
+//! when that directory is not writable, a file in Pure's state directory
+//! (`$XDG_STATE_HOME/pure/recovery`, falling back to
+//! `~/.local/state/pure/recovery`). Untitled documents always go to the state
+//! directory. Only the owner can read a recovery file, whoever may read the
+//! document. Saving the document, or quitting without saving, removes the
+//! recovery file again — so one that is still around when the document is
+//! opened next holds changes from a session that did not end cleanly. The
+//! recovery files of untitled documents are named after the process that
+//! wrote them; one whose process is gone is offered when Pure starts
+//! without a document.
+//!
+//! The module also holds the buttons of the prompt that offers to restore
+//! such a file; the surrounding [`crate::app::App`] shows it and carries out the
+//! choice.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use tdoc::Document;
+use tdoc::ftml::{Writer, parse};
+
+use crate::choice_prompt::Choice;
+use crate::lock;
+use crate::save;
+
+/// How long unsaved changes may go without being written to the recovery
+/// file.
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+const SUFFIX: &str = ".pure-recovery";
+
+/// The recovery file next to `path`.
+fn sibling_path(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_string_lossy();
+    Some(path.with_file_name(format!(".{name}{SUFFIX}")))
+}
+
+/// Pure's directory inside the user's state directory.
+pub(crate) fn state_home() -> Option<PathBuf> {
+    let base = env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+    Some(base.join("pure"))
+}
+
+/// A file name standing for the document at `path` in the state directory:
+/// the document's absolute path with its separators replaced by `%`.
+pub(crate) fn state_file_name(path: &Path) -> Option<String> {
+    let absolute = std::path::absolute(path).ok()?;
+    Some(
+        absolute
+            .to_string_lossy()
+            .chars()
+            .map(|ch| if std::path::is_separator(ch) { '%' } else { ch })
+            .collect(),
+    )
+}
+
+/// Pure's recovery directory inside the user's state directory.
+fn state_dir() -> Option<PathBuf> {
+    Some(state_home()?.join("recovery"))
+}
+
+/// The recovery file for `path` inside the state directory.
+fn state_path(path: &Path) -> Option<PathBuf> {
+    let name = state_file_name(path)?;
+    Some(state_dir()?.join(format!("{name}{SUFFIX}")))
+}
+
+/// The document a recovery file belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Origin<'a> {
+    File(&'a Path),
+    /// The untitled document with this number; several can be open at once.
+    Untitled(usize),
+}
+
+/// The recovery file of an untitled document, unique to this process.
+fn untitled_path(number: usize) -> Option<PathBuf> {
+    Some(state_dir()?.join(format!("untitled-{}-{number}.ftml", std::process::id())))
+}
+
+/// The newest recovery file of an untitled document whose Pure is gone.
+pub fn find_untitled() -> Option<PathBuf> {
+    orphans_in(&state_dir()?).into_iter().next()
+}
+
+/// The recovery files of untitled documents in `dir` whose process no
+/// longer runs, newest first.
+fn orphans_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut orphans: Vec<(SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name();
+            let pid = name
+                .to_str()
+                .and_then(|name| name.strip_prefix("untitled-"))
+                .and_then(|rest| rest.split_once('-'))
+                .and_then(|(pid, _)| pid.parse().ok());
+            pid.is_some_and(|pid| !lock::process_exists(pid))
+        })
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .collect();
+    orphans.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    orphans.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Where the recovery file of `origin` may go, in order of preference.
+fn candidates(origin: Origin) -> Vec<PathBuf> {
+    match origin {
+        Origin::File(path) => [sibling_path(path), state_path(path)],
+        Origin::Untitled(number) => [untitled_path(number), None],
+    }
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Write `document` to the recovery file of `origin` and return where it
+/// went.
+pub fn write(origin: Origin, document: &Document) -> io::Result<PathBuf> {
+    let contents = Writer::new()
+        .write_to_string(document)
+        .map_err(io::Error::other)?;
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "no location for a recovery file");
+    for candidate in candidates(origin) {
+        let written = candidate
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| save::write_privately(&candidate, contents.as_bytes()));
+        match written {
+            Ok(()) => return Ok(candidate),
+            Err(err) => last_error = err,
+        }
+    }
+    Err(last_error)
+}
+
+/// The newest recovery file of the document at `path` that is newer than
+/// the document itself, i.e. holds changes that never made it into the file.
+pub fn find(path: &Path) -> Option<PathBuf> {
+    let modified = |path: &Path| fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    let document_modified: Option<SystemTime> = modified(path);
+    candidates(Origin::File(path))
+        .into_iter()
+        .filter_map(|candidate| Some((modified(&candidate)?, candidate)))
+        .filter(|(recovered, _)| document_modified.is_none_or(|document| *recovered > document))
+        .max_by_key(|(recovered, _)| *recovered)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Read a recovery file back.
+pub fn read(recovery: &Path) -> Result<Document> {
+    let content = fs::read_to_string(recovery)
+        .with_context(|| format!("failed to read {}", recovery.display()))?;
+    parse(io::Cursor::new(content))
+        .with_context(|| format!("failed to parse {}", recovery.display()))
+}
+
+/// Delete the recovery files of `origin`.
+pub fn remove(origin: Origin) {
+    for candidate in candidates(origin) {
+        let _ = fs::remove_file(candidate);
+    }
+}
+
+/// What to do with a recovery file found when opening a document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecoveryChoice {
+    Restore,
+    Discard,
+}
+
+/// The recovery prompt's buttons, in display order, chosen by their
+/// initials.
+pub const CHOICES: [Choice<RecoveryChoice>; 2] = [
+    Choice {
+        value: RecoveryChoice::Restore,
+        label: "Restore",
+        accelerators: "r",
+    },
+    Choice {
+        value: RecoveryChoice::Discard,
+        label: "Discard",
+        accelerators: "d",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::TempDir;
+    use tdoc::ftml;
+
+    fn set_modified(path: &Path, time: SystemTime) {
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .and_then(|file| file.set_modified(time))
+            .expect("set modification time");
+    }
+
+    #[test]
+    fn recovery_file_goes_next_to_the_document() {
+        let dir = TempDir::new("recovery-sibling");
+        let document_path = dir.join("notes.md");
+        fs::write(&document_path, "# Notes\n").expect("write document");
+
+        let written =
+            write(Origin::File(&document_path), &ftml! { p { "Unsaved" } }).expect("write");
+        assert_eq!(written, dir.join(".notes.md.pure-recovery"));
+        set_modified(&document_path, SystemTime::now() - Duration::from_secs(60));
+        assert_eq!(find(&document_path), Some(written.clone()));
+
+        let restored = read(&written).expect("read back");
+        assert_eq!(restored, ftml! { p { "Unsaved" } });
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&written).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600, "only the owner may read unsaved text");
+        }
+
+        remove(Origin::File(&document_path));
+        assert!(!written.exists());
+        assert_eq!(find(&document_path), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn untitled_recovery_files_of_gone_processes_are_found() {
+        let dir = TempDir::new("recovery-untitled");
+        // Beyond any process id the kernel hands out.
+        let older = dir.join("untitled-999999998-1.ftml");
+        let newer = dir.join("untitled-999999999-2.ftml");
+        let own = dir.join(format!("untitled-{}-1.ftml", std::process::id()));
+        for path in [&older, &newer, &own, &dir.join("untitled-notes.ftml")] {
+            fs::write(path, "<p>Unsaved</p>").expect("write recovery file");
+        }
+        set_modified(&older, SystemTime::now() - Duration::from_secs(60));
+        assert_eq!(orphans_in(&dir), [newer, older]);
+    }
+
+    #[test]
+    fn recovery_files_older_than_the_document_are_ignored() {
+        let dir = TempDir::new("recovery-stale");
+        let document_path = dir.join("notes.ftml");
+        let written = write(Origin::File(&document_path), &ftml! { p { "Old" } }).expect("write");
+        // A document that does not exist yet is older than any recovery file.
+        assert_eq!(find(&document_path), Some(written.clone()));
+
+        fs::write(&document_path, "<p>Saved elsewhere</p>").expect("write document");
+        set_modified(&written, SystemTime::now() - Duration::from_secs(60));
+        assert_eq!(find(&document_path), None);
+    }
+}
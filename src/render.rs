@@ -9,7 +9,8 @@ use unicode_width::UnicodeWidthChar;
 use tdoc::{Document, InlineStyle, Paragraph, ParagraphType, Span as DocSpan};
 
 use crate::editor::{
-    CursorPointer, ParagraphPath, RevealTagKind, RevealTagRef, SegmentKind, SpanPath,
+    CursorPointer, FoldRef, ParagraphPath, RevealTagKind, RevealTagRef, SearchHighlight,
+    SegmentKind, SpanPath,
 };
 use crate::theme::Theme;
 
@@ -27,8 +28,15 @@ pub struct DirectCursorTracking<'a> {
     pub cursor: Option<&'a CursorPointer>,
     pub selection: Option<(&'a CursorPointer, &'a CursorPointer)>,
     pub track_all_positions: bool,
+    /// Search matches to paint, split into per-span parts
+    pub search_highlights: &'a [SearchHighlight],
+    /// Folds to draw as summary lines, with their content left out
+    pub folds: &'a [FoldRef],
 }
 
+/// Marks the head of a fold, before its text.
+const FOLD_MARKER: &str = "▸ ";
+
 #[derive(Clone, Copy, Debug)]
 pub struct CursorVisualPosition {
     pub line: usize,
@@ -154,6 +162,23 @@ pub fn layout_paragraph(
     // Render the paragraph
     renderer.render_paragraph(paragraph, prefix);
 
+    // A paragraph in a folded section takes no lines at all. It is still
+    // rendered for the selection state it leaves behind.
+    if renderer
+        .folds
+        .iter()
+        .any(|fold| fold.hidden_roots.contains(&paragraph_index))
+    {
+        return ParagraphLayout {
+            lines: Vec::new(),
+            line_metrics: Vec::new(),
+            positions: Vec::new(),
+            cursor: None,
+            line_count: 0,
+            selection_active_end: renderer.selection_active,
+        };
+    }
+
     // Extract results
     let lines = renderer.lines;
     let line_metrics = renderer.line_metrics;
@@ -211,6 +236,8 @@ struct DirectRenderer<'a> {
     selection_start: Option<&'a CursorPointer>,
     selection_end: Option<&'a CursorPointer>,
     track_all_positions: bool,
+    search_highlights: &'a [SearchHighlight],
+    folds: &'a [FoldRef],
 
     // Current position during rendering
     current_paragraph_index: usize,
@@ -264,6 +291,8 @@ impl<'a> DirectRenderer<'a> {
             selection_start: direct_tracking.selection.map(|(start, _)| start),
             selection_end: direct_tracking.selection.map(|(_, end)| end),
             track_all_positions: direct_tracking.track_all_positions,
+            search_highlights: direct_tracking.search_highlights,
+            folds: direct_tracking.folds,
             current_paragraph_index: 0,
             current_paragraph_path: ParagraphPath::default(),
             marker_pending: HashMap::new(),
@@ -280,9 +309,17 @@ impl<'a> DirectRenderer<'a> {
         let mut selection_active = false;
         let mut pending_bottom_margin = 0usize;
         for (idx, paragraph) in document.paragraphs.iter().enumerate() {
+            let hidden = self
+                .folds
+                .iter()
+                .any(|fold| fold.hidden_roots.contains(&idx));
             let (top_margin, bottom_margin) = Self::paragraph_margins(paragraph);
             let base_gap = if idx > 0 { 1 } else { 0 };
-            let margin_lines = base_gap.max(pending_bottom_margin).max(top_margin);
+            let margin_lines = if hidden {
+                0
+            } else {
+                base_gap.max(pending_bottom_margin).max(top_margin)
+            };
 
             for _ in 0..margin_lines {
                 self.push_plain_line("", true);
@@ -296,6 +333,8 @@ impl<'a> DirectRenderer<'a> {
                 cursor: self.cursor_pointer,
                 selection: self.selection_start.zip(self.selection_end),
                 track_all_positions: self.track_all_positions,
+                search_highlights: self.search_highlights,
+                folds: self.folds,
             };
 
             let reveal_tags: Vec<RevealTagRef> = self.reveal_tags.values().cloned().collect();
@@ -374,7 +413,9 @@ impl<'a> DirectRenderer<'a> {
                 positions: paragraph_positions,
             });
 
-            pending_bottom_margin = bottom_margin;
+            if !hidden {
+                pending_bottom_margin = bottom_margin;
+            }
         }
 
         for _ in 0..pending_bottom_margin {
@@ -432,6 +473,32 @@ impl<'a> DirectRenderer<'a> {
         first_prefix: &str,
         continuation_prefix: &str,
     ) {
+        let lines = self.text_paragraph_lines(paragraph, first_prefix, continuation_prefix);
+        self.consume_lines_direct(lines);
+    }
+
+    /// Render a text paragraph as the head of `fold`: behind the fold
+    /// marker, and followed by what the fold hides.
+    fn render_folded_text(
+        &mut self,
+        paragraph: &Paragraph,
+        first_prefix: &str,
+        continuation_prefix: &str,
+        fold: &FoldRef,
+    ) {
+        let first_prefix = format!("{first_prefix}{FOLD_MARKER}");
+        let continuation_prefix = format!("{continuation_prefix}  ");
+        let mut lines = self.text_paragraph_lines(paragraph, &first_prefix, &continuation_prefix);
+        self.append_fold_summary(&mut lines, fold);
+        self.consume_lines_direct(lines);
+    }
+
+    fn text_paragraph_lines(
+        &mut self,
+        paragraph: &Paragraph,
+        first_prefix: &str,
+        continuation_prefix: &str,
+    ) -> Vec<LineOutput> {
         let mut fragments = Vec::new();
         let base_span_path = SpanPath {
             indices: Vec::new(),
@@ -443,13 +510,41 @@ impl<'a> DirectRenderer<'a> {
             &mut fragments,
         );
         let fragments = trim_layout_fragments(fragments);
-        let lines = self.wrap_fragments_direct(
+        self.wrap_fragments_direct(
             &fragments,
             first_prefix,
             continuation_prefix,
             self.wrap_limit,
-        );
-        self.consume_lines_direct(lines);
+        )
+    }
+
+    /// The fold whose head is the paragraph being rendered, if any.
+    fn current_fold(&self) -> Option<&'a FoldRef> {
+        self.folds
+            .iter()
+            .find(|fold| fold.path == self.current_paragraph_path)
+    }
+
+    /// Add the count of what `fold` hides to the end of `lines`.
+    fn append_fold_summary(&self, lines: &mut Vec<LineOutput>, fold: &FoldRef) {
+        let segment = LineSegment {
+            text: format!(" ({})", fold.summary),
+            style: self.theme.structural_style(),
+        };
+        match lines.last_mut() {
+            Some(line) => line.spans.push(segment),
+            None => lines.push(LineOutput {
+                spans: vec![segment],
+                events: Vec::new(),
+            }),
+        }
+    }
+
+    /// The line standing in for folded content that does not start with
+    /// text of its own.
+    fn push_fold_summary_line(&mut self, prefix: &str, fold: &FoldRef) {
+        let line = format!("{prefix}{FOLD_MARKER}({})", fold.summary);
+        self.push_styled_line(&line, self.theme.structural_style(), false);
     }
 
     fn render_header(&mut self, paragraph: &Paragraph, prefix: &str, level: HeaderLevel) {
@@ -464,7 +559,18 @@ impl<'a> DirectRenderer<'a> {
             &mut fragments,
         );
         let fragments = trim_layout_fragments(fragments);
-        let mut lines = self.wrap_fragments_direct(&fragments, prefix, prefix, self.wrap_limit);
+        // A folded heading is a single summary line for its whole section.
+        let fold = self.current_fold();
+        let (first_prefix, continuation_prefix) = match fold {
+            Some(_) => (format!("{prefix}{FOLD_MARKER}"), format!("{prefix}  ")),
+            None => (prefix.to_string(), prefix.to_string()),
+        };
+        let mut lines = self.wrap_fragments_direct(
+            &fragments,
+            &first_prefix,
+            &continuation_prefix,
+            self.wrap_limit,
+        );
 
         match level {
             HeaderLevel::One => {
@@ -483,13 +589,17 @@ impl<'a> DirectRenderer<'a> {
             }
         }
 
+        if let Some(fold) = fold {
+            self.append_fold_summary(&mut lines, fold);
+        }
+
         if matches!(level, HeaderLevel::One) {
             self.center_header_lines(&mut lines, prefix);
         }
 
         self.consume_lines_direct(lines);
 
-        if matches!(level, HeaderLevel::Two | HeaderLevel::Three) {
+        if fold.is_none() && matches!(level, HeaderLevel::Two | HeaderLevel::Three) {
             let width = self.lines.last().map(|line| line_width(line)).unwrap_or(0);
             let underline_char = match level {
                 HeaderLevel::Two => '=',
@@ -589,111 +699,201 @@ impl<'a> DirectRenderer<'a> {
         self.push_styled_line(&fence, self.theme.structural_style(), false);
     }
 
-    /// Render a (read-only) table.
+    /// Render a table as a box-drawn grid.
     ///
-    /// The table is laid out by tdoc's ANSI formatter into a box-drawn,
-    /// multi-line block; we parse that output back into styled ratatui lines.
-    /// Each rendered line is treated like an immutable line of preformatted
-    /// text: the cursor can be positioned at any character offset (so vertical
-    /// and horizontal navigation pass transparently through the table), but the
-    /// editor rejects insertions/deletions while the cursor sits inside it.
+    /// Every cell is laid out like a small paragraph of its own: its spans are
+    /// collected into fragments (so cursor, selection and reveal-code events
+    /// work as in ordinary text) and wrapped to the width allocated to its
+    /// column. The column widths follow tdoc's formatter, so the grid looks
+    /// the same as when the document is printed.
     fn render_table(&mut self, paragraph: &Paragraph, prefix: &str) {
         let prefix_width = visible_width(prefix);
-        // Width budget handed to the formatter: the room left on the line after
-        // our own prefix/indentation. The formatter draws its own borders within
-        // this budget; we prepend `prefix` (and left padding) ourselves.
-        let content_width = self
-            .wrap_width
-            .saturating_sub(prefix_width)
-            .max(MIN_TABLE_WIDTH);
-        let mut rendered = render_table_lines(paragraph, content_width);
-        if rendered.is_empty() {
-            // Keep an empty/degenerate table navigable with a single stop.
-            rendered.push(RenderedTableLine::default());
+        let column_count = paragraph
+            .rows()
+            .iter()
+            .map(|row| row.cells.len())
+            .max()
+            .unwrap_or(0);
+        if column_count == 0 {
+            // Keep an empty table navigable with a single stop.
+            let base_span_path = SpanPath::new(Vec::new());
+            let fragments = self.empty_content_fragments(&base_span_path);
+            let lines = self.wrap_fragments_direct(&fragments, prefix, prefix, self.wrap_limit);
+            self.consume_lines_direct(lines);
+            return;
         }
 
-        let padding = self.left_padding.min(u16::MAX as usize) as u16;
+        let mut cells: Vec<Vec<TableCellFragments>> = Vec::new();
+        for (row_index, row) in paragraph.rows().iter().enumerate() {
+            let mut row_cells = Vec::with_capacity(row.cells.len());
+            for (column_index, cell) in row.cells.iter().enumerate() {
+                self.current_paragraph_path
+                    .push_table_cell(row_index, column_index);
+                let base_span_path = SpanPath::new(Vec::new());
+                let mut fragments = Vec::new();
+                self.collect_fragments_direct(
+                    &cell.content,
+                    &base_span_path,
+                    Style::default(),
+                    &mut fragments,
+                );
+                if cell.content.is_empty() {
+                    fragments = self.empty_content_fragments(&base_span_path);
+                }
+                self.current_paragraph_path.pop();
+                row_cells.push(TableCellFragments {
+                    fragments: trim_layout_fragments(fragments),
+                    is_header: cell.is_header,
+                });
+            }
+            cells.push(row_cells);
+        }
 
-        for (line_index, rendered_line) in rendered.iter().enumerate() {
-            let mut spans: Vec<Span<'static>> = Vec::new();
-            if !prefix.is_empty() {
-                spans.extend(self.style_structural_prefix(prefix, Style::default()));
+        let mut natural = vec![0usize; column_count];
+        let mut minimum = vec![0usize; column_count];
+        for row in &cells {
+            for (column, cell) in row.iter().enumerate() {
+                let (cell_natural, cell_minimum) = fragment_extents(&cell.fragments);
+                natural[column] = natural[column].max(cell_natural);
+                minimum[column] = minimum[column].max(cell_minimum);
             }
-            for (text, style) in &rendered_line.spans {
-                spans.push(Span::styled(text.clone(), *style));
+        }
+        // Each column adds two spaces of padding and one border; the grid
+        // closes with one more border on the right.
+        let structural = 3 * column_count + 1;
+        let budget = self
+            .wrap_limit
+            .saturating_sub(prefix_width)
+            .saturating_sub(structural);
+        let mut widths = allocate_table_widths(&natural, &minimum, budget);
+        for width in &mut widths {
+            *width = (*width).max(1);
+        }
+
+        // Wrap all cells in document order so selection state carries over
+        // from one cell to the next.
+        let mut wrapped: Vec<Vec<Vec<LineOutput>>> = Vec::with_capacity(cells.len());
+        for row in &cells {
+            let mut row_lines = Vec::with_capacity(row.len());
+            for (column, cell) in row.iter().enumerate() {
+                let mut lines = self.wrap_fragments_direct(&cell.fragments, "", "", widths[column]);
+                if cell.is_header {
+                    for line in &mut lines {
+                        for segment in &mut line.spans {
+                            segment.style = segment.style.add_modifier(Modifier::BOLD);
+                        }
+                    }
+                }
+                for line in &mut lines {
+                    // Whitespace at a wrap point may overhang the column;
+                    // drop it rather than widening the whole column.
+                    let overhang = segment_width(&line.spans).saturating_sub(widths[column]);
+                    trim_trailing_whitespace(&mut line.spans, overhang);
+                    widths[column] = widths[column].max(segment_width(&line.spans));
+                }
+                row_lines.push(lines);
             }
-            let spans = self.prepend_padding(spans);
-            self.lines.push(Line::from(spans));
-            self.line_metrics.push(LineMetric {
-                counts_as_content: true,
-            });
+            wrapped.push(row_lines);
+        }
 
-            // Emit a cursor stop for every character boundary on this line so the
-            // cursor can be placed anywhere within the rendered grid. The span
-            // path stores the rendered line index; the offset is the character
-            // index. These mirror the synthetic per-line segments the editor
-            // builds for the table (see EditorDisplay::sync_readonly_segments).
-            let span_path = SpanPath::new(vec![line_index]);
-            let mut content_column = prefix_width;
-            let mut chars = rendered_line.plain.chars();
-            let char_count = rendered_line.plain.chars().count();
-            for offset in 0..=char_count {
-                let col = content_column.min(u16::MAX as usize) as u16;
-                self.record_table_position(&span_path, offset, col, col.saturating_add(padding));
-                if let Some(ch) = chars.next() {
-                    content_column += UnicodeWidthChar::width(ch).unwrap_or(0);
+        let structural_style = self.theme.structural_style();
+        self.push_styled_line(
+            &table_rule(prefix, &widths, '┌', '┬', '┐'),
+            structural_style,
+            false,
+        );
+        for (row_index, row_lines) in wrapped.into_iter().enumerate() {
+            if row_index > 0 {
+                self.push_styled_line(
+                    &table_rule(prefix, &widths, '├', '┼', '┤'),
+                    structural_style,
+                    false,
+                );
+            }
+            let height = row_lines.iter().map(Vec::len).max().unwrap_or(0).max(1);
+            let mut outputs = Vec::with_capacity(height);
+            for line_index in 0..height {
+                let mut output = LineOutput {
+                    spans: Vec::new(),
+                    events: Vec::new(),
+                };
+                if !prefix.is_empty() {
+                    output.spans.push(LineSegment {
+                        text: prefix.to_string(),
+                        style: Style::default(),
+                    });
+                }
+                let mut column_start = prefix_width;
+                for (column, width) in widths.iter().enumerate() {
+                    output.spans.push(LineSegment {
+                        text: "│ ".to_string(),
+                        style: structural_style,
+                    });
+                    column_start += 2;
+                    let mut used = 0;
+                    if let Some(line) = row_lines
+                        .get(column)
+                        .and_then(|lines| lines.get(line_index))
+                    {
+                        used = segment_width(&line.spans);
+                        output.spans.extend(line.spans.iter().cloned());
+                        let shift = column_start.min(u16::MAX as usize) as u16;
+                        let content_shift =
+                            (column_start - prefix_width).min(u16::MAX as usize) as u16;
+                        output
+                            .events
+                            .extend(line.events.iter().map(|event| LocatedEvent {
+                                column: event.column.saturating_add(shift),
+                                content_column: event.content_column.saturating_add(content_shift),
+                                kind: event.kind,
+                            }));
+                    }
+                    output.spans.push(LineSegment {
+                        text: " ".repeat(width.saturating_sub(used) + 1),
+                        style: Style::default(),
+                    });
+                    column_start += width + 1;
                 }
+                output.spans.push(LineSegment {
+                    text: "│".to_string(),
+                    style: structural_style,
+                });
+                outputs.push(output);
             }
-
-            self.current_line_index += 1;
+            self.consume_lines_direct(outputs);
         }
+        self.push_styled_line(
+            &table_rule(prefix, &widths, '└', '┴', '┘'),
+            structural_style,
+            false,
+        );
     }
 
-    /// Record a navigable position inside a table at an already-known visual
-    /// column. Mirrors the cursor/marker bookkeeping that `check_position_match`
-    /// and `consume_lines_direct` perform for ordinary text, but without routing
-    /// through the fragment/wrapping pipeline (the formatter already laid the
-    /// line out for us).
-    fn record_table_position(
-        &mut self,
-        span_path: &SpanPath,
-        offset: usize,
-        content_column: u16,
-        column: u16,
-    ) {
-        let pending = PendingPosition {
-            line: self.current_line_index,
-            column,
-            content_column,
-        };
-
-        if let Some(cursor) = self.cursor_pointer
-            && cursor.paragraph_path == self.current_paragraph_path
-            && cursor.span_path == *span_path
-            && cursor.offset == offset
-            && cursor.segment_kind == SegmentKind::Text
-        {
-            self.cursor_pending = Some(pending);
-        }
-
-        if self.track_all_positions {
-            let marker_id = self.next_marker_id;
-            self.next_marker_id += 1;
-            self.marker_to_pointer.insert(
-                marker_id,
-                CursorPointer {
-                    paragraph_path: self.current_paragraph_path.clone(),
-                    span_path: span_path.clone(),
-                    offset,
-                    segment_kind: SegmentKind::Text,
-                },
-            );
-            self.marker_pending.insert(marker_id, pending);
+    /// A zero-width fragment carrying the position events for offset 0, so
+    /// content roots without any spans still get a cursor stop.
+    fn empty_content_fragments(&mut self, base_span_path: &SpanPath) -> Vec<FragmentItem> {
+        let position_events = self.check_position_match(base_span_path, 0, SegmentKind::Text);
+        if position_events.is_empty() {
+            return Vec::new();
         }
+        let frag = DirectFragment {
+            text: String::new(),
+            style: Style::default(),
+            kind: FragmentKind::Word,
+            width: 0,
+            content_width: 0,
+            events: position_events,
+            reveal_kind: None,
+        };
+        vec![FragmentItem::Token(self.convert_direct_fragment(frag))]
     }
 
     fn render_quote(&mut self, paragraph: &Paragraph, prefix: &str) {
         let quote_prefix = format!("{}| ", prefix);
+        if let Some(fold) = self.current_fold() {
+            self.render_folded_quote(paragraph, &quote_prefix, fold);
+            return;
+        }
         if !paragraph.content().is_empty() {
             self.render_text_paragraph(paragraph, &quote_prefix, &quote_prefix);
         }
@@ -709,6 +909,25 @@ impl<'a> DirectRenderer<'a> {
         }
     }
 
+    /// Render only the first paragraph of a folded quote.
+    fn render_folded_quote(&mut self, paragraph: &Paragraph, quote_prefix: &str, fold: &FoldRef) {
+        if !paragraph.content().is_empty() {
+            self.render_folded_text(paragraph, quote_prefix, quote_prefix, fold);
+            return;
+        }
+        let Some(first) = paragraph.children().first() else {
+            return;
+        };
+        self.current_paragraph_path.push_child(0);
+        if first.paragraph_type() == ParagraphType::Text {
+            self.render_folded_text(first, quote_prefix, quote_prefix, fold);
+        } else {
+            self.render_paragraph(first, quote_prefix);
+            self.push_fold_summary_line(quote_prefix, fold);
+        }
+        self.current_paragraph_path.pop();
+    }
+
     fn render_unordered_list(&mut self, paragraph: &Paragraph, prefix: &str) {
         for (entry_idx, entry) in paragraph.entries().iter().enumerate() {
             if entry_idx > 0 {
@@ -764,9 +983,14 @@ impl<'a> DirectRenderer<'a> {
         self.current_paragraph_path
             .push_checklist_item(indices.clone());
 
+        let fold = self.current_fold();
         let marker = if item.checked { "[✓] " } else { "[ ] " };
-        let first_prefix = format!("{}{}", prefix, marker);
-        let continuation_prefix = format!("{}{}", prefix, " ".repeat(marker.chars().count()));
+        let mut first_prefix = format!("{}{}", prefix, marker);
+        let mut continuation_prefix = format!("{}{}", prefix, " ".repeat(marker.chars().count()));
+        if fold.is_some() {
+            first_prefix.push_str(FOLD_MARKER);
+            continuation_prefix.push_str("  ");
+        }
 
         let mut fragments = Vec::new();
         let base_span_path = SpanPath {
@@ -781,33 +1005,28 @@ impl<'a> DirectRenderer<'a> {
 
         // For empty content, ensure we track at least position 0
         if item.content.is_empty() {
-            let position_events = self.check_position_match(&base_span_path, 0, SegmentKind::Text);
-            if !position_events.is_empty() {
-                // Create a zero-width fragment with the position events
-                let frag = DirectFragment {
-                    text: String::new(),
-                    style: Style::default(),
-                    kind: FragmentKind::Word,
-                    width: 0,
-                    content_width: 0,
-                    events: position_events,
-                    reveal_kind: None,
-                };
-                fragments.push(FragmentItem::Token(self.convert_direct_fragment(frag)));
-            }
+            fragments = self.empty_content_fragments(&base_span_path);
         }
 
         let fragments = trim_layout_fragments(fragments);
-        let lines = self.wrap_fragments_direct(
+        let mut lines = self.wrap_fragments_direct(
             &fragments,
             &first_prefix,
             &continuation_prefix,
             self.wrap_limit,
         );
+        if let Some(fold) = fold {
+            self.append_fold_summary(&mut lines, fold);
+        }
         self.consume_lines_direct(lines);
 
-        // Render nested checklist items with extended indices
-        for (child_idx, child) in item.children.iter().enumerate() {
+        // Render nested checklist items with extended indices, unless folded
+        let children = if fold.is_some() {
+            &[][..]
+        } else {
+            &item.children[..]
+        };
+        for (child_idx, child) in children.iter().enumerate() {
             let child_prefix = format!("{}    ", prefix);
             let mut child_indices = indices.clone();
             child_indices.push(child_idx);
@@ -879,6 +1098,19 @@ impl<'a> DirectRenderer<'a> {
             // Update paragraph path for this entry paragraph
             self.current_paragraph_path.push_entry(entry_idx, para_idx);
 
+            // A folded entry shows its first paragraph only.
+            if let Some(fold) = self.current_fold() {
+                if first.paragraph_type() == ParagraphType::Text {
+                    self.render_folded_text(first, first_prefix, continuation_prefix, fold);
+                } else {
+                    self.push_plain_line(first_prefix, false);
+                    self.render_paragraph(first, continuation_prefix);
+                    self.push_fold_summary_line(continuation_prefix, fold);
+                }
+                self.current_paragraph_path.pop();
+                return;
+            }
+
             match first.paragraph_type() {
                 ParagraphType::Text => {
                     self.render_text_paragraph(first, first_prefix, continuation_prefix);
@@ -1099,6 +1331,14 @@ impl<'a> DirectRenderer<'a> {
         let mut builder: Option<DirectTokenBuilder> = None;
         let mut buffer: Vec<char> = Vec::new();
         let chars: Vec<char> = text.chars().collect();
+        let highlights: Vec<&SearchHighlight> = self
+            .search_highlights
+            .iter()
+            .filter(|highlight| {
+                highlight.span_path == *span_path
+                    && highlight.paragraph_path == self.current_paragraph_path
+            })
+            .collect();
 
         for (char_offset, ch) in chars.iter().enumerate() {
             // Check if this position matches any cursor we're tracking
@@ -1139,11 +1379,26 @@ impl<'a> DirectRenderer<'a> {
                 &buffer
             };
 
+            // Search matches change the style mid-word, so they end the
+            // current token like a change in whitespace does.
+            let char_style = match highlights
+                .iter()
+                .find(|highlight| highlight.start <= char_offset && char_offset < highlight.end)
+            {
+                Some(highlight) if highlight.current => {
+                    style.patch(self.theme.search_current_style())
+                }
+                Some(_) => style.patch(self.theme.search_match_style()),
+                None => style,
+            };
+
             for actual in expanded {
                 let is_whitespace = actual.is_whitespace();
                 if builder
                     .as_ref()
-                    .map(|existing| existing.kind_matches(is_whitespace))
+                    .map(|existing| {
+                        existing.kind_matches(is_whitespace) && existing.style == char_style
+                    })
                     .unwrap_or(false)
                 {
                     if let Some(current) = builder.as_mut() {
@@ -1156,7 +1411,7 @@ impl<'a> DirectRenderer<'a> {
                             self.convert_direct_fragment(existing.finish()),
                         ));
                     }
-                    let mut new_builder = DirectTokenBuilder::new(style, is_whitespace);
+                    let mut new_builder = DirectTokenBuilder::new(char_style, is_whitespace);
                     new_builder.add_events(position_events.clone());
                     new_builder.push_char(*actual);
                     builder = Some(new_builder);
@@ -1509,6 +1764,7 @@ impl<'a> DirectRenderer<'a> {
         // - Checklist: "[✓] " or "[ ] "
         // - Ordered list: "1. ", "2. ", etc.
         // - Quote: "| "
+        // - Fold: "▸ "
         if text.starts_with("• ") {
             Some(text.split_at("• ".len()))
         } else if text.starts_with("[✓] ") {
@@ -1517,6 +1773,8 @@ impl<'a> DirectRenderer<'a> {
             Some(text.split_at("[ ] ".len()))
         } else if text.starts_with("| ") {
             Some(text.split_at("| ".len()))
+        } else if text.starts_with(FOLD_MARKER) {
+            Some(text.split_at(FOLD_MARKER.len()))
         } else if let Some(prefix_end) = text.find(". ") {
             // Check if it's a number followed by ". "
             let prefix = &text[..prefix_end];
@@ -2180,156 +2438,172 @@ impl<'a> LineBuilder<'a> {
     }
 }
 
-/// Smallest content width handed to the table formatter, so a deeply nested or
-/// very narrow viewport still produces a (cramped but valid) grid.
-const MIN_TABLE_WIDTH: usize = 8;
-
-/// A single line of a table after it has been rendered by tdoc's ANSI formatter
-/// and parsed back into ratatui styling.
-#[derive(Debug, Clone, Default)]
-struct RenderedTableLine {
-    /// Visible text with all escape sequences stripped. Used for cursor
-    /// offset/width math and for the synthetic per-line editor segments.
-    plain: String,
-    /// Styled runs that reproduce the formatter's ANSI styling.
-    spans: Vec<(String, Style)>,
+/// The collected fragments of one table cell, ready to be wrapped once the
+/// column widths are known.
+struct TableCellFragments {
+    fragments: Vec<FragmentItem>,
+    is_header: bool,
 }
 
-/// Render a `Paragraph::Table` to styled lines using tdoc's ANSI formatter.
-///
-/// The table is wrapped in a throwaway single-paragraph document and rendered
-/// with [`FormattingStyle::ansi`] (Unicode box-drawing borders + SGR emphasis),
-/// then split into lines whose SGR sequences are translated into ratatui
-/// [`Style`]s. OSC 8 hyperlinks and link footnotes are disabled so the only
-/// escape sequences we have to interpret are simple SGR codes.
-fn render_table_lines(table: &Paragraph, content_width: usize) -> Vec<RenderedTableLine> {
-    use tdoc::formatter::{Formatter, FormattingStyle};
-
-    let mut style = FormattingStyle::ansi();
-    style.wrap_width = content_width.max(MIN_TABLE_WIDTH);
-    style.left_padding = 0;
-    style.enable_osc8_hyperlinks = false;
-    style.link_footnotes = false;
-
-    let doc = Document::new().with_paragraphs(vec![table.clone()]);
-    let mut buf: Vec<u8> = Vec::new();
-    if Formatter::new(&mut buf, style)
-        .write_document(&doc)
-        .is_err()
-    {
-        return Vec::new();
-    }
-    let text = String::from_utf8_lossy(&buf);
-
-    let mut lines: Vec<RenderedTableLine> = text.split('\n').map(parse_ansi_table_line).collect();
-    // Trim blank lines the document formatter adds around the block (paragraph
-    // spacing, the trailing newline, and the final reset escape on its own line).
-    while lines.last().is_some_and(|line| line.plain.is_empty()) {
-        lines.pop();
-    }
-    while lines.first().is_some_and(|line| line.plain.is_empty()) {
-        lines.remove(0);
+/// The natural width (widest line) and minimum width (widest unbreakable
+/// word) of a cell's fragments.
+fn fragment_extents(fragments: &[FragmentItem]) -> (usize, usize) {
+    let mut natural = 0;
+    let mut minimum = 0;
+    let mut line = 0;
+    for item in fragments {
+        match item {
+            FragmentItem::LineBreak => line = 0,
+            FragmentItem::Token(fragment) => {
+                line += fragment.width;
+                if fragment.kind != FragmentKind::Whitespace {
+                    minimum = minimum.max(fragment.width);
+                }
+            }
+        }
+        natural = natural.max(line);
     }
-    lines
+    (natural, minimum)
 }
 
-/// The per-line character counts a table renders to at the given content width.
-///
-/// This is the single source of truth the editor uses to build its synthetic
-/// per-line cursor segments for a table, so navigation stays in lockstep with
-/// what [`DirectRenderer::render_table`] draws (both go through
-/// [`render_table_lines`] at the same width). Returns one entry per rendered
-/// line; an empty vec means the table produced no output.
-pub(crate) fn table_line_char_counts(table: &Paragraph, content_width: usize) -> Vec<usize> {
-    render_table_lines(table, content_width)
+fn segment_width(segments: &[LineSegment]) -> usize {
+    segments
         .iter()
-        .map(|line| line.plain.chars().count())
-        .collect()
+        .map(|segment| visible_width(&segment.text))
+        .sum()
 }
 
-/// Parse a single formatter output line, splitting it into styled runs at SGR
-/// boundaries. Only the SGR codes that [`FormattingStyle::ansi`] emits are
-/// recognised; anything else is treated as literal text.
-fn parse_ansi_table_line(raw: &str) -> RenderedTableLine {
-    let mut plain = String::new();
-    let mut spans: Vec<(String, Style)> = Vec::new();
-    let mut current = String::new();
-    let mut mods = Modifier::empty();
-    let mut chars = raw.chars().peekable();
-
-    while let Some(ch) = chars.next() {
-        if ch == '\x1b' {
-            if chars.peek() == Some(&'[') {
-                chars.next(); // consume '['
-                let mut code = String::new();
-                let mut terminated = false;
-                for c in chars.by_ref() {
-                    if c == 'm' {
-                        terminated = true;
-                        break;
-                    }
-                    if c.is_ascii_digit() || c == ';' {
-                        code.push(c);
-                    } else {
-                        // Not an SGR sequence we understand; stop consuming.
-                        break;
-                    }
-                }
-                if terminated {
-                    let new_mods = apply_sgr_modifiers(mods, &code);
-                    if new_mods != mods {
-                        if !current.is_empty() {
-                            spans.push((std::mem::take(&mut current), modifier_style(mods)));
-                        }
-                        mods = new_mods;
-                    }
+/// Removes up to `width` columns of trailing whitespace from a wrapped line.
+fn trim_trailing_whitespace(segments: &mut Vec<LineSegment>, mut width: usize) {
+    while width > 0 {
+        let Some(last) = segments.last_mut() else {
+            break;
+        };
+        match last.text.chars().last() {
+            Some(ch) if ch.is_whitespace() => {
+                last.text.pop();
+                width = width.saturating_sub(UnicodeWidthChar::width(ch).unwrap_or(0).max(1));
+                if last.text.is_empty() {
+                    segments.pop();
                 }
             }
-            // Lone ESC (or an unrecognised sequence) is dropped.
-            continue;
+            _ => break,
         }
-        current.push(ch);
-        plain.push(ch);
     }
+}
 
-    if !current.is_empty() {
-        spans.push((current, modifier_style(mods)));
+/// A horizontal table rule such as `┌───┬───┐` for the given column widths.
+fn table_rule(prefix: &str, widths: &[usize], left: char, middle: char, right: char) -> String {
+    let mut rule = String::from(prefix);
+    rule.push(left);
+    for (index, width) in widths.iter().enumerate() {
+        if index > 0 {
+            rule.push(middle);
+        }
+        rule.extend(std::iter::repeat_n('─', width + 2));
+    }
+    rule.push(right);
+    rule
+}
+
+/// Distributes `content_budget` columns of cell content across the table's
+/// columns, mirroring tdoc's formatter:
+///
+/// 1. If every column can take its natural width, do so.
+/// 2. Otherwise, give each column its minimum (longest-word) width and share
+///    the remaining room in proportion to how much each column wants to grow.
+/// 3. If even the minimum widths do not fit, split the budget in proportion
+///    to the natural widths, flooring each column at one.
+fn allocate_table_widths(
+    natural: &[usize],
+    minimum: &[usize],
+    content_budget: usize,
+) -> Vec<usize> {
+    let column_count = natural.len();
+    if column_count == 0 {
+        return Vec::new();
+    }
+
+    let natural_total: usize = natural.iter().sum();
+    if natural_total <= content_budget {
+        return natural.to_vec();
+    }
+
+    let minimum_total: usize = minimum.iter().sum();
+    if minimum_total <= content_budget {
+        let slack = content_budget - minimum_total;
+        let wants: Vec<usize> = (0..column_count)
+            .map(|i| natural[i].saturating_sub(minimum[i]))
+            .collect();
+        let extra = proportional_split(slack, &wants);
+        return (0..column_count).map(|i| minimum[i] + extra[i]).collect();
     }
 
-    RenderedTableLine { plain, spans }
+    let mut widths = proportional_split(content_budget, natural);
+    enforce_floor_one(&mut widths);
+    widths
 }
 
-fn modifier_style(mods: Modifier) -> Style {
-    if mods.is_empty() {
-        Style::default()
-    } else {
-        Style::default().add_modifier(mods)
+/// Distributes `amount` across buckets proportionally to `weights`, using the
+/// largest-remainder method so the parts sum to exactly `amount`. When all
+/// weights are zero the amount is spread as evenly as possible.
+fn proportional_split(amount: usize, weights: &[usize]) -> Vec<usize> {
+    let n = weights.len();
+    if n == 0 {
+        return Vec::new();
+    }
+
+    let total: usize = weights.iter().sum();
+    let mut out = vec![0usize; n];
+
+    if total == 0 {
+        let base = amount / n;
+        for slot in out.iter_mut() {
+            *slot = base;
+        }
+        for slot in out.iter_mut().take(amount - base * n) {
+            *slot += 1;
+        }
+        return out;
+    }
+
+    let mut assigned = 0usize;
+    let mut remainders: Vec<(usize, usize)> = Vec::with_capacity(n);
+    for (i, &weight) in weights.iter().enumerate() {
+        let numerator = amount * weight;
+        out[i] = numerator / total;
+        assigned += out[i];
+        remainders.push((numerator % total, i));
+    }
+
+    let mut leftover = amount.saturating_sub(assigned);
+    // Hand the leftover to the columns with the largest fractional parts.
+    remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    for (_, i) in remainders {
+        if leftover == 0 {
+            break;
+        }
+        out[i] += 1;
+        leftover -= 1;
     }
+
+    out
 }
 
-/// Apply the numeric parameters of an SGR (`ESC [ … m`) sequence to the set of
-/// active text modifiers. Unknown parameters are ignored.
-fn apply_sgr_modifiers(mut mods: Modifier, code: &str) -> Modifier {
-    if code.is_empty() {
-        return Modifier::empty(); // `ESC[m` is shorthand for `ESC[0m`.
-    }
-    for part in code.split(';') {
-        match part.parse::<u16>().unwrap_or(0) {
-            0 => mods = Modifier::empty(),
-            1 => mods.insert(Modifier::BOLD),
-            22 => mods.remove(Modifier::BOLD),
-            3 => mods.insert(Modifier::ITALIC),
-            23 => mods.remove(Modifier::ITALIC),
-            4 => mods.insert(Modifier::UNDERLINED),
-            24 => mods.remove(Modifier::UNDERLINED),
-            7 => mods.insert(Modifier::REVERSED),
-            27 => mods.remove(Modifier::REVERSED),
-            9 => mods.insert(Modifier::CROSSED_OUT),
-            29 => mods.remove(Modifier::CROSSED_OUT),
-            _ => {}
-        }
-    }
-    mods
+/// Ensures no column is allocated zero width, stealing a column from the
+/// currently widest column where possible.
+fn enforce_floor_one(widths: &mut [usize]) {
+    for i in 0..widths.len() {
+        if widths[i] == 0 {
+            if let Some(victim) = (0..widths.len())
+                .filter(|&j| widths[j] > 1)
+                .max_by_key(|&j| widths[j])
+            {
+                widths[victim] -= 1;
+            }
+            widths[i] = 1;
+        }
+    }
 }
 
 fn visible_width(text: &str) -> usize {
@@ -2381,6 +2655,8 @@ mod tests {
             cursor: None,
             selection: None,
             track_all_positions: false,
+            search_highlights: &[],
+            folds: &[],
         };
         let theme = Theme::default();
         render_document_direct(&document, width, 0, &[], tracking, &theme)
@@ -2458,6 +2734,8 @@ mod tests {
             cursor: Some(&editor.cursor_pointer()),
             selection: None,
             track_all_positions: false,
+            search_highlights: &[],
+            folds: &[],
         };
         let theme = Theme::default();
         let rendered = render_document_direct(editor.document(), 120, 0, &[], tracking, &theme);
@@ -2479,6 +2757,8 @@ mod tests {
             cursor: None,
             selection: None,
             track_all_positions: false,
+            search_highlights: &[],
+            folds: &[],
         };
         let theme = Theme::default();
         let rendered = render_document_direct(&document, 120, 0, &[], tracking, &theme);
@@ -3011,6 +3291,8 @@ mod tests {
             cursor: Some(&editor.cursor_pointer()),
             selection: None,
             track_all_positions: false,
+            search_highlights: &[],
+            folds: &[],
         };
         let theme = Theme::default();
         let rendered = render_document_direct(editor.document(), 120, 0, &[], tracking, &theme);
@@ -3043,6 +3325,8 @@ mod tests {
             cursor: Some(&editor.cursor_pointer()),
             selection: None,
             track_all_positions: false,
+            search_highlights: &[],
+            folds: &[],
         };
         let theme = Theme::default();
         let rendered = render_document_direct(editor.document(), 120, 0, &[], tracking, &theme);
@@ -3074,6 +3358,8 @@ mod tests {
             cursor: Some(&editor.cursor_pointer()),
             selection: None,
             track_all_positions: false,
+            search_highlights: &[],
+            folds: &[],
         };
         let theme = Theme::default();
         let rendered = render_document_direct(editor.document(), 120, 0, &[], tracking, &theme);
@@ -3096,6 +3382,8 @@ mod tests {
             cursor: Some(&editor.cursor_pointer()),
             selection: None,
             track_all_positions: true,
+            search_highlights: &[],
+            folds: &[],
         };
         let theme = Theme::default();
         let rendered = render_document_direct(editor.document(), 12, 0, &[], tracking, &theme);
@@ -3142,6 +3430,8 @@ mod tests {
             cursor: Some(&editor.cursor_pointer()),
             selection: None,
             track_all_positions: true,
+            search_highlights: &[],
+            folds: &[],
         };
         let theme = Theme::default();
         let rendered = render_document_direct(editor.document(), 10, 0, &[], tracking, &theme);
@@ -3188,6 +3478,8 @@ mod tests {
             cursor: Some(&editor.cursor_pointer()),
             selection: None,
             track_all_positions: false,
+            search_highlights: &[],
+            folds: &[],
         };
         let theme = Theme::default();
         let rendered =
@@ -3229,6 +3521,8 @@ mod tests {
                 cursor: Some(&pointer),
                 selection: None,
                 track_all_positions: false,
+                search_highlights: &[],
+                folds: &[],
             };
             let theme = Theme::default();
             let rendered =
@@ -3322,6 +3616,8 @@ mod tests {
             cursor: Some(&editor.cursor_pointer()),
             selection: None,
             track_all_positions: false,
+            search_highlights: &[],
+            folds: &[],
         };
         let theme = Theme::default();
         let rendered0 = render_document_direct(editor.document(), 120, 0, &[], tracking0, &theme);
@@ -3332,6 +3628,8 @@ mod tests {
             cursor: Some(&editor.cursor_pointer()),
             selection: None,
             track_all_positions: false,
+            search_highlights: &[],
+            folds: &[],
         };
         let rendered4 = render_document_direct(editor.document(), 120, 4, &[], tracking4, &theme);
         let cursor4 = rendered4.cursor.expect("cursor position missing");
@@ -3360,6 +3658,8 @@ mod tests {
             cursor: Some(&editor.cursor_pointer()),
             selection: None,
             track_all_positions: false,
+            search_highlights: &[],
+            folds: &[],
         };
         let theme = Theme::default();
         let rendered =
@@ -3480,6 +3780,8 @@ mod tests {
             cursor: None,
             selection: None,
             track_all_positions: false,
+            search_highlights: &[],
+            folds: &[],
         };
         let theme = Theme::default();
         let rendered =
@@ -3553,23 +3855,75 @@ mod tests {
             cursor: None,
             selection: None,
             track_all_positions: true,
+            search_highlights: &[],
+            folds: &[],
         };
         let theme = Theme::default();
         let rendered = render_document_direct(&document, 40, 0, &[], tracking, &theme);
         let lines = lines_to_strings(&rendered.lines);
 
-        // Unicode box-drawing borders from the ANSI formatter.
-        assert!(
-            lines.iter().any(|line| line.contains('┌')),
-            "expected a top border, got {lines:?}"
+        assert_eq!(
+            lines,
+            vec![
+                "┌───────┬───────────┐",
+                "│ Name  │ Role      │",
+                "├───────┼───────────┤",
+                "│ Alice │ Developer │",
+                "└───────┴───────────┘",
+            ]
         );
-        assert!(lines.iter().any(|line| line.contains('│')));
-        assert!(lines.iter().any(|line| line.contains("Name")));
-        assert!(lines.iter().any(|line| line.contains("Alice")));
-        // No escape sequences should survive parsing into ratatui spans.
+    }
+
+    #[test]
+    fn table_header_cells_are_bold() {
+        let document = Document::new().with_paragraphs(vec![sample_table()]);
+        let tracking = DirectCursorTracking {
+            cursor: None,
+            selection: None,
+            track_all_positions: false,
+            search_highlights: &[],
+            folds: &[],
+        };
+        let theme = Theme::default();
+        let rendered = render_document_direct(&document, 40, 0, &[], tracking, &theme);
+
+        let bold = |line: usize, text: &str| {
+            rendered.lines[line]
+                .spans
+                .iter()
+                .find(|span| span.content.contains(text))
+                .map(|span| span.style.add_modifier.contains(Modifier::BOLD))
+        };
+        assert_eq!(bold(1, "Name"), Some(true));
+        assert_eq!(bold(3, "Alice"), Some(false));
+    }
+
+    #[test]
+    fn table_cells_wrap_within_narrow_width() {
+        use tdoc::{Paragraph, Span as DocSpan, TableCell, TableRow};
+        let table = Paragraph::new_table().with_rows(vec![TableRow::new().with_cells(vec![
+            TableCell::new_data().with_content(vec![DocSpan::new_text("Key")]),
+            TableCell::new_data()
+                .with_content(vec![DocSpan::new_text("a rather long description")]),
+        ])]);
+        let document = Document::new().with_paragraphs(vec![table]);
+        let tracking = DirectCursorTracking {
+            cursor: None,
+            selection: None,
+            track_all_positions: false,
+            search_highlights: &[],
+            folds: &[],
+        };
+        let theme = Theme::default();
+        let rendered = render_document_direct(&document, 24, 0, &[], tracking, &theme);
+        let lines = lines_to_strings(&rendered.lines);
+
+        assert!(lines.len() > 3, "expected the long cell to wrap: {lines:?}");
+        let width = lines[0].chars().count();
+        assert!(width < 24, "grid should fit the wrap width: {lines:?}");
         assert!(
-            lines.iter().all(|line| !line.contains('\u{1b}')),
-            "ANSI escapes leaked into rendered text: {lines:?}"
+            lines.iter().all(|line| line.chars().count() == width),
+            "all grid lines should have the same width: {lines:?}"
         );
     }
 
@@ -3580,38 +3934,105 @@ mod tests {
             cursor: None,
             selection: None,
             track_all_positions: true,
+            search_highlights: &[],
+            folds: &[],
         };
         let theme = Theme::default();
         let rendered = render_document_direct(&document, 40, 0, &[], tracking, &theme);
 
-        let total_positions: usize = rendered
+        let positions: Vec<_> = rendered
             .paragraph_lines
             .iter()
-            .map(|info| info.positions.len())
-            .sum();
-        assert!(
-            total_positions > 0,
-            "expected the table to emit navigable cursor positions"
-        );
+            .flat_map(|info| info.positions.iter())
+            .collect();
+        // One stop per character boundary in each of the four cells.
+        assert_eq!(positions.len(), 5 + 5 + 6 + 10);
+
+        // Every position addresses a cell of the table, and sits right after
+        // the cell's left border.
+        let mut alice = ParagraphPath::new_root(0);
+        alice.push_table_cell(1, 0);
+        let (_, start) = positions
+            .iter()
+            .find(|(pointer, _)| pointer.paragraph_path == alice && pointer.offset == 0)
+            .expect("expected a stop at the start of the Alice cell");
+        assert_eq!(start.line, 3);
+        assert_eq!(start.column, 2);
+
+        let mut role = ParagraphPath::new_root(0);
+        role.push_table_cell(0, 1);
+        let (_, start) = positions
+            .iter()
+            .find(|(pointer, _)| pointer.paragraph_path == role && pointer.offset == 0)
+            .expect("expected a stop at the start of the Role cell");
+        assert_eq!(start.line, 1);
+        assert_eq!(start.column, 10);
+    }
 
-        // Every recorded position should point at the table paragraph and carry
-        // a line index in its span path.
-        for info in &rendered.paragraph_lines {
-            for (pointer, _) in &info.positions {
-                assert_eq!(pointer.paragraph_path.root_index(), Some(0));
-                assert!(!pointer.span_path.indices().is_empty());
-            }
-        }
+    #[test]
+    fn allocate_table_widths_shares_slack_by_demand() {
+        assert_eq!(allocate_table_widths(&[3, 5], &[3, 5], 20), vec![3, 5]);
+        // The minimums fit; the slack goes to the column that wants to grow.
+        assert_eq!(allocate_table_widths(&[3, 30], &[3, 8], 20), vec![3, 17]);
+        // Even the minimums do not fit: split by natural width, floor at one.
+        assert_eq!(allocate_table_widths(&[1, 40], &[1, 12], 4), vec![1, 3]);
     }
 
     #[test]
-    fn ansi_parser_translates_bold_and_strips_escapes() {
-        let line = parse_ansi_table_line("\u{1b}[1mBold\u{1b}[22m plain");
-        assert_eq!(line.plain, "Bold plain");
-        assert_eq!(line.spans.len(), 2);
-        assert!(line.spans[0].1.add_modifier.contains(Modifier::BOLD));
-        assert!(!line.spans[1].1.add_modifier.contains(Modifier::BOLD));
-        assert_eq!(line.spans[0].0, "Bold");
-        assert_eq!(line.spans[1].0, " plain");
+    fn folds_render_as_summary_lines() {
+        let document = ftml! {
+            h2 { "Route" }
+            p { "North along the coast." }
+            p { "Two nights each." }
+            h2 { "Packing" }
+            ul {
+                li { p { "Tent" } p { "The small one." } }
+                li { p { "Stove" } }
+            }
+        };
+        let mut editor = DocumentEditor::new(document.clone());
+        editor.toggle_fold();
+        let mut entry = ParagraphPath::new_root(4);
+        entry.push_entry(0, 0);
+        editor.move_to_pointer(&CursorPointer {
+            paragraph_path: entry,
+            span_path: SpanPath::new(vec![0]),
+            offset: 0,
+            segment_kind: SegmentKind::Text,
+        });
+        editor.toggle_fold();
+        let folds = editor.fold_refs();
+
+        let tracking = DirectCursorTracking {
+            cursor: None,
+            selection: None,
+            track_all_positions: false,
+            search_highlights: &[],
+            folds: &folds,
+        };
+        let rendered = render_document_direct(&document, 40, 0, &[], tracking, &Theme::default());
+        let lines = lines_to_strings(&rendered.lines);
+        assert_eq!(
+            lines,
+            vec![
+                "",
+                "",
+                "",
+                "▸ Route (2 paragraphs)",
+                "",
+                "",
+                "",
+                "Packing",
+                "=======",
+                "",
+                "",
+                "• ▸ Tent (1 paragraph)",
+                "",
+                "• Stove",
+            ]
+        );
+        // The hidden paragraphs are there, without any lines.
+        let hidden = &rendered.paragraph_lines[1];
+        assert!(hidden.end_line < hidden.start_line);
     }
 }
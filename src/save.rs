@@ -0,0 +1,228 @@
+//! Crash-safe writing of documents to disk.
+//!
+//! A document is never written in place. Its new contents go to a temporary
+//! file in the same directory, which is flushed to disk and then renamed over
+//! the original — so at any moment the path holds either the complete old or
+//! the complete new version. The temporary file takes over the original's
+//! permissions, and a symlinked document is written through to the file it
+//! points at. Optionally, the previous version is kept as a backup copy.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Whether, and under which name, the previous version of a file is kept
+/// when it is overwritten.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backup {
+    #[default]
+    None,
+    /// `notes.md~`, as Emacs and Vim name their backups.
+    Tilde,
+    /// `notes.md.bak`.
+    Bak,
+}
+
+impl Backup {
+    /// The backup of the file at `path`, if backups are enabled.
+    pub fn path(self, path: &Path) -> Option<PathBuf> {
+        let suffix = match self {
+            Backup::None => return None,
+            Backup::Tilde => "~",
+            Backup::Bak => ".bak",
+        };
+        let mut name = path.file_name()?.to_os_string();
+        name.push(suffix);
+        Some(path.with_file_name(name))
+    }
+}
+
+/// Replace the contents of the file at `path` with `contents`, keeping the
+/// previous version according to `backup`. On failure the file is left
+/// untouched and no temporary file stays behind.
+pub fn write_atomically(path: &Path, contents: &[u8], backup: Backup) -> io::Result<()> {
+    write(path, contents, backup, false)
+}
+
+/// Like [`write_atomically`], for files that may hold text the user would
+/// not share, such as unsaved changes: only the owner can read the file,
+/// whatever the permissions of an earlier version were.
+pub fn write_privately(path: &Path, contents: &[u8]) -> io::Result<()> {
+    write(path, contents, Backup::None, true)
+}
+
+fn write(path: &Path, contents: &[u8], backup: Backup, private: bool) -> io::Result<()> {
+    let target = resolve_symlink(path)?;
+    let previous = match fs::metadata(&target) {
+        Ok(metadata) => Some(metadata),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err),
+    };
+
+    let (temp_path, mut temp) = create_temp_file(&target, private)?;
+    let result = (|| {
+        temp.write_all(contents)?;
+        if let Some(metadata) = previous.as_ref().filter(|_| !private) {
+            temp.set_permissions(metadata.permissions())?;
+        }
+        temp.sync_all()?;
+        drop(temp);
+        if previous.is_some()
+            && let Some(backup_path) = backup.path(&target)
+        {
+            fs::copy(&target, backup_path)?;
+        }
+        fs::rename(&temp_path, &target)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+        return result;
+    }
+
+    // Make the rename itself durable. Not every platform can open (or sync)
+    // a directory, and the file is already in place, so failures are
+    // ignored.
+    if let Ok(dir) = File::open(parent_dir(&target)) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// The file a symlink at `path` points to, so saving replaces the target
+/// rather than the link; any other path is returned as is.
+fn resolve_symlink(path: &Path) -> io::Result<PathBuf> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => match fs::canonicalize(path) {
+            Ok(target) => Ok(target),
+            // A dangling link: create the file it points to.
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                let link = fs::read_link(path)?;
+                Ok(parent_dir(path).join(link))
+            }
+            Err(err) => Err(err),
+        },
+        _ => Ok(path.to_path_buf()),
+    }
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// Create a new, hidden temporary file next to `path`; being in the same
+/// directory (and so on the same file system) lets it be renamed over
+/// `path`. A `private` file is created readable by its owner only.
+fn create_temp_file(path: &Path, private: bool) -> io::Result<(PathBuf, File)> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file path"))?
+        .to_string_lossy();
+    let mut attempt = 0;
+    loop {
+        let temp_path =
+            path.with_file_name(format!(".{name}.{}-{attempt}.tmp", std::process::id()));
+        let mut options = File::options();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        if private {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        match options.open(&temp_path) {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => {
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::TempDir;
+
+    fn entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .expect("list dir")
+            .map(|entry| {
+                entry
+                    .expect("dir entry")
+                    .file_name()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn replaces_the_file_and_keeps_a_backup() {
+        let dir = TempDir::new("save-backup");
+        let path = dir.join("notes.md");
+
+        write_atomically(&path, b"first", Backup::Tilde).expect("create");
+        assert_eq!(entries(&dir), ["notes.md"], "nothing to back up yet");
+
+        write_atomically(&path, b"second", Backup::Tilde).expect("replace");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_to_string(dir.join("notes.md~")).unwrap(), "first");
+
+        write_atomically(&path, b"third", Backup::Bak).expect("replace");
+        assert_eq!(
+            fs::read_to_string(dir.join("notes.md.bak")).unwrap(),
+            "second"
+        );
+        assert_eq!(entries(&dir), ["notes.md", "notes.md.bak", "notes.md~"]);
+    }
+
+    #[test]
+    fn failures_leave_no_temporary_file() {
+        let dir = TempDir::new("save-failure");
+        let path = dir.join("notes.md");
+        fs::write(&path, "kept").expect("seed file");
+        // A directory where the backup should go makes the save fail after
+        // the temporary file was written.
+        fs::create_dir(dir.join("notes.md~")).expect("block backup path");
+
+        assert!(write_atomically(&path, b"lost", Backup::Tilde).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "kept");
+        assert_eq!(entries(&dir), ["notes.md", "notes.md~"]);
+
+        assert!(write_atomically(&dir.join("missing/notes.md"), b"x", Backup::None).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_permissions_and_symlinks() {
+        use std::os::unix::fs::{PermissionsExt, symlink};
+
+        let dir = TempDir::new("save-unix");
+        let path = dir.join("script.md");
+        fs::write(&path, "old").expect("seed file");
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).expect("chmod");
+        let link = dir.join("link.md");
+        symlink("script.md", &link).expect("symlink");
+
+        write_atomically(&link, b"new", Backup::None).expect("save through link");
+        assert!(
+            fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+
+        write_privately(&path, b"secret").expect("save privately");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "secret");
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
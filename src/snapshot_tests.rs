@@ -8,7 +8,7 @@
 use crossterm::event::{Event, KeyCode, KeyModifiers};
 use tdoc::{Document, ftml};
 
-use super::TestApp;
+use super::{TempDir, TestApp};
 
 const WIDTH: u16 = 72;
 const HEIGHT: u16 = 18;
@@ -112,6 +112,39 @@ fn cursor_is_drawn_inside_the_table() {
     );
 }
 
+#[test]
+fn tab_walks_table_cells_and_appends_a_row() {
+    let mut app = TestApp::new(WIDTH, HEIGHT, table_document());
+    app.draw();
+    let (row, line) = app
+        .buffer_lines()
+        .into_iter()
+        .enumerate()
+        .find(|(_, line)| line.contains("Bob"))
+        .expect("Bob row is drawn");
+    let column = line.chars().take_while(|&c| c != 'B').count();
+    app.click(column as u16 + 3, row as u16);
+
+    // Bob -> Designer -> UI, then a new row appears.
+    for _ in 0..3 {
+        app.key(KeyCode::Tab);
+    }
+    app.type_text("Carol");
+    app.key_with(KeyCode::BackTab, KeyModifiers::SHIFT);
+    app.type_text("!");
+    app.draw();
+
+    let lines = app.buffer_lines();
+    assert!(
+        lines.iter().any(|l| l.contains("│ Carol ")),
+        "Tab in the last cell should append a row: {lines:#?}"
+    );
+    assert!(
+        lines.iter().any(|l| l.contains("│ UI!")),
+        "Shift+Tab should return to the previous cell: {lines:#?}"
+    );
+}
+
 #[test]
 fn initial_document() {
     let mut app = sample_app();
@@ -514,15 +547,26 @@ fn open_dialog_loads_selected_file() {
 }
 
 #[test]
-fn open_dialog_asks_before_discarding_unsaved_changes() {
+fn open_dialog_adds_a_buffer() {
     let mut app = sample_app();
     app.type_text("Summer ");
     app.ctrl('o');
     app.type_text("tests/fixtures/beta.md");
     app.key(KeyCode::Enter);
-    assert_svg("file_dialog_unsaved_changes_warning", &mut app);
+    assert_svg("file_dialog_opened_in_new_buffer", &mut app);
+
+    // The edited document stays open in the first buffer.
+    app.key_with(KeyCode::PageUp, KeyModifiers::CONTROL);
+    let screen = app.buffer_lines().join("\n");
+    assert!(screen.contains("Summer"), "got: {screen}");
+    assert!(screen.contains("test.ftml* [1/2]"), "got: {screen}");
+
+    // Opening it again shows the existing buffer.
+    app.ctrl('o');
+    app.type_text("tests/fixtures/beta.md");
     app.key(KeyCode::Enter);
-    assert_svg("file_dialog_discarded_and_opened", &mut app);
+    assert_eq!(app.app.buffer_count(), 2);
+    assert!(app.buffer_lines().join("\n").contains("is already open"));
 }
 
 #[test]
@@ -538,6 +582,7 @@ fn esc_cancels_file_dialog() {
 fn open_save_as_dialog(app: &mut TestApp) {
     app.key_with(KeyCode::Char('f'), KeyModifiers::ALT);
     app.key(KeyCode::Down); // Open...
+    app.key(KeyCode::Down); // Open as Plain Text
     app.key(KeyCode::Down); // Save
     app.key(KeyCode::Down); // Save As...
     app.key(KeyCode::Enter);
@@ -550,46 +595,149 @@ fn untitled_document_shows_untitled_in_status_bar() {
 }
 
 #[test]
-fn ctrl_n_replaces_document_with_untitled_one() {
+fn ctrl_n_starts_an_untitled_document() {
     let mut app = sample_app();
     app.ctrl('n');
     assert_svg("new_document", &mut app);
 }
 
 #[test]
-fn ctrl_n_asks_before_discarding_unsaved_changes() {
+fn ctrl_n_keeps_the_previous_document_open() {
     let mut app = sample_app();
     app.type_text("Summer ");
     app.ctrl('n');
     assert!(
-        app.svg().contains("Summer"),
-        "the first Ctrl+N must only warn, not discard the document"
+        !app.svg().contains("Summer"),
+        "the new document is shown in a buffer of its own"
     );
-    assert_svg("new_document_unsaved_warning", &mut app);
+    app.key_with(KeyCode::PageDown, KeyModifiers::CONTROL);
+    let screen = app.buffer_lines().join("\n");
+    assert!(screen.contains("Summer"), "got: {screen}");
+    assert!(screen.contains("test.ftml* [1/2]"), "got: {screen}");
+}
+
+/// Choose Buffer List... from the Buffers menu.
+fn open_buffer_list(app: &mut TestApp) {
+    app.key_with(KeyCode::Char('b'), KeyModifiers::ALT);
+    app.key(KeyCode::Down); // Previous Buffer
+    app.key(KeyCode::Down); // Buffer List...
+    app.key(KeyCode::Enter);
+}
+
+#[test]
+fn buffer_list_switches_buffers() {
+    let mut app = sample_app();
+    app.type_text("Summer ");
     app.ctrl('n');
-    assert_svg("new_document_after_confirm", &mut app);
+    app.ctrl('n');
+    open_buffer_list(&mut app);
+    assert_svg("buffer_list", &mut app);
+
+    app.type_text("test");
+    app.key(KeyCode::Enter);
+    let screen = app.buffer_lines().join("\n");
+    assert!(screen.contains("Summer"), "got: {screen}");
+    assert!(screen.contains("test.ftml* [1/3]"), "got: {screen}");
 }
 
 #[test]
-fn typing_after_new_document_warning_requires_another_warning() {
+fn closing_a_buffer_asks_before_discarding_changes() {
     let mut app = sample_app();
     app.type_text("Summer ");
     app.ctrl('n');
-    // Editing after the warning invalidates it: the next Ctrl+N warns again.
-    app.type_text("and Winter ");
+    app.key_with(KeyCode::PageUp, KeyModifiers::CONTROL);
+    app.key_with(KeyCode::Char('b'), KeyModifiers::ALT);
+    app.key(KeyCode::Up); // Close Buffer, wrapping around
+    app.key(KeyCode::Enter);
+    assert_svg("close_buffer_prompt", &mut app);
+
+    app.key(KeyCode::Char('d'));
+    assert!(!app.app.should_quit());
+    assert_eq!(app.app.buffer_count(), 1);
+    let screen = app.buffer_lines().join("\n");
+    assert!(!screen.contains("Summer"), "got: {screen}");
+    assert!(screen.contains("Closed test.ftml"), "got: {screen}");
+}
+
+fn run_palette_command(app: &mut TestApp, query: &str) {
+    app.enhance_keyboard();
+    app.key_with(
+        KeyCode::Char('p'),
+        KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+    );
+    app.type_text(query);
+    app.key(KeyCode::Enter);
+}
+
+#[test]
+fn split_view_shows_edits_in_both_panes() {
+    let mut app = sample_app();
+    run_palette_command(&mut app, "split hor");
+    app.type_text("Summer ");
+    assert_svg("split_view_horizontal", &mut app);
+    let screen = app.buffer_lines().join("\n");
+    assert_eq!(screen.matches("Summer Packing List").count(), 2, "{screen}");
+
+    run_palette_command(&mut app, "unsplit");
+    let screen = app.buffer_lines().join("\n");
+    assert_eq!(screen.matches("Summer Packing List").count(), 1, "{screen}");
+}
+
+#[test]
+fn panes_show_different_buffers() {
+    let mut app = sample_app();
+    run_palette_command(&mut app, "split vert");
     app.ctrl('n');
-    assert!(
-        app.svg().contains("Winter"),
-        "Ctrl+N after further edits must warn again instead of discarding"
+    app.type_text("Notes");
+    assert_svg("split_view_vertical", &mut app);
+
+    // F6 moves to the other pane, whose buffer becomes the current one.
+    app.key(KeyCode::F(6));
+    app.type_text("Summer ");
+    let screen = app.buffer_lines().join("\n");
+    assert!(screen.contains("Summer Packing List"), "{screen}");
+    assert!(screen.contains("Notes"), "{screen}");
+    assert!(screen.contains("test.ftml* [1/2]"), "{screen}");
+
+    app.key(KeyCode::F(6));
+    app.type_text("!");
+    let screen = app.buffer_lines().join("\n");
+    assert!(screen.contains("Notes!"), "{screen}");
+    assert!(screen.contains("Untitled* [2/2]"), "{screen}");
+}
+
+#[test]
+fn files_that_fail_to_parse_are_shown_read_only() {
+    let dir = TempDir::new("broken");
+    let source = dir.join("broken.ftml");
+    std::fs::write(
+        &source,
+        "<h1>Packing List</h1>\n<p>Pack the <b>essentials</b>.</p>\nstray text\n<p>Passport</p>\n",
+    )
+    .expect("write broken file");
+    let loaded = crate::app::load_document(&source).expect("load broken file");
+
+    let mut app = TestApp::with_path(
+        WIDTH,
+        HEIGHT,
+        loaded.document,
+        std::path::PathBuf::from("broken.ftml"),
     );
+    app.app
+        .protect(loaded.parse_failure.expect("parse failure reported"));
+    app.type_text("x");
+    assert_svg("parse_failure_read_only", &mut app);
+
+    app.ctrl('s');
+    assert_svg("parse_failure_overwrite_prompt", &mut app);
+    app.key(KeyCode::Esc);
+    assert!(app.buffer_lines().join("\n").contains("Not saved"));
 }
 
 #[test]
 fn saving_untitled_document_opens_save_as_dialog() {
-    let dir = std::env::temp_dir().join(format!("pure-untitled-{}", std::process::id()));
-    std::fs::create_dir_all(&dir).expect("create temp dir");
+    let dir = TempDir::new("untitled");
     let target = dir.join("named.ftml");
-    let _ = std::fs::remove_file(&target);
 
     let mut app = TestApp::untitled(WIDTH, HEIGHT, sample_document());
     app.ctrl('s');
@@ -618,15 +766,12 @@ fn saving_untitled_document_opens_save_as_dialog() {
         contents.contains("More"),
         "the second save must write the edited document, got: {contents}"
     );
-    std::fs::remove_dir_all(&dir).ok();
 }
 
 #[test]
 fn save_as_via_menu_writes_new_file() {
-    let dir = std::env::temp_dir().join(format!("pure-save-as-{}", std::process::id()));
-    std::fs::create_dir_all(&dir).expect("create temp dir");
+    let dir = TempDir::new("save-as");
     let target = dir.join("saved.ftml");
-    let _ = std::fs::remove_file(&target);
 
     let mut app = sample_app();
     open_save_as_dialog(&mut app);
@@ -640,13 +785,11 @@ fn save_as_via_menu_writes_new_file() {
         contents.contains("Packing List"),
         "saved file must contain the document, got: {contents}"
     );
-    std::fs::remove_dir_all(&dir).ok();
 }
 
 #[test]
 fn save_as_requires_confirmation_before_overwriting() {
-    let dir = std::env::temp_dir().join(format!("pure-overwrite-{}", std::process::id()));
-    std::fs::create_dir_all(&dir).expect("create temp dir");
+    let dir = TempDir::new("overwrite");
     let target = dir.join("existing.md");
     std::fs::write(&target, "old contents").expect("seed existing file");
 
@@ -667,7 +810,6 @@ fn save_as_requires_confirmation_before_overwriting() {
         contents.starts_with("# Packing List"),
         "saving as .md must write Markdown, got: {contents}"
     );
-    std::fs::remove_dir_all(&dir).ok();
 }
 
 #[test]
@@ -703,6 +845,7 @@ fn unindent_split_renders_fully_with_cursor_in_place() {
         p { "Tail" }
     };
     let mut app = TestApp::new(40, 14, document);
+    app.enhance_keyboard();
     app.key(KeyCode::Down); // onto the empty item
     app.ctrl('p'); // continuation paragraph within the item
     app.ctrl('['); // unindent: splits the list at the new paragraph
@@ -862,3 +1005,517 @@ fn space_on_open_button_keeps_dialog_and_reports_opening() {
         "Open reports progress in the status line"
     );
 }
+
+#[test]
+fn find_bar_highlights_matches() {
+    let mut app = sample_app();
+    app.ctrl('f');
+    app.type_text("the");
+    assert_svg("find_bar_highlights_matches", &mut app);
+
+    // Enter moves on to the second match, in "before the".
+    app.key(KeyCode::Enter);
+    let lines = app.buffer_lines();
+    assert!(
+        lines.iter().any(|line| line.contains("2 of 2")),
+        "the bar should report the current match: {lines:#?}"
+    );
+
+    // Esc closes the bar and leaves the cursor on the match.
+    app.key(KeyCode::Esc);
+    app.type_text("X");
+    assert!(
+        app.buffer_lines()
+            .iter()
+            .any(|line| line.contains("before Xthe")),
+        "typing goes back into the document"
+    );
+}
+
+#[test]
+fn replace_all_keeps_styles_and_undoes_in_one_step() {
+    let mut app = sample_app();
+    app.ctrl('h');
+    app.type_text("essentials");
+    app.key(KeyCode::Tab);
+    app.type_text("basics");
+    assert_svg("replace_bar", &mut app);
+
+    app.key_with(KeyCode::Char('a'), KeyModifiers::ALT);
+    app.key(KeyCode::Esc);
+    assert_svg("replace_all_keeps_bold", &mut app);
+
+    app.ctrl('z');
+    assert!(
+        app.buffer_lines()
+            .iter()
+            .any(|line| line.contains("Pack the essentials")),
+        "a single undo restores the replaced text"
+    );
+}
+
+#[test]
+fn invalid_regex_is_reported_in_the_find_bar() {
+    let mut app = sample_app();
+    app.ctrl('f');
+    app.key_with(KeyCode::Char('r'), KeyModifiers::ALT);
+    app.type_text("(Pass");
+    let lines = app.buffer_lines();
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.contains("Invalid pattern") && line.contains("[x] Regex")),
+        "the bar should flag the pattern: {lines:#?}"
+    );
+}
+
+#[test]
+fn quitting_a_clean_document_does_not_ask() {
+    let mut app = sample_app();
+    app.ctrl('q');
+    assert!(app.app.should_quit());
+}
+
+#[test]
+fn quit_prompt_guards_unsaved_changes() {
+    let mut app = sample_app();
+    app.type_text("Summer ");
+    app.ctrl('q');
+    assert!(
+        !app.app.should_quit(),
+        "unsaved changes must not be dropped"
+    );
+    assert_svg("quit_prompt", &mut app);
+
+    // Esc returns to the document, keeping the edit.
+    app.key(KeyCode::Esc);
+    assert!(!app.app.should_quit());
+    assert!(app.svg().contains("Summer"));
+
+    // Discard quits without saving.
+    app.ctrl('q');
+    app.key(KeyCode::Char('d'));
+    assert!(app.app.should_quit());
+}
+
+#[test]
+fn quit_prompt_visits_every_buffer_with_unsaved_changes() {
+    let mut app = sample_app();
+    app.type_text("Summer ");
+    app.ctrl('n');
+    app.type_text("Draft");
+    app.ctrl('n');
+    app.ctrl('q');
+    assert!(!app.app.should_quit());
+    let screen = app.buffer_lines().join("\n");
+    assert!(
+        screen.contains("Summer") && screen.contains("Save changes to test.ftml"),
+        "the first buffer with unsaved changes is shown: {screen}"
+    );
+
+    // Discarding closes that buffer and moves on to the next one.
+    app.key(KeyCode::Char('d'));
+    assert!(!app.app.should_quit());
+    let screen = app.buffer_lines().join("\n");
+    assert!(
+        screen.contains("Draft") && screen.contains("Save changes to the untitled"),
+        "got: {screen}"
+    );
+
+    // Cancelling stops quitting; the discarded buffer stays closed.
+    app.key(KeyCode::Esc);
+    assert!(!app.app.should_quit());
+    assert_eq!(app.app.buffer_count(), 2);
+    app.ctrl('q');
+    app.key(KeyCode::Char('d'));
+    assert!(app.app.should_quit());
+}
+
+#[test]
+fn quit_prompt_saves_before_quitting() {
+    let dir = TempDir::new("quit-save");
+    let target = dir.join("notes.ftml");
+    std::fs::write(&target, "<p>old</p>").expect("seed file");
+
+    let mut app = TestApp::with_path(WIDTH, HEIGHT, sample_document(), target.clone());
+    app.type_text("Summer ");
+    app.ctrl('q');
+    // Save is focused, so Enter keeps the work.
+    app.key(KeyCode::Enter);
+    assert!(app.app.should_quit());
+    let contents = std::fs::read_to_string(&target).expect("file saved");
+    assert!(contents.contains("Summer"), "got: {contents}");
+}
+
+#[test]
+fn failed_save_is_reported_in_the_status_line() {
+    let dir = TempDir::new("save-failure");
+    let target = dir.join("missing").join("notes.ftml");
+
+    let mut app = TestApp::with_path(WIDTH, HEIGHT, sample_document(), target.clone());
+    app.type_text("Summer ");
+    app.ctrl('s');
+    assert!(!target.exists());
+    let screen = app.buffer_lines().join("\n");
+    assert!(screen.contains("failed to write"), "got: {screen}");
+    // The changes are still unsaved, so quitting asks first.
+    app.ctrl('q');
+    assert!(!app.app.should_quit());
+}
+
+#[test]
+fn quit_prompt_on_untitled_document_saves_through_save_as() {
+    let dir = TempDir::new("quit-untitled");
+    let target = dir.join("named.ftml");
+
+    let mut app = TestApp::untitled(WIDTH, HEIGHT, sample_document());
+    app.type_text("Summer ");
+    app.ctrl('q');
+    app.key(KeyCode::Char('s'));
+    assert!(!app.app.should_quit(), "quitting waits for the file name");
+    assert!(app.svg().contains("Save As"));
+
+    // Cancelling the dialog cancels the quit, too: a later Save As from
+    // Ctrl+S does not quit.
+    app.key(KeyCode::Esc);
+    app.ctrl('s');
+    app.key(KeyCode::Esc);
+    assert!(!app.app.should_quit());
+
+    app.ctrl('q');
+    app.key(KeyCode::Char('s'));
+    app.type_text(target.to_str().expect("utf-8 temp path"));
+    app.key(KeyCode::Enter);
+    assert!(
+        app.app.should_quit(),
+        "saving under a name completes the quit"
+    );
+    let contents = std::fs::read_to_string(&target).expect("file saved");
+    assert!(contents.contains("Summer"), "got: {contents}");
+}
+
+#[test]
+fn recovery_prompt_restores_unsaved_changes() {
+    let dir = TempDir::new("restore");
+    let recovery_path = dir.join(".test.ftml.pure-recovery");
+    std::fs::write(&recovery_path, "<p>Recovered text</p>").expect("write recovery file");
+
+    let mut app = TestApp::new(WIDTH, HEIGHT, sample_document());
+    app.app.offer_recovery(recovery_path.clone());
+    app.draw();
+    assert_svg("recovery_prompt", &mut app);
+
+    app.key(KeyCode::Char('r'));
+    let screen = app.buffer_lines().join("\n");
+    assert!(
+        screen.contains("Recovered text"),
+        "the recovered document is shown"
+    );
+    assert!(screen.contains("Restored unsaved changes"));
+    assert!(
+        recovery_path.exists(),
+        "the recovery file stays until saved"
+    );
+    // The restored changes are not in the file yet, so quitting asks first.
+    app.ctrl('q');
+    assert!(!app.app.should_quit());
+    assert!(app.svg().contains("Unsaved Changes"));
+}
+
+#[test]
+fn recovery_prompt_can_discard_the_recovery_file() {
+    let dir = TempDir::new("discard");
+    let recovery_path = dir.join(".test.ftml.pure-recovery");
+    std::fs::write(&recovery_path, "<p>Recovered text</p>").expect("write recovery file");
+
+    let mut app = TestApp::new(WIDTH, HEIGHT, sample_document());
+    app.app.offer_recovery(recovery_path.clone());
+    app.key(KeyCode::Right);
+    app.key(KeyCode::Enter);
+    assert!(!recovery_path.exists());
+    let screen = app.buffer_lines().join("\n");
+    assert!(screen.contains("Packing List"), "the document is unchanged");
+    app.ctrl('q');
+    assert!(
+        app.app.should_quit(),
+        "discarding leaves the document clean"
+    );
+}
+
+#[test]
+fn config_rebinds_keys_and_reports_problems() {
+    let mut app = sample_app();
+    app.app.apply_config(crate::config::parse(
+        r#"
+        [editor]
+        max_text_width = 30
+
+        [theme]
+        sparkles = "red"
+
+        [keys]
+        "ctrl+g" = "find"
+        "#,
+    ));
+    app.draw();
+    let lines = app.buffer_lines();
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.contains("Config: unknown theme color `sparkles`")),
+        "problems show up in the status line: {lines:#?}"
+    );
+    // The narrower text column wraps the second paragraph.
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.trim_end().ends_with("before the")),
+        "{lines:#?}"
+    );
+
+    // A bound key runs its command instead of the built-in shortcut.
+    app.ctrl('g');
+    app.type_text("trip");
+    assert!(
+        app.buffer_lines()
+            .iter()
+            .any(|line| line.contains("1 of 1")),
+        "Ctrl+G opens the find bar"
+    );
+}
+
+#[test]
+fn emacs_keymap_labels_the_menus() {
+    let mut app = sample_app();
+    app.app
+        .apply_config(crate::config::parse("[editor]\nkeymap = \"emacs\"\n"));
+    // Alt+F moves a word forward, so the menus open with F10.
+    app.key_with(KeyCode::Char('f'), KeyModifiers::ALT);
+    assert!(!app.svg().contains("Save As"));
+    app.key(KeyCode::F(10));
+    app.key(KeyCode::Down);
+    assert_svg("menu_bar_file_menu_emacs", &mut app);
+}
+
+#[test]
+fn emacs_keymap_saves_with_a_key_sequence() {
+    let dir = TempDir::new("emacs-save");
+    let target = dir.join("notes.ftml");
+
+    let mut app = TestApp::with_path(WIDTH, HEIGHT, sample_document(), target.clone());
+    app.app
+        .apply_config(crate::config::parse("[editor]\nkeymap = \"emacs\"\n"));
+    app.ctrl('e');
+    app.type_text("!");
+    app.ctrl('x');
+    let screen = app.buffer_lines().join("\n");
+    assert!(screen.contains("Ctrl+X -"), "the prefix is shown: {screen}");
+    assert!(!target.exists());
+
+    app.ctrl('s');
+    let saved = std::fs::read_to_string(&target).expect("saved file");
+    assert!(saved.contains("!"), "{saved}");
+
+    // Esc abandons a sequence; an unbound one is reported.
+    app.ctrl('x');
+    app.key(KeyCode::Esc);
+    app.ctrl('s');
+    assert!(app.svg().contains("Find"), "Ctrl+S on its own searches");
+    app.key(KeyCode::Esc);
+    app.ctrl('x');
+    app.ctrl('z');
+    let screen = app.buffer_lines().join("\n");
+    assert!(screen.contains("Ctrl+X Ctrl+Z is not bound"), "{screen}");
+}
+
+#[test]
+fn command_palette_filters_commands() {
+    let mut app = sample_app();
+    app.enhance_keyboard();
+    app.key_with(
+        KeyCode::Char('p'),
+        KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+    );
+    app.type_text("head");
+    assert_svg("command_palette_filtered", &mut app);
+
+    // Enter runs the highlighted command and closes the palette.
+    app.key(KeyCode::Down);
+    app.key(KeyCode::Enter);
+    let screen = app.buffer_lines().join("\n");
+    assert!(!screen.contains("Command Palette"));
+    assert!(
+        screen.contains("test.ftml*"),
+        "the heading changed: {screen}"
+    );
+}
+
+#[test]
+fn command_palette_skips_disabled_commands() {
+    let mut app = sample_app();
+    // The palette is in the View menu, too.
+    app.key_with(KeyCode::Char('v'), KeyModifiers::ALT);
+    app.key(KeyCode::Down);
+    app.key(KeyCode::Down);
+    app.key(KeyCode::Enter);
+    // Bold needs a selection, so Enter leaves the palette open.
+    app.type_text("bold");
+    app.key(KeyCode::Enter);
+    let screen = app.buffer_lines().join("\n");
+    assert!(screen.contains("Command Palette"), "{screen}");
+
+    app.key(KeyCode::Esc);
+    let screen = app.buffer_lines().join("\n");
+    assert!(!screen.contains("Command Palette"));
+    assert!(!screen.contains("test.ftml*"), "nothing changed: {screen}");
+}
+
+#[test]
+fn start_positions_move_the_cursor() {
+    let paragraphs = (1..=30)
+        .map(|n| {
+            if n % 10 == 0 {
+                tdoc::Paragraph::new_header2()
+                    .with_content(vec![tdoc::Span::new_text(format!("Part {}", n / 10))])
+            } else {
+                tdoc::Paragraph::new_text()
+                    .with_content(vec![tdoc::Span::new_text(format!("Paragraph {n}"))])
+            }
+        })
+        .collect();
+    let mut app = TestApp::new(WIDTH, HEIGHT, Document::new().with_paragraphs(paragraphs));
+    let status = |app: &TestApp| app.buffer_lines().last().cloned().unwrap_or_default();
+
+    app.app.go_to_line(13);
+    app.draw();
+    assert!(status(&app).starts_with("13:1 "), "got: {}", status(&app));
+    // A blank line between paragraphs goes to the text below it.
+    app.app.go_to_line(12);
+    app.draw();
+    assert!(status(&app).starts_with("13:1 "), "got: {}", status(&app));
+
+    app.app.go_to_line(1000);
+    app.draw();
+    assert_eq!(cursor_line(&mut app).trim(), "Part 3");
+
+    assert!(app.app.go_to_heading("part 2"));
+    app.draw();
+    assert_eq!(cursor_line(&mut app).trim(), "Part 2");
+    assert!(app.app.go_to_heading("3"));
+    app.draw();
+    assert_eq!(cursor_line(&mut app).trim(), "Part 3");
+    assert!(!app.app.go_to_heading("Appendix"));
+    app.draw();
+    assert!(status(&app).contains("No heading \"Appendix\""));
+}
+
+/// The screen line the cursor is on.
+fn cursor_line(app: &mut TestApp) -> String {
+    let row = app.cursor_position().expect("cursor shown").y as usize;
+    app.buffer_lines()[row].clone()
+}
+
+#[test]
+fn commit_message_comments_are_read_only() {
+    let document = crate::commit_message::parse(
+        "\n# Please enter the commit message for your changes. Lines starting\n\
+         # with '#' will be ignored, and an empty message aborts the commit.\n#\n\
+         # On branch main\n# Changes to be committed:\n#\tmodified:   src/app.rs\n#\n",
+    );
+    let mut app = TestApp::with_path(
+        WIDTH,
+        HEIGHT,
+        document,
+        std::path::PathBuf::from(".git/COMMIT_EDITMSG"),
+    );
+    app.app.edit_commit_message();
+    app.type_text("Explain the frobnicator");
+    app.key(KeyCode::Down);
+    app.type_text("x");
+    assert_svg("commit_message_comments_read_only", &mut app);
+}
+
+fn outline_document() -> Document {
+    ftml! {
+        h1 { "Trip" }
+        p { "Where we go and what to bring." }
+        h2 { "Route" }
+        p { "North along the coast." }
+        h3 { "Stops" }
+        p { "Two nights each." }
+        h2 { "Packing" }
+        p { "Travel light." }
+    }
+}
+
+#[test]
+fn the_outline_jumps_to_headings_and_follows_their_edits() {
+    let mut app = TestApp::new(WIDTH, HEIGHT, outline_document());
+    app.key(KeyCode::F(7));
+    let screen = app.buffer_lines().join("\n");
+    assert!(screen.contains("▸Trip"), "{screen}");
+
+    app.key(KeyCode::Down);
+    app.key(KeyCode::Down);
+    app.key(KeyCode::Enter);
+    app.draw();
+    assert!(
+        cursor_line(&mut app).trim_end().ends_with("│Stops"),
+        "{}",
+        app.buffer_lines().join("\n")
+    );
+
+    // Typing goes to the document again, and the outline follows.
+    app.type_text("Overnight ");
+    let screen = app.buffer_lines().join("\n");
+    assert!(screen.contains("▸    Overnight Stops"), "{screen}");
+    assert_svg("outline_follows_heading_edits", &mut app);
+}
+
+#[test]
+fn clicking_an_outline_heading_moves_the_cursor_there() {
+    let mut app = TestApp::new(WIDTH, HEIGHT, outline_document());
+    app.key(KeyCode::F(7));
+    // Away from the outline, then back to hiding it.
+    app.key(KeyCode::Esc);
+    app.type_text("Our ");
+    app.key(KeyCode::F(7));
+    app.key(KeyCode::F(7));
+    assert!(!app.buffer_lines().join("\n").contains("▸"));
+
+    app.key(KeyCode::F(7));
+    app.key(KeyCode::Esc);
+    // The fourth entry, "Packing", on the fourth row.
+    app.click(1, 3);
+    app.draw();
+    assert!(
+        cursor_line(&mut app).trim_end().ends_with("│Packing"),
+        "{}",
+        app.buffer_lines().join("\n")
+    );
+    let screen = app.buffer_lines().join("\n");
+    assert!(screen.contains("▸  Packing"), "{screen}");
+    assert!(screen.contains(" Our Trip"), "{screen}");
+}
+
+#[test]
+fn folded_sections_show_as_summary_lines() {
+    let mut app = TestApp::new(WIDTH, HEIGHT, outline_document());
+    app.key(KeyCode::Down);
+    app.key(KeyCode::Down);
+    app.key(KeyCode::F(8));
+    let screen = app.buffer_lines().join("\n");
+    assert!(screen.contains("▸ Route (3 paragraphs)"), "{screen}");
+    assert!(!screen.contains("North along the coast."), "{screen}");
+
+    // The cursor moves past the folded content.
+    app.key(KeyCode::Down);
+    app.type_text("Light ");
+    app.draw();
+    assert!(
+        cursor_line(&mut app).contains("Light Packing"),
+        "{}",
+        app.buffer_lines().join("\n")
+    );
+    assert_svg("folded_section", &mut app);
+}
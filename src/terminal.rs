@@ -0,0 +1,58 @@
+//! The terminal Pure draws on.
+//!
+//! That is standard output, unless it carries something else — the document
+//! itself with `pure -`, or a file it was redirected to. Then Pure draws on
+//! the controlling terminal instead, the way other editors run as `$EDITOR`
+//! in a pipeline do. Keyboard input already comes from there: crossterm
+//! reads the controlling terminal whenever standard input is not one.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::sync::OnceLock;
+
+#[cfg(windows)]
+const CONTROLLING_TERMINAL: &str = "CONOUT$";
+#[cfg(not(windows))]
+const CONTROLLING_TERMINAL: &str = "/dev/tty";
+
+static TTY: OnceLock<File> = OnceLock::new();
+
+/// Draw on the controlling terminal from now on rather than on standard
+/// output.
+pub fn use_controlling_terminal() -> io::Result<()> {
+    let tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(CONTROLLING_TERMINAL)?;
+    let _ = TTY.set(tty);
+    Ok(())
+}
+
+/// Where drawing and other escape sequences for the terminal go.
+pub fn output() -> Output {
+    match TTY.get() {
+        Some(tty) => Output::Tty(tty),
+        None => Output::Stdout(io::stdout()),
+    }
+}
+
+pub enum Output {
+    Stdout(io::Stdout),
+    Tty(&'static File),
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Stdout(stdout) => stdout.write(buf),
+            Output::Tty(tty) => (*tty).write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Stdout(stdout) => stdout.flush(),
+            Output::Tty(tty) => (*tty).flush(),
+        }
+    }
+}
@@ -16,7 +16,8 @@
 //! record the README's `demo.gif` from the same SVG frames.
 
 use std::fmt::Write as _;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crossterm::event::{
     Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
@@ -88,12 +89,22 @@ impl TestApp {
     fn build(width: u16, height: u16, document: Document, path: Option<PathBuf>) -> Self {
         let mut app = App::new(document, path, DocumentFormat::Ftml, None);
         app.set_interactive(false);
+        // Tests run side by side on the same files, the repository's
+        // test.ftml among them; none of them may leave a lock next to it.
+        app.release_locks();
         let terminal = Terminal::new(TestBackend::new(width, height)).expect("test terminal");
         let mut test_app = Self { app, terminal };
         test_app.draw();
         test_app
     }
 
+    /// Send keys as a terminal speaking the kitty keyboard protocol does,
+    /// with Ctrl+Shift chords and Ctrl+[ told apart from others.
+    pub fn enhance_keyboard(&mut self) {
+        self.app.set_enhanced_keyboard(true);
+        self.draw();
+    }
+
     /// Render one frame. The main loop redraws after every event, so
     /// [`TestApp::event`] does this automatically.
     pub fn draw(&mut self) {
@@ -174,6 +185,45 @@ impl TestApp {
     }
 }
 
+/// A fresh directory under the system's temp directory, removed again when
+/// dropped — also when a failing assertion unwinds the test using it.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Create `pure-<name>-<pid>-<n>`, with `n` counting up so tests
+    /// picking the same name do not share a directory.
+    pub fn new(name: &str) -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let count = COUNT.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("pure-{name}-{}-{count}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).expect("create temp dir");
+        Self { path }
+    }
+}
+
+impl std::ops::Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
 /// The resolved visual style of a cell, with `REVERSED` already applied.
 #[derive(Clone, PartialEq)]
 struct CellStyle {
@@ -0,0 +1,141 @@
+//! A single-line text input with a caret, for bars and prompts that ask the
+//! user for a short string (such as the find bar's query).
+
+#[derive(Clone, Debug, Default)]
+pub struct TextField {
+    text: String,
+    /// Cursor position in `text`, as a char index.
+    cursor: usize,
+}
+
+impl TextField {
+    /// A field holding `text`, with the caret at its end.
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let cursor = text.chars().count();
+        Self { text, cursor }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Caret position as a char index.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn insert_char(&mut self, ch: char) {
+        if ch.is_control() {
+            return;
+        }
+        let at = byte_index(&self.text, self.cursor);
+        self.text.insert(at, ch);
+        self.cursor += 1;
+    }
+
+    /// Insert pasted text, dropping control characters (newlines included).
+    pub fn insert_str(&mut self, text: &str) {
+        let filtered: String = text.chars().filter(|ch| !ch.is_control()).collect();
+        let at = byte_index(&self.text, self.cursor);
+        self.text.insert_str(at, &filtered);
+        self.cursor += filtered.chars().count();
+    }
+
+    pub fn backspace(&mut self) {
+        if self.cursor == 0 {
+            return;
+        }
+        let start = byte_index(&self.text, self.cursor - 1);
+        let end = byte_index(&self.text, self.cursor);
+        self.text.replace_range(start..end, "");
+        self.cursor -= 1;
+    }
+
+    pub fn delete(&mut self) {
+        if self.cursor >= self.text.chars().count() {
+            return;
+        }
+        let start = byte_index(&self.text, self.cursor);
+        let end = byte_index(&self.text, self.cursor + 1);
+        self.text.replace_range(start..end, "");
+    }
+
+    /// Delete from the cursor back to the start of the previous whitespace-
+    /// delimited word.
+    pub fn delete_word_backward(&mut self) {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut target = self.cursor;
+        while target > 0 && chars[target - 1].is_whitespace() {
+            target -= 1;
+        }
+        while target > 0 && !chars[target - 1].is_whitespace() {
+            target -= 1;
+        }
+        let start = byte_index(&self.text, target);
+        let end = byte_index(&self.text, self.cursor);
+        self.text.replace_range(start..end, "");
+        self.cursor = target;
+    }
+
+    pub fn move_cursor_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_cursor_right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.text.chars().count());
+    }
+
+    pub fn move_cursor_start(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn move_cursor_end(&mut self) {
+        self.cursor = self.text.chars().count();
+    }
+}
+
+fn byte_index(text: &str, char_index: usize) -> usize {
+    text.char_indices()
+        .nth(char_index)
+        .map(|(index, _)| index)
+        .unwrap_or(text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn editing_follows_the_caret() {
+        let mut field = TextField::new("hllo");
+        field.move_cursor_start();
+        field.move_cursor_right();
+        field.insert_char('e');
+        assert_eq!(field.text(), "hello");
+        assert_eq!(field.cursor(), 2);
+
+        field.move_cursor_end();
+        field.backspace();
+        field.move_cursor_start();
+        field.delete();
+        assert_eq!(field.text(), "ell");
+    }
+
+    #[test]
+    fn pasted_text_drops_control_characters() {
+        let mut field = TextField::default();
+        field.insert_str("one\ntwo\t");
+        assert_eq!(field.text(), "onetwo");
+        assert_eq!(field.cursor(), 6);
+    }
+
+    #[test]
+    fn delete_word_backward_stops_at_whitespace() {
+        let mut field = TextField::new("one two three");
+        field.delete_word_backward();
+        assert_eq!(field.text(), "one two ");
+        field.delete_word_backward();
+        assert_eq!(field.text(), "one ");
+    }
+}
@@ -33,6 +33,18 @@ pub struct Theme {
     /// Color for links
     pub link_color: Color,
 
+    /// Foreground color for search matches
+    pub search_match_fg: Color,
+
+    /// Background color for search matches
+    pub search_match_bg: Color,
+
+    /// Foreground color for the search match at the cursor
+    pub search_current_fg: Color,
+
+    /// Background color for the search match at the cursor
+    pub search_current_bg: Color,
+
     /// Foreground color for reveal tags
     pub reveal_tag_fg: Color,
 
@@ -90,6 +102,10 @@ impl Default for Theme {
             highlight_fg: Color::Black,
             highlight_bg: Color::LightYellow,
             link_color: Color::Blue,
+            search_match_fg: Color::Black,
+            search_match_bg: Color::Yellow,
+            search_current_fg: Color::Black,
+            search_current_bg: Color::LightRed,
             reveal_tag_fg: Color::Black,
             reveal_tag_bg: Color::Gray,
             scrollbar_knob_fg: Color::Gray,
@@ -114,6 +130,43 @@ impl Theme {
         Self::default()
     }
 
+    /// The color called `name`, i.e. the field of that name, so the
+    /// configuration file can override it.
+    pub fn color_mut(&mut self, name: &str) -> Option<&mut Color> {
+        let color = match name {
+            "background" => &mut self.background,
+            "status_bar_fg" => &mut self.status_bar_fg,
+            "status_bar_bg" => &mut self.status_bar_bg,
+            "filename_color" => &mut self.filename_color,
+            "cursor_color" => &mut self.cursor_color,
+            "selection_fg" => &mut self.selection_fg,
+            "selection_bg" => &mut self.selection_bg,
+            "highlight_fg" => &mut self.highlight_fg,
+            "highlight_bg" => &mut self.highlight_bg,
+            "link_color" => &mut self.link_color,
+            "search_match_fg" => &mut self.search_match_fg,
+            "search_match_bg" => &mut self.search_match_bg,
+            "search_current_fg" => &mut self.search_current_fg,
+            "search_current_bg" => &mut self.search_current_bg,
+            "reveal_tag_fg" => &mut self.reveal_tag_fg,
+            "reveal_tag_bg" => &mut self.reveal_tag_bg,
+            "scrollbar_knob_fg" => &mut self.scrollbar_knob_fg,
+            "scrollbar_knob_bg" => &mut self.scrollbar_knob_bg,
+            "scrollbar_track_fg" => &mut self.scrollbar_track_fg,
+            "scrollbar_track_bg" => &mut self.scrollbar_track_bg,
+            "menu_fg" => &mut self.menu_fg,
+            "menu_bg" => &mut self.menu_bg,
+            "menu_disabled_fg" => &mut self.menu_disabled_fg,
+            "menu_selected_fg" => &mut self.menu_selected_fg,
+            "menu_selected_bg" => &mut self.menu_selected_bg,
+            "menu_selected_disabled_fg" => &mut self.menu_selected_disabled_fg,
+            "structural_fg" => &mut self.structural_fg,
+            "checkmark_fg" => &mut self.checkmark_fg,
+            _ => return None,
+        };
+        Some(color)
+    }
+
     /// Get the style for the status bar
     pub fn status_bar_style(&self) -> Style {
         Style::default()
@@ -136,6 +189,20 @@ impl Theme {
         Style::default().fg(self.highlight_fg).bg(self.highlight_bg)
     }
 
+    /// Get the style for search matches
+    pub fn search_match_style(&self) -> Style {
+        Style::default()
+            .fg(self.search_match_fg)
+            .bg(self.search_match_bg)
+    }
+
+    /// Get the style for the search match at the cursor
+    pub fn search_current_style(&self) -> Style {
+        Style::default()
+            .fg(self.search_current_fg)
+            .bg(self.search_current_bg)
+    }
+
     /// Get the style for links
     pub fn link_style(&self) -> Style {
         Style::default().fg(self.link_color)